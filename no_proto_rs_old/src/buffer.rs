@@ -1,22 +1,33 @@
 //! Top level abstraction for buffer objects
 
 use alloc::prelude::v1::Box;
-use crate::{json_decode, json_flex::JSMAP, pointer::NP_Cursor_Parent, schema::{NP_Bytes_Data, NP_Map_List_Data, NP_String_Data, NP_Struct_Data, NP_Tuple_Data}};
+use crate::{json_decode, json_flex::JSMAP, pointer::NP_Cursor_Parent, schema::{NP_Bytes_Data, NP_Enum_Data, NP_Map_List_Data, NP_Portal_Data, NP_Schema, NP_String_Data, NP_Struct_Data, NP_Tuple_Data, NP_Value_Kind}};
 use alloc::string::String;
 use crate::{NP_Size_Data, schema::NP_TypeKeys};
-use crate::{memory::NP_Memory, utils::opt_err};
+use crate::{memory::{NP_Allocator, NP_Memory}, utils::opt_err};
 use crate::collection::tuple::NP_Tuple;
 
-use crate::{pointer::{NP_Scalar}};
+use crate::{pointer::{NP_Scalar, NP_Value_Owned}};
 use crate::{collection::map::NP_Map};
 use crate::{pointer::NP_Value};
+use crate::NP_Factory;
 use crate::pointer::NP_Cursor;
+use crate::pointer::dec::NP_Dec;
+use crate::pointer::option::NP_Enum;
 use crate::{schema::NP_Parsed_Schema, collection::struc::NP_Struct};
 use alloc::vec::Vec;
+use alloc::collections::{BTreeMap, BTreeSet};
 use crate::{collection::{list::NP_List}};
 use crate::error::NP_Error;
-use crate::{json_flex::NP_JSON};
+use crate::hashmap::{crc32, murmurhash3_x86_32, SEED};
+use crate::{json_flex::{NP_JSON, json_decode_bytes}};
+use crate::visitor::{NP_Hash_Visitor, NP_Msgpack_Visitor, NP_Visitor, visit_cursor};
 use crate::alloc::borrow::ToOwned;
+use core::cell::Cell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::marker::PhantomData;
+use core::ops::Deref;
+use alloc::sync::Arc;
 
 /// The address location of the root pointer.
 #[doc(hidden)]
@@ -28,48 +39,521 @@ pub const LIST_MAX_SIZE: usize = core::u16::MAX as usize;
 pub const VTABLE_SIZE: usize = 4;
 #[doc(hidden)]
 pub const VTABLE_BYTES: usize = 20;
+/// Default limit for how deeply nested values are followed
+#[doc(hidden)]
+pub const DEFAULT_MAX_DEPTH: usize = 255;
+
+/// Every buffer gets its own id from here, so cursor handles can't be used with a buffer they didn't come from
+static NEXT_BUFFER_ID: AtomicUsize = AtomicUsize::new(1);
+
+
+/// Options used to control how values that aren't in the buffer are exported to JSON.
+///
+/// The default options export the same output as `json_encode`.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NP_JSON_Opts {
+    /// Include values that aren't set in the buffer.  If `false`, unset struct fields are left out of the output entirely.
+    pub include_absent: bool,
+    /// If absent values are included, render them as `null` instead of the schema default.
+    pub absent_as_null: bool
+}
+
+impl Default for NP_JSON_Opts {
+    fn default() -> Self {
+        Self { include_absent: true, absent_as_null: false }
+    }
+}
 
+/// Allocation counts for a buffer, returned by `NP_Buffer::memory_stats`.
+/// 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NP_Memory_Stats {
+    /// How many times new bytes were allocated
+    pub malloc_count: usize,
+    /// Total number of bytes allocated
+    pub bytes_allocated: usize
+}
 
 /// Buffers contain the bytes of each object and allow you to perform reads, updates, deletes and compaction.
 /// 
 /// 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct NP_Buffer {
     /// Memory object used by this buffer
     memory: NP_Memory,
     /// Is this buffer mutable?
     pub mutable: bool,
-    cursor: NP_Cursor
+    cursor: NP_Cursor,
+    /// Unique id of this buffer, clones get a new one
+    owner: usize,
+    /// Incremented on compaction and every change that can move or remove values, used to invalidate cursor handles
+    generation: usize,
+    /// Read counts for each schema address, only kept once tracking is enabled
+    access_counts: Option<Vec<Cell<u32>>>,
+    /// Schema id written in front of the bytes when the buffer is finished
+    schema_tag: Option<u32>
 }
 
 unsafe impl Send for NP_Buffer {}
 
+impl Clone for NP_Buffer {
+    fn clone(&self) -> Self {
+        NP_Buffer {
+            memory: self.memory.clone(),
+            mutable: self.mutable,
+            cursor: self.cursor.clone(),
+            // the clone can change on its own, handles from this buffer shouldn't work with it
+            owner: NEXT_BUFFER_ID.fetch_add(1, Ordering::Relaxed),
+            generation: self.generation,
+            access_counts: self.access_counts.clone(),
+            schema_tag: self.schema_tag
+        }
+    }
+}
+
+/// Handle to a location in a buffer, created with `NP_Buffer::cursor_at`.
+/// 
+/// Reads and writes through the handle start at its location instead of selecting the path from the root every time.
+/// 
+#[derive(Debug, Clone)]
+pub struct NP_Cursor_Handle {
+    cursor: NP_Cursor,
+    owner: usize,
+    generation: usize
+}
+
+/// Appends values to the end of a list, created with `NP_Buffer::list_appender`.
+/// 
+pub struct NP_List_Appender<'appender> {
+    buffer: &'appender mut NP_Buffer,
+    list: NP_Cursor,
+    tail: usize,
+    next_index: usize
+}
+
+impl<'appender> NP_List_Appender<'appender> {
+    /// Push a value onto the end of the list, returns the index of the new item.
+    /// 
+    pub fn push<'push, X: 'push>(&mut self, value: X) -> Result<u16, NP_Error> where X: NP_Value<'push> + NP_Scalar<'push> {
+
+        let memory = &self.buffer.memory;
+
+        let data = unsafe { &*(*memory.get_schema(self.list.schema_addr).data as *const NP_Map_List_Data) };
+
+        // type does not match schema
+        if X::type_idx().1 != memory.get_schema(data.child).i {
+            return Err(NP_Buffer::set_type_mismatch(X::type_idx().0, &memory.get_schema(data.child).i));
+        }
+
+        if self.next_index > u16::MAX as usize {
+            return Err(NP_Error::new("Index cannot be greater than 2^16!"))
+        }
+
+        let new_item_addr = memory.malloc_borrow(&[0u8; 10])?; // list item
+        let new_cursor = NP_Cursor::new(new_item_addr, data.child, self.list.schema_addr);
+        new_cursor.get_value_mut(memory).set_index(self.next_index as u16);
+
+        // write the value before the item is linked, so a failed write leaves the list as it was
+        X::set_value(new_cursor, memory, value)?;
+
+        let list_data = NP_List::get_list(self.list.get_value(memory).get_addr_value() as usize, memory);
+
+        if self.tail == 0 { // empty list
+            list_data.set_head(new_item_addr as u32);
+        } else {
+            NP_Cursor::new(self.tail, data.child, self.list.schema_addr).get_value_mut(memory).set_next_addr(new_item_addr as u32);
+        }
+        list_data.set_tail(new_item_addr as u32);
+
+        self.tail = new_item_addr;
+        self.next_index += 1;
+
+        Ok((self.next_index - 1) as u16)
+    }
+}
+
 /// Finished buffer, can't be edited.  Just exported.
 /// 
 #[derive(Debug)]
 pub struct NP_Finished_Buffer {
-    memory: NP_Memory
+    memory: NP_Memory,
+    schema_tag: Option<u32>
 }
 
 impl NP_Finished_Buffer {
     /// How large the buffer is
     /// 
     pub fn buffer_len(self) -> usize {
-        self.memory.read_bytes().len()
+        self.memory.read_bytes().len() + self.tag_len()
     }
 
     /// How many bytes the data is using in the buffer
     /// 
     pub fn data_len(self) -> usize {
-        self.memory.length()
+        self.memory.length() + self.tag_len()
     }
 
     /// Get an owned copy of the bytes in the buffer
     /// If the buffer was a `ref` or `ref_mut` this creates a copy of the underlying bytes.
     /// If the buffer was an owned type, this moves the bytes out of the buffer
     /// 
+    /// Buffers from `NP_Factory::new_buffer_tagged` or `open_buffer_tagged` start with the 4 byte schema id.
+    /// 
     pub fn bytes(self) -> Vec<u8> {
-        self.memory.dump()
+        match self.schema_tag {
+            Some(tag) => {
+                let data = self.memory.dump();
+                let mut bytes = Vec::with_capacity(data.len() + 4);
+                bytes.extend_from_slice(&tag.to_be_bytes());
+                bytes.extend_from_slice(&data);
+                bytes
+            },
+            None => self.memory.dump()
+        }
+    }
+
+    fn tag_len(&self) -> usize {
+        if self.schema_tag.is_some() { 4 } else { 0 }
+    }
+}
+
+/// Read only view of a buffer, created with `NP_Buffer::freeze`.
+/// 
+/// The reader only has methods that read from the buffer, so it can't be changed after it's frozen.  Clones share the same bytes behind an `Arc` and the reader is `Send + Sync`, a frozen record can be cached and read from many threads at once.
+/// 
+#[derive(Debug, Clone)]
+pub struct NP_BufferReader {
+    buffer: Arc<NP_Buffer>
+}
+
+// the memory is owned and never written to once frozen, every reader method only takes `&self` paths that don't touch the `Cell`/`RefCell` fields of the memory
+unsafe impl Send for NP_BufferReader {}
+unsafe impl Sync for NP_BufferReader {}
+
+impl NP_BufferReader {
+    /// Get a value from the buffer, see [`NP_Buffer::get`](struct.NP_Buffer.html#method.get)
+    pub fn get<'get, X: 'get>(&'get self, path: &[&str]) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
+        self.buffer.get(path)
+    }
+
+    /// Get an owned value from the buffer, see [`NP_Buffer::get_owned`](struct.NP_Buffer.html#method.get_owned)
+    pub fn get_owned<X: NP_Value_Owned>(&self, path: &[&str]) -> Result<Option<X>, NP_Error> {
+        self.buffer.get_owned(path)
+    }
+
+    /// Get the length of a collection or string, see [`NP_Buffer::get_length`](struct.NP_Buffer.html#method.get_length)
+    pub fn get_length(&self, path: &[&str]) -> Result<Option<usize>, NP_Error> {
+        self.buffer.get_length(path)
+    }
+
+    /// Check if a value is in the buffer, see [`NP_Buffer::contains`](struct.NP_Buffer.html#method.contains)
+    pub fn contains(&self, path: &[&str]) -> Result<bool, NP_Error> {
+        self.buffer.contains(path)
+    }
+
+    /// Get the schema type at a path, see [`NP_Buffer::get_schema_type`](struct.NP_Buffer.html#method.get_schema_type)
+    pub fn get_schema_type(&self, path: &[&str]) -> Result<Option<NP_TypeKeys>, NP_Error> {
+        self.buffer.get_schema_type(path)
+    }
+
+    /// List the path of every value in the buffer, see [`NP_Buffer::set_paths`](struct.NP_Buffer.html#method.set_paths)
+    pub fn set_paths(&self) -> Result<Vec<Vec<String>>, NP_Error> {
+        self.buffer.set_paths()
+    }
+
+    /// Copy the values at a path into JSON, see [`NP_Buffer::json_encode`](struct.NP_Buffer.html#method.json_encode)
+    pub fn json_encode(&self, path: &[&str]) -> Result<NP_JSON, NP_Error> {
+        self.buffer.json_encode(path)
+    }
+
+    /// Get the size of the buffer before and after compaction, see [`NP_Buffer::calc_bytes`](struct.NP_Buffer.html#method.calc_bytes)
+    pub fn calc_bytes(&self) -> Result<NP_Size_Data, NP_Error> {
+        self.buffer.calc_bytes()
+    }
+
+    /// Read the bytes of the buffer
+    pub fn read_bytes(&self) -> &[u8] {
+        self.buffer.read_bytes()
+    }
+}
+
+/// Buffer that gets its memory from a borrowed allocator, created with `NP_Factory::new_buffer_in`.
+/// 
+/// It works like an [`NP_Buffer`](struct.NP_Buffer.html), reads go through `Deref` and the methods that change the buffer are forwarded.  The inner buffer is never handed out mutably, so the lifetime keeps it from outliving the allocator.  It's only `Send` if the allocator is `Sync`.  The methods that consume the buffer copy its bytes out of the allocator first.
+/// 
+/// ```compile_fail
+/// # use no_proto::NP_Factory;
+/// # use no_proto::memory::NP_Allocator;
+/// # struct Heap;
+/// # unsafe impl NP_Allocator for Heap {
+/// #     fn alloc(&self, size: usize) -> *mut u8 { Box::leak(vec![0u8; size].into_boxed_slice()).as_mut_ptr() }
+/// #     fn grow(&self, _ptr: *mut u8, _old: usize, new_size: usize) -> *mut u8 { self.alloc(new_size) }
+/// # }
+/// let factory = NP_Factory::new("string()").unwrap();
+/// let buffer = {
+///     let heap = Heap;
+///     factory.new_buffer_in(&heap, None).unwrap()
+/// };
+/// ```
+/// 
+/// The inner buffer can't be swapped out either.
+/// 
+/// ```compile_fail
+/// # use no_proto::NP_Factory;
+/// # use no_proto::memory::NP_Allocator;
+/// # struct Heap;
+/// # unsafe impl NP_Allocator for Heap {
+/// #     fn alloc(&self, size: usize) -> *mut u8 { Box::leak(vec![0u8; size].into_boxed_slice()).as_mut_ptr() }
+/// #     fn grow(&self, _ptr: *mut u8, _old: usize, new_size: usize) -> *mut u8 { self.alloc(new_size) }
+/// # }
+/// let factory = NP_Factory::new("string()").unwrap();
+/// let mut buffer = factory.new_buffer(None);
+/// {
+///     let heap = Heap;
+///     let mut buffer_in = factory.new_buffer_in(&heap, None).unwrap();
+///     core::mem::swap(&mut *buffer_in, &mut buffer);
+/// }
+/// ```
+/// 
+pub struct NP_Buffer_In<'alloc, A: NP_Allocator> {
+    buffer: NP_Buffer,
+    _alloc: PhantomData<&'alloc A>
+}
+
+impl<'alloc, A: NP_Allocator> NP_Buffer_In<'alloc, A> {
+    #[doc(hidden)]
+    pub fn _new(buffer: NP_Buffer) -> Self {
+        NP_Buffer_In { buffer, _alloc: PhantomData }
+    }
+
+    /// Finish the buffer, the bytes are copied out of the allocator.  See [`NP_Buffer::finish`](struct.NP_Buffer.html#method.finish)
+    pub fn finish(self) -> NP_Finished_Buffer {
+        NP_Finished_Buffer { memory: self.buffer.memory.into_read_only(), schema_tag: self.buffer.schema_tag }
+    }
+
+    /// Freeze the buffer into a reader, the bytes are copied out of the allocator.  See [`NP_Buffer::freeze`](struct.NP_Buffer.html#method.freeze)
+    pub fn freeze(self) -> NP_BufferReader {
+        self.buffer.freeze()
+    }
+
+    /// Copy the buffer bytes into an existing vec.  See [`NP_Buffer::close_into`](struct.NP_Buffer.html#method.close_into)
+    pub fn close_into(self, out: &mut Vec<u8>) {
+        self.buffer.close_into(out)
+    }
+
+    /// Get the buffer bytes with a checksum.  See [`NP_Buffer::close_with_checksum`](struct.NP_Buffer.html#method.close_with_checksum)
+    pub fn close_with_checksum(self) -> Vec<u8> {
+        self.buffer.close_with_checksum()
+    }
+
+    // the buffer is never handed out as `&mut NP_Buffer`, it could be swapped out from under the lifetime
+    /// See [`NP_Buffer::move_cursor`](struct.NP_Buffer.html#method.move_cursor)
+    #[inline(always)]
+    pub fn move_cursor(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
+        self.buffer.move_cursor(path)
+    }
+    /// See [`NP_Buffer::cursor_to_root`](struct.NP_Buffer.html#method.cursor_to_root)
+    #[inline(always)]
+    pub fn cursor_to_root(&mut self) {
+        self.buffer.cursor_to_root()
+    }
+    /// See [`NP_Buffer::set_at`](struct.NP_Buffer.html#method.set_at)
+    #[inline(always)]
+    pub fn set_at<'set, X: 'set>(&mut self, handle: &NP_Cursor_Handle, path: &[&str], value: X) -> Result<bool, NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> {
+        self.buffer.set_at(handle, path, value)
+    }
+    /// See [`NP_Buffer::set_max`](struct.NP_Buffer.html#method.set_max)
+    #[inline(always)]
+    pub fn set_max(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
+        self.buffer.set_max(path)
+    }
+    /// See [`NP_Buffer::fill_defaults`](struct.NP_Buffer.html#method.fill_defaults)
+    #[inline(always)]
+    pub fn fill_defaults(&mut self) -> Result<(), NP_Error> {
+        self.buffer.fill_defaults()
+    }
+    /// See [`NP_Buffer::set_min`](struct.NP_Buffer.html#method.set_min)
+    #[inline(always)]
+    pub fn set_min(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
+        self.buffer.set_min(path)
+    }
+    /// See [`NP_Buffer::set`](struct.NP_Buffer.html#method.set)
+    #[inline(always)]
+    pub fn set<'set, X: 'set>(&mut self, path: &[&str], value: X) -> Result<bool, NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> {
+        self.buffer.set(path, value)
+    }
+    /// See [`NP_Buffer::set_checked`](struct.NP_Buffer.html#method.set_checked)
+    #[inline(always)]
+    pub fn set_checked<'set, X: 'set>(&mut self, path: &[&str], value: X) -> Result<(), NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> {
+        self.buffer.set_checked(path, value)
+    }
+    /// See [`NP_Buffer::set_many`](struct.NP_Buffer.html#method.set_many)
+    #[inline(always)]
+    pub fn set_many<'set, X: 'set>(&mut self, entries: &[(&[&str], X)]) -> Result<(), NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> + Clone {
+        self.buffer.set_many(entries)
+    }
+    /// See [`NP_Buffer::set_with_json`](struct.NP_Buffer.html#method.set_with_json)
+    #[inline(always)]
+    pub fn set_with_json<S: Into<String>>(&mut self, path: &[&str], json_value: S) -> Result<bool, NP_Error> {
+        self.buffer.set_with_json(path, json_value)
+    }
+    /// See [`NP_Buffer::set_json`](struct.NP_Buffer.html#method.set_json)
+    #[inline(always)]
+    pub fn set_json(&mut self, path: &[&str], json: &NP_JSON, apply_null: bool) -> Result<(), NP_Error> {
+        self.buffer.set_json(path, json, apply_null)
+    }
+    /// See [`NP_Buffer::set_json_str`](struct.NP_Buffer.html#method.set_json_str)
+    #[inline(always)]
+    pub fn set_json_str(&mut self, path: &[&str], json: &str, apply_null: bool) -> Result<(), NP_Error> {
+        self.buffer.set_json_str(path, json, apply_null)
+    }
+    /// See [`NP_Buffer::apply_patch`](struct.NP_Buffer.html#method.apply_patch)
+    #[inline(always)]
+    pub fn apply_patch(&mut self, patch: &[(Vec<String>, NP_JSON)]) -> Result<(), NP_Error> {
+        self.buffer.apply_patch(patch)
+    }
+    /// See [`NP_Buffer::list_push`](struct.NP_Buffer.html#method.list_push)
+    #[inline(always)]
+    pub fn list_push<'push, X: 'push>(&mut self, path: &[&str], value: X) -> Result<Option<u16>, NP_Error> where X: NP_Value<'push> + NP_Scalar<'push> {
+        self.buffer.list_push(path, value)
+    }
+    /// See [`NP_Buffer::set_list`](struct.NP_Buffer.html#method.set_list)
+    #[inline(always)]
+    pub fn set_list<'set, X: 'set>(&mut self, path: &[&str], items: &[X]) -> Result<(), NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> + Clone {
+        self.buffer.set_list(path, items)
+    }
+    /// See [`NP_Buffer::set_map`](struct.NP_Buffer.html#method.set_map)
+    #[inline(always)]
+    pub fn set_map<'set, X: 'set>(&mut self, path: &[&str], pairs: &[(&str, X)]) -> Result<(), NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> + Clone {
+        self.buffer.set_map(path, pairs)
+    }
+    /// See [`NP_Buffer::list_appender`](struct.NP_Buffer.html#method.list_appender)
+    #[inline(always)]
+    pub fn list_appender(&mut self, path: &[&str]) -> Result<NP_List_Appender, NP_Error> {
+        self.buffer.list_appender(path)
+    }
+    /// See [`NP_Buffer::list_insert`](struct.NP_Buffer.html#method.list_insert)
+    #[inline(always)]
+    pub fn list_insert<'insert, X: 'insert>(&mut self, path: &[&str], index: u16, value: X) -> Result<bool, NP_Error> where X: NP_Value<'insert> + NP_Scalar<'insert> {
+        self.buffer.list_insert(path, index, value)
+    }
+    /// See [`NP_Buffer::list_remove`](struct.NP_Buffer.html#method.list_remove)
+    #[inline(always)]
+    pub fn list_remove(&mut self, path: &[&str], index: u16) -> Result<bool, NP_Error> {
+        self.buffer.list_remove(path, index)
+    }
+    /// See [`NP_Buffer::list_truncate`](struct.NP_Buffer.html#method.list_truncate)
+    #[inline(always)]
+    pub fn list_truncate(&mut self, path: &[&str], len: usize) -> Result<(), NP_Error> {
+        self.buffer.list_truncate(path, len)
+    }
+    /// See [`NP_Buffer::map_rename_key`](struct.NP_Buffer.html#method.map_rename_key)
+    #[inline(always)]
+    pub fn map_rename_key(&mut self, path: &[&str], old: &str, new: &str) -> Result<bool, NP_Error> {
+        self.buffer.map_rename_key(path, old, new)
+    }
+    /// See [`NP_Buffer::del`](struct.NP_Buffer.html#method.del)
+    #[inline(always)]
+    pub fn del(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
+        self.buffer.del(path)
+    }
+    /// See [`NP_Buffer::swap`](struct.NP_Buffer.html#method.swap)
+    #[inline(always)]
+    pub fn swap(&mut self, path_a: &[&str], path_b: &[&str]) -> Result<(), NP_Error> {
+        self.buffer.swap(path_a, path_b)
+    }
+    /// See [`NP_Buffer::move_value`](struct.NP_Buffer.html#method.move_value)
+    #[inline(always)]
+    pub fn move_value(&mut self, from: &[&str], to: &[&str]) -> Result<bool, NP_Error> {
+        self.buffer.move_value(from, to)
+    }
+    /// See [`NP_Buffer::clear_collection`](struct.NP_Buffer.html#method.clear_collection)
+    #[inline(always)]
+    pub fn clear_collection(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
+        self.buffer.clear_collection(path)
+    }
+    /// See [`NP_Buffer::set_by_pointer`](struct.NP_Buffer.html#method.set_by_pointer)
+    #[inline(always)]
+    pub fn set_by_pointer<'set, X: 'set>(&mut self, pointer: &str, value: X) -> Result<bool, NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> {
+        self.buffer.set_by_pointer(pointer, value)
+    }
+    /// See [`NP_Buffer::set_enum_index`](struct.NP_Buffer.html#method.set_enum_index)
+    #[inline(always)]
+    pub fn set_enum_index(&mut self, path: &[&str], index: u8) -> Result<(), NP_Error> {
+        self.buffer.set_enum_index(path, index)
+    }
+    /// See [`NP_Buffer::set_enum_lenient`](struct.NP_Buffer.html#method.set_enum_lenient)
+    #[inline(always)]
+    pub fn set_enum_lenient(&mut self, path: &[&str], value: &str) -> Result<bool, NP_Error> {
+        self.buffer.set_enum_lenient(path, value)
+    }
+    /// See [`NP_Buffer::set_raw_bytes`](struct.NP_Buffer.html#method.set_raw_bytes)
+    #[inline(always)]
+    pub fn set_raw_bytes(&mut self, path: &[&str], bytes: &[u8]) -> Result<(), NP_Error> {
+        self.buffer.set_raw_bytes(path, bytes)
+    }
+    /// See [`NP_Buffer::maybe_compact`](struct.NP_Buffer.html#method.maybe_compact)
+    #[inline(always)]
+    pub fn maybe_compact<F>(&mut self, new_capacity: Option<usize>, mut callback: F) -> Result<(), NP_Error> where F: FnMut(NP_Size_Data) -> bool {
+        self.buffer.maybe_compact(new_capacity, callback)
+    }
+    /// See [`NP_Buffer::compact_if`](struct.NP_Buffer.html#method.compact_if)
+    #[inline(always)]
+    pub fn compact_if<F>(&mut self, predicate: F) -> Result<bool, NP_Error> where F: Fn(&NP_Size_Data) -> bool {
+        self.buffer.compact_if(predicate)
+    }
+    /// See [`NP_Buffer::compact`](struct.NP_Buffer.html#method.compact)
+    #[inline(always)]
+    pub fn compact<'compact>(&mut self, new_capacity: Option<usize>) -> Result<(), NP_Error> {
+        self.buffer.compact(new_capacity)
+    }
+    /// See [`NP_Buffer::compact_and_report`](struct.NP_Buffer.html#method.compact_and_report)
+    #[inline(always)]
+    pub fn compact_and_report(&mut self) -> Result<NP_Size_Data, NP_Error> {
+        self.buffer.compact_and_report()
+    }
+    /// See [`NP_Buffer::compact_into`](struct.NP_Buffer.html#method.compact_into)
+    #[inline(always)]
+    pub fn compact_into(&mut self, new_capacity: Option<usize>) -> Result<NP_Buffer, NP_Error> {
+        self.buffer.compact_into(new_capacity)
+    }
+    /// See [`NP_Buffer::graft`](struct.NP_Buffer.html#method.graft)
+    #[inline(always)]
+    pub fn graft(&mut self, path: &[&str], sub: &NP_Buffer) -> Result<(), NP_Error> {
+        self.buffer.graft(path, sub)
+    }
+    /// See [`NP_Buffer::set_max_data_length`](struct.NP_Buffer.html#method.set_max_data_length)
+    #[inline(always)]
+    pub fn set_max_data_length(&mut self, len: usize) {
+        self.buffer.set_max_data_length(len)
+    }
+    /// See [`NP_Buffer::set_max_depth`](struct.NP_Buffer.html#method.set_max_depth)
+    #[inline(always)]
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.buffer.set_max_depth(depth)
+    }
+    /// See [`NP_Buffer::shrink_to_fit`](struct.NP_Buffer.html#method.shrink_to_fit)
+    #[inline(always)]
+    pub fn shrink_to_fit(&mut self) {
+        self.buffer.shrink_to_fit()
+    }
+    /// See [`NP_Buffer::reset`](struct.NP_Buffer.html#method.reset)
+    #[inline(always)]
+    pub fn reset(&mut self) -> Result<(), NP_Error> {
+        self.buffer.reset()
+    }
+    /// See [`NP_Buffer::enable_access_tracking`](struct.NP_Buffer.html#method.enable_access_tracking)
+    #[inline(always)]
+    pub fn enable_access_tracking(&mut self) {
+        self.buffer.enable_access_tracking()
+    }
+}
+
+impl<'alloc, A: NP_Allocator> Deref for NP_Buffer_In<'alloc, A> {
+    type Target = NP_Buffer;
+    fn deref(&self) -> &NP_Buffer {
+        &self.buffer
     }
 }
 
@@ -81,10 +565,19 @@ impl NP_Buffer {
         NP_Buffer {
             cursor: NP_Cursor::new(memory.root, 0, 0),
             mutable: memory.is_mutable,
-            memory: memory
+            memory: memory,
+            owner: NEXT_BUFFER_ID.fetch_add(1, Ordering::Relaxed),
+            generation: 0,
+            access_counts: None,
+            schema_tag: None
         }
     }
 
+    #[doc(hidden)]
+    pub fn _set_schema_tag(&mut self, tag: Option<u32>) {
+        self.schema_tag = tag;
+    }
+
     /// Copy an object at the provided path and all it's children into JSON.
     /// 
     /// ```
@@ -126,12 +619,174 @@ impl NP_Buffer {
 
     }
 
+    /// Copy the object at the cursor and all it's children into JSON, using the provided options to decide how unset values are handled.
+    ///
+    /// `json_encode` always exports the schema default where there isn't data, this method lets you tell "set to default" apart from "absent".
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::buffer::NP_JSON_Opts;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         age: u8({default: 20}),
+    ///         name: string()
+    ///     }})
+    /// "#)?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "Jeb Kermin")?;
+    ///
+    /// // same as json_encode
+    /// assert_eq!(r#"{"value":{"age":20,"name":"Jeb Kermin"}}"#, new_buffer.to_json_with(NP_JSON_Opts::default())?.stringify());
+    /// // absent values as null
+    /// assert_eq!(r#"{"value":{"age":null,"name":"Jeb Kermin"}}"#, new_buffer.to_json_with(NP_JSON_Opts { include_absent: true, absent_as_null: true })?.stringify());
+    /// // absent values left out
+    /// assert_eq!(r#"{"value":{"name":"Jeb Kermin"}}"#, new_buffer.to_json_with(NP_JSON_Opts { include_absent: false, absent_as_null: false })?.stringify());
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn to_json_with(&self, opts: NP_JSON_Opts) -> Result<NP_JSON, NP_Error> {
+
+        let mut json_map = JSMAP::new();
+
+        json_map.insert(String::from("value"), NP_Cursor::json_encode_with(0, &self.cursor, &self.memory, &opts));
+
+        Ok(NP_JSON::Dictionary(json_map))
+    }
+
+    /// Walk every value in the buffer, calling the visitor for each one.
+    ///
+    /// This reads values straight out of the buffer, nothing is copied into an intermediate structure first.  Values that aren't set are passed to the visitor as their schema default, or `visit_none` if there is no default.
+    ///
+    /// See the [`visitor`](../visitor/index.html) module for the callbacks and a JSON building reference implementation.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::visitor::NP_Visitor;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         age: u8(),
+    ///         tags: list({of: string()})
+    ///     }})
+    /// "#)?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["age"], 20u8)?;
+    /// new_buffer.set(&["tags", "0"], "hello")?;
+    /// new_buffer.set(&["tags", "1"], "world")?;
+    ///
+    /// #[derive(Default)]
+    /// struct Counter { strings: usize, total: usize }
+    ///
+    /// impl NP_Visitor for Counter {
+    ///     fn visit_string(&mut self, value: &str) -> Result<(), NP_Error> {
+    ///         self.strings += 1;
+    ///         self.total += value.len();
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mut counter = Counter::default();
+    /// new_buffer.visit(&mut counter)?;
+    ///
+    /// assert_eq!(counter.strings, 2);
+    /// assert_eq!(counter.total, 10);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn visit<V: NP_Visitor>(&self, visitor: &mut V) -> Result<(), NP_Error> {
+        visit_cursor(0, &self.cursor, &self.memory, visitor)
+    }
+
+    /// Hash the values in the buffer instead of its bytes.
+    ///
+    /// Values are hashed in schema order with murmurhash3, map keys are sorted first.  Two buffers from the same schema with the same values give the same hash, even if the values were set in a different order, one of them wasn't compacted or they were built by different versions of the library.  Unset values hash the same as their schema default, and structs, lists and maps that had all of their values deleted hash the same as ones that were never set.
+    ///
+    /// The hash is relative to the buffer cursor.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), tags: map({value: u8()})}})")?;
+    ///
+    /// let mut a = factory.new_buffer(None);
+    /// a.set(&["name"], "Jeb Kermin")?;
+    /// a.set(&["tags", "one"], 1u8)?;
+    /// a.set(&["tags", "two"], 2u8)?;
+    ///
+    /// let mut b = factory.new_buffer(None);
+    /// b.set(&["tags", "two"], 2u8)?;
+    /// b.set(&["name"], "Bill")?;
+    /// b.set(&["tags", "one"], 1u8)?;
+    /// b.set(&["name"], "Jeb Kermin")?;
+    ///
+    /// assert_ne!(a.read_bytes(), b.read_bytes());
+    /// assert_eq!(a.logical_hash()?, b.logical_hash()?);
+    ///
+    /// b.set(&["tags", "two"], 3u8)?;
+    /// assert_ne!(a.logical_hash()?, b.logical_hash()?);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn logical_hash(&self) -> Result<u64, NP_Error> {
+        let mut visitor = NP_Hash_Visitor::default();
+        self.visit(&mut visitor)?;
+        let bytes = visitor.into_bytes();
+
+        let high = murmurhash3_x86_32(&bytes, SEED) as u64;
+        let low = murmurhash3_x86_32(&bytes, !SEED) as u64;
+
+        Ok((high << 32) | low)
+    }
+
+    /// Export the values in the buffer as MessagePack bytes.
+    ///
+    /// The values are walked the same way as `json_encode`, so unset values are exported as their schema default or `nil`.  Structs and maps become msgpack maps, lists and tuples become arrays and bytes use the msgpack binary type.  See [`NP_Msgpack_Visitor`](../visitor/struct.NP_Msgpack_Visitor.html) for how the other types are written.
+    ///
+    /// The export is relative to the buffer cursor.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), tags: list({of: u16()})}})")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "Bob")?;
+    /// new_buffer.set(&["tags", "0"], 1u16)?;
+    /// new_buffer.set(&["tags", "1"], 300u16)?;
+    ///
+    /// assert_eq!(new_buffer.to_msgpack()?, vec![
+    ///     0x82, // map with 2 pairs
+    ///     0xa4, b'n', b'a', b'm', b'e', 0xa3, b'B', b'o', b'b',
+    ///     0xa4, b't', b'a', b'g', b's', 0x92, 0x01, 0xcd, 0x01, 0x2c
+    /// ]);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, NP_Error> {
+        let mut visitor = NP_Msgpack_Visitor::new();
+        self.visit(&mut visitor)?;
+        Ok(visitor.into_bytes())
+    }
+
     /// Finish the buffer.
     /// 
     /// If the buffer is an onwed type typically opened with `.open_buffer` or created with `.new_empty` you will get the bytes of the buffer returned from this method.
     /// 
     /// If the buffer is a ref type typically opened with `.open_buffer_ref` or `.open_buffer_ref_mut` this method returns an empty `Vec<u8>`.
     /// 
+    /// Buffers created with `NP_Factory::new_buffer_tagged` or `open_buffer_tagged` get the 4 byte schema id in front of the bytes, open them again with `open_buffer_tagged`.
+    /// 
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
@@ -150,66 +805,292 @@ impl NP_Buffer {
     /// ```
     /// 
     pub fn finish(self) -> NP_Finished_Buffer {
-        NP_Finished_Buffer { memory: self.memory }
-    }
-
-    /// Read the bytes of the buffer immutably.  No touching!
-    /// 
-    pub fn read_bytes(&self) -> &[u8] {
-        self.memory.read_bytes()
+        NP_Finished_Buffer { memory: self.memory, schema_tag: self.schema_tag }
     }
 
-    /// Move buffer cursor to new location.  Cursors can only be moved into children.  If you need to move up reset the cursor to root, then move back down to the desired level.
+    /// Freeze the buffer into a read only [`NP_BufferReader`](struct.NP_BufferReader.html).
     /// 
-    /// This also creates objects/collections along the path as needed.  If you attempt to move into a path that doesn't exist, this method will return `false`.  Otherwise it will return `true` of the path requested exists or is something that can be made to exist.
+    /// The reader can't be changed, not even by accident, and it can be cloned cheaply and shared between threads.  The cursor position is kept.  Buffers opened from borrowed bytes are copied into owned memory, so the reader doesn't depend on the original bytes.  Access tracking is turned off.
     /// 
-    pub fn move_cursor(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
-
-        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), self.mutable, false, path)?;
-
-        let cursor = if let Some(x) = value_cursor {
-            x
-        } else {
-            return Ok(false);
-        };
-
-        self.cursor = cursor;
-
-        Ok(true)
-    }
-
-    /// Moves cursor position to root of buffer, the default.
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::buffer::NP_BufferReader;
     /// 
-    pub fn cursor_to_root(&mut self) {
-        self.cursor = NP_Cursor::new(self.memory.root, 0, 0);
-    }
-
-    /// Set the max value allowed for the specific data type at the given key.
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), tags: list({of: string()})}})")?;
     /// 
-    /// String & Byte types only work if a `size` property is set in the schema.
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "config")?;
+    /// new_buffer.set(&["tags", "0"], "shared")?;
     /// 
-    /// Will return `true` if a value was found and succesfully set, `false` otherwise.
+    /// let reader: NP_BufferReader = new_buffer.freeze();
+    /// let copy = reader.clone();
     /// 
-    /// *WARNING* If you call this on a collection (Map, Tuple, List, or Table) ALL children will be overwritten/set.  The method is recursive, so this will hit *all* children, including nested children.
+    /// assert_eq!(reader.get::<&str>(&["name"])?, Some("config"));
+    /// assert_eq!(copy.get_length(&["tags"])?, Some(1));
     /// 
-    /// When this is applied to a `string` data type, only ascii values are supported.
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
     /// 
+    pub fn freeze(self) -> NP_BufferReader {
+        let memory = self.memory.into_read_only();
+        NP_BufferReader {
+            buffer: Arc::new(NP_Buffer {
+                memory,
+                mutable: false,
+                cursor: self.cursor,
+                owner: self.owner,
+                generation: self.generation,
+                access_counts: None,
+                schema_tag: self.schema_tag
+            })
+        }
+    }
+
+    /// Close the buffer and copy its bytes into an existing `Vec<u8>`.
+    ///
+    /// The vec is cleared first, then filled with only the bytes in use by the buffer, after the schema id if the buffer is tagged.  Reusing the same vec for many buffers avoids an allocation for each buffer once the vec has grown large enough.
+    ///
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// 
-    /// let factory: NP_Factory = NP_Factory::new(r#"
-    ///     tuple({
-    ///         sorted: true,
-    ///         values: [string({size: 10}), u32()]
-    ///     })
-    /// "#)?;
-    /// 
-    /// let mut low_buffer = factory.new_buffer(None);
-    /// // set all types to minimum value
-    /// low_buffer.set_min(&[])?;
-    /// // get bytes
-    /// let low_bytes: Vec<u8> = low_buffer.finish().bytes();
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("string()")?;
+    ///
+    /// let mut out: Vec<u8> = Vec::new();
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&[], "hello, world")?;
+    /// new_buffer.close_into(&mut out);
+    /// assert_eq!(out.len(), 22);
+    ///
+    /// // the vec is reused, nothing from the last buffer is left behind
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&[], "hello")?;
+    /// new_buffer.close_into(&mut out);
+    /// assert_eq!(out, [0, 0, 0, 0, 0, 6, 0, 0, 0, 5, 104, 101, 108, 108, 111].to_vec());
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn close_into(self, out: &mut Vec<u8>) {
+        out.clear();
+        if let Some(tag) = self.schema_tag {
+            out.extend_from_slice(&tag.to_be_bytes());
+        }
+        out.extend_from_slice(self.memory.read_data());
+    }
+
+    /// Close the buffer and get its bytes with a 4 byte CRC32 checksum appended to the end.
+    ///
+    /// The checksum is big endian and covers only the bytes in use by the buffer.  Open the bytes with `NP_Factory::open_buffer_checked` to verify and strip the checksum, opening them with `open_buffer` will not work.
+    ///
+    /// Tagged buffers get the schema id in front of the bytes like `finish` does, the checksum covers the id too.  Open those bytes with `NP_Factory::open_buffer_tagged_checked`.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("string()")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&[], "hello")?;
+    /// let bytes = new_buffer.close_with_checksum();
+    /// assert_eq!(bytes.len(), 15 + 4);
+    ///
+    /// let buffer = factory.open_buffer_checked(bytes)?;
+    /// assert_eq!(buffer.get::<&str>(&[])?, Some("hello"));
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn close_with_checksum(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.memory.read_data().len() + 8);
+        if let Some(tag) = self.schema_tag {
+            bytes.extend_from_slice(&tag.to_be_bytes());
+        }
+        bytes.extend_from_slice(self.memory.read_data());
+        let checksum = crc32(&bytes);
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+        bytes
+    }
+
+    /// Read the bytes of the buffer immutably.  No touching!
+    /// 
+    /// This borrows the bytes in the buffer that are in use, the unused space at the end of buffers created with `new_buffer_ref_mut` or `open_buffer_ref_mut` isn't included.  Unlike `finish` the buffer isn't consumed, so it's useful to inspect or hash the buffer while you keep using it.
+    ///
+    /// Nothing is copied, so this is the cheapest way to write a buffer to a socket or file without giving up the buffer.  The slice borrows the buffer, so it has to be dropped before the buffer can be changed again with `set`, `del` or any other mutating method.  Read the bytes again after a change to get the new contents.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("string()")?;
+    /// 
+    /// let mut bytes = [0u8; 100];
+    /// let mut new_buffer = factory.new_buffer_ref_mut(&mut bytes);
+    /// new_buffer.set(&[], "hello")?;
+    /// 
+    /// assert_eq!(new_buffer.read_bytes().len(), new_buffer.data_length());
+    /// assert_eq!(new_buffer.read_bytes(), &[0, 0, 0, 0, 0, 6, 0, 0, 0, 5, 104, 101, 108, 108, 111]);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn read_bytes(&self) -> &[u8] {
+        self.memory.read_data()
+    }
+
+    /// Write the buffer to a `std::io::Write` destination, like a file or socket.
+    /// 
+    /// The bytes in use are written after a 4 byte big endian length, so many buffers can be written to the same stream and read back one at a time with `NP_Factory::read_from`.  Returns the total number of bytes written, including the length.
+    /// 
+    /// Tagged buffers write the schema id in front of the bytes like `finish` does, the length includes the id.  Read them back with `NP_Factory::read_from_tagged`.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<usize> {
+        let bytes = self.memory.read_data();
+        let tag_len = if self.schema_tag.is_some() { 4 } else { 0 };
+        // buffers can't be larger than u32::MAX
+        w.write_all(&((bytes.len() + tag_len) as u32).to_be_bytes())?;
+        if let Some(tag) = self.schema_tag {
+            w.write_all(&tag.to_be_bytes())?;
+        }
+        w.write_all(bytes)?;
+        Ok(bytes.len() + tag_len + 4)
+    }
+
+    /// Move buffer cursor to new location.  Cursors can only be moved into children.  If you need to move up reset the cursor to root, then move back down to the desired level.
+    /// 
+    /// This also creates objects/collections along the path as needed.  If you attempt to move into a path that doesn't exist, this method will return `false`.  Otherwise it will return `true` of the path requested exists or is something that can be made to exist.
+    /// 
+    pub fn move_cursor(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
+
+        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), self.mutable, false, path)?;
+
+        let cursor = if let Some(x) = value_cursor {
+            x
+        } else {
+            return Ok(false);
+        };
+
+        self.cursor = cursor;
+
+        Ok(true)
+    }
+
+    /// Moves cursor position to root of buffer, the default.
+    /// 
+    pub fn cursor_to_root(&mut self) {
+        self.cursor = NP_Cursor::new(self.memory.root, 0, 0);
+    }
+
+    /// Get a handle to the value at the provided path.  The handle can be used with `get_at` and `set_at` to read and write values below it without selecting the path from the root again.
+    /// 
+    /// The path is relative to the buffer cursor.  This doesn't create anything in the buffer, so `None` is returned if the path doesn't exist in the schema or hasn't been created in the buffer yet.
+    /// 
+    /// Handles only work with the buffer they came from.  They're invalidated when the buffer is compacted or reset and by anything that can move or remove values, like `del`, `clear_collection`, `move_value`, `swap`, `set_list`, `set_map` and the list and map editing methods.  Using them afterwards will return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         user: struct({fields: {
+    ///             name: string(),
+    ///             age: u8()
+    ///         }})
+    ///     }})
+    /// "#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// assert!(new_buffer.cursor_at(&["user"])?.is_none());
+    /// 
+    /// new_buffer.set(&["user", "name"], "Bob")?;
+    /// 
+    /// let user = new_buffer.cursor_at(&["user"])?.unwrap();
+    /// new_buffer.set_at(&user, &["name"], "Jeb Kermin")?;
+    /// new_buffer.set_at(&user, &["age"], 30u8)?;
+    /// 
+    /// assert_eq!(new_buffer.get_at::<&str>(&user, &["name"])?, Some("Jeb Kermin"));
+    /// assert_eq!(new_buffer.get::<u8>(&["user", "age"])?, Some(30));
+    /// 
+    /// // handles can't be used after values are removed
+    /// new_buffer.del(&["user", "age"])?;
+    /// assert!(new_buffer.get_at::<u8>(&user, &["age"]).is_err());
+    /// 
+    /// // or with another buffer
+    /// let user = new_buffer.cursor_at(&["user"])?.unwrap();
+    /// let copy = new_buffer.clone();
+    /// assert!(copy.get_at::<&str>(&user, &["name"]).is_err());
+    /// assert_eq!(new_buffer.get_at::<&str>(&user, &["name"])?, Some("Jeb Kermin"));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn cursor_at(&self, path: &[&str]) -> Result<Option<NP_Cursor_Handle>, NP_Error> {
+        match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(cursor) => Ok(Some(NP_Cursor_Handle { cursor: cursor, owner: self.owner, generation: self.generation })),
+            None => Ok(None)
+        }
+    }
+
+    /// Get a value at a path relative to a cursor handle.
+    /// 
+    /// Works exactly like `.get()` except the path starts at the handle instead of the buffer cursor.
+    /// 
+    pub fn get_at<'get, X: 'get>(&'get self, handle: &NP_Cursor_Handle, path: &[&str]) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
+        self._get(self.handle_cursor(handle)?, path)
+    }
+
+    /// Set a value at a path relative to a cursor handle.
+    /// 
+    /// Works exactly like `.set()` except the path starts at the handle instead of the buffer cursor.
+    /// 
+    pub fn set_at<'set, X: 'set>(&mut self, handle: &NP_Cursor_Handle, path: &[&str], value: X) -> Result<bool, NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> {
+        let cursor = self.handle_cursor(handle)?;
+        self._set(cursor, path, value)
+    }
+
+    fn handle_cursor(&self, handle: &NP_Cursor_Handle) -> Result<NP_Cursor, NP_Error> {
+        if handle.owner != self.owner {
+            return Err(NP_Error::new("Cursor handle belongs to a different buffer!"));
+        }
+        if handle.generation != self.generation {
+            return Err(NP_Error::new("Cursor handle is no longer valid, the buffer has been compacted or values have been moved or removed!"));
+        }
+        Ok(handle.cursor.clone())
+    }
+
+    /// Set the max value allowed for the specific data type at the given key.
+    /// 
+    /// String & Byte types only work if a `size` property is set in the schema.
+    /// 
+    /// Will return `true` if a value was found and succesfully set, `false` otherwise.
+    /// 
+    /// *WARNING* If you call this on a collection (Map, Tuple, List, or Table) ALL children will be overwritten/set.  The method is recursive, so this will hit *all* children, including nested children.
+    /// 
+    /// When this is applied to a `string` data type, only ascii values are supported.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     tuple({
+    ///         sorted: true,
+    ///         values: [string({size: 10}), u32()]
+    ///     })
+    /// "#)?;
+    /// 
+    /// let mut low_buffer = factory.new_buffer(None);
+    /// // set all types to minimum value
+    /// low_buffer.set_min(&[])?;
+    /// // get bytes
+    /// let low_bytes: Vec<u8> = low_buffer.finish().bytes();
     /// 
     /// let mut high_buffer = factory.new_buffer(None);
     /// // set all types to max value
@@ -230,6 +1111,47 @@ impl NP_Buffer {
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
+    /// ## Range Scans
+    /// With a sorted tuple the buffer bytes can be used as keys in a sorted key/value store.  To scan every key that shares a prefix, set the prefix values and then call `set_min` or `set_max` on the rest of the tuple.  This gives the smallest and largest possible keys under that prefix.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     tuple({
+    ///         sorted: true,
+    ///         values: [u32(), u32()]
+    ///     })
+    /// "#)?;
+    /// 
+    /// // all keys with 5 in the first slot
+    /// let mut lower = factory.new_buffer(None);
+    /// lower.set(&["0"], 5u32)?;
+    /// lower.set_min(&["1"])?;
+    /// let lower_bytes: Vec<u8> = lower.finish().bytes();
+    /// 
+    /// let mut upper = factory.new_buffer(None);
+    /// upper.set(&["0"], 5u32)?;
+    /// upper.set_max(&["1"])?;
+    /// let upper_bytes: Vec<u8> = upper.finish().bytes();
+    /// 
+    /// let mut inside = factory.new_buffer(None);
+    /// inside.set(&["0"], 5u32)?;
+    /// inside.set(&["1"], 2000u32)?;
+    /// let inside_bytes: Vec<u8> = inside.finish().bytes();
+    /// 
+    /// let mut outside = factory.new_buffer(None);
+    /// outside.set(&["0"], 6u32)?;
+    /// outside.set(&["1"], 0u32)?;
+    /// let outside_bytes: Vec<u8> = outside.finish().bytes();
+    /// 
+    /// assert!(lower_bytes <= inside_bytes && inside_bytes <= upper_bytes);
+    /// assert!(outside_bytes > upper_bytes);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
     pub fn set_max(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
         if self.mutable == false {
             return Err(NP_Error::MemoryReadOnly)
@@ -244,6 +1166,50 @@ impl NP_Buffer {
         }
     }
 
+    /// Write the schema `default` into every value that has one and is currently unset.
+    ///
+    /// Starts at the root of the buffer and recurses through structs and tuples.  Lists and maps are never created by this method.
+    ///
+    /// This is useful when you need defaults physically present in the buffer, for example to keep byte-wise sorting consistent.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         name: string({default: "bob"}),
+    ///         age: u8({default: 20}),
+    ///         tags: list({of: string()})
+    ///     }})
+    /// "#)?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["age"], 30u8)?;
+    /// let before = new_buffer.data_length();
+    /// new_buffer.fill_defaults()?;
+    ///
+    /// // default was written into the buffer
+    /// assert!(new_buffer.data_length() > before);
+    /// assert_eq!(new_buffer.get::<&str>(&["name"])?, Some("bob"));
+    /// // existing values are not touched
+    /// assert_eq!(new_buffer.get::<u8>(&["age"])?, Some(30));
+    /// // collections are not created
+    /// assert_eq!(new_buffer.get_length(&["tags"])?, None);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn fill_defaults(&mut self) -> Result<(), NP_Error> {
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        let root = NP_Cursor::new(self.memory.root, 0, 0);
+
+        NP_Cursor::fill_defaults(0, root, &self.memory)
+    }
+
     /// Set the min value allowed for the specific data type at the given key.
     /// 
     /// String & Byte types only work if a `size` property is set in the schema.
@@ -290,6 +1256,8 @@ impl NP_Buffer {
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
+    /// See [`set_max`](#method.set_max) for using this to build range scan keys.
+    /// 
     pub fn set_min(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
 
         if self.mutable == false {
@@ -307,7 +1275,7 @@ impl NP_Buffer {
 
     /// Used to set scalar values inside the buffer.
     /// 
-    /// The type that you set with will be compared to the schema, if it doesn't match the schema the request will fail.
+    /// The type that you set with will be compared to the schema, if it doesn't match the schema the request will fail.  Use [`set_checked`](#method.set_checked) to compare the type before anything is written.
     /// 
     /// ```
     /// use no_proto::error::NP_Error;
@@ -331,23 +1299,22 @@ impl NP_Buffer {
     /// ```
     /// 
     pub fn set<'set, X: 'set>(&mut self, path: &[&str], value: X) -> Result<bool, NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> {
+        self._set(self.cursor.clone(), path, value)
+    }
+
+    fn _set<'set, X: 'set>(&mut self, from: NP_Cursor, path: &[&str], value: X) -> Result<bool, NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> {
 
         if self.mutable == false {
             return Err(NP_Error::MemoryReadOnly);
         }
 
-        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), self.mutable, false, path)?;
+        let value_cursor = NP_Cursor::select(&self.memory, from, self.mutable, false, path)?;
         match value_cursor {
             Some(x) => {
 
                 // type does not match schema
                 if X::type_idx().1 != self.memory.get_schema(x.schema_addr).i {
-                    let mut err = "TypeError: Attempted to set value for type (".to_owned();
-                    err.push_str(X::type_idx().0);
-                    err.push_str(") into schema of type (");
-                    err.push_str(self.memory.get_schema(x.schema_addr).i.into_type_idx().0);
-                    err.push_str(")\n");
-                    return Err(NP_Error::new(err));
+                    return Err(NP_Buffer::set_type_mismatch(X::type_idx().0, &self.memory.get_schema(x.schema_addr).i));
                 }
 
                 if x.parent_type == NP_Cursor_Parent::Tuple {
@@ -361,6 +1328,126 @@ impl NP_Buffer {
         }
     }
 
+    /// Set a scalar value after checking the type against the schema, without touching the buffer if it doesn't match.
+    /// 
+    /// `set` compares the type too, but only after it has selected into the buffer, so a mismatch can leave empty collections behind along the path.  This method looks up the schema first and returns an error naming the path and both types, like ``TypeError: `age` is uint16, got string``.  Paths that aren't in the schema are also an error instead of returning `false`.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {user: struct({fields: {age: u16()}})}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// assert!(new_buffer.set_checked(&["user", "age"], "thirty").is_err());
+    /// assert!(new_buffer.set_checked(&["user", "name"], "Bob").is_err());
+    /// // nothing was written
+    /// assert_eq!(new_buffer.data_length(), 6);
+    /// 
+    /// new_buffer.set_checked(&["user", "age"], 30u16)?;
+    /// assert_eq!(new_buffer.get::<u16>(&["user", "age"])?, Some(30));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn set_checked<'set, X: 'set>(&mut self, path: &[&str], value: X) -> Result<(), NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly);
+        }
+
+        let display_path = if path.len() == 0 { String::from("root") } else { format!("`{}`", path.join(".")) };
+
+        let schema_addr = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Err(NP_Error::new(format!("TypeError: {} isn't in the schema", display_path)))
+        };
+
+        let schema_type = self.memory.get_schema(schema_addr).i;
+
+        if X::type_idx().1 != schema_type {
+            return Err(NP_Error::new(format!("TypeError: {} is {}, got {}", display_path, schema_type.into_type_idx().0, X::type_idx().0)));
+        }
+
+        if self._set(self.cursor.clone(), path, value)? {
+            Ok(())
+        } else {
+            Err(NP_Error::new(format!("No value found at {}!", display_path)))
+        }
+    }
+
+    /// Set many values in one call.
+    /// 
+    /// This works the same as calling `.set()` for each entry in order, including creating the paths as needed.  Paths that can't be found are skipped just like `.set()` would return `false` for them.
+    /// 
+    /// Entries that share the same parent with the entry before them reuse the parent, so the common prefix is only selected once.  Setting many columns of the same struct in a row is much faster this way.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         user: struct({fields: {
+    ///             first: string(),
+    ///             last: string(),
+    ///             email: string()
+    ///         }}),
+    ///         title: string()
+    ///     }})
+    /// "#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set_many(&[
+    ///     (&["user", "first"], "Jeb"),
+    ///     (&["user", "last"], "Kermin"),
+    ///     (&["user", "email"], "jeb@kerbal.space"),
+    ///     (&["title"], "Pilot")
+    /// ])?;
+    /// 
+    /// assert_eq!(new_buffer.get::<&str>(&["user", "last"])?, Some("Kermin"));
+    /// assert_eq!(new_buffer.get::<&str>(&["title"])?, Some("Pilot"));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn set_many<'set, X: 'set>(&mut self, entries: &[(&[&str], X)]) -> Result<(), NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> + Clone {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly);
+        }
+
+        // parent path and cursor of the previous entry
+        let mut parent: Option<(&[&str], Option<NP_Cursor>)> = None;
+
+        for (path, value) in entries.iter() {
+
+            if path.len() == 0 {
+                self._set(self.cursor.clone(), path, value.clone())?;
+                continue;
+            }
+
+            let prefix = &path[..(path.len() - 1)];
+
+            let reuse = match &parent {
+                Some((parent_path, _)) => *parent_path == prefix,
+                None => false
+            };
+
+            if reuse == false {
+                let parent_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, prefix)?;
+                parent = Some((prefix, parent_cursor));
+            }
+
+            if let Some((_, Some(parent_cursor))) = &parent {
+                let parent_cursor = parent_cursor.clone();
+                self._set(parent_cursor, &path[(path.len() - 1)..], value.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Set value with JSON
     /// 
     /// This works with all types including portals.
@@ -420,24 +1507,512 @@ impl NP_Buffer {
         }
     }
 
-    
-    /// Get an iterator for a collection
+    /// Merge a parsed JSON value into the buffer at the given path, creating the path as needed.
     /// 
+    /// If `apply_null` is `true`, `null` values in the JSON will delete the matching values in the buffer.  Otherwise `null` values are ignored.
+    /// 
+    /// This is useful for patching a nested object from an external JSON payload.
     /// 
-    /// ## List Example
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::NP_Size_Data;
+    /// use no_proto::json_flex::json_decode;
     /// 
-    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         user: struct({fields: {
+    ///             name: string(),
+    ///             email: string()
+    ///         }})
+    ///     }})
+    /// "#)?;
     /// 
     /// let mut new_buffer = factory.new_buffer(None);
-    /// // set value at 1 index
-    /// new_buffer.set(&["1"], "hello")?;
-    /// // set value at 4 index
-    /// new_buffer.set(&["4"], "world")?;
-    /// // push value onto the end
+    /// new_buffer.set(&["user", "email"], "jeb@kermin.com")?;
+    /// 
+    /// let patch = json_decode(String::from(r#"{"name": "Jeb Kermin", "email": null}"#))?;
+    /// new_buffer.set_json(&["user"], &patch, true)?;
+    /// 
+    /// assert_eq!(new_buffer.get::<&str>(&["user", "name"])?, Some("Jeb Kermin"));
+    /// assert_eq!(new_buffer.get::<&str>(&["user", "email"])?, None);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn set_json(&mut self, path: &[&str], json: &NP_JSON, apply_null: bool) -> Result<(), NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        // nulls delete values
+        if apply_null {
+            self.generation += 1;
+        }
+
+        if apply_null && *json == NP_JSON::Null {
+            self.del(path)?;
+            return Ok(())
+        }
+
+        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, path)?;
+        match value_cursor {
+            Some(x) => {
+                NP_Cursor::set_from_json(0, apply_null, x, &self.memory, &Box::new(json.clone()))
+            }
+            None => Err(NP_Error::new("No value found at the provided path!"))
+        }
+    }
+
+    /// Parse a JSON string and merge it into the buffer at the given path.
+    ///
+    /// This is `json_decode` followed by [`set_json`](#method.set_json), parse errors include the byte offset where the JSON went wrong.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u8()}})")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set_json_str(&[], r#"{"name": "Jeb Kermin", "age": 30}"#, true)?;
+    ///
+    /// assert_eq!(new_buffer.get::<&str>(&["name"])?, Some("Jeb Kermin"));
+    /// assert_eq!(new_buffer.get::<u8>(&["age"])?, Some(30));
+    ///
+    /// assert!(new_buffer.set_json_str(&[], r#"{"name": tru}"#, true).is_err());
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn set_json_str(&mut self, path: &[&str], json: &str, apply_null: bool) -> Result<(), NP_Error> {
+        let parsed = json_decode_bytes(json.as_bytes())?;
+        self.set_json(path, &parsed, apply_null)
+    }
+
+    /// Apply a changeset of path/value entries to the buffer.
+    ///
+    /// Each entry is merged in order with [`set_json`](#method.set_json), paths are created as needed and `null` values delete whatever is at the path.  Applying stops at the first entry that fails, the error says which entry it was.  Entries before the failed one stay applied.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::json_flex::NP_JSON;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u8(), tags: list({of: string()})}})")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["age"], 30u8)?;
+    ///
+    /// new_buffer.apply_patch(&[
+    ///     (vec![String::from("name")], NP_JSON::String(String::from("Jeb"))),
+    ///     (vec![String::from("tags"), String::from("1")], NP_JSON::String(String::from("pilot"))),
+    ///     (vec![String::from("age")], NP_JSON::Null)
+    /// ])?;
+    ///
+    /// assert_eq!(new_buffer.get::<&str>(&["name"])?, Some("Jeb"));
+    /// assert_eq!(new_buffer.get::<&str>(&["tags", "1"])?, Some("pilot"));
+    /// assert_eq!(new_buffer.get::<u8>(&["age"])?, None);
+    ///
+    /// // the first bad entry stops the patch
+    /// assert!(new_buffer.apply_patch(&[(vec![String::from("nope")], NP_JSON::Integer(2))]).is_err());
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn apply_patch(&mut self, patch: &[(Vec<String>, NP_JSON)]) -> Result<(), NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        for (index, (path, value)) in patch.iter().enumerate() {
+            let path: Vec<&str> = path.iter().map(|x| x.as_str()).collect();
+
+            if let Err(e) = self.set_json(&path, value, true) {
+                let reason = match e {
+                    NP_Error::Custom { message } => message,
+                    other => format!("{:?}", other)
+                };
+                return Err(NP_Error::new(format!("Patch entry {} ({}) failed: {}", index, path.join("/"), reason)))
+            }
+        }
+
+        Ok(())
+    }
+
+
+    /// Get a lazy iterator over the values of a list.
+    ///
+    /// The list is only found once and each step decodes the next item into `T`, much faster than calling `.get()` for every index.  Unset items in the list yield `Ok(None)`, an item that can't be decoded yields the error.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["0"], "hello")?;
+    /// new_buffer.set(&["2"], "world")?;
+    ///
+    /// let items: Vec<Option<&str>> = new_buffer.iter_list(&[])?.collect::<Result<_, NP_Error>>()?;
+    /// assert_eq!(items, vec![Some("hello"), None, Some("world")]);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn iter_list<'b, T: 'b>(&'b self, path: &[&str]) -> Result<impl Iterator<Item = Result<Option<T>, NP_Error>> + 'b, NP_Error> where T: NP_Value<'b> + NP_Scalar<'b> {
+
+        let memory = &self.memory;
+
+        let list_schema = match NP_Cursor::select(memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => memory.get_schema(x.schema_addr),
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        if list_schema.i != NP_TypeKeys::List {
+            return Err(NP_Error::new("Tried to iterate over a non list item!"));
+        }
+
+        let data = unsafe { &*(*list_schema.data as *const NP_Map_List_Data) };
+
+        // type does not match schema
+        if T::type_idx().1 != memory.get_schema(data.child).i {
+            return Err(NP_Buffer::type_mismatch(T::type_idx().0, &memory.get_schema(data.child).i));
+        }
+
+        let mut list_iter = match NP_Cursor::select(memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => Some(NP_List::new_iter(&x, memory, false, 0)),
+            None => None
+        };
+
+        Ok(core::iter::from_fn(move || {
+            let (_index, item) = list_iter.as_mut()?.step_iter(memory)?;
+            match item {
+                Some(cursor) => Some(T::into_value(&cursor, memory)),
+                None => Some(Ok(None))
+            }
+        }))
+    }
+
+    /// Get an iterator over the values of a list, starting at the last item and ending at index 0.
+    ///
+    /// Items come out in the reverse order of `iter_list`, unset items yield `Ok(None)` and an item that can't be decoded yields the error.  List items only link forward, so the location of each item is found with one walk when the iterator is made, values are decoded one at a time as they come out.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["0"], "first")?;
+    /// new_buffer.set(&["1"], "second")?;
+    /// new_buffer.set(&["3"], "latest")?;
+    ///
+    /// let items: Vec<Option<&str>> = new_buffer.iter_list_rev(&[])?.collect::<Result<_, NP_Error>>()?;
+    /// assert_eq!(items, vec![Some("latest"), None, Some("second"), Some("first")]);
+    ///
+    /// // most recent two
+    /// let recent: Vec<Option<&str>> = new_buffer.iter_list_rev(&[])?.filter(|x| !matches!(x, Ok(None))).take(2).collect::<Result<_, NP_Error>>()?;
+    /// assert_eq!(recent, vec![Some("latest"), Some("second")]);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn iter_list_rev<'b, T: 'b>(&'b self, path: &[&str]) -> Result<impl Iterator<Item = Result<Option<T>, NP_Error>> + 'b, NP_Error> where T: NP_Value<'b> + NP_Scalar<'b> {
+
+        let memory = &self.memory;
+
+        let list_schema = match NP_Cursor::select(memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => memory.get_schema(x.schema_addr),
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        if list_schema.i != NP_TypeKeys::List {
+            return Err(NP_Error::new("Tried to iterate over a non list item!"));
+        }
+
+        let data = unsafe { &*(*list_schema.data as *const NP_Map_List_Data) };
+
+        // type does not match schema
+        if T::type_idx().1 != memory.get_schema(data.child).i {
+            return Err(NP_Buffer::type_mismatch(T::type_idx().0, &memory.get_schema(data.child).i));
+        }
+
+        let mut items: Vec<(usize, NP_Cursor)> = Vec::new();
+
+        if let Some(x) = NP_Cursor::select(memory, self.cursor.clone(), false, false, path)? {
+            let mut list_iter = NP_List::new_iter(&x, memory, true, 0);
+            while let Some((index, item)) = list_iter.step_iter(memory) {
+                if let Some(cursor) = item {
+                    items.push((index, cursor));
+                }
+            }
+        }
+
+        // one past the index that comes out next
+        let mut index = match items.last() {
+            Some((last, _cursor)) => last + 1,
+            None => 0
+        };
+
+        Ok(core::iter::from_fn(move || {
+            if index == 0 {
+                return None;
+            }
+            index -= 1;
+
+            match items.last() {
+                Some((item_index, _cursor)) if *item_index == index => {
+                    let (_item_index, cursor) = items.pop()?;
+                    Some(T::into_value(&cursor, memory))
+                },
+                _ => Some(Ok(None))
+            }
+        }))
+    }
+
+    /// Get a lazy iterator over the keys and values of a map.
+    ///
+    /// Items come out in the order they're stored in the map's linked list, not sorted.  New keys are inserted at the head of the list, so the most recently added key is first.  Deleted items are skipped, a value that can't be decoded yields the error.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("map({value: string()})")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["foo"], "bar")?;
+    /// new_buffer.set(&["hello"], "world")?;
+    /// new_buffer.set(&["deleted"], "value")?;
+    /// new_buffer.del(&["deleted"])?;
+    ///
+    /// let items: Vec<(&str, Option<&str>)> = new_buffer.iter_map(&[])?.collect::<Result<_, NP_Error>>()?;
+    /// assert_eq!(items, vec![("hello", Some("world")), ("foo", Some("bar"))]);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn iter_map<'b, T: 'b>(&'b self, path: &[&str]) -> Result<impl Iterator<Item = Result<(&'b str, Option<T>), NP_Error>> + 'b, NP_Error> where T: NP_Value<'b> + NP_Scalar<'b> {
+
+        let memory = &self.memory;
+
+        let map_schema = match NP_Cursor::select(memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => memory.get_schema(x.schema_addr),
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        if map_schema.i != NP_TypeKeys::Map {
+            return Err(NP_Error::new("Tried to iterate over a non map item!"));
+        }
+
+        let data = unsafe { &*(*map_schema.data as *const NP_Map_List_Data) };
+
+        // type does not match schema
+        if T::type_idx().1 != memory.get_schema(data.child).i {
+            return Err(NP_Buffer::type_mismatch(T::type_idx().0, &memory.get_schema(data.child).i));
+        }
+
+        let mut map_iter = match NP_Cursor::select(memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => Some(NP_Map::new_iter(&x, memory)),
+            None => None
+        };
+
+        Ok(core::iter::from_fn(move || {
+            let map = map_iter.as_mut()?;
+            loop {
+                let (_key, item) = map.step_iter(memory)?;
+                let item_value = item.get_value(memory);
+                // deleted item
+                if item_value.get_addr_value() == 0 {
+                    continue;
+                }
+                let key = item_value.get_key(memory);
+                return Some(T::into_value(&item, memory).map(|value| (key, value)))
+            }
+        }))
+    }
+
+    /// Count the values in a list or map that match a predicate.
+    ///
+    /// The collection is walked once and each value is decoded into `T` and passed to `pred`, nothing is collected along the way.  Unset list items and deleted map items aren't passed to the predicate.  If the collection isn't in the buffer the count is `0`.
+    ///
+    /// Returns an error if the path isn't a list or map, if `T` doesn't match the type of the values, or if a value can't be decoded.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {scores: map({value: u32()}), tags: list({of: string()})}})")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["scores", "jeb"], 120u32)?;
+    /// new_buffer.set(&["scores", "bill"], 80u32)?;
+    /// new_buffer.set(&["scores", "bob"], 200u32)?;
+    /// new_buffer.set(&["tags", "0"], "red")?;
+    /// new_buffer.set(&["tags", "4"], "blue")?;
+    ///
+    /// assert_eq!(new_buffer.count_where(&["scores"], |score: &u32| *score > 100)?, 2);
+    /// assert_eq!(new_buffer.count_where(&["tags"], |tag: &&str| tag.starts_with('b'))?, 1);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn count_where<'b, T: 'b, F>(&'b self, path: &[&str], pred: F) -> Result<usize, NP_Error> where T: NP_Value<'b> + NP_Scalar<'b>, F: Fn(&T) -> bool {
+
+        let memory = &self.memory;
+
+        let collection_schema = match NP_Cursor::select(memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => memory.get_schema(x.schema_addr),
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        if collection_schema.i != NP_TypeKeys::List && collection_schema.i != NP_TypeKeys::Map {
+            return Err(NP_Error::new("Tried to count over a non list or map item!"));
+        }
+
+        let data = unsafe { &*(*collection_schema.data as *const NP_Map_List_Data) };
+
+        // type does not match schema
+        if T::type_idx().1 != memory.get_schema(data.child).i {
+            return Err(NP_Buffer::type_mismatch(T::type_idx().0, &memory.get_schema(data.child).i));
+        }
+
+        let collection = match NP_Cursor::select(memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => x,
+            None => return Ok(0)
+        };
+
+        let mut count = 0usize;
+
+        if collection_schema.i == NP_TypeKeys::List {
+            let mut list_iter = NP_List::new_iter(&collection, memory, true, 0);
+            while let Some((_index, item)) = list_iter.step_iter(memory) {
+                if let Some(cursor) = item {
+                    if let Some(value) = T::into_value(&cursor, memory)? {
+                        if pred(&value) { count += 1; }
+                    }
+                }
+            }
+        } else {
+            let mut map_iter = NP_Map::new_iter(&collection, memory);
+            while let Some((_key, item)) = map_iter.step_iter(memory) {
+                // deleted item
+                if item.get_value(memory).get_addr_value() == 0 {
+                    continue;
+                }
+                if let Some(value) = T::into_value(&item, memory)? {
+                    if pred(&value) { count += 1; }
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Get a value under every item of a list or map, like selecting `users.*.name`.
+    ///
+    /// The collection at `collection_path` is walked once and `sub_path` is selected from each item, the value is decoded like `get` does, so values that aren't set give the schema default or `None`.  Each value comes with the key of its map item or the index of its list item.  Unset list items and deleted map items are skipped.  If the collection isn't in the buffer the result is empty.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     users: list({of: struct({fields: {name: string(), age: u8()}})})
+    /// }})"#)?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["users", "0", "name"], "Jeb")?;
+    /// new_buffer.set(&["users", "2", "name"], "Bill")?;
+    /// new_buffer.set(&["users", "3", "age"], 20u8)?;
+    ///
+    /// let names = new_buffer.get_all::<&str>(&["users"], &["name"])?;
+    /// assert_eq!(names, vec![
+    ///     (String::from("0"), Some("Jeb")),
+    ///     (String::from("2"), Some("Bill")),
+    ///     (String::from("3"), None)
+    /// ]);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn get_all<'b, T: 'b>(&'b self, collection_path: &[&str], sub_path: &[&str]) -> Result<Vec<(String, Option<T>)>, NP_Error> where T: NP_Value<'b> + NP_Scalar<'b> {
+
+        let memory = &self.memory;
+
+        let collection_schema = match NP_Cursor::select(memory, self.cursor.clone(), false, true, collection_path)? {
+            Some(x) => memory.get_schema(x.schema_addr),
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        if collection_schema.i != NP_TypeKeys::List && collection_schema.i != NP_TypeKeys::Map {
+            return Err(NP_Error::new("Tried to select all items of a non list or map item!"));
+        }
+
+        // check the sub path against the schema once, instead of for every item
+        let mut query_path: Vec<&str> = collection_path.to_vec();
+        query_path.push("0");
+        query_path.extend_from_slice(sub_path);
+
+        let value_schema = match NP_Cursor::select(memory, self.cursor.clone(), false, true, &query_path)? {
+            Some(x) => memory.get_schema(x.schema_addr),
+            None => return Err(NP_Error::new("No value found at the provided sub path!"))
+        };
+
+        // type does not match schema
+        if T::type_idx().1 != value_schema.i {
+            return Err(NP_Buffer::type_mismatch(T::type_idx().0, &value_schema.i));
+        }
+
+        let collection = match NP_Cursor::select(memory, self.cursor.clone(), false, false, collection_path)? {
+            Some(x) => x,
+            None => return Ok(Vec::new())
+        };
+
+        let mut results: Vec<(String, Option<T>)> = Vec::new();
+
+        if collection_schema.i == NP_TypeKeys::List {
+            let mut list_iter = NP_List::new_iter(&collection, memory, true, 0);
+            while let Some((index, item)) = list_iter.step_iter(memory) {
+                if let Some(cursor) = item {
+                    results.push((format!("{}", index), self._get_all_item(cursor, sub_path)?));
+                }
+            }
+        } else {
+            let mut map_iter = NP_Map::new_iter(&collection, memory);
+            while let Some((key, item)) = map_iter.step_iter(memory) {
+                // deleted item
+                if item.get_value(memory).get_addr_value() == 0 {
+                    continue;
+                }
+                results.push((key.to_owned(), self._get_all_item(item, sub_path)?));
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Get an iterator for a collection
+    ///
+    /// 
+    /// ## List Example
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // set value at 1 index
+    /// new_buffer.set(&["1"], "hello")?;
+    /// // set value at 4 index
+    /// new_buffer.set(&["4"], "world")?;
+    /// // push value onto the end
     /// new_buffer.list_push(&[], "!")?;
     /// 
     /// // get iterator of root (list item)
@@ -660,12 +2235,7 @@ impl NP_Buffer {
 
                 // type does not match schema
                 if X::type_idx().1 != of_schema.i {
-                    let mut err = "TypeError: Attempted to set value for type (".to_owned();
-                    err.push_str(X::type_idx().0);
-                    err.push_str(") into schema of type (");
-                    err.push_str(of_schema.i.into_type_idx().0);
-                    err.push_str(")\n");
-                    return Err(NP_Error::new(err));
+                    return Err(NP_Buffer::set_type_mismatch(X::type_idx().0, &of_schema.i));
                 }
             },
             _ => return Err(NP_Error::new("Trying to push onto non list item!"))
@@ -680,387 +2250,2347 @@ impl NP_Buffer {
         }
     }
 
-
-    /// Get length of String, Bytes, Table, Tuple, List or Map Type
-    /// 
-    /// If the type found at the path provided does not support length operations, you'll get `None`.
+    /// Replace the list at a path with the values in a slice.
     /// 
-    /// If there is no value at the path provodid, you will get `None`.
+    /// Any existing list at the path is cleared first, then the list and all of its items are allocated in a single pass.  This is much faster than setting each index one at a time since the list never has to be walked.
     /// 
-    /// If an item is found and it's length is zero, you can expect `Some(0)`.
+    /// The type in the slice must match the schema of the list items.
     /// 
-    /// ## String Example
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::NP_Size_Data;
     /// 
-    /// let factory: NP_Factory = NP_Factory::new("string()")?;
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {tags: list({of: string()})}})")?;
     /// 
     /// let mut new_buffer = factory.new_buffer(None);
-    /// // set initial value
-    /// new_buffer.set(&[], "hello")?;
-    /// // get length of value at root (String)
-    /// assert_eq!(new_buffer.get_length(&[])?, Some(5));
+    /// new_buffer.set(&["tags", "6"], "old")?;
     /// 
-    /// # Ok::<(), NP_Error>(()) 
-    /// ```
+    /// new_buffer.set_list(&["tags"], &["rocket", "launch", "moon"])?;
     /// 
-    /// ## Collection (List) Example
-    /// ```
-    /// use no_proto::error::NP_Error;
-    /// use no_proto::NP_Factory;
-    /// use no_proto::NP_Size_Data;
+    /// assert_eq!(new_buffer.get_length(&["tags"])?, Some(3));
+    /// assert_eq!(new_buffer.get::<&str>(&["tags", "0"])?, Some("rocket"));
+    /// assert_eq!(new_buffer.get::<&str>(&["tags", "2"])?, Some("moon"));
     /// 
-    /// let factory: NP_Factory = NP_Factory::new("list({ of: string() })")?;
+    /// // values can still be pushed on the end
+    /// new_buffer.list_push(&["tags"], "mars")?;
+    /// assert_eq!(new_buffer.get::<&str>(&["tags", "3"])?, Some("mars"));
     /// 
-    /// let mut new_buffer = factory.new_buffer(None);
-    /// // set value at 9th index
-    /// new_buffer.set(&["9"], "hello")?;
-    /// // get length of value at root (List)
-    /// assert_eq!(new_buffer.get_length(&[])?, Some(10));
+    /// // wrong type
+    /// assert!(new_buffer.set_list(&["tags"], &[1u32, 2u32]).is_err());
     /// 
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    /// ## Collection (Table) Example
+    pub fn set_list<'set, X: 'set>(&mut self, path: &[&str], items: &[X]) -> Result<(), NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> + Clone {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        let schema = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => self.memory.get_schema(x.schema_addr),
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        if schema.i != NP_TypeKeys::List {
+            return Err(NP_Error::new("Trying to set list values on non list item!"))
+        }
+
+        let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+
+        let of_schema = self.memory.get_schema(data.child);
+
+        // type does not match schema
+        if X::type_idx().1 != of_schema.i {
+            return Err(NP_Buffer::set_type_mismatch(X::type_idx().0, &of_schema.i));
+        }
+
+        self.generation += 1;
+
+        let list_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, path)? {
+            Some(x) => x,
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        if list_cursor.parent_type == NP_Cursor_Parent::Tuple {
+            self.memory.write_bytes()[list_cursor.buff_addr - 1] = 1;
+        }
+
+        let first_item = NP_List::make_items(&list_cursor, &self.memory, items.len())?;
+
+        for (index, item) in items.iter().enumerate() {
+            let item_cursor = NP_Cursor::new(first_item + (index * 10), data.child, list_cursor.schema_addr);
+            X::set_value(item_cursor, &self.memory, item.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace the map at a path with the key value pairs in a slice.
+    /// 
+    /// Any existing map at the path is cleared first, then all of the map items are allocated in a single pass instead of searching the map for every key.  Iterating the map afterward gives the keys in the same order as the slice.
+    /// 
+    /// If a key is in the slice more than once the later value overwrites the earlier one, the key stays where it was first seen.
+    /// 
+    /// The type in the slice must match the schema of the map values.
+    /// 
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::NP_Size_Data;
     /// 
-    /// let factory: NP_Factory = NP_Factory::new(r#"
-    ///     struct({fields: {
-    ///         age: u8(),
-    ///         name: string()
-    ///     }})
-    /// "#)?;
+    /// let factory: NP_Factory = NP_Factory::new("map({value: u32()})")?;
     /// 
     /// let mut new_buffer = factory.new_buffer(None);
-    /// // get length of value at root (Table)
+    /// new_buffer.set(&["old"], 1u32)?;
+    /// 
+    /// new_buffer.set_map(&[], &[("rocket", 5u32), ("launch", 10u32), ("rocket", 20u32)])?;
+    /// 
     /// assert_eq!(new_buffer.get_length(&[])?, Some(2));
+    /// assert_eq!(new_buffer.get::<u32>(&["rocket"])?, Some(20));
+    /// assert_eq!(new_buffer.get::<u32>(&["launch"])?, Some(10));
+    /// assert_eq!(new_buffer.get::<u32>(&["old"])?, None);
+    /// 
+    /// // wrong type
+    /// assert!(new_buffer.set_map(&[], &[("moon", "landing")]).is_err());
     /// 
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    /// ## Collection (Map) Example
+    pub fn set_map<'set, X: 'set>(&mut self, path: &[&str], pairs: &[(&str, X)]) -> Result<(), NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> + Clone {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        let schema = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => self.memory.get_schema(x.schema_addr),
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        if schema.i != NP_TypeKeys::Map {
+            return Err(NP_Error::new("Trying to set map values on non map item!"))
+        }
+
+        let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+
+        let of_schema = self.memory.get_schema(data.child);
+
+        // type does not match schema
+        if X::type_idx().1 != of_schema.i {
+            return Err(NP_Buffer::set_type_mismatch(X::type_idx().0, &of_schema.i));
+        }
+
+        self.generation += 1;
+
+        let map_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, path)? {
+            Some(x) => x,
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        // later duplicates replace the earlier value
+        let mut keys: Vec<&str> = Vec::with_capacity(pairs.len());
+        let mut values: Vec<&X> = Vec::with_capacity(pairs.len());
+        let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+        for (key, value) in pairs.iter() {
+            match seen.get(key) {
+                Some(index) => values[*index] = value,
+                None => {
+                    seen.insert(key, keys.len());
+                    keys.push(key);
+                    values.push(value);
+                }
+            }
+        }
+
+        if map_cursor.parent_type == NP_Cursor_Parent::Tuple {
+            self.memory.write_bytes()[map_cursor.buff_addr - 1] = 1;
+        }
+
+        let first_item = NP_Map::make_items(&map_cursor, &self.memory, &keys)?;
+
+        for (index, value) in values.into_iter().enumerate() {
+            let item_cursor = NP_Cursor::new(first_item + (index * 12), data.child, map_cursor.schema_addr);
+            X::set_value(item_cursor, &self.memory, value.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Get an appender to push many values onto the end of a list.
+    /// 
+    /// `list_push` has to find the list from the buffer cursor for every value, the appender finds the list once and keeps track of the tail so each push only writes the new item.  The list is created if it doesn't exist.
+    /// 
+    /// The appender borrows the buffer mutably, so the buffer can't be changed in any other way while it's in use.  Get a new appender after other changes.
+    /// 
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::NP_Size_Data;
     /// 
-    /// let factory: NP_Factory = NP_Factory::new("map({value: string() })")?;
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {ids: list({of: u32()})}})")?;
     /// 
     /// let mut new_buffer = factory.new_buffer(None);
-    /// // set values
-    /// new_buffer.set(&["foo"], "bar")?;
-    /// new_buffer.set(&["foo2"], "bar2")?;
-    /// // get length of value at root (Map)
-    /// assert_eq!(new_buffer.get_length(&[])?, Some(2));
+    /// new_buffer.set(&["ids", "0"], 100u32)?;
+    /// 
+    /// let mut appender = new_buffer.list_appender(&["ids"])?;
+    /// for x in 1..1000u32 {
+    ///     appender.push(x)?;
+    /// }
+    /// 
+    /// assert_eq!(new_buffer.get_length(&["ids"])?, Some(1000));
+    /// assert_eq!(new_buffer.get::<u32>(&["ids", "0"])?, Some(100));
+    /// assert_eq!(new_buffer.get::<u32>(&["ids", "255"])?, Some(255));
     /// 
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    /// ## Collection (Tuple) Example
+    pub fn list_appender(&mut self, path: &[&str]) -> Result<NP_List_Appender, NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        let list_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, path)? {
+            Some(x) => x,
+            None => return Err(NP_Error::new("No value found at the provided path!"))
+        };
+
+        if self.memory.get_schema(list_cursor.schema_addr).i != NP_TypeKeys::List {
+            return Err(NP_Error::new("Trying to append onto non list item!"))
+        }
+
+        if list_cursor.get_value(&self.memory).get_addr_value() == 0 {
+            NP_List::make_list(&list_cursor, &self.memory)?;
+        }
+
+        let data = unsafe { &*(*self.memory.get_schema(list_cursor.schema_addr).data as *const NP_Map_List_Data) };
+
+        let tail = NP_List::get_list(list_cursor.get_value(&self.memory).get_addr_value() as usize, &self.memory).get_tail() as usize;
+
+        let next_index = if tail == 0 {
+            0
+        } else {
+            NP_Cursor::new(tail, data.child, list_cursor.schema_addr).get_value(&self.memory).get_index() as usize + 1
+        };
+
+        Ok(NP_List_Appender {
+            buffer: self,
+            list: list_cursor,
+            tail: tail,
+            next_index: next_index
+        })
+    }
+
+
+    /// Insert a value into a list at the given index, the item at the index and every item after it are moved back by one.
+    /// 
+    /// The list is created if it doesn't exist.  If the index is past the end of the list the value is set at that index, like `.set()`.
+    /// 
+    /// Returns `false` if the path to the list doesn't exist.
+    /// 
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::NP_Size_Data;
     /// 
-    /// let factory: NP_Factory = NP_Factory::new("tuple({values: [string(), string()]})")?;
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
     /// 
     /// let mut new_buffer = factory.new_buffer(None);
-    /// // get length of value at root (Tuple)
-    /// assert_eq!(new_buffer.get_length(&[])?, Some(2));
+    /// new_buffer.list_push(&[], "hello")?;
+    /// new_buffer.list_push(&[], "world")?;
+    /// 
+    /// new_buffer.list_insert(&[], 1, "there")?;
+    /// new_buffer.list_insert(&[], 0, "oh")?;
+    /// 
+    /// assert_eq!(new_buffer.get_length(&[])?, Some(4));
+    /// assert_eq!(new_buffer.get::<&str>(&["0"])?, Some("oh"));
+    /// assert_eq!(new_buffer.get::<&str>(&["1"])?, Some("hello"));
+    /// assert_eq!(new_buffer.get::<&str>(&["2"])?, Some("there"));
+    /// assert_eq!(new_buffer.get::<&str>(&["3"])?, Some("world"));
+    /// 
+    /// assert_eq!(new_buffer.list_remove(&[], 1)?, true);
+    /// assert_eq!(new_buffer.get_length(&[])?, Some(3));
+    /// assert_eq!(new_buffer.get::<&str>(&["1"])?, Some("there"));
+    /// assert_eq!(new_buffer.get::<&str>(&["2"])?, Some("world"));
+    /// 
+    /// // past the end of the list
+    /// assert_eq!(new_buffer.list_remove(&[], 10)?, false);
     /// 
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    pub fn get_length(&self, path: &[&str]) -> Result<Option<usize>, NP_Error> {
-        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)?;
+    pub fn list_insert<'insert, X: 'insert>(&mut self, path: &[&str], index: u16, value: X) -> Result<bool, NP_Error> where X: NP_Value<'insert> + NP_Scalar<'insert> {
 
-        let found_cursor = if let Some(x) = value_cursor {
-            x
-        } else {
-            return Ok(None);
-        };
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
 
-        let addr_value = found_cursor.get_value(&self.memory).get_addr_value();
+        self.generation += 1;
 
-        let schema = self.memory.get_schema(found_cursor.schema_addr);
+        let list_cursor = if path.len() == 0 { self.cursor.clone() } else { match NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, path)? {
+            Some(x) => x,
+            None => return Ok(false)
+        }};
+
+        let schema = self.memory.get_schema(list_cursor.schema_addr);
 
         match schema.i {
             NP_TypeKeys::List => {
-                if addr_value == 0 {
-                    return Ok(None);
-                }
 
                 let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
 
-                let of = data.child;
+                let of_schema = &self.memory.get_schema(data.child);
 
-                let list_data = NP_List::get_list(addr_value as usize, &self.memory);
-                let tail_addr = list_data.get_tail() as usize;
-                if tail_addr == 0 {
-                    Ok(Some(0))
-                } else {
-                    let tail_cursor = NP_Cursor::new(tail_addr, of, found_cursor.schema_addr);
-                    let cursor_data = tail_cursor.get_value(&self.memory);
-                    Ok(Some(cursor_data.get_index() as usize + 1))
+                // type does not match schema
+                if X::type_idx().1 != of_schema.i {
+                    return Err(NP_Buffer::set_type_mismatch(X::type_idx().0, &of_schema.i));
                 }
             },
-            NP_TypeKeys::Map => {
-                if addr_value == 0 {
-                    return Ok(None);
-                }
-                let mut count = 0usize;
-                {
-                    let mut map_iter = NP_Map::new_iter(&found_cursor, &self.memory);
+            _ => return Err(NP_Error::new("Trying to insert into non list item!"))
+        }
 
-                    while let Some((_ikey, _item)) = map_iter.step_iter(&self.memory) {
-                        count += 1;
-                    }
-                }
+        let new_item = NP_List::insert_at(&list_cursor, &self.memory, index)?;
+        X::set_value(new_item, &self.memory, value)?;
+        Ok(true)
+    }
 
-                Ok(Some(count))
-            },
-            NP_TypeKeys::Struct => {
-                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
-                Ok(Some(data.fields.len()))
-            },
-            NP_TypeKeys::Tuple => {
-                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
-                Ok(Some(data.values.len()))
-            },
-            NP_TypeKeys::Bytes => {
+    /// Remove the value at the given index of a list, every item after it is moved forward by one.
+    /// 
+    /// Returns `false` if the list doesn't exist or the index is past the end of the list.
+    /// 
+    /// See `.list_insert()` for an example.
+    /// 
+    pub fn list_remove(&mut self, path: &[&str], index: u16) -> Result<bool, NP_Error> {
 
-                let data = unsafe { &*(*schema.data as *const NP_Bytes_Data) };
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
 
-                let size = data.size;
-         
-                if size > 0 {
-                    Ok(Some(size as usize))
-                } else {
-                    let length_bytes = self.memory.get_4_bytes(addr_value as usize).unwrap_or(&[0u8; 4]);
-                    Ok(Some(u32::from_be_bytes(*length_bytes) as usize))
-                }
-               
-            },
-            NP_TypeKeys::UTF8String => {
+        self.generation += 1;
 
-                let data = unsafe { &*(*schema.data as *const NP_String_Data) };
+        let list_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => x,
+            None => return Ok(false)
+        };
 
-                let size = data.size;
-            
-                if size > 0 {
-                    Ok(Some(size as usize))
-                } else {
-                    let length_bytes = self.memory.get_4_bytes(addr_value as usize).unwrap_or(&[0u8; 4]);
-                    Ok(Some(u32::from_be_bytes(*length_bytes) as usize))
-                }
-    
-            },
-            _ => {
-                Ok(None)
-            }
+        match self.memory.get_schema(list_cursor.schema_addr).i {
+            NP_TypeKeys::List => NP_List::remove_at(&list_cursor, &self.memory, index),
+            _ => Err(NP_Error::new("Trying to remove from non list item!"))
         }
-  
     }
 
-    /// Clear an inner value from the buffer.
-    /// This can also be used to clear deeply nested collection objects or scalar objects.
+    /// Shorten a list to the given length, every item at or after `len` is dropped.
     /// 
-    /// Returns `true` if it found a value to delete (and deleted it), `false` otherwise.
+    /// The dropped items are left in the buffer as wasted bytes, use `compact` to reclaim them.  If the list is already shorter than `len` or doesn't exist nothing happens.
     /// 
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::NP_Size_Data;
     /// 
-    /// let factory: NP_Factory = NP_Factory::new("list({ of: string() })")?;
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
     /// 
     /// let mut new_buffer = factory.new_buffer(None);
-    /// // set index 0
-    /// new_buffer.set(&["0"], "hello")?;
-    /// // del index 0
-    /// new_buffer.del(&["0"])?;
-    /// // value is gone now!
-    /// assert_eq!(None, new_buffer.get::<&str>(&["0"])?);
+    /// new_buffer.list_push(&[], "hello")?;
+    /// new_buffer.list_push(&[], "there")?;
+    /// new_buffer.list_push(&[], "world")?;
+    /// 
+    /// new_buffer.list_truncate(&[], 2)?;
+    /// assert_eq!(new_buffer.get_length(&[])?, Some(2));
+    /// assert_eq!(new_buffer.json_encode(&[])?.stringify(), r#"{"value":["hello","there"]}"#);
+    /// 
+    /// // already shorter
+    /// new_buffer.list_truncate(&[], 10)?;
+    /// assert_eq!(new_buffer.get_length(&[])?, Some(2));
     /// 
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    pub fn del(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
+    pub fn list_truncate(&mut self, path: &[&str], len: usize) -> Result<(), NP_Error> {
 
         if self.mutable == false {
             return Err(NP_Error::MemoryReadOnly)
         }
 
-        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)?;
-        
-        match value_cursor {
-            Some(x) => {
-                NP_Cursor::delete(x, &self.memory)
-            }
-            None => Ok(false)
+        self.generation += 1;
+
+        let list_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => x,
+            None => return Ok(())
+        };
+
+        match self.memory.get_schema(list_cursor.schema_addr).i {
+            NP_TypeKeys::List => NP_List::truncate(&list_cursor, &self.memory, len),
+            _ => Err(NP_Error::new("Trying to truncate non list item!"))
         }
     }
 
-    /// Retrieve the schema type at a given path.
+    /// Rename a key in a map without copying the value.
     /// 
+    /// Only the new key is written to the buffer, the value stays where it is no matter how large it is.  The old key bytes become wasted space that `compact` will reclaim.
+    /// 
+    /// Returns `false` if the map or the `old` key don't exist.  If `new` is already a key in the map an error is returned.
     /// 
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::schema::NP_TypeKeys;
     /// 
-    /// let factory: NP_Factory = NP_Factory::new("tuple({values: [ geo8(), dec({exp: 2}), string() ]})")?;
+    /// let factory: NP_Factory = NP_Factory::new("map({value: list({of: string()})})")?;
     /// 
     /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["crew", "0"], "Jeb")?;
+    /// new_buffer.set(&["crew", "1"], "Bill")?;
     /// 
-    /// assert_eq!(new_buffer.get_schema_type(&[])?.unwrap(), NP_TypeKeys::Tuple);
-    /// assert_eq!(new_buffer.get_schema_type(&["0"])?.unwrap(), NP_TypeKeys::Geo);
-    /// assert_eq!(new_buffer.get_schema_type(&["1"])?.unwrap(), NP_TypeKeys::Decimal);
-    /// assert_eq!(new_buffer.get_schema_type(&["2"])?.unwrap(), NP_TypeKeys::UTF8String);
+    /// assert!(new_buffer.map_rename_key(&[], "crew", "pilots")?);
+    /// assert_eq!(new_buffer.get::<&str>(&["pilots", "1"])?, Some("Bill"));
+    /// assert_eq!(new_buffer.get_length(&["crew"])?, None);
+    /// 
+    /// assert_eq!(new_buffer.map_rename_key(&[], "crew", "pilots")?, false);
     /// 
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    pub fn get_schema_type(&self, path: &[&str]) -> Result<Option<NP_TypeKeys>, NP_Error> {
+    pub fn map_rename_key(&mut self, path: &[&str], old: &str, new: &str) -> Result<bool, NP_Error> {
 
-        match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
-            Some(x) => {
-                Ok(Some(self.memory.get_schema(x.schema_addr).i))
-            }
-            None => Ok(None)
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        self.generation += 1;
+
+        let map_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => x,
+            None => return Ok(false)
+        };
+
+        match self.memory.get_schema(map_cursor.schema_addr).i {
+            NP_TypeKeys::Map => NP_Map::rename_key(&map_cursor, &self.memory, old, new),
+            _ => Err(NP_Error::new("Trying to rename key of non map item!"))
         }
     }
 
-    /// Retrieve the schema default at a given path.
-    /// 
-    /// This is useful for `geo` and `dec` data types where there is information about the value in the schema.
+    /// Fold over every value in a map without collecting them first.
     /// 
-    /// For example, when you create an `NP_Geo` type to put into a `geo` field, you must know the resolution (4/8/16).  If you use this method you can get an empty `NP_Geo` type that already has the correct resolution set based on the schema.
+    /// Each value is decoded into `X` and passed to the closure along with its key and the accumulator.  Items without a value (and no default in the schema) are skipped.  If `X` doesn't match the map's value type an error is returned.
     /// 
-    /// The type that you cast the request to will be compared to the schema, if it doesn't match the schema the request will fail.
+    /// If there is no map at the path the initial value is returned, after the types are checked against the schema.
     /// 
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::pointer::dec::NP_Dec;
-    /// use no_proto::pointer::geo::NP_Geo;
     /// 
-    /// // a list where each item is a map where each key has a value containing a list of strings
-    /// let factory: NP_Factory = NP_Factory::new(r#"
-    ///     tuple({values: [
-    ///         geo8(),
-    ///         dec({exp: 2})
-    ///     ]})
-    /// "#)?;
+    /// let factory: NP_Factory = NP_Factory::new("map({value: u32()})")?;
     /// 
     /// let mut new_buffer = factory.new_buffer(None);
-    /// // Get an empty NP_Geo type that has the correct resolution for the schema
-    /// // 
-    /// let geo_default: NP_Geo = new_buffer.get_schema_default::<NP_Geo>(&["0"])?.unwrap();
-    /// assert_eq!(geo_default.size, 8); // geo is size 8 in schema
     /// 
-    /// // Get an empty NP_Dec type that has the correct exp for the schema
-    /// // 
-    /// let dec_default: NP_Dec = new_buffer.get_schema_default::<NP_Dec>(&["1"])?.unwrap();
-    /// assert_eq!(dec_default.exp, 2); // exponent is 2 in schema
+    /// // the value type is checked even when there's no map yet
+    /// assert_eq!(new_buffer.map_reduce(&[], 0u64, |sum, _key, hits: u32| sum + hits as u64)?, 0);
+    /// assert!(new_buffer.map_reduce(&[], 0u64, |sum, _key, hits: u8| sum + hits as u64).is_err());
+    /// 
+    /// new_buffer.set(&["home"], 20u32)?;
+    /// new_buffer.set(&["about"], 5u32)?;
+    /// new_buffer.set(&["login"], 12u32)?;
+    /// 
+    /// let total = new_buffer.map_reduce(&[], 0u64, |sum, _key, hits: u32| sum + hits as u64)?;
+    /// assert_eq!(total, 37);
+    /// 
+    /// let busiest = new_buffer.map_reduce(&[], ("", 0u32), |best, key, hits: u32| {
+    ///     if hits > best.1 { (key, hits) } else { best }
+    /// })?;
+    /// assert_eq!(busiest, ("home", 20));
+    /// 
+    /// // wrong value type
+    /// assert!(new_buffer.map_reduce(&[], 0u64, |sum, _key, hits: u8| sum + hits as u64).is_err());
     /// 
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    pub fn get_schema_default<'get, X: 'get>(&'get self, path: &[&str]) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
+    pub fn map_reduce<'reduce, X: 'reduce, A, F>(&'reduce self, path: &[&str], init: A, mut f: F) -> Result<A, NP_Error> where X: NP_Value<'reduce> + NP_Scalar<'reduce>, F: FnMut(A, &'reduce str, X) -> A {
 
-        match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
-            Some(x) => {
-                                
-                // type does not match schema
+        // check the types against the schema first, so a mismatch is an error even when the map isn't in the buffer
+        let schema_addr = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Ok(init)
+        };
+
+        let map_schema = self.memory.get_schema(schema_addr);
+
+        if map_schema.i != NP_TypeKeys::Map {
+            return Err(NP_Error::new("Trying to reduce non map item!"))
+        }
+
+        let data = unsafe { &*(*map_schema.data as *const NP_Map_List_Data) };
+
+        let value_schema = self.memory.get_schema(data.child);
+
+        // type does not match schema
+        if X::type_idx().1 != value_schema.i {
+            return Err(NP_Buffer::type_error(format!("reduce values of type ({}) for schema of type", X::type_idx().0).as_str(), &value_schema.i, ""));
+        }
+
+        let map_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => x,
+            None => return Ok(init)
+        };
+
+        let mut acc = init;
+
+        let mut map_iter = NP_Map::new_iter(&map_cursor, &self.memory);
+
+        while let Some((key, item)) = map_iter.step_iter(&self.memory) {
+            let value = match X::into_value(&item, &self.memory)? {
+                Some(x) => Some(x),
+                None => X::default_value(0, item.schema_addr, &self.memory.get_schemas())
+            };
+
+            if let Some(x) = value {
+                acc = f(acc, key, x);
+            }
+        }
+
+        Ok(acc)
+    }
+
+    /// Get length of String, Bytes, Table, Tuple, List or Map Type
+    /// 
+    /// If the type found at the path provided does not support length operations, you'll get `None`.
+    /// 
+    /// If there is no value at the path provodid, you will get `None`.
+    /// 
+    /// If an item is found and it's length is zero, you can expect `Some(0)`.
+    /// 
+    /// ## String Example
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("string()")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // set initial value
+    /// new_buffer.set(&[], "hello")?;
+    /// // get length of value at root (String)
+    /// assert_eq!(new_buffer.get_length(&[])?, Some(5));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    /// ## Collection (List) Example
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("list({ of: string() })")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // set value at 9th index
+    /// new_buffer.set(&["9"], "hello")?;
+    /// // get length of value at root (List)
+    /// assert_eq!(new_buffer.get_length(&[])?, Some(10));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    /// ## Collection (Table) Example
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         age: u8(),
+    ///         name: string()
+    ///     }})
+    /// "#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // get length of value at root (Table)
+    /// assert_eq!(new_buffer.get_length(&[])?, Some(2));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    /// ## Collection (Map) Example
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("map({value: string() })")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // set values
+    /// new_buffer.set(&["foo"], "bar")?;
+    /// new_buffer.set(&["foo2"], "bar2")?;
+    /// // get length of value at root (Map)
+    /// assert_eq!(new_buffer.get_length(&[])?, Some(2));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    /// ## Collection (Tuple) Example
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("tuple({values: [string(), string()]})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // get length of value at root (Tuple)
+    /// assert_eq!(new_buffer.get_length(&[])?, Some(2));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get_length(&self, path: &[&str]) -> Result<Option<usize>, NP_Error> {
+        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)?;
+
+        let found_cursor = if let Some(x) = value_cursor {
+            x
+        } else {
+            return Ok(None);
+        };
+
+        let addr_value = found_cursor.get_value(&self.memory).get_addr_value();
+
+        let schema = self.memory.get_schema(found_cursor.schema_addr);
+
+        match schema.i {
+            NP_TypeKeys::List => {
+                if addr_value == 0 {
+                    return Ok(None);
+                }
+
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+
+                let of = data.child;
+
+                let list_data = NP_List::get_list(addr_value as usize, &self.memory);
+                let tail_addr = list_data.get_tail() as usize;
+                if tail_addr == 0 {
+                    Ok(Some(0))
+                } else {
+                    let tail_cursor = NP_Cursor::new(tail_addr, of, found_cursor.schema_addr);
+                    let cursor_data = tail_cursor.get_value(&self.memory);
+                    Ok(Some(cursor_data.get_index() as usize + 1))
+                }
+            },
+            NP_TypeKeys::Map => {
+                if addr_value == 0 {
+                    return Ok(None);
+                }
+                let mut count = 0usize;
+                {
+                    let mut map_iter = NP_Map::new_iter(&found_cursor, &self.memory);
+
+                    while let Some((_ikey, _item)) = map_iter.step_iter(&self.memory) {
+                        count += 1;
+                    }
+                }
+
+                Ok(Some(count))
+            },
+            NP_TypeKeys::Struct => {
+                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                Ok(Some(data.fields.len()))
+            },
+            NP_TypeKeys::Tuple => {
+                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                Ok(Some(data.values.len()))
+            },
+            NP_TypeKeys::Bytes => {
+
+                let data = unsafe { &*(*schema.data as *const NP_Bytes_Data) };
+
+                let size = data.size;
+         
+                if size > 0 {
+                    Ok(Some(size as usize))
+                } else {
+                    let length_bytes = self.memory.get_4_bytes(addr_value as usize).unwrap_or(&[0u8; 4]);
+                    Ok(Some(u32::from_be_bytes(*length_bytes) as usize))
+                }
+               
+            },
+            NP_TypeKeys::UTF8String => {
+
+                let data = unsafe { &*(*schema.data as *const NP_String_Data) };
+
+                let size = data.size;
+            
+                if size > 0 {
+                    Ok(Some(size as usize))
+                } else {
+                    let length_bytes = self.memory.get_4_bytes(addr_value as usize).unwrap_or(&[0u8; 4]);
+                    Ok(Some(u32::from_be_bytes(*length_bytes) as usize))
+                }
+    
+            },
+            _ => {
+                Ok(None)
+            }
+        }
+  
+    }
+
+    /// Clear an inner value from the buffer.
+    /// This can also be used to clear deeply nested collection objects or scalar objects.
+    /// 
+    /// Returns `true` if it found a value to delete (and deleted it), `false` otherwise.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("list({ of: string() })")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // set index 0
+    /// new_buffer.set(&["0"], "hello")?;
+    /// // del index 0
+    /// new_buffer.del(&["0"])?;
+    /// // value is gone now!
+    /// assert_eq!(None, new_buffer.get::<&str>(&["0"])?);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn del(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        self.generation += 1;
+
+        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)?;
+        
+        match value_cursor {
+            Some(x) => {
+                NP_Cursor::delete(x, &self.memory)
+            }
+            None => Ok(false)
+        }
+    }
+
+    /// Exchange the values at two paths.
+    /// 
+    /// Only the pointers to the values are swapped, the values themselves aren't copied.  Both paths must have identical schemas, otherwise an error is returned.  A path also can't be swapped with one of its own children.
+    /// 
+    /// Missing list items and map keys are created, swapping with an empty value moves the other value over and leaves an empty value behind.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {names: list({of: string()}), first: string(), age: u8()}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["names", "0"], "hello")?;
+    /// new_buffer.set(&["names", "1"], "world")?;
+    /// new_buffer.set(&["first"], "Bob")?;
+    /// 
+    /// new_buffer.swap(&["names", "0"], &["names", "1"])?;
+    /// assert_eq!(new_buffer.get::<&str>(&["names", "0"])?, Some("world"));
+    /// assert_eq!(new_buffer.get::<&str>(&["names", "1"])?, Some("hello"));
+    /// 
+    /// new_buffer.swap(&["names", "0"], &["first"])?;
+    /// assert_eq!(new_buffer.get::<&str>(&["names", "0"])?, Some("Bob"));
+    /// assert_eq!(new_buffer.get::<&str>(&["first"])?, Some("world"));
+    /// 
+    /// // schemas must match
+    /// assert!(new_buffer.swap(&["first"], &["age"]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn swap(&mut self, path_a: &[&str], path_b: &[&str]) -> Result<(), NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        self.generation += 1;
+
+        if path_a.starts_with(path_b) || path_b.starts_with(path_a) {
+            if path_a == path_b {
+                return Ok(())
+            }
+            return Err(NP_Error::new("Can't swap a value with one of its own children!"))
+        }
+
+        let cursor_a = opt_err(NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, path_a)?)?;
+        let cursor_b = opt_err(NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, path_b)?)?;
+
+        let schemas = self.memory.get_schemas();
+        let schema_a = &schemas[cursor_a.schema_addr];
+        let schema_b = &schemas[cursor_b.schema_addr];
+
+        if schema_a.i != schema_b.i {
+            return Err(NP_Buffer::type_error(format!("swap value of type ({}) with value of type", schema_a.i.into_type_idx().0).as_str(), &schema_b.i, ""));
+        }
+
+        if cursor_a.schema_addr != cursor_b.schema_addr && NP_Schema::_type_to_json(schemas, cursor_a.schema_addr)? != NP_Schema::_type_to_json(schemas, cursor_b.schema_addr)? {
+            return Err(NP_Error::new("Can't swap values with different schemas!"));
+        }
+
+        let in_tuple_a = cursor_a.parent_type == NP_Cursor_Parent::Tuple;
+        let in_tuple_b = cursor_b.parent_type == NP_Cursor_Parent::Tuple;
+
+        let bytes = self.memory.write_bytes();
+
+        match (&schema_a.val, in_tuple_a, in_tuple_b) {
+            // both in tuples, swap the whole slot including the set flag
+            (kind, true, true) => {
+                let size = match kind { NP_Value_Kind::Fixed(x) => *x as usize, NP_Value_Kind::Pointer => 4 };
+                for x in 0..(size + 1) {
+                    bytes.swap(cursor_a.buff_addr - 1 + x, cursor_b.buff_addr - 1 + x);
+                }
+            },
+            (NP_Value_Kind::Fixed(_), true, false) | (NP_Value_Kind::Fixed(_), false, true) => {
+                return Err(NP_Error::new("Fixed size tuple values can only be swapped with values in a tuple!"));
+            },
+            _ => {
+                let addr_a = cursor_a.get_value(&self.memory).get_addr_value();
+                let addr_b = cursor_b.get_value(&self.memory).get_addr_value();
+                cursor_a.get_value_mut(&self.memory).set_addr_value(addr_b);
+                cursor_b.get_value_mut(&self.memory).set_addr_value(addr_a);
+                if in_tuple_a {
+                    bytes[cursor_a.buff_addr - 1] = if addr_b == 0 { 0 } else { 1 };
+                }
+                if in_tuple_b {
+                    bytes[cursor_b.buff_addr - 1] = if addr_a == 0 { 0 } else { 1 };
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Move the value at one path to another path, leaving the first path empty.
+    /// 
+    /// If both paths have identical schemas only the pointer to the value is moved, nothing is copied no matter how large the value is.  If the schemas are different but have the same type (or are both numbers) the value is converted through JSON into the new schema instead.  Any other combination returns an error.  A path also can't be moved into or out of one of its own children.
+    /// 
+    /// Whatever was at the destination before becomes wasted bytes until the buffer is compacted.  Returns `false` if there is no value at the `from` path.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     draft: struct({fields: {tags: list({of: string()}), score: u8()}}),
+    ///     tags: list({of: string()}),
+    ///     score: u32(),
+    ///     name: string()
+    /// }})"#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["draft", "tags", "0"], "hello")?;
+    /// new_buffer.set(&["draft", "tags", "1"], "world")?;
+    /// new_buffer.set(&["draft", "score"], 200u8)?;
+    /// 
+    /// // same schema, the list is moved without copying
+    /// assert!(new_buffer.move_value(&["draft", "tags"], &["tags"])?);
+    /// assert_eq!(new_buffer.get::<&str>(&["tags", "1"])?, Some("world"));
+    /// assert_eq!(new_buffer.get_length(&["draft", "tags"])?, None);
+    /// 
+    /// // different number types are converted
+    /// assert!(new_buffer.move_value(&["draft", "score"], &["score"])?);
+    /// assert_eq!(new_buffer.get::<u32>(&["score"])?, Some(200));
+    /// assert_eq!(new_buffer.get::<u8>(&["draft", "score"])?, None);
+    /// 
+    /// // nothing to move
+    /// assert_eq!(new_buffer.move_value(&["draft", "score"], &["score"])?, false);
+    /// 
+    /// // incompatible types
+    /// assert!(new_buffer.move_value(&["score"], &["name"]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn move_value(&mut self, from: &[&str], to: &[&str]) -> Result<bool, NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        self.generation += 1;
+
+        if from.starts_with(to) || to.starts_with(from) {
+            if from == to {
+                return self.contains(from)
+            }
+            return Err(NP_Error::new("Can't move a value into or out of one of its own children!"))
+        }
+
+        let from_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, from)? {
+            Some(x) => x,
+            None => return Ok(false)
+        };
+
+        if NP_Cursor::is_absent(&from_cursor, &self.memory) {
+            return Ok(false)
+        }
+
+        let to_cursor = opt_err(NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, to)?)?;
+
+        let schemas = self.memory.get_schemas();
+        let schema_from = &schemas[from_cursor.schema_addr];
+        let schema_to = &schemas[to_cursor.schema_addr];
+
+        let same_schema = from_cursor.schema_addr == to_cursor.schema_addr || (schema_from.i == schema_to.i && NP_Schema::_type_to_json(schemas, from_cursor.schema_addr)? == NP_Schema::_type_to_json(schemas, to_cursor.schema_addr)?);
+
+        let in_tuple_from = from_cursor.parent_type == NP_Cursor_Parent::Tuple;
+        let in_tuple_to = to_cursor.parent_type == NP_Cursor_Parent::Tuple;
+
+        if same_schema {
+            let bytes = self.memory.write_bytes();
+
+            match (&schema_from.val, in_tuple_from, in_tuple_to) {
+                // both in tuples, copy the whole slot including the set flag
+                (kind, true, true) => {
+                    let size = match kind { NP_Value_Kind::Fixed(x) => *x as usize, NP_Value_Kind::Pointer => 4 };
+                    bytes.copy_within((from_cursor.buff_addr - 1)..(from_cursor.buff_addr + size), to_cursor.buff_addr - 1);
+                    match kind {
+                        NP_Value_Kind::Fixed(_) => { NP_Cursor::delete(from_cursor, &self.memory)?; },
+                        NP_Value_Kind::Pointer => {
+                            bytes[from_cursor.buff_addr - 1] = 0;
+                            from_cursor.get_value_mut(&self.memory).set_addr_value(0);
+                        }
+                    }
+                    return Ok(true)
+                },
+                // fixed tuple values are stored in place, they have to be copied
+                (NP_Value_Kind::Fixed(_), true, false) | (NP_Value_Kind::Fixed(_), false, true) => { },
+                _ => {
+                    let addr = from_cursor.get_value(&self.memory).get_addr_value();
+                    to_cursor.get_value_mut(&self.memory).set_addr_value(addr);
+                    from_cursor.get_value_mut(&self.memory).set_addr_value(0);
+                    if in_tuple_to {
+                        bytes[to_cursor.buff_addr - 1] = 1;
+                    }
+                    if in_tuple_from {
+                        bytes[from_cursor.buff_addr - 1] = 0;
+                    }
+                    return Ok(true)
+                }
+            }
+        } else {
+            let is_number = |key: NP_TypeKeys| { (key as u8) >= (NP_TypeKeys::Int8 as u8) && (key as u8) <= (NP_TypeKeys::Decimal as u8) };
+
+            if schema_from.i != schema_to.i && (is_number(schema_from.i) == false || is_number(schema_to.i) == false) {
+                return Err(NP_Buffer::type_error(format!("move value of type ({}) into value of type", schema_from.i.into_type_idx().0).as_str(), &schema_to.i, ""));
+            }
+        }
+
+        // convert the value into the new schema
+        let json = NP_Cursor::json_encode(0, &from_cursor, &self.memory);
+        NP_Cursor::delete(to_cursor, &self.memory)?;
+        self.set_json(to, &json, false)?;
+        NP_Cursor::delete(from_cursor, &self.memory)?;
+
+        Ok(true)
+    }
+
+    /// Remove everything from a list, map, struct or tuple while keeping the collection itself.
+    /// 
+    /// This is much faster than deleting each item one at a time.  The removed items become wasted bytes until the buffer is compacted.
+    /// 
+    /// Returns `true` if something was cleared, `false` if the collection was empty or doesn't exist.  Using this on a scalar value returns an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {tags: list({of: string()}), name: string()}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["tags", "0"], "hello")?;
+    /// new_buffer.set(&["tags", "1"], "world")?;
+    /// new_buffer.set(&["name"], "Bob")?;
+    /// 
+    /// assert_eq!(new_buffer.clear_collection(&["tags"])?, true);
+    /// assert_eq!(new_buffer.get_length(&["tags"])?, Some(0));
+    /// assert_eq!(new_buffer.clear_collection(&["tags"])?, false);
+    /// 
+    /// new_buffer.set(&["tags", "0"], "again")?;
+    /// assert_eq!(new_buffer.get::<&str>(&["tags", "0"])?, Some("again"));
+    /// 
+    /// assert_eq!(new_buffer.clear_collection(&[])?, true);
+    /// assert_eq!(new_buffer.get::<&str>(&["name"])?, None);
+    /// 
+    /// assert!(new_buffer.clear_collection(&["name"]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn clear_collection(&mut self, path: &[&str]) -> Result<bool, NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        self.generation += 1;
+
+        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)?;
+
+        match value_cursor {
+            Some(x) => NP_Cursor::clear(0, x, &self.memory),
+            None => Ok(false)
+        }
+    }
+
+    /// Retrieve the schema type at a given path.
+    /// 
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::schema::NP_TypeKeys;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("tuple({values: [ geo8(), dec({exp: 2}), string() ]})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// 
+    /// assert_eq!(new_buffer.get_schema_type(&[])?.unwrap(), NP_TypeKeys::Tuple);
+    /// assert_eq!(new_buffer.get_schema_type(&["0"])?.unwrap(), NP_TypeKeys::Geo);
+    /// assert_eq!(new_buffer.get_schema_type(&["1"])?.unwrap(), NP_TypeKeys::Decimal);
+    /// assert_eq!(new_buffer.get_schema_type(&["2"])?.unwrap(), NP_TypeKeys::UTF8String);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get_schema_type(&self, path: &[&str]) -> Result<Option<NP_TypeKeys>, NP_Error> {
+
+        match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => {
+                Ok(Some(self.memory.get_schema(x.schema_addr).i))
+            }
+            None => Ok(None)
+        }
+    }
+
+    /// Get the `default` configured in the schema at a given path, as JSON.
+    ///
+    /// This only looks at the schema, so the result is the same whether or not a value is set in the buffer.  `None` is returned if the path isn't in the schema or the type has no default configured.  Collections never have defaults.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         name: string({default: "Anonymous"}),
+    ///         age: u8({default: 18}),
+    ///         email: string()
+    ///     }})
+    /// "#)?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "Jeb Kermin")?;
+    ///
+    /// assert_eq!(new_buffer.schema_default_json(&["name"])?.unwrap().stringify(), r#""Anonymous""#);
+    /// assert_eq!(new_buffer.schema_default_json(&["age"])?.unwrap().stringify(), "18");
+    /// assert!(new_buffer.schema_default_json(&["email"])?.is_none());
+    /// assert!(new_buffer.schema_default_json(&[])?.is_none());
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn schema_default_json(&self, path: &[&str]) -> Result<Option<NP_JSON>, NP_Error> {
+
+        let mut schema_addr = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Ok(None)
+        };
+
+        let schemas = self.memory.get_schemas();
+
+        let mut loop_count = 0usize;
+        while schemas[schema_addr].i == NP_TypeKeys::Portal && loop_count < schemas.len() {
+            schema_addr = unsafe { &*(*schemas[schema_addr].data as *const NP_Portal_Data) }.schema;
+            loop_count += 1;
+        }
+
+        match schemas[schema_addr].i {
+            NP_TypeKeys::Struct | NP_TypeKeys::List | NP_TypeKeys::Map | NP_TypeKeys::Tuple | NP_TypeKeys::Portal => Ok(None),
+            _ => {
+                match NP_Schema::_type_to_json(schemas, schema_addr)? {
+                    NP_JSON::Dictionary(map) => Ok(map.get("default").map(|x| x.clone())),
+                    _ => Ok(None)
+                }
+            }
+        }
+    }
+
+    /// Get the type of the value that lives at a given path.
+    /// 
+    /// This is a pure schema lookup, the buffer isn't read and `None` is only returned if the path doesn't resolve in the schema.  Unlike `get_schema_type`, portals at the end of the path are followed, so the type returned is the type of the value that will actually be stored there.  This lets generic readers pick the right type for `get` without knowing the schema ahead of time.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::schema::NP_TypeKeys;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         name: string(),
+    ///         age: u8(),
+    ///         tags: list({of: string()}),
+    ///         next: portal({to: ""})
+    ///     }})
+    /// "#)?;
+    /// 
+    /// let new_buffer = factory.new_buffer(None);
+    /// 
+    /// assert_eq!(new_buffer.type_at(&["name"])?, Some(NP_TypeKeys::UTF8String));
+    /// assert_eq!(new_buffer.type_at(&["age"])?, Some(NP_TypeKeys::Uint8));
+    /// assert_eq!(new_buffer.type_at(&["tags"])?, Some(NP_TypeKeys::List));
+    /// assert_eq!(new_buffer.type_at(&["tags", "1"])?, Some(NP_TypeKeys::UTF8String));
+    /// 
+    /// // portals give the type they point to
+    /// assert_eq!(new_buffer.type_at(&["next"])?, Some(NP_TypeKeys::Struct));
+    /// assert_eq!(new_buffer.type_at(&["next", "age"])?, Some(NP_TypeKeys::Uint8));
+    /// assert_eq!(new_buffer.get_schema_type(&["next"])?, Some(NP_TypeKeys::Portal));
+    /// 
+    /// // not in the schema
+    /// assert_eq!(new_buffer.type_at(&["nope"])?, None);
+    /// assert_eq!(new_buffer.type_at(&["age", "1"])?, None);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn type_at(&self, path: &[&str]) -> Result<Option<NP_TypeKeys>, NP_Error> {
+
+        let mut schema_addr = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Ok(None)
+        };
+
+        // follow portals to the type they point to
+        let mut loop_count = 0usize;
+        while self.memory.get_schema(schema_addr).i == NP_TypeKeys::Portal {
+            loop_count += 1;
+            if loop_count > self.memory.max_depth {
+                return Err(NP_Error::RecursionLimit)
+            }
+            schema_addr = unsafe { &*(*self.memory.get_schema(schema_addr).data as *const NP_Portal_Data) }.schema;
+        }
+
+        Ok(Some(self.memory.get_schema(schema_addr).i))
+    }
+
+    /// Retrieve the schema default at a given path.
+    /// 
+    /// This is useful for `geo` and `dec` data types where there is information about the value in the schema.
+    /// 
+    /// For example, when you create an `NP_Geo` type to put into a `geo` field, you must know the resolution (4/8/16).  If you use this method you can get an empty `NP_Geo` type that already has the correct resolution set based on the schema.
+    /// 
+    /// The type that you cast the request to will be compared to the schema, if it doesn't match the schema the request will fail.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::pointer::dec::NP_Dec;
+    /// use no_proto::pointer::geo::NP_Geo;
+    /// 
+    /// // a list where each item is a map where each key has a value containing a list of strings
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     tuple({values: [
+    ///         geo8(),
+    ///         dec({exp: 2})
+    ///     ]})
+    /// "#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // Get an empty NP_Geo type that has the correct resolution for the schema
+    /// // 
+    /// let geo_default: NP_Geo = new_buffer.get_schema_default::<NP_Geo>(&["0"])?.unwrap();
+    /// assert_eq!(geo_default.size, 8); // geo is size 8 in schema
+    /// 
+    /// // Get an empty NP_Dec type that has the correct exp for the schema
+    /// // 
+    /// let dec_default: NP_Dec = new_buffer.get_schema_default::<NP_Dec>(&["1"])?.unwrap();
+    /// assert_eq!(dec_default.exp, 2); // exponent is 2 in schema
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get_schema_default<'get, X: 'get>(&'get self, path: &[&str]) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
+
+        match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => {
+                                
+                // type does not match schema
+                if X::type_idx().1 != self.memory.get_schema(x.schema_addr).i {
+                    let mut err = "TypeError: Attempted to get schema for type (".to_owned();
+                    err.push_str(X::type_idx().0);
+                    err.push_str(") for schema of type (");
+                    err.push_str(self.memory.get_schema(x.schema_addr).i.into_type_idx().0);
+                    err.push_str(")\n");
+                    return Err(NP_Error::new(err));
+                }
+
+                Ok(X::schema_default(&self.memory.get_schema(x.schema_addr)))
+            }
+            None => Ok(None)
+        }
+    }
+  
+    /// Retrieve an inner value from the buffer. 
+    /// 
+    /// The type that you cast the request to will be compared to the schema, if it doesn't match the schema the request will fail.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// // a list where each item is a map where each key has a value containing a list of strings
+    /// let factory: NP_Factory = NP_Factory::new(r#"list({of: map({ value: list({of: string() }) })})"#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // third item in the top level list -> key "alpha" of map at 3rd element -> 9th element of list at "alpha" key
+    /// // 
+    /// new_buffer.set(&["3", "alpha", "9"], "who would build a schema like this")?;
+    /// 
+    /// // get the same item we just set
+    /// let message = new_buffer.get::<&str>(&["3", "alpha", "9"])?;
+    /// 
+    /// assert_eq!(message, Some("who would build a schema like this"));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get<'get, X: 'get>(&'get self, path: &[&str]) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
+        self._get(self.cursor.clone(), path)
+    }
+
+    /// Read several columns of the struct at the path into a rust struct.
+    ///
+    /// The struct's vtables are walked once and every field is passed to `T::from_column`, this is much cheaper for wide structs than a `get` call for each field.  Fields that aren't set are still passed in, their `get` returns the schema default if there is one.
+    ///
+    /// The [`columns!`](../macro.columns.html) macro implements `FromColumns` for structs made of `Option` fields named after the columns.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::columns;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         name: string(),
+    ///         age: u8({default: 18}),
+    ///         email: string(),
+    ///         score: u32()
+    ///     }})
+    /// "#)?;
+    ///
+    /// #[derive(Default, Debug, PartialEq)]
+    /// struct User { name: Option<String>, age: Option<u8>, score: Option<u32> }
+    ///
+    /// columns!(User { name: String, age: u8, score: u32 });
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "Jeb Kermin")?;
+    /// new_buffer.set(&["email"], "jeb@example.com")?;
+    ///
+    /// let user: User = new_buffer.get_columns(&[])?;
+    /// assert_eq!(user, User { name: Some(String::from("Jeb Kermin")), age: Some(18), score: None });
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn get_columns<T: FromColumns>(&self, path: &[&str]) -> Result<T, NP_Error> {
+
+        let schema_cursor = opt_err(NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)?)?;
+
+        let schema = self.memory.get_schema(schema_cursor.schema_addr);
+
+        if schema.i != NP_TypeKeys::Struct {
+            return Err(NP_Buffer::type_error("get columns for schema of type", &schema.i, ""));
+        }
+
+        let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+
+        let mut result = T::default();
+
+        let struct_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(x) if x.get_value(&self.memory).get_addr_value() != 0 => x,
+            _ => { // no struct in the buffer, every column gets its default
+                for field in data.fields.iter() {
+                    result.from_column(&NP_Column { name: &field.col, cursor: None, schema_addr: field.schema, memory: &self.memory })?;
+                }
+                return Ok(result);
+            }
+        };
+
+        let mut struc = NP_Struct::new_iter(&struct_cursor, &self.memory);
+
+        while let Some((index, key, item)) = struc.step_iter(&self.memory) {
+            result.from_column(&NP_Column { name: key, cursor: item, schema_addr: data.fields[index].schema, memory: &self.memory })?;
+        }
+
+        Ok(result)
+    }
+
+    /// Retrieve an inner value from the buffer using an [RFC 6901](https://tools.ietf.org/html/rfc6901) JSON Pointer instead of a path slice.
+    ///
+    /// The pointer `"/users/0/name"` is the same as the path `&["users", "0", "name"]`.  `~1` and `~0` in a segment are unescaped to `/` and `~`, the empty pointer `""` is the root of the buffer.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {users: list({of: struct({fields: {name: string()}})}), headers: map({value: string()})}})")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set_by_pointer("/users/0/name", "Jeb")?;
+    /// new_buffer.set_by_pointer("/headers/content~1type", "text/plain")?;
+    ///
+    /// assert_eq!(new_buffer.get_by_pointer::<&str>("/users/0/name")?, Some("Jeb"));
+    /// assert_eq!(new_buffer.get::<&str>(&["headers", "content/type"])?, Some("text/plain"));
+    ///
+    /// // pointers must start with a slash
+    /// assert!(new_buffer.get_by_pointer::<&str>("users/0/name").is_err());
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn get_by_pointer<'get, X: 'get>(&'get self, pointer: &str) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
+        let segments = Self::_parse_json_pointer(pointer)?;
+        let path: Vec<&str> = segments.iter().map(|x| x.as_str()).collect();
+        self._get(self.cursor.clone(), &path)
+    }
+
+    /// Set a value in the buffer using an [RFC 6901](https://tools.ietf.org/html/rfc6901) JSON Pointer instead of a path slice.
+    ///
+    /// See [`get_by_pointer`](#method.get_by_pointer) for how pointers are turned into paths.
+    ///
+    pub fn set_by_pointer<'set, X: 'set>(&mut self, pointer: &str, value: X) -> Result<bool, NP_Error> where X: NP_Value<'set> + NP_Scalar<'set> {
+        let segments = Self::_parse_json_pointer(pointer)?;
+        let path: Vec<&str> = segments.iter().map(|x| x.as_str()).collect();
+        self._set(self.cursor.clone(), &path, value)
+    }
+
+    /// Split a JSON Pointer into unescaped path segments
+    fn _parse_json_pointer(pointer: &str) -> Result<Vec<String>, NP_Error> {
+        if pointer.len() == 0 {
+            return Ok(Vec::new())
+        }
+
+        if pointer.starts_with('/') == false {
+            return Err(NP_Error::new("JSON Pointer must be empty or start with '/'!"))
+        }
+
+        let mut segments: Vec<String> = Vec::new();
+
+        for raw in pointer[1..].split('/') {
+            let mut segment = String::with_capacity(raw.len());
+            let mut chars = raw.chars();
+            while let Some(c) = chars.next() {
+                if c == '~' {
+                    match chars.next() {
+                        Some('0') => segment.push('~'),
+                        Some('1') => segment.push('/'),
+                        _ => return Err(NP_Error::new("JSON Pointer has an invalid '~' escape, only '~0' and '~1' are allowed!"))
+                    }
+                } else {
+                    segment.push(c);
+                }
+            }
+            segments.push(segment);
+        }
+
+        Ok(segments)
+    }
+
+    /// Retrieve an owned copy of an inner value from the buffer.
+    /// 
+    /// Works like `.get()` but the returned value doesn't borrow the buffer, so it can outlive it.  Strings and bytes have to be copied out of the buffer with `String` and `Vec<u8>`, `.get()` with `&str` or `&[u8]` is still the zero copy way to read them.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u8()}})")?;
+    /// 
+    /// fn read_name(factory: &NP_Factory, bytes: Vec<u8>) -> Result<Option<String>, NP_Error> {
+    ///     let buffer = factory.open_buffer(bytes);
+    ///     buffer.get_owned::<String>(&["name"])
+    /// }
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "Bob")?;
+    /// new_buffer.set(&["age"], 30u8)?;
+    /// assert_eq!(new_buffer.get_owned::<u8>(&["age"])?, Some(30));
+    /// 
+    /// assert_eq!(read_name(&factory, new_buffer.finish().bytes())?, Some(String::from("Bob")));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get_owned<X: NP_Value_Owned>(&self, path: &[&str]) -> Result<Option<X>, NP_Error> {
+        self._get(self.cursor.clone(), path)
+    }
+
+    fn _get<'get, X: 'get>(&'get self, from: NP_Cursor, path: &[&str]) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
+        let value_cursor = NP_Cursor::select(&self.memory, from, false, false, path)?;
+
+        match value_cursor {
+            Some(x) => {
+
+                if let Some(counts) = &self.access_counts {
+                    if let Some(count) = counts.get(x.schema_addr) {
+                        count.set(count.get().saturating_add(1));
+                    }
+                }
+                                
+                // type does not match schema
                 if X::type_idx().1 != self.memory.get_schema(x.schema_addr).i {
-                    let mut err = "TypeError: Attempted to get schema for type (".to_owned();
-                    err.push_str(X::type_idx().0);
-                    err.push_str(") for schema of type (");
-                    err.push_str(self.memory.get_schema(x.schema_addr).i.into_type_idx().0);
-                    err.push_str(")\n");
-                    return Err(NP_Error::new(err));
+                    return Err(NP_Buffer::type_mismatch(X::type_idx().0, &self.memory.get_schema(x.schema_addr).i));
+                }
+
+                match X::into_value(&x, &self.memory)? {
+                    Some(x) => {
+                        Ok(Some(x))
+                    },
+                    None => { // no value found here, return default from schema
+                        match X::default_value(0, x.schema_addr, &self.memory.get_schemas()) {
+                            Some(y) => {
+                                Ok(Some(y))
+                            },
+                            None => { // no default in schema, no value to provide
+                                Ok(None)
+                            }
+                        }                        
+                    }
+                }
+            }
+            None => Ok(None)
+        }
+    }
+
+    /// Same as `_get`, but a list along the path that isn't in the buffer gives `None` instead of an error.
+    fn _get_all_item<'get, X: 'get>(&'get self, from: NP_Cursor, path: &[&str]) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
+        let mut cursor = from;
+
+        for segment in path.iter() {
+            if self.memory.get_schema(cursor.schema_addr).i == NP_TypeKeys::List && NP_Cursor::is_absent(&cursor, &self.memory) {
+                return Ok(None);
+            }
+
+            cursor = match NP_Cursor::select(&self.memory, cursor, false, false, &[*segment])? {
+                Some(x) => x,
+                None => return Ok(None)
+            };
+        }
+
+        self._get(cursor, &[])
+    }
+
+    /// Error for a value type that doesn't match the schema it's read from.
+    #[inline(always)]
+    fn type_mismatch(expected: &str, found: &NP_TypeKeys) -> NP_Error {
+        NP_Buffer::type_error(format!("get value for type ({}) for schema of type", expected).as_str(), found, "")
+    }
+
+    /// Error for a value type that doesn't match the schema it's written into.
+    #[inline(always)]
+    fn set_type_mismatch(expected: &str, found: &NP_TypeKeys) -> NP_Error {
+        NP_Buffer::type_error(format!("set value for type ({}) into schema of type", expected).as_str(), found, "")
+    }
+
+    /// Error for a schema type `action` can't be used with, the message reads `TypeError: Attempted to <action> (<found>)<note>`.
+    #[inline(always)]
+    fn type_error(action: &str, found: &NP_TypeKeys, note: &str) -> NP_Error {
+        let mut err = "TypeError: Attempted to ".to_owned();
+        err.push_str(action);
+        err.push_str(" (");
+        err.push_str(found.into_type_idx().0);
+        err.push_str(")");
+        err.push_str(note);
+        err.push_str("\n");
+        NP_Error::new(err)
+    }
+
+    /// Check if there is a value at the given path without decoding it.
+    /// 
+    /// This works for every type and doesn't use schema defaults.  Paths that don't exist in the schema or the buffer return `false`.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u8({default: 10}), pair: tuple({values: [u8(), u8()]})}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "Bob")?;
+    /// new_buffer.set(&["pair", "1"], 2u8)?;
+    /// 
+    /// assert_eq!(new_buffer.contains(&["name"])?, true);
+    /// assert_eq!(new_buffer.contains(&["age"])?, false);
+    /// assert_eq!(new_buffer.contains(&["pair", "0"])?, false);
+    /// assert_eq!(new_buffer.contains(&["pair", "1"])?, true);
+    /// assert_eq!(new_buffer.contains(&["not", "here"])?, false);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn contains(&self, path: &[&str]) -> Result<bool, NP_Error> {
+        match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => Ok(NP_Cursor::is_absent(&x, &self.memory) == false),
+            None => Ok(false)
+        }
+    }
+
+    /// Get the path of every scalar value that's set in the buffer, relative to the buffer cursor.
+    ///
+    /// Schema defaults aren't used and collections without any set values don't show up.  Struct fields and tuple columns come in schema order, list items by index and map keys in the order they're stored in the buffer, so the same buffer always gives the same paths.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         name: string(),
+    ///         age: u8({default: 20}),
+    ///         tags: list({of: string()}),
+    ///         meta: map({value: string()})
+    ///     }})
+    /// "#)?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "Jeb Kermin")?;
+    /// new_buffer.set(&["tags", "2"], "rocket")?;
+    /// new_buffer.set(&["meta", "ship"], "Kerbal X")?;
+    ///
+    /// assert_eq!(new_buffer.set_paths()?, vec![
+    ///     vec!["name"],
+    ///     vec!["tags", "2"],
+    ///     vec!["meta", "ship"]
+    /// ]);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn set_paths(&self) -> Result<Vec<Vec<String>>, NP_Error> {
+        let mut paths: Vec<Vec<String>> = Vec::new();
+        NP_Cursor::collect_paths(0, &self.cursor, &self.memory, &mut Vec::new(), &mut paths)?;
+        Ok(paths)
+    }
+
+    /// Get the field names of a struct in schema order, along with whether each field has a value in the buffer.
+    /// 
+    /// Schema defaults aren't used, a field is only present if it's been set.  If the path is a struct in the schema but there's no struct in the buffer yet every field is returned as not present.  Paths that aren't a struct return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u8({default: 10}), tags: list({of: string()})}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// assert_eq!(new_buffer.struct_fields(&[])?, vec![("name", false), ("age", false), ("tags", false)]);
+    /// 
+    /// new_buffer.set(&["name"], "Bob")?;
+    /// new_buffer.set(&["tags", "0"], "admin")?;
+    /// assert_eq!(new_buffer.struct_fields(&[])?, vec![("name", true), ("age", false), ("tags", true)]);
+    /// 
+    /// assert!(new_buffer.struct_fields(&["name"]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn struct_fields(&self, path: &[&str]) -> Result<Vec<(&str, bool)>, NP_Error> {
+
+        let struct_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)?;
+
+        let schema_addr = match &struct_cursor {
+            Some(x) => x.schema_addr,
+            None => match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+                Some(x) => x.schema_addr,
+                None => return Err(NP_Error::new("Path not found in schema!"))
+            }
+        };
+
+        let schema = self.memory.get_schema(schema_addr);
+
+        if schema.i != NP_TypeKeys::Struct {
+            return Err(NP_Error::new("Fields can only be listed for struct types!"))
+        }
+
+        let struct_cursor = match struct_cursor {
+            Some(x) => x,
+            None => { // no struct in the buffer, nothing is present
+                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                return Ok(data.fields.iter().map(|field| (field.col.as_str(), false)).collect())
+            }
+        };
+
+        let mut fields = Vec::new();
+
+        let mut struc = NP_Struct::new_iter(&struct_cursor, &self.memory);
+
+        while let Some((_index, key, item)) = struc.step_iter(&self.memory) {
+            let present = match item {
+                Some(x) => x.get_value(&self.memory).get_addr_value() != 0,
+                None => false
+            };
+            fields.push((key, present));
+        }
+
+        Ok(fields)
+    }
+
+    /// Get the index of an enum value in the schema's choice list instead of the choice itself.
+    /// 
+    /// This is the compact number stored in the buffer, useful for protocols that exchange enums as numbers.  If there is no value in the buffer the index of the schema default is returned, if there's no default you'll get `None`.  Paths that aren't an enum return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::pointer::option::NP_Enum;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     color: enum({choices: ["red", "green", "blue"]}),
+    ///     size: enum({choices: ["small", "large"], default: "large"})
+    /// }})"#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// assert_eq!(new_buffer.get_enum_index(&["color"])?, None);
+    /// assert_eq!(new_buffer.get_enum_index(&["size"])?, Some(1));
+    /// 
+    /// new_buffer.set_enum_index(&["color"], 2)?;
+    /// assert_eq!(new_buffer.get::<NP_Enum>(&["color"])?, Some(NP_Enum::new("blue")));
+    /// 
+    /// new_buffer.set(&["color"], NP_Enum::new("green"))?;
+    /// assert_eq!(new_buffer.get_enum_index(&["color"])?, Some(1));
+    /// 
+    /// // out of range
+    /// assert!(new_buffer.set_enum_index(&["color"], 3).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get_enum_index(&self, path: &[&str]) -> Result<Option<u8>, NP_Error> {
+
+        let cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)?;
+
+        let schema_addr = match &cursor {
+            Some(x) => x.schema_addr,
+            None => match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+                Some(x) => x.schema_addr,
+                None => return Ok(None)
+            }
+        };
+
+        let schema = self.memory.get_schema(schema_addr);
+
+        if schema.i != NP_TypeKeys::Enum {
+            return Err(NP_Error::new("Trying to get enum index of non enum item!"))
+        }
+
+        let data = unsafe { &*(*schema.data as *const NP_Enum_Data) };
+
+        let cursor = match cursor {
+            Some(x) if NP_Cursor::is_absent(&x, &self.memory) == false => x,
+            _ => { // no value here, use the default from the schema
+                return Ok(match &data.default {
+                    Some(default) => data.choices.iter().position(|choice| choice == default).map(|idx| idx as u8),
+                    None => None
+                })
+            }
+        };
+
+        let value_addr = cursor.get_value(&self.memory).get_addr_value() as usize;
+
+        let index = match self.memory.read_bytes().get(value_addr) {
+            Some(x) => *x,
+            None => return Err(NP_Error::BadAddress { addr: value_addr, context: "value is out of bounds" })
+        };
+
+        if index as usize >= data.choices.len() {
+            return Err(NP_Error::BadAddress { addr: value_addr, context: "enum index is not in the choices" })
+        }
+
+        Ok(Some(index))
+    }
+
+    /// Get any number type as an `f64` without knowing which number type the schema uses.
+    /// 
+    /// Works with `i8` through `u64`, `f32`, `f64` and `dec` values, the schema default is used if there's no value in the buffer.  This is meant for display, charting and aggregation where exact types don't matter: `i64` and `u64` values past 2^53 lose precision and `dec` values are converted with `NP_Dec::to_float`, so large exponents pick up rounding errors.  Paths that aren't a number type return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::pointer::dec::NP_Dec;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     count: u8(),
+    ///     total: i64({default: 12}),
+    ///     price: dec({exp: 2}),
+    ///     name: string()
+    /// }})"#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["count"], 5u8)?;
+    /// new_buffer.set(&["price"], NP_Dec::new(1999, 2))?;
+    /// 
+    /// assert_eq!(new_buffer.get_as_f64(&["count"])?, Some(5f64));
+    /// assert_eq!(new_buffer.get_as_f64(&["total"])?, Some(12f64));
+    /// assert_eq!(new_buffer.get_as_f64(&["price"])?, Some(19.99f64));
+    /// assert!(new_buffer.get_as_f64(&["name"]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get_as_f64(&self, path: &[&str]) -> Result<Option<f64>, NP_Error> {
+
+        let schema_addr = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Ok(None)
+        };
+
+        Ok(match self.memory.get_schema(schema_addr).i {
+            NP_TypeKeys::Int8    => self.get::<i8>(path)?.map(|x| x as f64),
+            NP_TypeKeys::Int16   => self.get::<i16>(path)?.map(|x| x as f64),
+            NP_TypeKeys::Int32   => self.get::<i32>(path)?.map(|x| x as f64),
+            NP_TypeKeys::Int64   => self.get::<i64>(path)?.map(|x| x as f64),
+            NP_TypeKeys::Uint8   => self.get::<u8>(path)?.map(|x| x as f64),
+            NP_TypeKeys::Uint16  => self.get::<u16>(path)?.map(|x| x as f64),
+            NP_TypeKeys::Uint32  => self.get::<u32>(path)?.map(|x| x as f64),
+            NP_TypeKeys::Uint64  => self.get::<u64>(path)?.map(|x| x as f64),
+            NP_TypeKeys::Float   => self.get::<f32>(path)?.map(|x| x as f64),
+            NP_TypeKeys::Double  => self.get::<f64>(path)?,
+            NP_TypeKeys::Decimal => self.get::<NP_Dec>(path)?.map(|x| x.to_float()),
+            other => {
+                return Err(NP_Buffer::type_error("get f64 for type", &other, ", only number types can be read as f64"));
+            }
+        })
+    }
+
+    /// Read a bytes value as text, any invalid UTF-8 is replaced with `U+FFFD`.
+    /// 
+    /// This is only meant for displaying bytes that usually hold text, the replaced characters mean the string can't be turned back into the same bytes.  Use `get::<&[u8]>` to read the exact bytes.
+    /// 
+    /// Paths that aren't a bytes type return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     note: bytes(),
+    ///     name: string()
+    /// }})"#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// assert_eq!(new_buffer.get_string_lossy(&["note"])?, None);
+    /// 
+    /// new_buffer.set(&["note"], &b"hello"[..])?;
+    /// assert_eq!(new_buffer.get_string_lossy(&["note"])?, Some(String::from("hello")));
+    /// 
+    /// new_buffer.set(&["note"], &[104u8, 105, 0xFF][..])?;
+    /// assert_eq!(new_buffer.get_string_lossy(&["note"])?, Some(String::from("hi\u{FFFD}")));
+    /// 
+    /// assert!(new_buffer.get_string_lossy(&["name"]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get_string_lossy(&self, path: &[&str]) -> Result<Option<String>, NP_Error> {
+
+        let schema_addr = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Ok(None)
+        };
+
+        let kind = self.memory.get_schema(schema_addr).i;
+
+        if kind != NP_TypeKeys::Bytes {
+            return Err(NP_Buffer::type_error("get lossy string for type", &kind, ", only bytes can be read as a lossy string"));
+        }
+
+        Ok(self.get::<&[u8]>(path)?.map(|bytes| String::from_utf8_lossy(bytes).into_owned()))
+    }
+
+    /// Set an enum value by its index in the schema's choice list.
+    /// 
+    /// The index must be less than the number of choices in the schema, otherwise an error is returned.  Paths that aren't an enum also return an error.
+    /// 
+    /// See `.get_enum_index()` for an example.
+    /// 
+    pub fn set_enum_index(&mut self, path: &[&str], index: u8) -> Result<(), NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        // check the schema before anything is written to the buffer
+        let schema_addr = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Err(NP_Error::new("Unable to find value at path!"))
+        };
+
+        let schema = self.memory.get_schema(schema_addr);
+
+        if schema.i != NP_TypeKeys::Enum {
+            return Err(NP_Error::new("Trying to set enum index of non enum item!"))
+        }
+
+        let data = unsafe { &*(*schema.data as *const NP_Enum_Data) };
+
+        let choice = match data.choices.get(index as usize) {
+            Some(x) => x.clone(),
+            None => return Err(NP_Error::new(format!("Enum index {} is out of range, there are only {} choices!", index, data.choices.len())))
+        };
+
+        if self.set(path, choice)? {
+            Ok(())
+        } else {
+            Err(NP_Error::new("Unable to find value at path!"))
+        }
+    }
+
+    /// Find the enum choice that matches a string from outside the buffer, ignoring whitespace at either end and case.
+    /// 
+    /// Returns the choice as it's spelled in the schema, or `None` if nothing matches.  An exact match always wins over a case insensitive one.  Nothing is written to the buffer, use `.set_enum_lenient()` to store the match.  Paths that aren't an enum return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::pointer::option::NP_Enum;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     color: enum({choices: ["Red", "Green", "Blue"]})
+    /// }})"#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// assert_eq!(new_buffer.match_enum(&["color"], " green\n")?, Some(NP_Enum::new("Green")));
+    /// assert_eq!(new_buffer.match_enum(&["color"], "purple")?, None);
+    /// 
+    /// assert_eq!(new_buffer.set_enum_lenient(&["color"], "BLUE ")?, true);
+    /// assert_eq!(new_buffer.get::<NP_Enum>(&["color"])?, Some(NP_Enum::new("Blue")));
+    /// 
+    /// // no match, the buffer isn't changed
+    /// assert_eq!(new_buffer.set_enum_lenient(&["color"], "purple")?, false);
+    /// assert_eq!(new_buffer.get::<NP_Enum>(&["color"])?, Some(NP_Enum::new("Blue")));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn match_enum(&self, path: &[&str], value: &str) -> Result<Option<NP_Enum>, NP_Error> {
+
+        let schema_addr = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Err(NP_Error::new("Unable to find value at path!"))
+        };
+
+        let schema = self.memory.get_schema(schema_addr);
+
+        if schema.i != NP_TypeKeys::Enum {
+            return Err(NP_Error::new("Trying to match enum choice of non enum item!"))
+        }
+
+        let data = unsafe { &*(*schema.data as *const NP_Enum_Data) };
+
+        let value = value.trim();
+
+        if let Some(choice) = data.choices.iter().find(|choice| choice.to_str() == value) {
+            return Ok(Some(choice.clone()))
+        }
+
+        let folded = value.to_lowercase();
+
+        Ok(data.choices.iter().find(|choice| choice.to_str().to_lowercase() == folded).cloned())
+    }
+
+    /// Set an enum from a string that might not exactly match the schema, like messy external data.
+    /// 
+    /// The string is matched with `.match_enum()` and the choice from the schema is stored.  Returns `false` without changing the buffer if no choice matches.  Use `.set()` with an `NP_Enum` for strict matching.
+    /// 
+    /// See `.match_enum()` for an example.
+    /// 
+    pub fn set_enum_lenient(&mut self, path: &[&str], value: &str) -> Result<bool, NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        match self.match_enum(path, value)? {
+            Some(choice) => self.set(path, choice),
+            None => Ok(false)
+        }
+    }
+
+    /// Get the first two values of a tuple in one call.
+    /// 
+    /// Each value works like `.get()`, a type that doesn't match the schema of its tuple element returns an error.  The tuple path is only selected once.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("tuple({values: [u8(), string(), bool()]})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["0"], 20u8)?;
+    /// new_buffer.set(&["1"], "hello")?;
+    /// 
+    /// assert_eq!(new_buffer.get_tuple2::<u8, &str>(&[])?, (Some(20), Some("hello")));
+    /// assert_eq!(new_buffer.get_tuple3::<u8, &str, bool>(&[])?, (Some(20), Some("hello"), None));
+    /// assert!(new_buffer.get_tuple2::<u8, u8>(&[]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get_tuple2<'get, A: 'get, B: 'get>(&'get self, path: &[&str]) -> Result<(Option<A>, Option<B>), NP_Error> where A: NP_Value<'get> + NP_Scalar<'get>, B: NP_Value<'get> + NP_Scalar<'get> {
+        match self.select_tuple(path)? {
+            Some(tuple) => Ok((self._get_tuple_item(tuple, 0)?, self._get_tuple_item(tuple, 1)?)),
+            None => Ok((None, None))
+        }
+    }
+
+    /// Get the first three values of a tuple in one call.
+    /// 
+    /// Works the same as `.get_tuple2()`.
+    /// 
+    pub fn get_tuple3<'get, A: 'get, B: 'get, C: 'get>(&'get self, path: &[&str]) -> Result<(Option<A>, Option<B>, Option<C>), NP_Error> where A: NP_Value<'get> + NP_Scalar<'get>, B: NP_Value<'get> + NP_Scalar<'get>, C: NP_Value<'get> + NP_Scalar<'get> {
+        match self.select_tuple(path)? {
+            Some(tuple) => Ok((self._get_tuple_item(tuple, 0)?, self._get_tuple_item(tuple, 1)?, self._get_tuple_item(tuple, 2)?)),
+            None => Ok((None, None, None))
+        }
+    }
+
+    /// Get the first four values of a tuple in one call.
+    /// 
+    /// Works the same as `.get_tuple2()`.
+    /// 
+    pub fn get_tuple4<'get, A: 'get, B: 'get, C: 'get, D: 'get>(&'get self, path: &[&str]) -> Result<(Option<A>, Option<B>, Option<C>, Option<D>), NP_Error> where A: NP_Value<'get> + NP_Scalar<'get>, B: NP_Value<'get> + NP_Scalar<'get>, C: NP_Value<'get> + NP_Scalar<'get>, D: NP_Value<'get> + NP_Scalar<'get> {
+        match self.select_tuple(path)? {
+            Some(tuple) => Ok((self._get_tuple_item(tuple, 0)?, self._get_tuple_item(tuple, 1)?, self._get_tuple_item(tuple, 2)?, self._get_tuple_item(tuple, 3)?)),
+            None => Ok((None, None, None, None))
+        }
+    }
+
+    /// Get every value of a tuple as it's own JSON value.
+    /// 
+    /// Each element is encoded the same way `.json_encode()` encodes it inside the tuple.  This is easier to work with than a JSON array when the elements have different types.  If the tuple isn't in the buffer you get an empty `Vec`, paths that aren't a tuple return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("tuple({values: [u8(), string(), bool()]})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// assert_eq!(new_buffer.tuple_to_json_vec(&[])?.len(), 0);
+    /// 
+    /// new_buffer.set(&["0"], 20u8)?;
+    /// new_buffer.set(&["1"], "hello")?;
+    /// 
+    /// let values = new_buffer.tuple_to_json_vec(&[])?;
+    /// assert_eq!(values[0].stringify(), "20");
+    /// assert_eq!(values.len(), 3);
+    /// assert_eq!(values[1].stringify(), "\"hello\"");
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn tuple_to_json_vec(&self, path: &[&str]) -> Result<Vec<NP_JSON>, NP_Error> {
+
+        // type check with the schema so missing tuples still error on the wrong type
+        let schema_addr = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Err(NP_Error::new("Unable to find value at path!"))
+        };
+
+        let schema_type = self.memory.get_schema(schema_addr).i;
+        if schema_type != NP_TypeKeys::Tuple {
+            return Err(NP_Buffer::type_error("get tuple values from schema of type", &schema_type, ""));
+        }
+
+        let mut values = Vec::new();
+
+        let tuple = match self.select_tuple(path)? {
+            Some(x) if x.get_value(&self.memory).get_addr_value() != 0 => x,
+            _ => return Ok(values)
+        };
+
+        let mut tuple_iter = NP_Tuple::new_iter(&tuple, &self.memory);
+
+        while let Some((_idx, item)) = tuple_iter.step_iter(&self.memory, false) {
+            match item {
+                Some(x) => values.push(NP_Cursor::json_encode(0, &x, &self.memory)),
+                None => values.push(NP_JSON::Null)
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn select_tuple(&self, path: &[&str]) -> Result<Option<NP_Cursor>, NP_Error> {
+        match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(cursor) => {
+                let schema_type = self.memory.get_schema(cursor.schema_addr).i;
+                if schema_type != NP_TypeKeys::Tuple {
+                    return Err(NP_Buffer::type_error("get tuple values from schema of type", &schema_type, ""));
+                }
+                Ok(Some(cursor))
+            },
+            None => Ok(None)
+        }
+    }
+
+    /// Get a value from a tuple cursor by index, the same way `_get` would with a path of `[index]`.
+    fn _get_tuple_item<'get, X: 'get>(&'get self, tuple: NP_Cursor, index: usize) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
+        match NP_Tuple::select(tuple, self.memory.get_schema(tuple.schema_addr), index, false, false, &self.memory)? {
+            Some(item) => self._get(item, &[]),
+            None => Ok(None)
+        }
+    }
+
+    /// Get the raw bytes of a scalar value without decoding it.
+    /// 
+    /// The returned slice borrows directly from the buffer and only contains the value's payload, for strings & bytes the length prefix is not included.  Fixed size strings include their padding.
+    /// 
+    /// Returns `None` if there is no value at the path, schema defaults aren't used.  Collections can't be read this way and will return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u16({default: 10})}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "hello")?;
+    /// 
+    /// assert_eq!(new_buffer.get_raw_bytes(&["name"])?, Some(&b"hello"[..]));
+    /// assert_eq!(new_buffer.get_raw_bytes(&["age"])?, None);
+    /// 
+    /// new_buffer.set(&["age"], 300u16)?;
+    /// assert_eq!(new_buffer.get_raw_bytes(&["age"])?, Some(&300u16.to_be_bytes()[..]));
+    /// 
+    /// assert!(new_buffer.get_raw_bytes(&[]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn get_raw_bytes(&self, path: &[&str]) -> Result<Option<&[u8]>, NP_Error> {
+
+        let mut cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => x,
+            None => return Ok(None)
+        };
+
+        let mut schema = self.memory.get_schema(cursor.schema_addr);
+
+        // portals point to the real type
+        let mut loop_max = self.memory.max_depth;
+        while schema.i == NP_TypeKeys::Portal {
+            if loop_max == 0 {
+                return Err(NP_Error::RecursionLimit)
+            }
+            let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+            cursor.schema_addr = data.schema;
+            cursor.parent_schema_addr = data.parent_schema;
+            schema = self.memory.get_schema(cursor.schema_addr);
+            loop_max -= 1;
+        }
+
+        match schema.i {
+            NP_TypeKeys::Struct | NP_TypeKeys::Tuple | NP_TypeKeys::List | NP_TypeKeys::Map | NP_TypeKeys::Any | NP_TypeKeys::None => {
+                return Err(NP_Error::new("Raw bytes are only available for scalar values!"))
+            },
+            _ => { }
+        }
+
+        if NP_Cursor::is_absent(&cursor, &self.memory) {
+            return Ok(None)
+        }
+
+        let bytes = self.memory.read_bytes();
+
+        // fixed tuple values are stored in place
+        let value_addr = if cursor.value_bytes.is_some() { cursor.buff_addr } else { cursor.get_value(&self.memory).get_addr_value() as usize };
+
+        let (start, end) = match schema.val {
+            NP_Value_Kind::Fixed(size) => (value_addr, value_addr + size as usize),
+            NP_Value_Kind::Pointer => { // dynamic size, 4 length bytes then the data
+                let size_bytes = opt_err(self.memory.get_4_bytes(value_addr))?;
+                let size = u32::from_be_bytes(*size_bytes) as usize;
+                (value_addr + 4, value_addr + 4 + size)
+            }
+        };
+
+        if end > bytes.len() {
+            return Err(NP_Error::new("Value is out of bounds!"))
+        }
+
+        Ok(Some(&bytes[start..end]))
+    }
+
+    /// Get the start and end offsets of a scalar value's payload in the buffer bytes, for building external indexes that point into stored buffers.
+    /// 
+    /// The range covers the same bytes `.get_raw_bytes()` returns, `&buffer.read_bytes()[start..end]` is the value.  Returns `None` if there is no value at the path and an error for collections.  Offsets are from the start of `.read_bytes()`, buffers from `new_buffer_tagged` have 4 more bytes in front once they're closed.
+    /// 
+    /// The range is only good until the buffer changes, any mutation can move the value and compaction moves everything.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u16()}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "hello")?;
+    /// assert_eq!(new_buffer.byte_range(&["age"])?, None);
+    /// 
+    /// let (start, end) = new_buffer.byte_range(&["name"])?.unwrap();
+    /// assert_eq!(end - start, 5);
+    /// 
+    /// let bytes = new_buffer.finish().bytes();
+    /// assert_eq!(&bytes[start..end], b"hello");
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn byte_range(&self, path: &[&str]) -> Result<Option<(usize, usize)>, NP_Error> {
+        match self.get_raw_bytes(path)? {
+            Some(value) => {
+                let start = value.as_ptr() as usize - self.memory.read_bytes().as_ptr() as usize;
+                Ok(Some((start, start + value.len())))
+            },
+            None => Ok(None)
+        }
+    }
+
+    /// Write pre-encoded bytes as the value of a scalar without encoding them.
+    /// 
+    /// The bytes are copied directly into the buffer as the value's payload, the caller is responsible for making sure they match the wire format of the type at the path.  This is most useful for copying a field between buffers of the same schema with `.get_raw_bytes()`.
+    /// 
+    /// Fixed size values must be exactly the size of the type.  Variable length values (strings & bytes without a `size`) must include the 4 byte big endian length prefix, which isn't included in the output of `.get_raw_bytes()`.  Collections can't be written this way and will return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u16()}})")?;
+    /// 
+    /// let mut old_buffer = factory.new_buffer(None);
+    /// old_buffer.set(&["name"], "hello")?;
+    /// old_buffer.set(&["age"], 300u16)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// 
+    /// // fixed size values are copied as is
+    /// new_buffer.set_raw_bytes(&["age"], old_buffer.get_raw_bytes(&["age"])?.unwrap())?;
+    /// assert_eq!(new_buffer.get::<u16>(&["age"])?, Some(300));
+    /// 
+    /// // variable length values need the length prefix
+    /// let name = old_buffer.get_raw_bytes(&["name"])?.unwrap();
+    /// let mut name_bytes = (name.len() as u32).to_be_bytes().to_vec();
+    /// name_bytes.extend_from_slice(name);
+    /// new_buffer.set_raw_bytes(&["name"], &name_bytes)?;
+    /// assert_eq!(new_buffer.get::<&str>(&["name"])?, Some("hello"));
+    /// 
+    /// // wrong size
+    /// assert!(new_buffer.set_raw_bytes(&["age"], &[1, 2, 3]).is_err());
+    /// assert!(new_buffer.set_raw_bytes(&["name"], name).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn set_raw_bytes(&mut self, path: &[&str], bytes: &[u8]) -> Result<(), NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        let mut cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, path)? {
+            Some(x) => x,
+            None => return Err(NP_Error::new("Unable to find value at path!"))
+        };
+
+        let mut schema = self.memory.get_schema(cursor.schema_addr);
+
+        // portals point to the real type
+        let mut loop_max = self.memory.max_depth;
+        while schema.i == NP_TypeKeys::Portal {
+            if loop_max == 0 {
+                return Err(NP_Error::RecursionLimit)
+            }
+            let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+            cursor.schema_addr = data.schema;
+            cursor.parent_schema_addr = data.parent_schema;
+            schema = self.memory.get_schema(cursor.schema_addr);
+            loop_max -= 1;
+        }
+
+        match schema.i {
+            NP_TypeKeys::Struct | NP_TypeKeys::Tuple | NP_TypeKeys::List | NP_TypeKeys::Map | NP_TypeKeys::Any | NP_TypeKeys::None => {
+                return Err(NP_Error::new("Raw bytes can only be set for scalar values!"))
+            },
+            _ => { }
+        }
+
+        match schema.val {
+            NP_Value_Kind::Fixed(size) => {
+                if bytes.len() != size as usize {
+                    return Err(NP_Error::new(format!("Raw bytes must be {} bytes long for this type!", size)))
+                }
+            },
+            NP_Value_Kind::Pointer => {
+                if bytes.len() < 4 || u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize != bytes.len() - 4 {
+                    return Err(NP_Error::new("Raw bytes for variable length types must start with their length!"))
                 }
+            }
+        }
+
+        if cursor.parent_type == NP_Cursor_Parent::Tuple {
+            self.memory.write_bytes()[cursor.buff_addr - 1] = 1;
+        }
+
+        let value_addr = cursor.get_value(&self.memory).get_addr_value() as usize;
+
+        match schema.val {
+            NP_Value_Kind::Fixed(_) if value_addr != 0 => { // fixed size values are updated in place
+                let write_bytes = self.memory.write_bytes();
+                write_bytes[value_addr..(value_addr + bytes.len())].copy_from_slice(bytes);
+            },
+            _ => {
+                let new_addr = self.memory.malloc_borrow(bytes)?;
+                cursor.get_value_mut(&self.memory).set_addr_value(new_addr as u32);
+            }
+        }
+
+        Ok(())
+    }
+
+
+    /// This performs a compaction if the closure provided as the second argument returns `true`.
+    /// Compaction is a pretty expensive operation (requires full copy of the whole buffer) so should be done sparingly.
+    /// The closure is provided an argument that contains the original size of the buffer, how many bytes could be saved by compaction, and how large the new buffer would be after compaction.  The closure should return `true` to perform compaction, `false` otherwise.
+    /// 
+    /// The first argument, new_capacity, is the capacity of the underlying Vec<u8> that we'll be copying the data into.  The default is the size of the old buffer.
+    /// 
+    /// **WARNING** Your cursor location will be reset to the root.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("string()")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// // set initial value
+    /// new_buffer.set(&[], "hello")?;
+    /// // using 15 bytes
+    /// assert_eq!(NP_Size_Data {
+    ///     current_buffer: 15,
+    ///     after_compaction: 15,
+    ///     wasted_bytes: 0
+    /// }, new_buffer.calc_bytes()?);
+    /// // update the value
+    /// new_buffer.set(&[], "hello, world")?;
+    /// // now using 31 bytes, with 9 bytes of wasted space
+    /// assert_eq!(NP_Size_Data {
+    ///     current_buffer: 31,
+    ///     after_compaction: 22,
+    ///     wasted_bytes: 9
+    /// }, new_buffer.calc_bytes()?);
+    /// // compact to save space
+    /// new_buffer.maybe_compact(None, |compact_data| {
+    ///     // only compact if wasted bytes are greater than 5
+    ///     if compact_data.wasted_bytes > 5 {
+    ///         true
+    ///     } else {
+    ///         false
+    ///     }
+    /// })?;
+    /// // back down to 18 bytes with no wasted bytes
+    /// assert_eq!(NP_Size_Data {
+    ///     current_buffer: 22,
+    ///     after_compaction: 22,
+    ///     wasted_bytes: 0
+    /// }, new_buffer.calc_bytes()?);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn maybe_compact<F>(&mut self, new_capacity: Option<usize>, mut callback: F) -> Result<(), NP_Error> where F: FnMut(NP_Size_Data) -> bool {
 
-                Ok(X::schema_default(&self.memory.get_schema(x.schema_addr)))
-            }
-            None => Ok(None)
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
         }
+
+        let bytes_data = self.calc_bytes()?;
+
+        if callback(bytes_data) {
+            self.compact(new_capacity)?;
+        }
+
+        return Ok(());
     }
-  
-    /// Retrieve an inner value from the buffer. 
+
+    /// Compact the buffer only if the predicate returns `true` for the buffer's size data.
     /// 
-    /// The type that you cast the request to will be compared to the schema, if it doesn't match the schema the request will fail.
+    /// Works like `.maybe_compact(None, ..)` but the predicate gets a reference to the size data and the return value tells you if a compaction happened.  The predicate sees the same size data `.calc_bytes()` returns.
+    /// 
+    /// **WARNING** Your cursor location will be reset to the root if a compaction happens.
     /// 
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::NP_Size_Data;
     /// 
-    /// // a list where each item is a map where each key has a value containing a list of strings
-    /// let factory: NP_Factory = NP_Factory::new(r#"list({of: map({ value: list({of: string() }) })})"#)?;
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
     /// 
     /// let mut new_buffer = factory.new_buffer(None);
-    /// // third item in the top level list -> key "alpha" of map at 3rd element -> 9th element of list at "alpha" key
-    /// // 
-    /// new_buffer.set(&["3", "alpha", "9"], "who would build a schema like this")?;
+    /// new_buffer.set(&["0"], "hello")?;
+    /// new_buffer.set(&["1"], "world")?;
     /// 
-    /// // get the same item we just set
-    /// let message = new_buffer.get::<&str>(&["3", "alpha", "9"])?;
+    /// // compact if more than 10% of the buffer is wasted
+    /// assert_eq!(new_buffer.compact_if(|d| d.wasted_bytes * 100 > d.current_buffer * 10)?, false);
     /// 
-    /// assert_eq!(message, Some("who would build a schema like this"));
+    /// new_buffer.del(&["1"])?;
+    /// assert_eq!(new_buffer.compact_if(|d| d.wasted_bytes * 100 > d.current_buffer * 10)?, true);
+    /// assert_eq!(new_buffer.calc_wasted_bytes()?, 0);
+    /// assert_eq!(new_buffer.get::<&str>(&["0"])?, Some("hello"));
     /// 
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    pub fn get<'get, X: 'get>(&'get self, path: &[&str]) -> Result<Option<X>, NP_Error> where X: NP_Value<'get> + NP_Scalar<'get> {
-        let value_cursor = NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)?;
+    pub fn compact_if<F>(&mut self, predicate: F) -> Result<bool, NP_Error> where F: Fn(&NP_Size_Data) -> bool {
 
-        match value_cursor {
-            Some(x) => {
-                                
-                // type does not match schema
-                if X::type_idx().1 != self.memory.get_schema(x.schema_addr).i {
-                    let mut err = "TypeError: Attempted to get value for type (".to_owned();
-                    err.push_str(X::type_idx().0);
-                    err.push_str(") for schema of type (");
-                    err.push_str(self.memory.get_schema(x.schema_addr).i.into_type_idx().0);
-                    err.push_str(")\n");
-                    return Err(NP_Error::new(err));
-                }
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
 
-                match X::into_value(&x, &self.memory)? {
-                    Some(x) => {
-                        Ok(Some(x))
-                    },
-                    None => { // no value found here, return default from schema
-                        match X::default_value(0, x.schema_addr, &self.memory.get_schemas()) {
-                            Some(y) => {
-                                Ok(Some(y))
-                            },
-                            None => { // no default in schema, no value to provide
-                                Ok(None)
-                            }
-                        }                        
-                    }
-                }
-            }
-            None => Ok(None)
+        let bytes_data = self.calc_bytes()?;
+
+        if predicate(&bytes_data) == false {
+            return Ok(false)
         }
-    }
 
+        self.compact(None)?;
 
-    /// This performs a compaction if the closure provided as the second argument returns `true`.
-    /// Compaction is a pretty expensive operation (requires full copy of the whole buffer) so should be done sparingly.
-    /// The closure is provided an argument that contains the original size of the buffer, how many bytes could be saved by compaction, and how large the new buffer would be after compaction.  The closure should return `true` to perform compaction, `false` otherwise.
+        Ok(true)
+    }
+
+    /// Compacts a buffer to remove an unused bytes or free space after a mutation.
+    /// This is a pretty expensive operation (requires full copy of the whole buffer) so should be done sparingly.
     /// 
     /// The first argument, new_capacity, is the capacity of the underlying Vec<u8> that we'll be copying the data into.  The default is the size of the old buffer.
     /// 
+    /// - If this buffer is an owned type typically created with `new_buffer` or opened with `open_buffer` the comapction will occur into the existing buffer. 
+    /// - If this buffer is a ref type typically opened with `open_buffer_ref` the compaction will fail.  Use `compact_into` instead.
+    /// - If this buffer is a mutable ref type typically opened with `open_buffer_ref_mut` the compaction will ocurr into the existing buffer and the length will be updated.
+    /// 
     /// **WARNING** Your cursor location will be reset to the root.
     /// 
     /// ```
@@ -1088,14 +4618,7 @@ impl NP_Buffer {
     ///     wasted_bytes: 9
     /// }, new_buffer.calc_bytes()?);
     /// // compact to save space
-    /// new_buffer.maybe_compact(None, |compact_data| {
-    ///     // only compact if wasted bytes are greater than 5
-    ///     if compact_data.wasted_bytes > 5 {
-    ///         true
-    ///     } else {
-    ///         false
-    ///     }
-    /// })?;
+    /// new_buffer.compact(None)?;
     /// // back down to 18 bytes with no wasted bytes
     /// assert_eq!(NP_Size_Data {
     ///     current_buffer: 22,
@@ -1106,145 +4629,357 @@ impl NP_Buffer {
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    pub fn maybe_compact<F>(&mut self, new_capacity: Option<usize>, mut callback: F) -> Result<(), NP_Error> where F: FnMut(NP_Size_Data) -> bool {
+    pub fn compact<'compact>(&mut self, new_capacity: Option<usize>) -> Result<(), NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        let capacity = Some(match new_capacity {
+            Some(x) => { x as usize },
+            None => self.memory.read_bytes().len()
+        });
+
+        let old_root = NP_Cursor::new(self.memory.root, 0, 0);
+        let new_root  = NP_Cursor::new(self.memory.root, 0, 0);
+
+        // comapcting a RefMut buffer, we have to compact into a Vec<u8>, then write it back into the RefMut
+        if self.memory.is_ref_mut() {
+            let new_bytes = NP_Memory::new(capacity, self.memory.get_schemas() as *const Vec<NP_Parsed_Schema>, self.memory.root);
+            NP_Cursor::compact(0, old_root, &self.memory, new_root, &new_bytes)?;
+
+            let new_length = new_bytes.length();
+            let read_bytes = new_bytes.read_bytes();
+            let memory = self.memory.write_bytes();
+
+            for x in 0..memory.len() {
+                if x < new_length {
+                    memory[x] = read_bytes[x];
+                } else {
+                    memory[x] = 0;
+                }
+            }
+
+            self.memory.set_length(new_length)?;
+
+            // interned keys are at the same addresses in the copied bytes
+            self.memory.interned_keys.replace(new_bytes.interned_keys.take());
+
+        // compacting from one owned buffer into itself
+        } else {
+            let new_bytes = self.memory.new_empty(capacity)?;
+            NP_Cursor::compact(0, old_root, &self.memory, new_root, &new_bytes)?;
+            self.memory = new_bytes;
+        }
+
+        self.cursor = NP_Cursor::new(self.memory.root, 0, 0);
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    /// Compacts the buffer and reports how many bytes were reclaimed.
+    /// 
+    /// Works like `.compact(None)` but returns the length of the buffer before the compaction, with `after_compaction` set to the length of the compacted buffer and `wasted_bytes` set to the number of bytes that were reclaimed.  Both lengths are measured, so an error only comes from the compaction itself.
+    /// 
+    /// **WARNING** Your cursor location will be reset to the root.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::NP_Size_Data;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("string()")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&[], "hello")?;
+    /// new_buffer.set(&[], "hello, world")?;
+    /// // compact and find out how much space was saved
+    /// assert_eq!(NP_Size_Data {
+    ///     current_buffer: 31,
+    ///     after_compaction: 22,
+    ///     wasted_bytes: 9
+    /// }, new_buffer.compact_and_report()?);
+    /// assert_eq!(new_buffer.data_length(), 22);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn compact_and_report(&mut self) -> Result<NP_Size_Data, NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        let old_length = self.memory.length();
+
+        self.compact(None)?;
+
+        // both sizes are measured, not estimated
+        let new_length = self.memory.length();
+
+        Ok(NP_Size_Data {
+            current_buffer: old_length,
+            after_compaction: new_length,
+            wasted_bytes: old_length.saturating_sub(new_length)
+        })
+    }
+
+    /// Compact the current buffer into a new owned buffer.
+    /// Returns an owned buffer of the compacted result.
+    /// 
+    /// This works identically to `.compact` except compaction happens into a new buffer, instead of into the existing buffer.
+    /// 
+    /// If the buffer was opened as read only with `.open_buffer_ref` this is the only way to do compaction.
+    /// 
+    /// The new buffer is tagged if this one is.
+    /// 
+    pub fn compact_into(&mut self, new_capacity: Option<usize>) -> Result<NP_Buffer, NP_Error> {
+
+        let capacity = Some(match new_capacity {
+            Some(x) => { x as usize },
+            None => self.memory.read_bytes().len()
+        });
+
+        let old_root = NP_Cursor::new(self.memory.root, 0, 0);
+
+        let new_bytes = NP_Memory::new(capacity, self.memory.get_schemas() as *const Vec<NP_Parsed_Schema>, self.memory.root);
+        let new_root  = NP_Cursor::new(self.memory.root, 0, 0);
+
+        NP_Cursor::compact(0, old_root, &self.memory, new_root, &new_bytes)?;
+
+        self.cursor = NP_Cursor::new(self.memory.root, 0, 0);
+
+        let mut new_buffer = NP_Buffer::_new(new_bytes);
+        new_buffer._set_schema_tag(self.schema_tag);
+        Ok(new_buffer)
+    }
+
+    /// Copy the value at `path` and all it's children into a new compacted buffer with it's own schema.
+    /// 
+    /// The schema of `sub_factory` has to match the schema at `path`, if it doesn't an error is returned.  Use this to split a large record up and store part of it separately.  This buffer isn't changed, if there's no value at the path the new buffer is empty.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     id: u32(),
+    ///     friends: list({of: string()})
+    /// }})"#)?;
+    /// let friends_factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["id"], 20u32)?;
+    /// new_buffer.set(&["friends", "0"], "Jeb")?;
+    /// new_buffer.set(&["friends", "2"], "Bill")?;
+    /// 
+    /// let friends = new_buffer.extract(&["friends"], &friends_factory)?;
+    /// assert_eq!(friends.get::<&str>(&["2"])?, Some("Bill"));
+    /// assert_eq!(friends.get_length(&[])?, Some(3));
+    /// 
+    /// // the schema has to match
+    /// assert!(new_buffer.extract(&["id"], &friends_factory).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn extract(&self, path: &[&str], sub_factory: &NP_Factory) -> Result<NP_Buffer, NP_Error> {
+
+        let schema_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x,
+            None => return Err(NP_Error::new("Unable to find value at path!"))
+        };
+
+        self.check_sub_schema(schema_cursor.schema_addr, &sub_factory.schema.parsed)?;
+
+        let from_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, false, path)? {
+            Some(x) => x,
+            None => return Ok(sub_factory.new_buffer(None))
+        };
+
+        // compact with this buffer's schema so child schema addresses line up, the bytes don't depend on the schema addresses
+        let new_bytes = NP_Memory::new(None, self.memory.get_schemas() as *const Vec<NP_Parsed_Schema>, self.memory.root);
+        let new_root = NP_Cursor::new(new_bytes.root, from_cursor.schema_addr, from_cursor.parent_schema_addr);
+
+        NP_Cursor::compact(0, from_cursor, &self.memory, new_root, &new_bytes)?;
+
+        Ok(sub_factory.open_buffer(new_bytes.dump()))
+    }
+
+    /// Copy everything in another buffer into this buffer at `path`, the opposite of `.extract()`.
+    /// 
+    /// The root schema of `sub` has to match the schema at `path`, if it doesn't an error is returned.  Whatever is at the path now is deleted first and replaced with the contents of `sub`, so grafting an empty buffer just deletes the value.  The old bytes are wasted space until the buffer is compacted.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     id: u32(),
+    ///     friends: list({of: string()})
+    /// }})"#)?;
+    /// let friends_factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    /// 
+    /// let mut friends = friends_factory.new_buffer(None);
+    /// friends.set(&["0"], "Jeb")?;
+    /// friends.set(&["1"], "Bill")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["friends", "5"], "Bob")?;
+    /// new_buffer.graft(&["friends"], &friends)?;
+    /// 
+    /// assert_eq!(new_buffer.get::<&str>(&["friends", "1"])?, Some("Bill"));
+    /// assert_eq!(new_buffer.get::<&str>(&["friends", "5"])?, None);
+    /// 
+    /// // the schema has to match
+    /// assert!(new_buffer.graft(&["id"], &friends).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn graft(&mut self, path: &[&str], sub: &NP_Buffer) -> Result<(), NP_Error> {
 
         if self.mutable == false {
             return Err(NP_Error::MemoryReadOnly)
         }
 
-        let bytes_data = self.calc_bytes()?;
+        let schema_cursor = match NP_Cursor::select(&self.memory, self.cursor.clone(), false, true, path)? {
+            Some(x) => x,
+            None => return Err(NP_Error::new("Unable to find value at path!"))
+        };
+
+        self.check_sub_schema(schema_cursor.schema_addr, sub.memory.get_schemas())?;
+
+        self.del(path)?;
+
+        // read the other buffer with this buffer's schema so child schema addresses line up
+        let sub_memory = NP_Memory::existing_ref(sub.memory.read_bytes() as *const [u8], self.memory.get_schemas() as *const Vec<NP_Parsed_Schema>, sub.memory.root);
+        let from_cursor = NP_Cursor::new(sub_memory.root, schema_cursor.schema_addr, schema_cursor.parent_schema_addr);
+
+        if from_cursor.get_value(&sub_memory).get_addr_value() == 0 {
+            return Ok(())
+        }
+
+        let to_cursor = opt_err(NP_Cursor::select(&self.memory, self.cursor.clone(), true, false, path)?)?;
+
+        NP_Cursor::compact(0, from_cursor, &sub_memory, to_cursor, &self.memory)?;
+
+        Ok(())
+    }
+
+    /// Make sure the root of another schema matches the schema at `schema_addr`, used to move values between buffers of different factories
+    fn check_sub_schema(&self, schema_addr: usize, sub_schema: &Vec<NP_Parsed_Schema>) -> Result<(), NP_Error> {
 
-        if callback(bytes_data) {
-            self.compact(new_capacity)?;
+        let path_json = NP_Schema::_type_to_json(self.memory.get_schemas(), schema_addr)?;
+        let sub_json = NP_Schema::_type_to_json(sub_schema, 0)?;
+
+        if path_json != sub_json {
+            let mut err = "TypeError: Schema (".to_owned();
+            err.push_str(sub_json.stringify().as_str());
+            err.push_str(") doesn't match the schema at path (");
+            err.push_str(path_json.stringify().as_str());
+            err.push_str(")\n");
+            return Err(NP_Error::new(err));
         }
 
-        return Ok(());
+        Ok(())
     }
 
-    /// Compacts a buffer to remove an unused bytes or free space after a mutation.
-    /// This is a pretty expensive operation (requires full copy of the whole buffer) so should be done sparingly.
+    /// Copy the current buffer into a new owned buffer.
     /// 
-    /// The first argument, new_capacity, is the capacity of the underlying Vec<u8> that we'll be copying the data into.  The default is the size of the old buffer.
+    /// The copy is tagged if this buffer is.
     /// 
-    /// - If this buffer is an owned type typically created with `new_buffer` or opened with `open_buffer` the comapction will occur into the existing buffer. 
-    /// - If this buffer is a ref type typically opened with `open_buffer_ref` the compaction will fail.  Use `compact_into` instead.
-    /// - If this buffer is a mutable ref type typically opened with `open_buffer_ref_mut` the compaction will ocurr into the existing buffer and the length will be updated.
+    pub fn copy_buffer(&self) -> NP_Buffer {
+        let copy_bytes = self.memory.read_bytes().to_vec();
+        let new_memory = NP_Memory::existing_owned(copy_bytes, self.memory.get_schemas() as *const Vec<NP_Parsed_Schema>, self.memory.root);
+        let mut new_buffer = NP_Buffer::_new(new_memory);
+        new_buffer._set_schema_tag(self.schema_tag);
+        new_buffer
+    }
+
+    /// Compare the values in two buffers, regardless of how the bytes are laid out.
     /// 
-    /// **WARNING** Your cursor location will be reset to the root.
+    /// Buffers holding the same data can be different byte for byte because of wasted space or the order values were set in.  This method compares what's actually stored instead: maps are compared without regard to key order, lists are compared index by index.  An unset value is not equal to a value set to the schema default.
+    /// 
+    /// Both buffers should be created with the same schema, values are compared from the root of each buffer.
     /// 
     /// ```
     /// use no_proto::error::NP_Error;
     /// use no_proto::NP_Factory;
-    /// use no_proto::NP_Size_Data;
     /// 
-    /// let factory: NP_Factory = NP_Factory::new("string()")?;
+    /// let factory: NP_Factory = NP_Factory::new("map({value: string()})")?;
     /// 
-    /// let mut new_buffer = factory.new_buffer(None);
-    /// // set initial value
-    /// new_buffer.set(&[], "hello")?;
-    /// // using 15 bytes
-    /// assert_eq!(NP_Size_Data {
-    ///     current_buffer: 15,
-    ///     after_compaction: 15,
-    ///     wasted_bytes: 0
-    /// }, new_buffer.calc_bytes()?);
-    /// // update the value
-    /// new_buffer.set(&[], "hello, world")?;
-    /// // now using 31 bytes, with 9 bytes of wasted space
-    /// assert_eq!(NP_Size_Data {
-    ///     current_buffer: 31,
-    ///     after_compaction: 22,
-    ///     wasted_bytes: 9
-    /// }, new_buffer.calc_bytes()?);
-    /// // compact to save space
-    /// new_buffer.compact(None)?;
-    /// // back down to 18 bytes with no wasted bytes
-    /// assert_eq!(NP_Size_Data {
-    ///     current_buffer: 22,
-    ///     after_compaction: 22,
-    ///     wasted_bytes: 0
-    /// }, new_buffer.calc_bytes()?);
+    /// let mut buffer_a = factory.new_buffer(None);
+    /// buffer_a.set(&["name"], "Jeb")?;
+    /// buffer_a.set(&["name"], "Jeb Kermin")?;
+    /// buffer_a.set(&["job"], "pilot")?;
+    /// 
+    /// let mut buffer_b = factory.new_buffer(None);
+    /// buffer_b.set(&["job"], "pilot")?;
+    /// buffer_b.set(&["name"], "Jeb Kermin")?;
+    /// 
+    /// assert_ne!(buffer_a.read_bytes(), buffer_b.read_bytes());
+    /// assert!(buffer_a.value_eq(&buffer_b)?);
+    /// 
+    /// buffer_b.set(&["job"], "engineer")?;
+    /// assert!(!buffer_a.value_eq(&buffer_b)?);
     /// 
     /// # Ok::<(), NP_Error>(()) 
     /// ```
     /// 
-    pub fn compact<'compact>(&mut self, new_capacity: Option<usize>) -> Result<(), NP_Error> {
-
-        if self.mutable == false {
-            return Err(NP_Error::MemoryReadOnly)
-        }
-
-        let capacity = Some(match new_capacity {
-            Some(x) => { x as usize },
-            None => self.memory.read_bytes().len()
-        });
-
-        let old_root = NP_Cursor::new(self.memory.root, 0, 0);
-        let new_root  = NP_Cursor::new(self.memory.root, 0, 0);
+    pub fn value_eq(&self, other: &NP_Buffer) -> Result<bool, NP_Error> {
 
-        // comapcting a RefMut buffer, we have to compact into a Vec<u8>, then write it back into the RefMut
-        if self.memory.is_ref_mut() {
-            let new_bytes = NP_Memory::new(capacity, self.memory.get_schemas() as *const Vec<NP_Parsed_Schema>, self.memory.root);
-            NP_Cursor::compact(0, old_root, &self.memory, new_root, &new_bytes)?;
-
-            let new_length = new_bytes.length();
-            let read_bytes = new_bytes.read_bytes();
-            let memory = self.memory.write_bytes();
-
-            for x in 0..memory.len() {
-                if x < new_length {
-                    memory[x] = read_bytes[x];
-                } else {
-                    memory[x] = 0;
-                }
-            }
-
-            self.memory.set_length(new_length)?;
-
-        // compacting from one owned buffer into itself
-        } else {
-            let new_bytes = self.memory.new_empty(capacity)?;
-            NP_Cursor::compact(0, old_root, &self.memory, new_root, &new_bytes)?;
-            self.memory = new_bytes;
+        // sortable tuples have fixed layouts, identical bytes mean identical values
+        if self.memory.get_schema(0).sortable && self.memory.read_data() == other.memory.read_data() {
+            return Ok(true)
         }
 
-        self.cursor = NP_Cursor::new(self.memory.root, 0, 0);
+        let a_root = NP_Cursor::new(self.memory.root, 0, 0);
+        let b_root = NP_Cursor::new(other.memory.root, 0, 0);
 
-        Ok(())
+        NP_Cursor::value_eq(0, &a_root, &self.memory, &b_root, &other.memory)
     }
 
-    /// Compact the current buffer into a new owned buffer.
-    /// Returns an owned buffer of the compacted result.
+    /// Get a compacted, independent copy of the buffer.
     /// 
-    /// This works identically to `.compact` except compaction happens into a new buffer, instead of into the existing buffer.
+    /// Unlike `clone` and `copy_buffer`, which copy the bytes as they are, the values are compacted into a new owned buffer sized to fit exactly.  The original buffer is left untouched, so this is a cheap way to fork a record before changing it.  Copies of tagged buffers are tagged too.
     /// 
-    /// If the buffer was opened as read only with `.open_buffer_ref` this is the only way to do compaction.
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
     /// 
-    pub fn compact_into(&mut self, new_capacity: Option<usize>) -> Result<NP_Buffer, NP_Error> {
+    /// let factory: NP_Factory = NP_Factory::new("string()")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&[], "hello")?;
+    /// new_buffer.set(&[], "hello, world")?;
+    /// 
+    /// let mut fork = new_buffer.deep_clone()?;
+    /// assert_eq!(fork.data_length(), 22);
+    /// assert_eq!(new_buffer.data_length(), 31);
+    /// 
+    /// fork.set(&[], "goodbye")?;
+    /// assert_eq!(fork.get::<&str>(&[])?, Some("goodbye"));
+    /// assert_eq!(new_buffer.get::<&str>(&[])?, Some("hello, world"));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn deep_clone(&self) -> Result<NP_Buffer, NP_Error> {
 
-        let capacity = Some(match new_capacity {
-            Some(x) => { x as usize },
-            None => self.memory.read_bytes().len()
-        });
+        let capacity = self.calc_bytes()?.after_compaction;
 
         let old_root = NP_Cursor::new(self.memory.root, 0, 0);
 
-        let new_bytes = NP_Memory::new(capacity, self.memory.get_schemas() as *const Vec<NP_Parsed_Schema>, self.memory.root);
+        let new_bytes = NP_Memory::new(Some(capacity), self.memory.get_schemas() as *const Vec<NP_Parsed_Schema>, self.memory.root);
         let new_root  = NP_Cursor::new(self.memory.root, 0, 0);
 
         NP_Cursor::compact(0, old_root, &self.memory, new_root, &new_bytes)?;
 
-        self.cursor = NP_Cursor::new(self.memory.root, 0, 0);
-
-        Ok(NP_Buffer::_new(new_bytes))
-    }
-
-    /// Copy the current buffer into a new owned buffer.
-    /// 
-    pub fn copy_buffer(&self) -> NP_Buffer {
-        let copy_bytes = self.memory.read_bytes().to_vec();
-        let new_memory = NP_Memory::existing_owned(copy_bytes, self.memory.get_schemas() as *const Vec<NP_Parsed_Schema>, self.memory.root);
-        NP_Buffer::_new(new_memory)
+        let mut new_buffer = NP_Buffer::_new(new_bytes);
+        new_buffer._set_schema_tag(self.schema_tag);
+        Ok(new_buffer)
     }
 
     /// Recursively measures how many bytes each element in the buffer is using.
@@ -1271,7 +5006,7 @@ impl NP_Buffer {
     pub fn calc_bytes<'bytes>(&self) -> Result<NP_Size_Data, NP_Error> {
 
         let root = NP_Cursor::new(self.memory.root, 0, 0);
-        let real_bytes = NP_Cursor::calc_size(0, &root, &self.memory)? + self.memory.root;
+        let real_bytes = NP_Cursor::calc_size(0, &root, &self.memory, &mut BTreeSet::new())? + self.memory.root;
         let total_size = self.memory.length();
 
         if total_size >= real_bytes {
@@ -1285,6 +5020,38 @@ impl NP_Buffer {
         }
     }
 
+    /// Calculate how many bytes in the buffer are not being used by any value.
+    /// 
+    /// Old values that have been replaced, the values of deleted list & map items and unused struct vtables are all counted.  These are the bytes that would be reclaimed by compaction.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["0"], "hello")?;
+    /// new_buffer.set(&["1"], "world")?;
+    /// assert_eq!(new_buffer.calc_wasted_bytes()?, 0);
+    /// 
+    /// // the value of the list item is now wasted
+    /// new_buffer.del(&["1"])?;
+    /// assert_eq!(new_buffer.calc_wasted_bytes()?, 9);
+    /// 
+    /// // compact if more than 10% of the buffer is wasted
+    /// if new_buffer.calc_wasted_bytes()? * 10 > new_buffer.data_length() {
+    ///     new_buffer.compact(None)?;
+    /// }
+    /// assert_eq!(new_buffer.calc_wasted_bytes()?, 0);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn calc_wasted_bytes(&self) -> Result<usize, NP_Error> {
+        Ok(self.calc_bytes()?.wasted_bytes)
+    }
+
 
     /// Set the maximum allowed of size of this buffer, in bytes.
     /// 
@@ -1296,13 +5063,252 @@ impl NP_Buffer {
         self.memory.set_max_length(len);
     }
 
+    /// Set the maximum depth that will be followed when walking values, the default is 255.
+    /// 
+    /// Portals allow schemas to be recursive, so buffers can contain very deeply nested (or malicious, self referencing) data.  Selecting a value more than `depth` levels deep will return `NP_Error::RecursionLimit` and exporting to JSON will stop at `depth` levels, returning `null` for anything deeper.  Every other walk over the buffer (size calculations, compaction, cloning, comparing, validating, setting JSON and filling defaults) returns `NP_Error::RecursionLimit` once it gets deeper than `depth`.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {value: u8(), next: portal({to: \"\"})}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["next", "next", "value"], 5u8)?;
+    /// 
+    /// new_buffer.set_max_depth(3);
+    /// assert_eq!(new_buffer.get::<u8>(&["next", "value"])?, None);
+    /// assert!(new_buffer.get::<u8>(&["next", "next", "value"]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.memory.set_max_depth(depth);
+    }
+
     /// Get the number of bytes used by the data in this buffer.
     /// 
-    /// This will be identical to `buffer.read_bytes().len()` unless you're using a RefMut buffer.
+    /// This will be identical to `buffer.read_bytes().len()` unless you're using a buffer opened with `open_buffer_ref`.
     /// 
     pub fn data_length(&self) -> usize {
         self.memory.length()
     }
+
+    /// Get how many bytes the buffer can hold before it has to grow.
+    /// 
+    /// For `open_buffer_ref` and `open_buffer_ref_mut` buffers this is the length of the slice.
+    /// 
+    pub fn capacity(&self) -> usize {
+        self.memory.capacity()
+    }
+
+    /// Release the capacity the buffer isn't using.
+    /// 
+    /// Buffers start with extra capacity so they don't have to reallocate as values are set.  Once a buffer is done changing, calling this before keeping it around keeps thousands of cached buffers from holding onto memory they'll never use.  Run `compact` first to also reclaim the bytes wasted by old values.
+    /// 
+    /// Only buffers that own their bytes can be shrunk, this does nothing for ref buffers and buffers from `new_buffer_in`.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {age: u8()}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(Some(1024));
+    /// new_buffer.set(&["age"], 20u8)?;
+    /// assert_eq!(new_buffer.capacity(), 1024);
+    /// 
+    /// new_buffer.shrink_to_fit();
+    /// assert_eq!(new_buffer.capacity(), new_buffer.data_length());
+    /// assert_eq!(new_buffer.get::<u8>(&["age"])?, Some(20));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn shrink_to_fit(&mut self) {
+        self.memory.shrink_to_fit();
+    }
+
+    /// Remove all the data from the buffer, keeping the memory it has already allocated.
+    ///
+    /// Afterwards the buffer is the same as a new buffer from the same factory, this lets a hot loop reuse one buffer instead of allocating a new one every time.  Cursor handles from before the reset can't be used anymore.
+    ///
+    /// Buffers opened with `open_buffer_ref` are read only and can't be reset.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string()}})")?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(Some(1024));
+    ///
+    /// for name in ["Jeb", "Bill", "Bob"].iter() {
+    ///     new_buffer.reset()?;
+    ///     new_buffer.set(&["name"], *name)?;
+    ///     assert_eq!(new_buffer.get::<&str>(&["name"])?, Some(*name));
+    /// }
+    ///
+    /// new_buffer.reset()?;
+    /// assert_eq!(new_buffer.get::<&str>(&["name"])?, None);
+    /// assert_eq!(new_buffer.data_length(), 6);
+    /// assert_eq!(new_buffer.capacity(), 1024);
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn reset(&mut self) -> Result<(), NP_Error> {
+
+        if self.mutable == false {
+            return Err(NP_Error::MemoryReadOnly)
+        }
+
+        self.memory.reset()?;
+        self.cursor = NP_Cursor::new(self.memory.root, 0, 0);
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    /// Get how many allocations have been made in this buffer and how many bytes they used.
+    /// 
+    /// The counts start at zero when a buffer is created or opened.  Compaction moves the data into new memory, so afterwards the counts only include the allocations made by the compaction and everything since.
+    /// 
+    /// This is useful for picking a good `capacity` for new buffers.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::buffer::NP_Memory_Stats;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// assert_eq!(new_buffer.memory_stats(), NP_Memory_Stats { malloc_count: 0, bytes_allocated: 0 });
+    /// 
+    /// new_buffer.set(&["0"], "hello")?;
+    /// // list head/tail, list item, string length and string bytes
+    /// assert_eq!(new_buffer.memory_stats(), NP_Memory_Stats { malloc_count: 4, bytes_allocated: 8 + 10 + 4 + 5 });
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn memory_stats(&self) -> NP_Memory_Stats {
+        NP_Memory_Stats {
+            malloc_count: self.memory.malloc_count.get(),
+            bytes_allocated: self.memory.bytes_allocated.get()
+        }
+    }
+
+    /// Start counting how many times each value in the schema is read with `get`.
+    /// 
+    /// Tracking is off by default and the counts are kept in this buffer only, other buffers from the same factory aren't affected.  Calling this again on a buffer that's already tracking does nothing.
+    /// 
+    /// Use `access_stats` to get the counts, for example to decide which fields are hot enough to keep near the front of the buffer.
+    /// 
+    pub fn enable_access_tracking(&mut self) {
+        if self.access_counts.is_none() {
+            self.access_counts = Some((0..self.memory.get_schemas().len()).map(|_| Cell::new(0)).collect());
+        }
+    }
+
+    /// Get how many times each path was read since `enable_access_tracking` was called.
+    /// 
+    /// Reads are counted per schema value, so every item of a list or map is counted under one path with `*` in place of the index or key.  Only paths that were read at least once are returned, in schema order.  If tracking isn't enabled the result is empty.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"
+    ///     struct({fields: {
+    ///         name: string(),
+    ///         tags: list({of: string()})
+    ///     }})
+    /// "#)?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["name"], "hello")?;
+    /// new_buffer.set(&["tags", "0"], "a")?;
+    /// new_buffer.set(&["tags", "1"], "b")?;
+    /// 
+    /// new_buffer.enable_access_tracking();
+    /// 
+    /// new_buffer.get::<&str>(&["name"])?;
+    /// new_buffer.get::<&str>(&["tags", "0"])?;
+    /// new_buffer.get::<&str>(&["tags", "1"])?;
+    /// 
+    /// assert_eq!(new_buffer.access_stats(), vec![
+    ///     (vec![String::from("name")], 1),
+    ///     (vec![String::from("tags"), String::from("*")], 2)
+    /// ]);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn access_stats(&self) -> Vec<(Vec<String>, u32)> {
+        let counts = match &self.access_counts {
+            Some(counts) => counts,
+            None => return Vec::new()
+        };
+
+        let schemas = self.memory.get_schemas();
+        let mut paths: Vec<Option<Vec<String>>> = (0..schemas.len()).map(|_| None).collect();
+        NP_Schema::_schema_paths(schemas, 0, &mut Vec::new(), &mut paths);
+
+        counts.iter().zip(paths.into_iter()).filter_map(|(count, path)| {
+            match (count.get(), path) {
+                (0, _) => None,
+                (count, Some(path)) => Some((path, count)),
+                (_, None) => None
+            }
+        }).collect()
+    }
+}
+
+/// Build a rust struct from the columns of a NoProto struct, used by [`NP_Buffer::get_columns`](struct.NP_Buffer.html#method.get_columns).
+///
+/// `from_column` is called once for every field in the schema, in schema order.  Ignore the columns you don't need.
+///
+/// Most of the time the [`columns!`](../macro.columns.html) macro can write this for you.
+///
+pub trait FromColumns: Default {
+    /// Read one column into `self`
+    fn from_column(&mut self, column: &NP_Column) -> Result<(), NP_Error>;
+}
+
+/// A single column of a struct, passed to [`FromColumns::from_column`](trait.FromColumns.html#tymethod.from_column).
+pub struct NP_Column<'column> {
+    /// Name of this column
+    pub name: &'column str,
+    cursor: Option<NP_Cursor>,
+    schema_addr: usize,
+    memory: &'column NP_Memory
+}
+
+impl<'column> NP_Column<'column> {
+
+    /// Get the value of this column, or the schema default if it isn't set.
+    ///
+    /// The type you request is compared to the schema, if it doesn't match the request will fail.
+    pub fn get<X>(&self) -> Result<Option<X>, NP_Error> where X: NP_Value<'column> + NP_Scalar<'column> {
+
+        let schema = self.memory.get_schema(self.schema_addr);
+
+        // type does not match schema
+        if X::type_idx().1 != schema.i {
+            return Err(NP_Buffer::type_mismatch(X::type_idx().0, &schema.i));
+        }
+
+        if let Some(cursor) = &self.cursor {
+            if let Some(x) = X::into_value(cursor, self.memory)? {
+                return Ok(Some(x));
+            }
+        }
+
+        Ok(X::default_value(0, self.schema_addr, self.memory.get_schemas()))
+    }
 }
 
 /// NP Item
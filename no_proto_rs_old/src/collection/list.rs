@@ -1,4 +1,5 @@
 use alloc::{string::String, sync::Arc};
+use alloc::collections::BTreeSet;
 use crate::{idl::{JS_AST, JS_Schema}, schema::{NP_Map_List_Data, NP_Value_Kind}, utils::opt_err};
 use crate::{error::NP_Error, json_flex::{JSMAP, NP_JSON}, memory::{NP_Memory}, pointer::{NP_Value}, pointer::{NP_Cursor}, schema::NP_Parsed_Schema, schema::{NP_Schema, NP_TypeKeys}};
 
@@ -193,6 +194,41 @@ impl NP_List {
         Ok(())
     }
 
+    /// Replace the list with a new one holding `count` empty items, indexed from zero.
+    /// 
+    /// The list and all of its items are allocated together, the address of the first item is returned and each following item is 10 bytes after the last one.
+    pub fn make_items<'make>(list_cursor: &NP_Cursor, memory: &'make NP_Memory, count: usize) -> Result<usize, NP_Error> {
+        if count > u16::MAX as usize + 1 {
+            return Err(NP_Error::new("Index cannot be greater than 2^16!"))
+        }
+
+        let list_addr = memory.malloc_borrow(&vec![0u8; 8 + (count * 10)])?; // head & tail, then items
+        list_cursor.get_value_mut(memory).set_addr_value(list_addr as u32);
+
+        if count == 0 {
+            return Ok(0)
+        }
+
+        let data = unsafe { &*(*memory.get_schema(list_cursor.schema_addr).data as *const NP_Map_List_Data) };
+        let first_item = list_addr + 8;
+
+        for index in 0..count {
+            let item_addr = first_item + (index * 10);
+            let item = NP_Cursor::new(item_addr, data.child, list_cursor.schema_addr);
+            let item_value = item.get_value_mut(memory);
+            item_value.set_index(index as u16);
+            if index + 1 < count {
+                item_value.set_next_addr((item_addr + 10) as u32);
+            }
+        }
+
+        let list_data = Self::get_list(list_addr, memory);
+        list_data.set_head(first_item as u32);
+        list_data.set_tail((first_item + ((count - 1) * 10)) as u32);
+
+        Ok(first_item)
+    }
+
     #[inline(always)]
     pub fn get_list<'list>(list_cursor_value_addr: usize, memory: &'list NP_Memory) -> &'list mut NP_List_Bytes {
         if list_cursor_value_addr > memory.read_bytes().len() { // attack
@@ -360,6 +396,162 @@ impl NP_List {
              
         
     }
+
+    /// Insert a new item at the given index, every item at or after the index is moved back by one.
+    #[inline(always)]
+    pub fn insert_at<'insert>(list_cursor: &NP_Cursor, memory: &NP_Memory, index: u16) -> Result<NP_Cursor, NP_Error> {
+
+        let list_value = || {list_cursor.get_value(memory)};
+
+        if list_value().get_addr_value() == 0 {
+            Self::make_list(&list_cursor, memory)?;
+        }
+
+        let data = unsafe { &*(*memory.get_schema(list_cursor.schema_addr).data as *const NP_Map_List_Data) };
+
+        let list_data = || {Self::get_list(list_value().get_addr_value() as usize, memory)};
+
+        let item = |addr: usize| { NP_Cursor::new(addr, data.child, list_cursor.schema_addr) };
+
+        // find the first item at or after the index
+        let mut prev_addr = 0usize;
+        let mut next_addr = list_data().get_head() as usize;
+        while next_addr != 0 && item(next_addr).get_value(memory).get_index() < index {
+            prev_addr = next_addr;
+            next_addr = item(next_addr).get_value(memory).get_next_addr() as usize;
+        }
+
+        if next_addr != 0 && item(list_data().get_tail() as usize).get_value(memory).get_index() == u16::MAX {
+            return Err(NP_Error::new("Index cannot be greater than 2^16!"))
+        }
+
+        // move following items back
+        let mut shift_addr = next_addr;
+        while shift_addr != 0 {
+            let shift_value = item(shift_addr).get_value_mut(memory);
+            shift_value.set_index(shift_value.get_index() + 1);
+            shift_addr = shift_value.get_next_addr() as usize;
+        }
+
+        let new_item_addr = memory.malloc_borrow(&[0u8; 10])?; // list item
+        let new_cursor = item(new_item_addr);
+        new_cursor.get_value_mut(memory).set_index(index);
+        new_cursor.get_value_mut(memory).set_next_addr(next_addr as u32);
+
+        if prev_addr == 0 {
+            list_data().set_head(new_item_addr as u32);
+        } else {
+            item(prev_addr).get_value_mut(memory).set_next_addr(new_item_addr as u32);
+        }
+
+        if next_addr == 0 {
+            list_data().set_tail(new_item_addr as u32);
+        }
+
+        Ok(new_cursor)
+    }
+
+    /// Remove the item at the given index, every item after the index is moved forward by one.
+    /// 
+    /// Returns `false` if the index is past the end of the list.
+    #[inline(always)]
+    pub fn remove_at<'remove>(list_cursor: &NP_Cursor, memory: &NP_Memory, index: u16) -> Result<bool, NP_Error> {
+
+        let list_value = || {list_cursor.get_value(memory)};
+
+        if list_value().get_addr_value() == 0 {
+            return Ok(false)
+        }
+
+        let data = unsafe { &*(*memory.get_schema(list_cursor.schema_addr).data as *const NP_Map_List_Data) };
+
+        let list_data = || {Self::get_list(list_value().get_addr_value() as usize, memory)};
+
+        let item = |addr: usize| { NP_Cursor::new(addr, data.child, list_cursor.schema_addr) };
+
+        let tail_addr = list_data().get_tail() as usize;
+
+        if tail_addr == 0 || item(tail_addr).get_value(memory).get_index() < index {
+            return Ok(false)
+        }
+
+        // find the first item at or after the index
+        let mut prev_addr = 0usize;
+        let mut next_addr = list_data().get_head() as usize;
+        while next_addr != 0 && item(next_addr).get_value(memory).get_index() < index {
+            prev_addr = next_addr;
+            next_addr = item(next_addr).get_value(memory).get_next_addr() as usize;
+        }
+
+        // unlink the item at the index
+        if next_addr != 0 && item(next_addr).get_value(memory).get_index() == index {
+            let after_addr = item(next_addr).get_value(memory).get_next_addr();
+
+            if prev_addr == 0 {
+                list_data().set_head(after_addr);
+            } else {
+                item(prev_addr).get_value_mut(memory).set_next_addr(after_addr);
+            }
+
+            if after_addr == 0 {
+                list_data().set_tail(prev_addr as u32);
+            }
+
+            next_addr = after_addr as usize;
+        }
+
+        // move following items forward
+        while next_addr != 0 {
+            let shift_value = item(next_addr).get_value_mut(memory);
+            shift_value.set_index(shift_value.get_index() - 1);
+            next_addr = shift_value.get_next_addr() as usize;
+        }
+
+        Ok(true)
+    }
+
+    /// Drop every item at or after the given index, the dropped items are left in the buffer as wasted bytes.
+    /// 
+    /// Does nothing if the list is already shorter than `len`.
+    #[inline(always)]
+    pub fn truncate<'truncate>(list_cursor: &NP_Cursor, memory: &NP_Memory, len: usize) -> Result<(), NP_Error> {
+
+        let list_value = || {list_cursor.get_value(memory)};
+
+        if list_value().get_addr_value() == 0 {
+            return Ok(())
+        }
+
+        let data = unsafe { &*(*memory.get_schema(list_cursor.schema_addr).data as *const NP_Map_List_Data) };
+
+        let list_data = || {Self::get_list(list_value().get_addr_value() as usize, memory)};
+
+        let item = |addr: usize| { NP_Cursor::new(addr, data.child, list_cursor.schema_addr) };
+
+        let tail_addr = list_data().get_tail() as usize;
+
+        if tail_addr == 0 || (item(tail_addr).get_value(memory).get_index() as usize) < len {
+            return Ok(())
+        }
+
+        // find the last item before the index
+        let mut prev_addr = 0usize;
+        let mut next_addr = list_data().get_head() as usize;
+        while next_addr != 0 && (item(next_addr).get_value(memory).get_index() as usize) < len {
+            prev_addr = next_addr;
+            next_addr = item(next_addr).get_value(memory).get_next_addr() as usize;
+        }
+
+        if prev_addr == 0 { // nothing left in the list
+            list_data().set_head(0);
+        } else {
+            item(prev_addr).get_value_mut(memory).set_next_addr(0);
+        }
+
+        list_data().set_tail(prev_addr as u32);
+
+        Ok(())
+    }
 }
 
 impl<'value> NP_Value<'value> for NP_List {
@@ -429,7 +621,7 @@ impl<'value> NP_Value<'value> for NP_List {
         Ok(())
     }
 
-    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
 
@@ -446,7 +638,7 @@ impl<'value> NP_Value<'value> for NP_List {
 
         while let Some((_index, item)) = Self::step_iter(&mut list_iter, memory) {
             if let Some(item_cursor) = &item {
-                acc_size += NP_Cursor::calc_size(depth + 1, item_cursor, memory)?;
+                acc_size += NP_Cursor::calc_size(depth + 1, item_cursor, memory, sized_keys)?;
             }
         }
 
@@ -496,7 +688,7 @@ impl<'value> NP_Value<'value> for NP_List {
             val: NP_Value_Kind::Pointer,
             i: NP_TypeKeys::List,
             sortable: false,
-            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: list_schema_addr + 1 })) as *const u8)
+            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: list_schema_addr + 1, intern_keys: false })) as *const u8)
         });
 
         let mut of_jst: Option<&JS_AST> = None;
@@ -535,7 +727,7 @@ impl<'value> NP_Value<'value> for NP_List {
         schema.push(NP_Parsed_Schema {
             val: NP_Value_Kind::Pointer,
             i: NP_TypeKeys::List,
-            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: list_schema_addr + 1})) as *const u8),
+            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: list_schema_addr + 1, intern_keys: false })) as *const u8),
             sortable: false
         });
 
@@ -547,7 +739,7 @@ impl<'value> NP_Value<'value> for NP_List {
         }
 
         // let of_addr = schema.len();
-        let (_sortable, child_bytes, schema) = NP_Schema::from_json(schema, &Box::new(json_schema["of"].clone()))?;
+        let (_sortable, child_bytes, schema) = NP_Schema::from_json(schema, &Box::new(json_schema["of"].clone())).map_err(|e| e.in_schema(&["of"]))?;
         
         schema_bytes.extend(child_bytes);
 
@@ -565,7 +757,7 @@ impl<'value> NP_Value<'value> for NP_List {
             val: NP_Value_Kind::Pointer,
             i: NP_TypeKeys::List,
             sortable: false,
-            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: list_schema_addr + 1})) as *const u8)
+            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: list_schema_addr + 1, intern_keys: false })) as *const u8)
         });
         
         let (_sortable, schema) = NP_Schema::from_bytes(schema, address + 1, bytes);
@@ -646,4 +838,224 @@ fn parseing_works() -> Result<(), NP_Error> {
     assert_eq!(new_buffer.get::<&str>(&["10"])?.unwrap(), "world");
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn insert_remove_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("list({of: u8()})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    // empty list
+    assert_eq!(buffer.list_remove(&[], 0)?, false);
+    buffer.list_insert(&[], 0, 2u8)?;
+    buffer.list_insert(&[], 0, 1u8)?;
+    buffer.list_insert(&[], 5, 6u8)?;
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[1,2,null,null,null,6]}"#);
+
+    // inserting into a gap
+    buffer.list_insert(&[], 3, 4u8)?;
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[1,2,null,4,null,null,6]}"#);
+
+    // removing a gap, the head and the tail
+    assert_eq!(buffer.list_remove(&[], 2)?, true);
+    assert_eq!(buffer.list_remove(&[], 0)?, true);
+    assert_eq!(buffer.list_remove(&[], 4)?, true);
+    // the list ends at the last item
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[2,4]}"#);
+    buffer.list_push(&[], 7u8)?;
+    buffer.list_insert(&[], 2, 5u8)?;
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[2,4,5,7]}"#);
+
+    buffer.compact(None)?;
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[2,4,5,7]}"#);
+
+    // remove everything
+    for _x in 0..4 {
+        buffer.list_remove(&[], 0)?;
+    }
+    assert_eq!(buffer.get_length(&[])?, Some(0));
+    buffer.list_push(&[], 9u8)?;
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[9]}"#);
+
+    Ok(())
+}
+
+#[test]
+fn truncate_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("list({of: u8()})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    // missing list
+    buffer.list_truncate(&[], 2)?;
+    assert_eq!(buffer.get_length(&[])?, None);
+
+    for x in 0..5u8 {
+        buffer.list_push(&[], x)?;
+    }
+    buffer.set(&["8"], 8u8)?;
+
+    // already shorter
+    buffer.list_truncate(&[], 20)?;
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[0,1,2,3,4,null,null,null,8]}"#);
+
+    // cut inside a gap
+    buffer.list_truncate(&[], 7)?;
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[0,1,2,3,4]}"#);
+
+    buffer.list_truncate(&[], 3)?;
+    assert_eq!(buffer.get_length(&[])?, Some(3));
+
+    // tail was updated, pushes continue after the last kept item
+    assert_eq!(buffer.list_push(&[], 9u8)?, Some(3));
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[0,1,2,9]}"#);
+
+    // dropped items are wasted bytes until compaction
+    let before = buffer.calc_bytes()?;
+    buffer.compact(None)?;
+    assert!(buffer.calc_bytes()?.current_buffer < before.current_buffer);
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[0,1,2,9]}"#);
+
+    // truncate everything
+    buffer.list_truncate(&[], 0)?;
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[]}"#);
+    assert_eq!(buffer.list_push(&[], 1u8)?, Some(0));
+
+    Ok(())
+}
+
+#[test]
+fn iter_list_decode_errors_work() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("list({of: string({size: 4, sort: \"desc\"})})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    buffer.set(&["0"], String::from("abcd"))?;
+    buffer.set(&["2"], String::from("efgh"))?;
+
+    // descending strings can't be borrowed, the error comes out with the item
+    let items: Vec<Result<Option<&str>, NP_Error>> = buffer.iter_list(&[])?.collect();
+    assert_eq!(items.len(), 3);
+    assert!(items[0].is_err());
+    assert!(matches!(items[1], Ok(None)));
+    assert!(items[2].is_err());
+    assert!(buffer.iter_list::<&str>(&[])?.collect::<Result<Vec<_>, NP_Error>>().is_err());
+
+    let backward: Vec<Result<Option<&str>, NP_Error>> = buffer.iter_list_rev(&[])?.collect();
+    assert!(backward[0].is_err());
+    assert!(matches!(backward[1], Ok(None)));
+    assert!(backward[2].is_err());
+
+    let owned: Vec<Option<String>> = buffer.iter_list(&[])?.collect::<Result<_, NP_Error>>()?;
+    assert_eq!(owned, vec![Some(String::from("abcd")), None, Some(String::from("efgh"))]);
+
+    Ok(())
+}
+
+#[test]
+fn iter_list_rev_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("struct({fields: {items: list({of: u8()})}})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    // nothing in the buffer yet
+    assert_eq!(buffer.iter_list_rev::<u8>(&["items"])?.count(), 0);
+
+    buffer.set(&["items", "4"], 4u8)?;
+    buffer.set(&["items", "1"], 1u8)?;
+    buffer.set(&["items", "2"], 2u8)?;
+    buffer.list_push(&["items"], 5u8)?;
+    buffer.del(&["items", "2"])?;
+
+    let forward: Vec<Option<u8>> = buffer.iter_list(&["items"])?.collect::<Result<_, NP_Error>>()?;
+    let mut backward: Vec<Option<u8>> = buffer.iter_list_rev(&["items"])?.collect::<Result<_, NP_Error>>()?;
+    assert_eq!(backward, vec![Some(5), Some(4), None, None, Some(1), None]);
+    backward.reverse();
+    assert_eq!(forward, backward);
+
+    assert!(buffer.iter_list_rev::<u16>(&["items"]).is_err());
+    assert!(buffer.iter_list_rev::<u8>(&[]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn list_appender_failed_push_works() -> Result<(), NP_Error> {
+    use crate::pointer::option::NP_Enum;
+
+    let factory = crate::NP_Factory::new("list({of: enum({choices: [\"red\", \"blue\"]})})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    let mut appender = buffer.list_appender(&[])?;
+    assert_eq!(appender.push(NP_Enum::new("red"))?, 0);
+    assert!(appender.push(NP_Enum::new("green")).is_err());
+    // the failed value doesn't use up an index
+    assert_eq!(appender.push(NP_Enum::new("blue"))?, 1);
+
+    assert_eq!(buffer.get_length(&[])?, Some(2));
+    assert_eq!(buffer.get::<NP_Enum>(&["1"])?, Some(NP_Enum::new("blue")));
+
+    Ok(())
+}
+
+#[test]
+fn set_list_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("struct({fields: {items: list({of: u32()}), names: list({of: string()})}})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    buffer.set(&["items", "250"], 7u32)?;
+
+    let values: Vec<u32> = (0..200u32).map(|x| x * 2).collect();
+    buffer.set_list(&["items"], &values)?;
+
+    assert_eq!(buffer.get_length(&["items"])?, Some(200));
+    let found: Vec<Option<u32>> = buffer.iter_list(&["items"])?.collect::<Result<_, NP_Error>>()?;
+    assert_eq!(found, values.iter().map(|x| Some(*x)).collect::<Vec<Option<u32>>>());
+
+    // the new list works like any other
+    buffer.del(&["items", "100"])?;
+    buffer.set(&["items", "150"], 1u32)?;
+    assert_eq!(buffer.list_push(&["items"], 3u32)?, Some(200));
+    assert_eq!(buffer.get::<u32>(&["items", "100"])?, None);
+    assert_eq!(buffer.get::<u32>(&["items", "150"])?, Some(1));
+
+    // a failed type check doesn't make the list
+    let length = buffer.data_length();
+    assert!(buffer.set_list(&["names"], &[1u32]).is_err());
+    assert_eq!(buffer.data_length(), length);
+    assert_eq!(buffer.get_length(&["names"])?, None);
+
+    buffer.set_list(&["names"], &["a", "b"])?;
+    buffer.compact(None)?;
+    assert_eq!(buffer.get::<&str>(&["names", "1"])?, Some("b"));
+    assert_eq!(buffer.get::<u32>(&["items", "199"])?, Some(398));
+    assert_eq!(buffer.get::<u32>(&["items", "200"])?, Some(3));
+
+    buffer.set_list::<&str>(&["names"], &[])?;
+    assert_eq!(buffer.get_length(&["names"])?, Some(0));
+    assert_eq!(buffer.list_push(&["names"], "c")?, Some(0));
+
+    assert!(buffer.set_list(&["names"], &[1u32]).is_err());
+    assert!(buffer.set_list(&[], &[1u32]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn compaction_keeps_deleted_items_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("list({of: string()})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["0"], "hello")?;
+    buffer.set(&["1"], "world")?;
+    buffer.set(&["2"], "again")?;
+    buffer.del(&["2"])?;
+    buffer.del(&["0"])?;
+
+    // only the values are waste, the item slots stay
+    assert_eq!(buffer.calc_bytes()?.wasted_bytes, 18);
+    buffer.compact(None)?;
+    assert_eq!(buffer.calc_bytes()?.wasted_bytes, 0);
+    assert_eq!(buffer.get_length(&[])?, Some(3));
+    assert_eq!(buffer.get::<&str>(&["0"])?, None);
+    assert_eq!(buffer.get::<&str>(&["1"])?, Some("world"));
+    assert_eq!(buffer.list_push(&[], "last")?, Some(3));
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use alloc::{string::String, sync::Arc};
+use alloc::collections::BTreeSet;
 use crate::{idl::{JS_AST, JS_Schema}, pointer::NP_Cursor, schema::{NP_Map_List_Data, NP_Value_Kind}};
 use crate::{json_flex::JSMAP};
+use crate::hashmap::{murmurhash3_x86_32, SEED};
 use crate::pointer::{NP_Value};
 use crate::{memory::{NP_Memory}, schema::{NP_Schema, NP_TypeKeys, NP_Parsed_Schema}, error::NP_Error, json_flex::NP_JSON};
 
@@ -29,6 +31,10 @@ impl NP_Map_Bytes {
     }
 }
 
+/// Schema byte for maps with `intern_keys`, it comes before the value schema.  Type keys never get this high so older map schemas still parse.
+#[doc(hidden)]
+pub const INTERN_KEYS_FLAG: u8 = 255;
+
 #[doc(hidden)]
 #[derive(Debug, Clone, Copy)]
 struct Map_Item<'item> {
@@ -181,8 +187,7 @@ impl<'map> NP_Map<'map> {
         let new_cursor = NP_Cursor::new(new_cursor_addr, value_of, map_cursor.schema_addr);
 
         // set key
-        let key_item_addr = memory.malloc_borrow(&[key.len() as u8])?;
-        memory.malloc_borrow(key.as_bytes())?;
+        let key_item_addr = Self::malloc_key(memory, key, data.intern_keys)?;
         new_cursor.get_value_mut(memory).set_key_addr(key_item_addr as u32);
 
         let head = map_value().get_addr_value() as usize;
@@ -197,6 +202,108 @@ impl<'map> NP_Map<'map> {
         Ok(new_cursor)
     }
 
+    /// Replace the map with a new one holding an empty value for each key, in the same order as the keys.  The keys should be unique.
+    /// 
+    /// All of the items are allocated together, the address of the first item is returned and each following item is 12 bytes after the last one.
+    pub fn make_items(map_cursor: &NP_Cursor, memory: &NP_Memory, keys: &[&str]) -> Result<usize, NP_Error> {
+
+        if keys.len() == 0 {
+            map_cursor.get_value_mut(memory).set_addr_value(0);
+            return Ok(0)
+        }
+
+        if keys.len() > u16::MAX as usize {
+            return Err(NP_Error::new("Map cannot have more than 2^16 keys!"));
+        }
+
+        if keys.iter().any(|key| key.len() >= 255) {
+            return Err(NP_Error::new("Key length cannot be larger than 255 charecters!"));
+        }
+
+        let data = unsafe { &*(*memory.get_schema(map_cursor.schema_addr).data as *const NP_Map_List_Data) };
+
+        let first_item = memory.malloc_borrow(&vec![0u8; keys.len() * 12])?;
+
+        for (index, key) in keys.iter().enumerate() {
+            let item_addr = first_item + (index * 12);
+            let key_item_addr = Self::malloc_key(memory, key, data.intern_keys)?;
+            let item = NP_Cursor::new(item_addr, data.child, map_cursor.schema_addr);
+            let item_value = item.get_value_mut(memory);
+            item_value.set_key_addr(key_item_addr as u32);
+            if index + 1 < keys.len() {
+                item_value.set_next_addr((item_addr + 12) as u32);
+            }
+        }
+
+        map_cursor.get_value_mut(memory).set_addr_value(first_item as u32);
+
+        Ok(first_item)
+    }
+
+    /// Write a key into the buffer and get the address of it's length byte.  If `intern` is true and the key is already in the buffer the existing bytes are used.
+    fn malloc_key(memory: &NP_Memory, key: &str, intern: bool) -> Result<usize, NP_Error> {
+
+        if intern == false {
+            let key_item_addr = memory.malloc_borrow(&[key.len() as u8])?;
+            memory.malloc_borrow(key.as_bytes())?;
+            return Ok(key_item_addr)
+        }
+
+        let hash = murmurhash3_x86_32(key.as_bytes(), SEED);
+
+        let existing = memory.interned_keys.borrow().get(&hash).copied();
+
+        if let Some(addr) = existing {
+            let addr = addr as usize;
+            let bytes = memory.read_bytes();
+            // make sure it's the same key and not a hash collision
+            if bytes.get(addr) == Some(&(key.len() as u8)) && bytes.get((addr + 1)..(addr + 1 + key.len())) == Some(key.as_bytes()) {
+                return Ok(addr)
+            }
+        }
+
+        let key_item_addr = Self::malloc_key(memory, key, false)?;
+        memory.interned_keys.borrow_mut().entry(hash).or_insert(key_item_addr as u32);
+
+        Ok(key_item_addr)
+    }
+
+    /// Point the item at `old` to a new key, the value isn't touched.  The old key bytes are left behind as wasted space.
+    pub fn rename_key(map_cursor: &NP_Cursor, memory: &'map NP_Memory, old: &str, new: &str) -> Result<bool, NP_Error> {
+
+        if new.len() >= 255 {
+            return Err(NP_Error::new("Key length cannot be larger than 255 charecters!"));
+        }
+
+        let mut found: Option<NP_Cursor> = None;
+        let mut new_exists = false;
+
+        let mut map_iter = Self::new_iter(map_cursor, memory);
+
+        while let Some((ikey, item)) = map_iter.step_iter(memory) {
+            if ikey == old && found.is_none() {
+                found = Some(item);
+            } else if ikey == new {
+                new_exists = true;
+            }
+        }
+
+        match found {
+            Some(item) => {
+                if new_exists {
+                    return Err(NP_Error::new("Key to rename to is already in the map!"));
+                }
+                if old != new {
+                    let data = unsafe { &*(*memory.get_schema(map_cursor.schema_addr).data as *const NP_Map_List_Data) };
+                    let key_item_addr = Self::malloc_key(memory, new, data.intern_keys)?;
+                    item.get_value_mut(memory).set_key_addr(key_item_addr as u32);
+                }
+                Ok(true)
+            },
+            None => Ok(false)
+        }
+    }
+
 }
 
 impl<'value> NP_Value<'value> for NP_Map<'value> {
@@ -249,12 +356,16 @@ impl<'value> NP_Value<'value> for NP_Map<'value> {
 
         let value_of = data.child;
 
+        if data.intern_keys {
+            schema_json.insert("intern_keys".to_owned(), NP_JSON::True);
+        }
+
         schema_json.insert("value".to_owned(), NP_Schema::_type_to_json(schema, value_of)?);
 
         Ok(NP_JSON::Dictionary(schema_json))
     }
 
-    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &'value NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &'value NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
 
@@ -262,15 +373,21 @@ impl<'value> NP_Value<'value> for NP_Map<'value> {
             return Ok(0) 
         }
 
+        let data = unsafe { &*(*memory.get_schema(cursor.schema_addr).data as *const NP_Map_List_Data) };
+
         let mut acc_size = 0usize;
 
         let mut map_iter = Self::new_iter(&cursor, memory);
 
         while let Some((_index, item)) = Self::step_iter(&mut map_iter, memory) {
-            let key_size = item.get_value(memory).get_key_size(memory);
-            acc_size += 1; // length byte
-            acc_size += key_size;
-            acc_size += NP_Cursor::calc_size(depth + 1, &item, memory)?;
+            // compaction writes each interned key once for the whole buffer
+            let new_key = data.intern_keys == false || sized_keys.insert(item.get_value(memory).get_key_addr() as usize);
+            if new_key {
+                let key_size = item.get_value(memory).get_key_size(memory);
+                acc_size += 1; // length byte
+                acc_size += key_size;
+            }
+            acc_size += NP_Cursor::calc_size(depth + 1, &item, memory, sized_keys)?;
         }
 
 
@@ -304,6 +421,9 @@ impl<'value> NP_Value<'value> for NP_Map<'value> {
 
         let mut result = String::from("map({value: ");
         result.push_str(NP_Schema::_type_to_idl(&schema, data.child)?.as_str());
+        if data.intern_keys {
+            result.push_str(", intern_keys: true");
+        }
         result.push_str("})");
         Ok(result)
          
@@ -312,24 +432,25 @@ impl<'value> NP_Value<'value> for NP_Map<'value> {
     fn from_idl_to_schema(mut schema: Vec<NP_Parsed_Schema>, _name: &str, idl: &JS_Schema, args: &Vec<JS_AST>) -> Result<(bool, Vec<u8>, Vec<NP_Parsed_Schema>), NP_Error> {
         let mut schema_data: Vec<u8> = Vec::new();
         schema_data.push(NP_TypeKeys::Map as u8);
-        
-
-        let value_addr = schema.len();
-        schema.push(NP_Parsed_Schema {
-            val: NP_Value_Kind::Pointer,
-            i: NP_TypeKeys::Map,
-            sortable: false,
-            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: value_addr + 1 })) as *const u8)
-        });
 
         let mut value_jst: Option<&JS_AST> = None;
+        let mut intern_keys = false;
 
         if args.len() > 0 {
             match &args[0] {
                 JS_AST::object { properties } => {
                     for (key, value) in properties {
-                        if idl.get_str(key).trim() == "value" {
-                            value_jst = Some(value);
+                        match idl.get_str(key).trim() {
+                            "value" => {
+                                value_jst = Some(value);
+                            },
+                            "intern_keys" => {
+                                match value {
+                                    JS_AST::bool { state } => { intern_keys = *state; },
+                                    _ => { return Err(NP_Error::new("intern_keys property must be true or false!")) }
+                                }
+                            },
+                            _ => { }
                         }
                     }
                 },
@@ -337,6 +458,18 @@ impl<'value> NP_Value<'value> for NP_Map<'value> {
             }
         };
 
+        if intern_keys {
+            schema_data.push(INTERN_KEYS_FLAG);
+        }
+
+        let value_addr = schema.len();
+        schema.push(NP_Parsed_Schema {
+            val: NP_Value_Kind::Pointer,
+            i: NP_TypeKeys::Map,
+            sortable: false,
+            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: value_addr + 1, intern_keys })) as *const u8)
+        });
+
         if let Some(x) = value_jst {
             // let of_addr = schema.len();
             let (_sortable, child_bytes, schema) = NP_Schema::from_idl(schema, idl, x)?;
@@ -354,11 +487,21 @@ impl<'value> NP_Value<'value> for NP_Map<'value> {
         let mut schema_data: Vec<u8> = Vec::new();
         schema_data.push(NP_TypeKeys::Map as u8);
 
+        let intern_keys = match json_schema["intern_keys"] {
+            NP_JSON::True => true,
+            NP_JSON::False | NP_JSON::Null => false,
+            _ => { return Err(NP_Error::new("intern_keys property must be true or false!")) }
+        };
+
+        if intern_keys {
+            schema_data.push(INTERN_KEYS_FLAG);
+        }
+
         let value_addr = schema.len();
         schema.push(NP_Parsed_Schema {
             val: NP_Value_Kind::Pointer,
             i: NP_TypeKeys::Map,
-            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: value_addr + 1 })) as *const u8),
+            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: value_addr + 1, intern_keys })) as *const u8),
             sortable: false
         });
 
@@ -370,7 +513,7 @@ impl<'value> NP_Value<'value> for NP_Map<'value> {
         }
 
         
-        let (_sortable, child_bytes, schema) = NP_Schema::from_json(schema, &Box::new(json_schema["value"].clone()))?;
+        let (_sortable, child_bytes, schema) = NP_Schema::from_json(schema, &Box::new(json_schema["value"].clone())).map_err(|e| e.in_schema(&["value"]))?;
         
         schema_data.extend(child_bytes);
 
@@ -383,14 +526,16 @@ impl<'value> NP_Value<'value> for NP_Map<'value> {
     }
 
     fn from_bytes_to_schema(mut schema: Vec<NP_Parsed_Schema>, address: usize, bytes: &[u8]) -> (bool, Vec<NP_Parsed_Schema>) {
+        let intern_keys = bytes[address + 1] == INTERN_KEYS_FLAG;
         let of_addr = schema.len();
         schema.push(NP_Parsed_Schema {
             val: NP_Value_Kind::Pointer,
             i: NP_TypeKeys::Map,
             sortable: false,
-            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: of_addr + 1 })) as *const u8)
+            data: Arc::new(Box::into_raw(Box::new(NP_Map_List_Data { child: of_addr + 1, intern_keys })) as *const u8)
         });
-        let (_sortable, schema) = NP_Schema::from_bytes(schema, address + 1, bytes);
+        let value_addr = if intern_keys { address + 2 } else { address + 1 };
+        let (_sortable, schema) = NP_Schema::from_bytes(schema, value_addr, bytes);
         (false, schema)
     }
 }
@@ -448,4 +593,212 @@ fn set_clear_value_and_compaction_works() -> Result<(), NP_Error> {
     assert_eq!(buffer.get::<&str>(&["foo2"])?, Some("bar2"));
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn rename_key_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("map({value: string()})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["foo"], "bar")?;
+    buffer.set(&["foo2"], "bar2")?;
+
+    let value_range = buffer.byte_range(&["foo"])?;
+    assert!(value_range.is_some());
+
+    assert_eq!(buffer.map_rename_key(&[], "foo", "baz")?, true);
+    assert_eq!(buffer.get::<&str>(&["foo"])?, None);
+    assert_eq!(buffer.get::<&str>(&["baz"])?, Some("bar"));
+    assert_eq!(buffer.get::<&str>(&["foo2"])?, Some("bar2"));
+    // value wasn't moved
+    assert_eq!(buffer.byte_range(&["baz"])?, value_range);
+
+    assert_eq!(buffer.map_rename_key(&[], "foo", "other")?, false);
+    assert_eq!(buffer.map_rename_key(&[], "baz", "baz")?, true);
+    assert!(buffer.map_rename_key(&[], "baz", "foo2").is_err());
+
+    // old key is wasted space until compaction
+    assert_eq!(buffer.calc_bytes()?.wasted_bytes, 4);
+    buffer.compact(None)?;
+    assert_eq!(buffer.get::<&str>(&["baz"])?, Some("bar"));
+    assert_eq!(buffer.get_length(&[])?, Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn intern_keys_works() -> Result<(), NP_Error> {
+    let schema = r#"list({of: map({value: string(), intern_keys: true})})"#;
+    let factory = crate::NP_Factory::new(schema)?;
+    assert_eq!(schema, factory.schema.to_idl()?);
+    let factory2 = crate::NP_Factory::new_bytes(factory.export_schema_bytes())?;
+    assert_eq!(schema, factory2.schema.to_idl()?);
+    let json_factory = crate::NP_Factory::new_json(r#"{"type":"map","intern_keys":true,"value":{"type":"string"}}"#)?;
+    assert_eq!(r#"{"type":"map","intern_keys":true,"value":{"type":"string"}}"#, json_factory.schema.to_json()?.stringify());
+
+    let plain = crate::NP_Factory::new("list({of: map({value: string()})})")?;
+
+    let mut buffer = factory.new_buffer(None);
+    let mut plain_buffer = plain.new_buffer(None);
+    for x in 0..3 {
+        let index = x.to_string();
+        buffer.set(&[index.as_str(), "name"], "bob")?;
+        plain_buffer.set(&[index.as_str(), "name"], "bob")?;
+    }
+
+    // "name" is only stored once
+    assert_eq!(buffer.data_length() + 10, plain_buffer.data_length());
+    assert_eq!(buffer.calc_bytes()?.after_compaction, buffer.calc_bytes()?.current_buffer);
+
+    // keys stay shared through compaction
+    let length = buffer.data_length();
+    buffer.compact(None)?;
+    assert_eq!(buffer.data_length(), length);
+    for x in 0..3 {
+        assert_eq!(buffer.get::<&str>(&[x.to_string().as_str(), "name"])?, Some("bob"));
+    }
+
+    // renamed keys are shared too
+    buffer.set(&["0", "age"], "20")?;
+    let length = buffer.data_length();
+    buffer.map_rename_key(&["1"], "name", "age")?;
+    assert_eq!(buffer.data_length(), length);
+    assert_eq!(buffer.get::<&str>(&["1", "age"])?, Some("bob"));
+    assert_eq!(buffer.get::<&str>(&["1", "name"])?, None);
+    assert_eq!(buffer.get::<&str>(&["2", "name"])?, Some("bob"));
+
+    Ok(())
+}
+
+#[test]
+fn iter_map_decode_errors_work() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("map({value: string({size: 4, sort: \"desc\"})})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    buffer.set(&["a"], String::from("abcd"))?;
+
+    // descending strings can't be borrowed, the error comes out with the item
+    let items: Vec<Result<(&str, Option<&str>), NP_Error>> = buffer.iter_map(&[])?.collect();
+    assert_eq!(items.len(), 1);
+    assert!(items[0].is_err());
+
+    let owned: Vec<(&str, Option<String>)> = buffer.iter_map(&[])?.collect::<Result<_, NP_Error>>()?;
+    assert_eq!(owned, vec![("a", Some(String::from("abcd")))]);
+
+    Ok(())
+}
+
+#[test]
+fn set_map_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("struct({fields: {counts: map({value: u32()}), names: list({of: map({value: string(), intern_keys: true})})}})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    buffer.set(&["counts", "old"], 1u32)?;
+    buffer.set_map(&["counts"], &[("c", 3u32), ("a", 1u32), ("b", 2u32), ("a", 4u32)])?;
+
+    let found: Vec<(&str, Option<u32>)> = buffer.iter_map(&["counts"])?.collect::<Result<_, NP_Error>>()?;
+    assert_eq!(found, vec![("c", Some(3)), ("a", Some(4)), ("b", Some(2))]);
+
+    // the new map works like any other
+    buffer.set(&["counts", "d"], 5u32)?;
+    buffer.set(&["counts", "c"], 6u32)?;
+    buffer.del(&["counts", "a"])?;
+    assert_eq!(buffer.get::<u32>(&["counts", "a"])?, None);
+    assert_eq!(buffer.get::<u32>(&["counts", "c"])?, Some(6));
+    assert_eq!(buffer.get::<u32>(&["counts", "d"])?, Some(5));
+
+    // interned keys are shared
+    buffer.set_map(&["names", "0"], &[("first", "bob"), ("last", "smith")])?;
+    let length = buffer.data_length();
+    buffer.set_map(&["names", "1"], &[("first", "bill"), ("last", "jones")])?;
+    // list item, map items, then the two values, no new key bytes
+    assert_eq!(buffer.data_length() - length, 10 + (2 * 12) + (4 + 4) + (4 + 5));
+
+    let found: Vec<(&str, Option<&str>)> = buffer.iter_map(&["names", "1"])?.collect::<Result<_, NP_Error>>()?;
+    assert_eq!(found, vec![("first", Some("bill")), ("last", Some("jones"))]);
+
+    let estimate = buffer.calc_bytes()?.after_compaction;
+    buffer.compact(None)?;
+    assert_eq!(buffer.data_length(), estimate);
+    assert_eq!(buffer.get::<&str>(&["names", "1", "last"])?, Some("jones"));
+    assert_eq!(buffer.get::<&str>(&["names", "0", "first"])?, Some("bob"));
+
+    buffer.set_map::<u32>(&["counts"], &[])?;
+    assert_eq!(buffer.iter_map::<u32>(&["counts"])?.count(), 0);
+
+    assert!(buffer.set_map(&["counts"], &[("a", "b")]).is_err());
+    assert!(buffer.set_map(&["names"], &[("a", "b")]).is_err());
+
+    // a failed type check doesn't make the map
+    let length = buffer.data_length();
+    assert!(buffer.set_map(&["names", "5"], &[("a", 1u32)]).is_err());
+    assert_eq!(buffer.data_length(), length);
+    assert_eq!(buffer.get_length(&["names", "5"])?, None);
+
+    Ok(())
+}
+
+#[test]
+fn clear_collection_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("struct({fields: {scores: map({value: u8()})}})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["scores", "foo"], 1u8)?;
+    buffer.set(&["scores", "bar"], 2u8)?;
+    buffer.set(&["scores", "baz"], 3u8)?;
+
+    assert_eq!(buffer.clear_collection(&["scores"])?, true);
+    assert_eq!(buffer.get_length(&["scores"])?, None);
+    assert_eq!(buffer.get::<u8>(&["scores", "foo"])?, None);
+    assert_eq!(buffer.get::<u8>(&["scores", "baz"])?, None);
+    assert_eq!(buffer.clear_collection(&["scores"])?, false);
+
+    // the map can be filled again
+    buffer.set(&["scores", "bar"], 4u8)?;
+    assert_eq!(buffer.get_length(&["scores"])?, Some(1));
+
+    Ok(())
+}
+
+#[test]
+fn intern_keys_size_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("list({of: map({value: string(), intern_keys: true})})")?;
+    let mut buffer = factory.new_buffer(None);
+    for x in 0..3 {
+        buffer.set(&[x.to_string().as_str(), "name"], "bob")?;
+    }
+
+    // the item that wrote the key bytes is gone, the others still share them
+    buffer.del(&["0", "name"])?;
+    let estimate = buffer.calc_bytes()?.after_compaction;
+    buffer.compact(None)?;
+    assert_eq!(buffer.data_length(), estimate);
+
+    // renamed keys are new bytes that no item comes right before
+    buffer.map_rename_key(&["1"], "name", "first")?;
+    let estimate = buffer.calc_bytes()?.after_compaction;
+    buffer.compact(None)?;
+    assert_eq!(buffer.data_length(), estimate);
+    assert_eq!(buffer.get::<&str>(&["1", "first"])?, Some("bob"));
+    assert_eq!(buffer.get::<&str>(&["2", "name"])?, Some("bob"));
+    assert_eq!(buffer.calc_bytes()?.after_compaction, buffer.data_length());
+
+    Ok(())
+}
+
+#[test]
+fn compaction_keeps_deleted_keys_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("map({value: string()})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["name"], "hello")?;
+    buffer.set(&["color"], "blue")?;
+    buffer.del(&["name"])?;
+
+    // only the value is waste, the key stays
+    assert_eq!(buffer.calc_bytes()?.wasted_bytes, 9);
+    buffer.compact(None)?;
+    assert_eq!(buffer.calc_bytes()?.wasted_bytes, 0);
+    assert_eq!(buffer.get_length(&[])?, Some(2));
+    assert_eq!(buffer.get::<&str>(&["name"])?, None);
+    assert_eq!(buffer.get::<&str>(&["color"])?, Some("blue"));
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use alloc::sync::Arc;
+use alloc::collections::BTreeSet;
 use crate::{idl::AST_STR, schema::{NP_Struct_Data, NP_Struct_Field, NP_Value_Kind}};
 use crate::{buffer::{VTABLE_BYTES, VTABLE_SIZE}, idl::{JS_AST, JS_Schema}};
 use alloc::string::String;
@@ -147,7 +148,7 @@ impl<'table> NP_Struct<'table> {
         let v_table =  self.index / VTABLE_SIZE; // which vtable
         let v_table_idx = self.index % VTABLE_SIZE; // which index on the selected vtable
 
-        if self.v_table_index > v_table {
+        if v_table > self.v_table_index {
             self.v_table_index = v_table;
             match &self.v_table {
                 Some(vtable) => {
@@ -310,7 +311,7 @@ impl<'value> NP_Value<'value> for NP_Struct<'value> {
         Ok(NP_JSON::Dictionary(schema_json))
     }
  
-    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &'value NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &'value NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
 
@@ -320,20 +321,18 @@ impl<'value> NP_Value<'value> for NP_Struct<'value> {
 
         let mut acc_size = 0usize;
 
-        let mut nex_vtable = c_value().get_addr_value() as usize;
-        let mut loop_max = 65usize;
-        while nex_vtable > 0 && loop_max > 0 {
-            acc_size += 20;
-            let vtable = Self::get_vtable(nex_vtable, memory);
-            nex_vtable = vtable.get_next() as usize;
-            loop_max -= 1;
-        }
+        // the first vtable is always kept, vtables after the last set value are dropped during compaction
+        let mut live_vtables = 1usize;
 
         let mut struc = Self::new_iter(&cursor, memory);
 
-        while let Some((_index, _key, item)) = struc.step_iter(memory) {
+        while let Some((index, _key, item)) = struc.step_iter(memory) {
             if let Some(real) = item {
-                let add_size = NP_Cursor::calc_size(depth + 1, &real, memory)?;
+                if real.get_value(memory).get_addr_value() == 0 {
+                    continue;
+                }
+                live_vtables = usize::max(live_vtables, (index / VTABLE_SIZE) + 1);
+                let add_size = NP_Cursor::calc_size(depth + 1, &real, memory, sized_keys)?;
                 if add_size > 4 {
                     // scalar cursor is part of vtable
                     acc_size += add_size - 4;             
@@ -341,7 +340,7 @@ impl<'value> NP_Value<'value> for NP_Struct<'value> {
             }         
         }
    
-        Ok(acc_size)
+        Ok(acc_size + (live_vtables * VTABLE_BYTES))
     }
 
 
@@ -368,10 +367,15 @@ impl<'value> NP_Value<'value> for NP_Struct<'value> {
         while let Some((idx, _key, item)) = struc.step_iter(from_memory) {
            if let Some(real) = item {
 
+                // cleared values don't need to be copied
+                if real.get_value(from_memory).get_addr_value() == 0 {
+                    continue;
+                }
+
                 let v_table =  idx / VTABLE_SIZE; // which vtable
                 let v_table_idx = idx % VTABLE_SIZE; // which index on the selected vtable
                 
-                if last_vtable_idx < v_table {
+                while last_vtable_idx < v_table {
                     let vtable_data = Self::get_vtable(last_real_vtable, to_memory);
                     last_real_vtable = Self::make_next_vtable(vtable_data, to_memory)?;
                     last_vtable_idx += 1;
@@ -552,7 +556,7 @@ impl<'value> NP_Value<'value> for NP_Struct<'value> {
             let field_schema_addr = schema_parsed.len();
             // fields.push((x, field_name.clone(), field_schema_addr));
             fields.push(NP_Struct_Field { idx: x as u8, col: field_name.clone(), schema: field_schema_addr, offset: 0});
-            let (_is_sortable, field_type, schema_p) = NP_Schema::from_json(schema_parsed, &Box::new(col[1].clone()))?;
+            let (_is_sortable, field_type, schema_p) = NP_Schema::from_json(schema_parsed, &Box::new(col[1].clone())).map_err(|e| e.in_schema(&["fields", x.to_string().as_str(), "1"]))?;
             schema_parsed = schema_p;
             field_data.push((field_name, field_type));
             x += 1;
@@ -701,4 +705,60 @@ fn test_vtables() -> Result<(), NP_Error> {
     assert_eq!(buffer.get::<u8>(&["rating"])?.unwrap(), 20u8);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn compaction_keeps_all_vtables() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new(r#"
+        struct({fields: {
+            age:    u8(),
+            name:   string(),
+            color:  string(),
+            car:    string(),
+            rating: u8()
+        }})
+    "#)?;
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["age"], 20u8)?;
+    buffer.set(&["rating"], 98u8)?;
+    buffer.set(&["rating"], 99u8)?;
+
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":{"age":20,"name":null,"color":null,"car":null,"rating":99}}"#);
+
+    let size = buffer.calc_bytes()?;
+    buffer.compact(None)?;
+    assert_eq!(buffer.calc_bytes()?.current_buffer, size.after_compaction);
+    assert_eq!(buffer.get::<u8>(&["age"])?, Some(20u8));
+    assert_eq!(buffer.get::<u8>(&["rating"])?, Some(99u8));
+
+    Ok(())
+}
+
+#[test]
+fn cleared_vtables_are_wasted() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new(r#"
+        struct({fields: {
+            age:    u8(),
+            name:   string(),
+            color:  string(),
+            car:    string(),
+            rating: u8()
+        }})
+    "#)?;
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["age"], 20u8)?;
+    buffer.set(&["rating"], 98u8)?;
+    assert_eq!(buffer.calc_wasted_bytes()?, 0);
+
+    // second vtable and the rating value are no longer needed
+    buffer.del(&["rating"])?;
+    assert_eq!(buffer.calc_wasted_bytes()?, 21);
+
+    buffer.compact(None)?;
+    assert_eq!(buffer.calc_wasted_bytes()?, 0);
+    assert_eq!(buffer.get::<u8>(&["age"])?, Some(20u8));
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use crate::{idl::JS_AST, pointer::NP_Cursor_Parent, schema::{NP_Tuple_Data, NP_Tuple_Field, NP_Value_Kind}};
 use alloc::{string::String, sync::Arc};
+use alloc::collections::BTreeSet;
 use crate::{idl::JS_Schema};
 
 use crate::{json_flex::JSMAP, pointer::{NP_Cursor}};
@@ -69,6 +70,23 @@ impl NP_Tuple {
 
     }
 
+    /// Add the next value to a tuple layout, every schema parser uses this so the same tuple always gets the same layout.
+    /// 
+    /// Each value has a set byte in front of it, then fixed size values are stored inline and everything else gets a 4 byte pointer.
+    fn layout_value(values: &mut Vec<NP_Tuple_Field>, data_offset: &mut usize, schema_addr: usize, kind: &NP_Value_Kind) {
+        match kind {
+            NP_Value_Kind::Pointer => {
+                values.push(NP_Tuple_Field { schema: schema_addr, offset: *data_offset, size: 0, fixed: false });
+                *data_offset += 4;
+            },
+            NP_Value_Kind::Fixed(x) => {
+                values.push(NP_Tuple_Field { schema: schema_addr, offset: *data_offset, size: *x as usize, fixed: true });
+                *data_offset += *x as usize;
+            }
+        }
+        *data_offset += 1;
+    }
+
     #[inline(always)]
     pub fn alloc_tuple<'make>(tuple_cursor: NP_Cursor, empty: &Vec<u8>, memory: &'make NP_Memory) -> Result<NP_Cursor, NP_Error> {
 
@@ -186,7 +204,7 @@ impl<'value> NP_Value<'value> for NP_Tuple {
         Ok(())
     }
 
-    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &'value NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &'value NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
 
@@ -207,7 +225,7 @@ impl<'value> NP_Value<'value> for NP_Tuple {
                 if schema_value.fixed {
                     acc_size += schema_value.size;
                 } else {
-                    acc_size += NP_Cursor::calc_size(depth + 1, &cursor, memory)?;
+                    acc_size += NP_Cursor::calc_size(depth + 1, &cursor, memory, sized_keys)?;
                 }   
             }   
         }
@@ -226,16 +244,22 @@ impl<'value> NP_Value<'value> for NP_Tuple {
 
         let data = unsafe { &*(*from_memory.get_schema(from_cursor.schema_addr).data as *const NP_Tuple_Data) };
 
-        let (col_schemas, _empty) = (&data.values, &data.empty);
-
         to_cursor = Self::alloc_tuple(to_cursor, &data.empty, to_memory)?;
 
         let mut tuple = Self::new_iter(&from_cursor, from_memory);
 
         while let Some((idx, item)) = tuple.step_iter(from_memory, false) {
             if let Some(old_cursor) = item {
-                to_memory.write_bytes()[old_cursor.buff_addr - 1] = 1;
-                NP_Cursor::compact(depth + 1, old_cursor.clone(), from_memory, NP_Cursor::new(old_cursor.buff_addr, col_schemas[idx].schema, to_cursor.schema_addr), to_memory)?;
+                // skip values that were never set
+                if from_memory.read_bytes()[old_cursor.buff_addr - 1] == 0 {
+                    continue;
+                }
+
+                // the new tuple block won't be at the same address as the old one
+                if let Some(new_cursor) = Self::select(to_cursor, to_memory.get_schema(to_cursor.schema_addr), idx, true, false, to_memory)? {
+                    to_memory.write_bytes()[new_cursor.buff_addr - 1] = 1;
+                    NP_Cursor::compact(depth + 1, old_cursor.clone(), from_memory, new_cursor, to_memory)?;
+                }
             }
         }
 
@@ -321,17 +345,7 @@ impl<'value> NP_Value<'value> for NP_Tuple {
             for col in tuple_vals {
                 let schema_len = working_schema.len();
                 let (is_sortable, schema_bytes, schema ) = NP_Schema::from_idl(working_schema, idl, &col)?;
-                match schema[schema_len].val {
-                    NP_Value_Kind::Pointer => {
-                        tuple_values.push(NP_Tuple_Field { schema: schema_len, offset: data_offset, size: 0, fixed: false });
-                        data_offset += 2;
-                    },
-                    NP_Value_Kind::Fixed(x) => {
-                        tuple_values.push(NP_Tuple_Field { schema: schema_len, offset: data_offset, size: x as usize, fixed: true });
-                        data_offset += x as usize;
-                    }
-                }
-                data_offset += 1;
+                Self::layout_value(&mut tuple_values, &mut data_offset, schema_len, &schema[schema_len].val);
                 working_schema = schema;
                 if sorted && is_sortable == false {
                     return Err(NP_Error::new("All children of a sorted tuple must be sortable items!"))
@@ -407,19 +421,9 @@ impl<'value> NP_Value<'value> for NP_Tuple {
             NP_JSON::Array(cols) => {
                 for col in cols {
                     let schema_len = working_schema.len();
-                    let (is_sortable, schema_bytes, schema ) = NP_Schema::from_json(working_schema, &Box::new(col.clone()))?;
+                    let (is_sortable, schema_bytes, schema ) = NP_Schema::from_json(working_schema, &Box::new(col.clone())).map_err(|e| e.in_schema(&["values", column_schemas.len().to_string().as_str()]))?;
                     
-                    match schema[schema_len].val {
-                        NP_Value_Kind::Pointer => {
-                            tuple_values.push(NP_Tuple_Field { schema: schema_len, offset: data_offset, size: 0, fixed: false });
-                            data_offset += 4;
-                        },
-                        NP_Value_Kind::Fixed(x) => {
-                            tuple_values.push(NP_Tuple_Field { schema: schema_len, offset: data_offset, size: x as usize, fixed: true });
-                            data_offset += x as usize;
-                        }
-                    }
-                    data_offset += 1;
+                    Self::layout_value(&mut tuple_values, &mut data_offset, schema_len, &schema[schema_len].val);
                     working_schema = schema;
                     if sorted && is_sortable == false {
                         return Err(NP_Error::new("All children of a sorted tuple must be sortable items!"))
@@ -494,17 +498,7 @@ impl<'value> NP_Value<'value> for NP_Tuple {
             ]) as usize;
             let schema_len = working_schema.len();
             let (_sortable, schema) = NP_Schema::from_bytes(working_schema, offset + 2, bytes);
-            match schema[schema_len].val {
-                NP_Value_Kind::Pointer => {
-                    tuple_values.push(NP_Tuple_Field { schema: schema_len, offset: data_offset, size: 0, fixed: false });
-                    data_offset += 2;
-                },
-                NP_Value_Kind::Fixed(x) => {
-                    tuple_values.push(NP_Tuple_Field { schema: schema_len, offset: data_offset, size: x as usize, fixed: true });
-                    data_offset += x as usize;
-                }
-            }
-            data_offset += 1;
+            Self::layout_value(&mut tuple_values, &mut data_offset, schema_len, &schema[schema_len].val);
             working_schema = schema;
 
             offset += schema_size + 2;
@@ -591,4 +585,185 @@ fn sorting_tuples_works() -> Result<(), NP_Error> {
     assert_eq!(buffer.read_bytes(), &[0, 0, 0, 0, 0, 6, 1, 104, 101, 108, 108, 111, 32, 32, 32, 32, 32, 1, 76, 230, 170, 176, 120, 208, 69, 186, 109, 122, 100, 179, 210, 224, 68, 195, 1, 20]);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn desc_sorting_works() -> Result<(), NP_Error> {
+    let schema = r#"tuple({values: [u8(), i16({sort: "desc"}), string({size: 4, sort: "desc"})], sorted: true})"#;
+    let factory = crate::NP_Factory::new(schema)?;
+    assert_eq!(schema, factory.schema.to_idl()?);
+    let factory2 = crate::NP_Factory::new_bytes(factory.export_schema_bytes())?;
+    assert_eq!(schema, factory2.schema.to_idl()?);
+
+    let make_key = |a: u8, b: i16, c: &str| -> Result<Vec<u8>, NP_Error> {
+        let mut buffer = factory.new_buffer(None);
+        buffer.set(&["0"], a)?;
+        buffer.set(&["1"], b)?;
+        buffer.set(&["2"], c)?;
+        assert_eq!(buffer.get::<u8>(&["0"])?, Some(a));
+        assert_eq!(buffer.get::<i16>(&["1"])?, Some(b));
+        assert_eq!(buffer.get::<String>(&["2"])?, Some(format!("{:<4}", c)));
+        Ok(buffer.finish().bytes())
+    };
+
+    // ascending on the first value, descending on the rest
+    let expected = [
+        make_key(1, 500, "bbb")?,
+        make_key(1, 500, "abc")?,
+        make_key(1, 500, "ab")?,
+        make_key(1, -20, "zzzz")?,
+        make_key(1, -300, "a")?,
+        make_key(2, i16::MAX, "a")?,
+        make_key(2, i16::MIN, "a")?,
+    ];
+
+    let mut sorted = expected.to_vec();
+    sorted.reverse();
+    sorted.sort();
+    assert_eq!(sorted, expected.to_vec());
+
+    // descending strings can't be borrowed from the buffer
+    let buffer = factory.open_buffer(expected[0].clone());
+    assert!(buffer.get::<&str>(&["2"]).is_err());
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[1,500,"bbb "]}"#);
+
+    assert!(crate::NP_Factory::new(r#"string({sort: "desc"})"#).is_err());
+    assert!(crate::NP_Factory::new(r#"f32({sort: "desc"})"#).is_err());
+    assert!(crate::NP_Factory::new(r#"u8({sort: "down"})"#).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn nested_compaction_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("struct({fields: {pair: tuple({values: [string(), u8(), string()]})}})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["pair", "0"], "hello")?;
+    buffer.set(&["pair", "1"], 20u8)?;
+    buffer.set(&["pair", "0"], "hello, world")?;
+
+    let size = buffer.calc_bytes()?;
+    buffer.compact(None)?;
+    assert_eq!(buffer.calc_bytes()?.current_buffer, size.after_compaction);
+    assert_eq!(buffer.get::<&str>(&["pair", "0"])?, Some("hello, world"));
+    assert_eq!(buffer.get::<u8>(&["pair", "1"])?, Some(20u8));
+    assert_eq!(buffer.get::<&str>(&["pair", "2"])?, None);
+
+    Ok(())
+}
+
+#[test]
+fn clear_collection_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("struct({fields: {pair: tuple({values: [string(), u8({default: 5})]}), scores: map({value: u8()})}})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["pair", "0"], "hello")?;
+    buffer.set(&["pair", "1"], 20u8)?;
+    buffer.set(&["scores", "foo"], 2u8)?;
+
+    assert_eq!(buffer.clear_collection(&["pair"])?, true);
+    assert_eq!(buffer.get::<&str>(&["pair", "0"])?, None);
+    assert_eq!(buffer.get::<u8>(&["pair", "1"])?, None);
+    assert_eq!(buffer.clear_collection(&["pair"])?, false);
+
+    assert_eq!(buffer.clear_collection(&["scores"])?, true);
+    assert_eq!(buffer.get::<u8>(&["scores", "foo"])?, None);
+
+    // cleared bytes are reclaimed by compaction
+    assert!(buffer.calc_wasted_bytes()? > 0);
+    buffer.compact(None)?;
+    assert_eq!(buffer.calc_wasted_bytes()?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn swap_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("struct({fields: {a: tuple({values: [u8(), string(), u8()]}), b: string(), c: u8()}})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["a", "0"], 20u8)?;
+    buffer.set(&["a", "1"], "hello")?;
+    buffer.set(&["b"], "world")?;
+
+    // fixed values inside a tuple
+    buffer.swap(&["a", "0"], &["a", "2"])?;
+    assert_eq!(buffer.get::<u8>(&["a", "0"])?, None);
+    assert_eq!(buffer.get::<u8>(&["a", "2"])?, Some(20));
+
+    // pointer values between a tuple and a struct
+    buffer.swap(&["a", "1"], &["b"])?;
+    assert_eq!(buffer.get::<&str>(&["a", "1"])?, Some("world"));
+    assert_eq!(buffer.get::<&str>(&["b"])?, Some("hello"));
+
+    // fixed tuple values can't leave the tuple
+    assert!(buffer.swap(&["a", "2"], &["c"]).is_err());
+    assert!(buffer.swap(&["a"], &["a", "1"]).is_err());
+
+    buffer.compact(None)?;
+    assert_eq!(buffer.get::<u8>(&["a", "2"])?, Some(20));
+    assert_eq!(buffer.get::<&str>(&["a", "1"])?, Some("world"));
+    assert_eq!(buffer.get::<&str>(&["b"])?, Some("hello"));
+
+    Ok(())
+}
+
+#[test]
+fn move_value_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("struct({fields: {a: tuple({values: [u8(), string()]}), b: tuple({values: [u8(), string()]}), c: u8(), d: string()}})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["a", "0"], 5u8)?;
+    buffer.set(&["a", "1"], "hello")?;
+
+    // tuple to tuple moves the whole slot
+    assert!(buffer.move_value(&["a", "0"], &["b", "0"])?);
+    assert!(buffer.move_value(&["a", "1"], &["b", "1"])?);
+    assert_eq!(buffer.contains(&["a", "0"])?, false);
+    assert_eq!(buffer.contains(&["a", "1"])?, false);
+    assert_eq!(buffer.get::<u8>(&["b", "0"])?, Some(5));
+    assert_eq!(buffer.get::<&str>(&["b", "1"])?, Some("hello"));
+
+    // fixed values are copied out of the tuple, pointers are moved
+    assert!(buffer.move_value(&["b", "0"], &["c"])?);
+    assert!(buffer.move_value(&["b", "1"], &["d"])?);
+    assert_eq!(buffer.contains(&["b", "0"])?, false);
+    assert_eq!(buffer.contains(&["b", "1"])?, false);
+    assert_eq!(buffer.get::<u8>(&["c"])?, Some(5));
+    assert_eq!(buffer.get::<&str>(&["d"])?, Some("hello"));
+
+    // and back into the tuple
+    assert!(buffer.move_value(&["c"], &["a", "0"])?);
+    assert!(buffer.move_value(&["d"], &["a", "1"])?);
+    assert_eq!(buffer.json_encode(&["a"])?.stringify(), r#"{"value":[5,"hello"]}"#);
+    assert_eq!(buffer.contains(&["c"])?, false);
+    assert_eq!(buffer.contains(&["d"])?, false);
+
+    buffer.compact(None)?;
+    assert_eq!(buffer.json_encode(&["a"])?.stringify(), r#"{"value":[5,"hello"]}"#);
+    assert_eq!(buffer.contains(&["b", "1"])?, false);
+
+    Ok(())
+}
+
+#[test]
+fn layout_matches_for_every_schema_format() -> Result<(), NP_Error> {
+    let idl = crate::NP_Factory::new("tuple({values: [string(), u8(), string()]})")?;
+    let json = crate::NP_Factory::new_json(r#"{"type":"tuple","values":[{"type":"string"},{"type":"u8"},{"type":"string"}]}"#)?;
+    let bytes = crate::NP_Factory::new_bytes(idl.export_schema_bytes())?;
+
+    let mut results: Vec<Vec<u8>> = Vec::new();
+    for factory in [&idl, &json, &bytes].iter() {
+        let mut buffer = factory.new_buffer(None);
+        buffer.set(&["0"], "hello")?;
+        buffer.set(&["1"], 20u8)?;
+        buffer.set(&["2"], "world")?;
+        // pointers are 4 bytes, so neighbors don't overwrite each other
+        assert_eq!(buffer.get::<&str>(&["0"])?, Some("hello"));
+        assert_eq!(buffer.get::<u8>(&["1"])?, Some(20u8));
+        assert_eq!(buffer.get::<&str>(&["2"])?, Some("world"));
+        results.push(buffer.read_bytes().to_vec());
+    }
+
+    assert_eq!(results[0], results[1]);
+    assert_eq!(results[0], results[2]);
+
+    Ok(())
+}
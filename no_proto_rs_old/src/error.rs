@@ -4,6 +4,7 @@ use alloc::string::FromUtf8Error;
 use alloc::string::String;
 use alloc::borrow::ToOwned;
 use alloc::string::ToString;
+use alloc::vec::Vec;
 
 /// The error type used for errors in this library
 #[derive(Debug)]
@@ -16,6 +17,31 @@ pub enum NP_Error {
     MemoryOutOfSpace,
     /// Too many recursive calls
     RecursionLimit,
+    /// The buffer contains something invalid at a specific address
+    BadAddress {
+        /// Address of the offending bytes in the buffer
+        addr: usize,
+        /// What was wrong at the address
+        context: &'static str
+    },
+    /// The bytes were tagged with a different schema than the factory opening them
+    SchemaMismatch {
+        /// Schema id of the factory opening the bytes
+        expected: u32,
+        /// Schema id found in the bytes
+        found: u32
+    },
+    /// The schema is invalid
+    BadSchema {
+        /// What was wrong with the schema
+        message: String,
+        /// Path from the root of a JSON schema to the offending value, made of object keys and array indexes
+        path: Vec<String>,
+        /// Character position of the offending value in the schema source, if the source is known
+        position: Option<usize>,
+        /// The schema source at `position`
+        snippet: String
+    },
     /// Custom error message
     Custom { 
         /// Error message
@@ -28,6 +54,53 @@ impl NP_Error {
     pub fn new<S: AsRef<str>>(message: S) -> Self {
         NP_Error::Custom { message: message.as_ref().to_owned() }
     }
+    /// Generate a schema error pointing at a byte index in the schema source
+    pub fn schema_at<S: AsRef<str>>(message: S, source: &str, index: usize) -> Self {
+        let (position, snippet) = Self::schema_snippet(source, index);
+        NP_Error::BadSchema { message: message.as_ref().to_owned(), path: Vec::new(), position: Some(position), snippet }
+    }
+    /// Character position and the next 20 characters of the schema source, starting at the first non whitespace character from a byte index
+    fn schema_snippet(source: &str, index: usize) -> (usize, String) {
+        let mut index = usize::min(index, source.len());
+        while source.is_char_boundary(index) == false {
+            index -= 1;
+        }
+        let snippet = source[index..].trim_start();
+        let index = source.len() - snippet.len();
+        let snippet = match snippet.char_indices().nth(20) {
+            Some((end, _)) => &snippet[..end],
+            None => snippet
+        };
+        (source[..index].chars().count(), snippet.trim_end().to_owned())
+    }
+    /// Put a path in front of a schema error's path, used as errors from nested JSON schemas are passed up to their parent
+    /// 
+    /// Errors that aren't about the schema are turned into schema errors.
+    pub fn in_schema(self, segments: &[&str]) -> Self {
+        match self {
+            NP_Error::BadSchema { message, path, position, snippet } => {
+                let mut full_path: Vec<String> = segments.iter().map(|x| x.to_string()).collect();
+                full_path.extend(path);
+                NP_Error::BadSchema { message, path: full_path, position, snippet }
+            },
+            NP_Error::Custom { message } => {
+                NP_Error::BadSchema { message, path: segments.iter().map(|x| x.to_string()).collect(), position: None, snippet: String::new() }
+            },
+            _ => self
+        }
+    }
+    /// Find the position of a schema error in the JSON schema source using the error's path
+    /// 
+    /// If the path goes somewhere the source doesn't, like into a `$ref`, the error points at the deepest value that was found.
+    pub fn locate_json(self, source: &str) -> Self {
+        match self {
+            NP_Error::BadSchema { message, path, position: None, .. } => {
+                let (position, snippet) = Self::schema_snippet(source, crate::json_flex::json_find(source.as_bytes(), &path));
+                NP_Error::BadSchema { message, path, position: Some(position), snippet }
+            },
+            _ => self
+        }
+    }
     /// Convert an option to an error type
     pub fn unwrap<T>(value: Option<T>) -> Result<T, NP_Error> {
         match value {
@@ -37,6 +110,37 @@ impl NP_Error {
     }
 }
 
+impl core::fmt::Display for NP_Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NP_Error::MemoryReadOnly => write!(f, "Buffer is read only, open the bytes with a mutable method to make changes"),
+            NP_Error::Unreachable => write!(f, "Reached a state that should be impossible, the buffer or schema is likely corrupt"),
+            NP_Error::MemoryOutOfSpace => write!(f, "Buffer is out of space, compact it or provide a larger backing slice or allocator"),
+            NP_Error::RecursionLimit => write!(f, "Recursion limit reached, the data or schema is nested too deeply"),
+            NP_Error::BadAddress { addr, context } => write!(f, "Invalid buffer at address {}: {}", addr, context),
+            NP_Error::SchemaMismatch { expected, found } => write!(f, "Schema mismatch, expected schema id {} but found {}", expected, found),
+            NP_Error::BadSchema { message, path, position, snippet } => {
+                match position {
+                    Some(position) => write!(f, "{} (at character {}: `{}`)", message, position, snippet),
+                    None if path.len() > 0 => write!(f, "{} (at `{}`)", message, path.join(".")),
+                    None => write!(f, "{}", message)
+                }
+            },
+            NP_Error::Custom { message } => write!(f, "{}", message)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NP_Error {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for NP_Error {
+    fn from(err: std::io::Error) -> NP_Error {
+        NP_Error::new(err.to_string().as_str())
+    }
+}
+
 impl From<FromUtf8Error> for NP_Error {
     fn from(err: FromUtf8Error) -> NP_Error {
         NP_Error::new(err.to_string().as_str())
@@ -166,7 +166,7 @@
 //! 
 //! Before each type is a byte that is either 1 or 0.  It should be zero if the value has not been set, otherwise it should be one.
 //! 
-//! If a child type is flexible in size, a pointer address is put in the tuple.
+//! If a child type is flexible in size, a 4 byte pointer address is put in the tuple.
 //! If a child type is fixed in size, it is placed inline in the tuple.
 //! 
 //! ```
@@ -495,7 +495,7 @@
 //! 
 //! ### int8, int16, int32, int64, uint8, uint16, uint32, uint64, float, double (Scalar)
 //! 
-//! Integer values store the data type followed by wether there is a default value or not, followed optionally by the default value
+//! Integer values store the data type followed by a flag byte, followed optionally by the default value.  The first bit of the flag byte is set if there is a default value, the seventh bit (`64`) is set if values are stored inverted for descending sorting.
 //! 
 //! ```
 //! use no_proto::error::NP_Error;
@@ -519,6 +519,16 @@
 //! 
 //! // [       6,           0]
 //! // [i32 type,  no default]
+//! 
+//! let factory: NP_Factory = NP_Factory::new_json(r#"{
+//!    "type": "i32",
+//!    "sort": "desc"
+//! }"#)?;
+//!
+//! assert_eq!(&[6, 64], factory.export_schema_bytes());
+//! 
+//! // [       6,                        64]
+//! // [i32 type,  no default & descending ]
 //!
 //! # Ok::<(), NP_Error>(()) 
 //! ```
@@ -613,7 +623,7 @@
 //! 
 //! Decimal stores the expontent in the second byte.
 //! 
-//! The third byte is 0 if there is no default value, otherwise it is 1.
+//! The third byte is a set of flags.  The first bit is set if there is a default value, the second bit is set if values are exported to JSON as numbers.
 //! 
 //! If there is a default value, multiply the default value by (10^exp) and convert it into an i64, then save it in the bytes following the default flag byte.
 //! 
@@ -642,6 +652,17 @@
 //! 
 //! // [       14,         2,                 1, 0, 0, 0, 0, 0, 0, 203, 164]
 //! // [data type, expontent, has default value,              default value]
+//! 
+//! let factory: NP_Factory = NP_Factory::new_json(r#"{
+//!    "type": "decimal",
+//!    "exp": 2,
+//!    "json": "number"
+//! }"#)?;
+//!
+//! assert_eq!(&[14, 2, 2], factory.export_schema_bytes());
+//! 
+//! // [       14,         2,                                  2]
+//! // [data type, expontent, no default value & JSON as number]
 //!
 //! # Ok::<(), NP_Error>(()) 
 //! ```
@@ -742,6 +763,17 @@
 //! // [        2,                   0,       0, 0, 0, 20,                0, 6, 104, 101, 108, 108, 111]
 //! // [data type, uppercase/lowercase,  fixed size (u32),  default size (u16),   h,   e,   l,   l,   o]
 //!
+//! let factory: NP_Factory = NP_Factory::new_json(r#"{
+//!    "type": "string",
+//!    "size": 20,
+//!    "sort": "desc"
+//! }"#)?;
+//!
+//! assert_eq!(&[2, 64, 0, 0, 0, 20, 0, 0], factory.export_schema_bytes());
+//! 
+//! // [        2,                  64,      0, 0, 0, 20,                 0, 0]
+//! // [data type,          descending, fixed size (u32),  default size (u16) ]
+//!
 //! # Ok::<(), NP_Error>(()) 
 //! ```
 //! 
@@ -836,11 +868,20 @@
 //! }"#)?;
 //! 
 //! assert_eq!(&[22, 8, 0], factory.export_schema_bytes());
-//! 
+//!
 //! // [       22,         8, 0]
 //! // [data type, value schema]
 //!
-//! # Ok::<(), NP_Error>(()) 
+//! // maps with interned keys have a 255 flag byte before the value schema
+//! let factory: NP_Factory = NP_Factory::new_json(r#"{
+//!     "type": "map",
+//!     "intern_keys": true,
+//!     "value": {"type": "u8"}
+//! }"#)?;
+//!
+//! assert_eq!(&[22, 255, 8, 0], factory.export_schema_bytes());
+//!
+//! # Ok::<(), NP_Error>(())
 //! ```
 //! 
 //! ### Tuple (Collection)
@@ -168,4 +168,21 @@ pub fn murmurhash3_x86_32(bytes: &[u8], seed: u32) -> u32 {
     h1 = fmix32(h1);
 
     return h1;
-}
\ No newline at end of file
+}
+/// CRC-32 (IEEE 802.3, the same polynomial as zip and png) of the given bytes.
+/// 
+/// Computed bit by bit to avoid a lookup table, speed isn't critical for checksums that are only calculated when buffers are closed or opened.
+#[inline(always)]
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB88320 & mask);
+        }
+    }
+
+    return !crc;
+}
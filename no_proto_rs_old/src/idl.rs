@@ -47,29 +47,44 @@ enum js_control {
 impl JS_Schema {
     /// Parse a JS style schema into AST
     pub fn new(schema: String) -> Result<Self, NP_Error> {
+        // comments and line breaks are blanked out instead of removed so positions in the AST match the original schema
         let mut no_comments: String = String::with_capacity(schema.len());
 
-        schema.trim().split("\n").for_each(|f| {
-            let trimmed = f.trim();
+        schema.split("\n").enumerate().for_each(|(idx, line)| {
+            if idx > 0 {
+                no_comments.push(' ');
+            }
 
-            if trimmed.len() > 0 {
-                if let Some(idx) = trimmed.find("//") {
-                    if idx > 0 {
-                        no_comments.push_str(&trimmed[..idx]);
-                    }
-                } else {
-                    no_comments.push_str(trimmed);
-                };
+            let code = match line.find("//") {
+                Some(comment) => &line[..comment],
+                None => line
+            };
+
+            no_comments.push_str(&code.replace("\r", " "));
+
+            for _x in code.len()..line.len() {
+                no_comments.push(' ');
             }
         });
 
+        let start = no_comments.len() - no_comments.trim_start().len();
+        let end = no_comments.trim_end().len();
 
         Ok(Self {
-            ast: Self::parse(0, 0, no_comments.len(), &no_comments)?,
+            ast: Self::parse(0, start, usize::max(start, end), &no_comments)?,
             value: no_comments,
         })
     }
 
+    /// Create an error pointing at a location in the schema
+    pub fn error_at(&self, message: &str, addr: &AST_STR) -> NP_Error {
+        Self::error_at_index(message, &self.value, addr.start)
+    }
+
+    fn error_at_index(message: &str, schema: &str, index: usize) -> NP_Error {
+        NP_Error::schema_at(message, schema, index)
+    }
+
     /// Get a str value from the schema
     pub fn get_str(&self, addr: &AST_STR) -> &str {
         &self.value[addr.start..addr.end]
@@ -186,9 +201,7 @@ impl JS_Schema {
                 }
 
                 if closed == false {
-                    let mut message = String::from("Missing matching square bracket for array! -> ");
-                    message.push_str(&schema[start..usize::min(end, start + 20)]);
-                    return Err(NP_Error::new(message.as_str()))
+                    return Err(Self::error_at_index("Missing matching square bracket for array!", schema, start))
                 }
 
                 Ok(JS_AST::array { values: arr })
@@ -207,9 +220,7 @@ impl JS_Schema {
                     }
 
                     if closed_first == false {
-                        let mut message = String::from("Missing closure open curly! -> ");
-                        message.push_str(&schema[start..usize::min(end, start + 20)]);
-                        return Err(NP_Error::new(message.as_str()))
+                        return Err(Self::error_at_index("Missing closure open curly!", schema, start))
                     }
 
                     moving_start = index;
@@ -290,9 +301,7 @@ impl JS_Schema {
                     }
 
                     if closed == false {
-                        let mut message = String::from("Missing matching paran for function! -> ");
-                        message.push_str(&schema[start..usize::min(end, start + 20)]);
-                        return Err(NP_Error::new(message.as_str()))
+                        return Err(Self::error_at_index("Missing matching paran for function!", schema, start))
                     }
 
                     Ok(JS_AST::closure { expressions })
@@ -375,11 +384,7 @@ impl JS_Schema {
                     }
 
                     if closed == false {
-                        let mut message = String::from("Missing matching paran for function!\n");
-                        message.push_str(&schema[start..usize::min(end, start + 10)]);
-                        message.push_str("\n");
-                        message.push_str("^------\n");
-                        return Err(NP_Error::new(message.as_str()))
+                        return Err(Self::error_at_index("Missing matching paran for function!", schema, start))
                     }
 
                     Ok(JS_AST::method { name: fn_name, args })
@@ -432,9 +437,7 @@ impl JS_Schema {
                                         moving_start = index + 1;
                                         key = Option::None;
                                     } else {
-                                        let mut message = String::from("Missing property name in object! -> ");
-                                        message.push_str(&schema[moving_start..usize::min(end, moving_start + 10)]);
-                                        return Err(NP_Error::new(message.as_str()))
+                                        return Err(Self::error_at_index("Missing property name in object!", schema, moving_start))
                                     }
                                     closed = true; 
                                 } else {
@@ -462,9 +465,7 @@ impl JS_Schema {
                                     moving_start = index + 1;
                                     key = Option::None;
                                 } else {
-                                    let mut message = String::from("Missing property name in object! -> ");
-                                    message.push_str(&schema[moving_start..usize::min(end, moving_start + 10)]);
-                                    return Err(NP_Error::new(message.as_str()))
+                                    return Err(Self::error_at_index("Missing property name in object!", schema, moving_start))
                                 }
                             }
                         },
@@ -476,9 +477,7 @@ impl JS_Schema {
                 }
 
                 if closed == false {
-                    let mut message = String::from("Missing matching curly bracket for object! -> ");
-                    message.push_str(&schema[start..usize::min(end, start + 20)]);
-                    return Err(NP_Error::new(message.as_str()))
+                    return Err(Self::error_at_index("Missing matching curly bracket for object!", schema, start))
                 }
 
                 Ok(JS_AST::object{ properties: obj })
@@ -502,9 +501,7 @@ impl JS_Schema {
                 }
 
                 if closed == false {
-                    let mut message = String::from("Missing matching qutoes for string! -> ");
-                    message.push_str(&schema[start..usize::min(end, start + 20)]);
-                    return Err(NP_Error::new(message.as_str()))
+                    return Err(Self::error_at_index("Missing matching qutoes for string!", schema, start))
                 }
 
                 Ok(JS_AST::string{ addr: AST_STR { start: moving_start, end: index - 1} })
@@ -516,4 +513,40 @@ impl JS_Schema {
 #[test]
 fn test() {
     println!("{:?}", JS_Schema::new(String::from("struct({fields:{key: string()}})")));
-}
\ No newline at end of file
+}
+
+#[test]
+fn errors_have_position() -> Result<(), NP_Error> {
+    let message = |schema: &str| -> String {
+        match crate::NP_Factory::new(schema) {
+            Err(e @ NP_Error::BadSchema { .. }) => alloc::format!("{}", e),
+            _ => String::new()
+        }
+    };
+
+    assert_eq!(message("struct({fields: {age: u9()}})"), "Can't find a type that matches this schema! u9 (at character 22: `u9()}})`)");
+
+    // positions count from the original schema, including comments and line breaks
+    let schema = r#"struct({fields: {
+        // the price
+        price: dec(),
+        name: string()
+    }})"#;
+    assert_eq!(message(schema), "Decimal type requires 'exp' property! (at character 54: `dec(),         name:`)");
+
+    assert_eq!(message("list({of: string()"), "Missing matching paran for function! (at character 0: `list({of: string()`)");
+
+    match crate::NP_Factory::new("list({of: u9()})") {
+        Err(NP_Error::BadSchema { position, snippet, .. }) => {
+            assert_eq!(position, Some(10));
+            assert_eq!(snippet, "u9()})");
+        },
+        _ => panic!()
+    }
+
+    // comments are still removed
+    let factory = crate::NP_Factory::new("list({of: string()}) // a list")?;
+    assert_eq!(factory.schema.to_idl()?, "list({of: string()})");
+
+    Ok(())
+}
@@ -495,11 +495,186 @@ fn recursive(v: &mut NP_JSON,
 
 /// Parse a JSON string into a JSON object in memory
 pub fn json_decode<'json>(text: String) -> Result<Box<NP_JSON>, NP_Error> {
+    Ok(Box::new(json_decode_bytes(text.as_bytes())?))
+}
 
-    let mut ret = Box::new(NP_JSON::Null);
+/// Read the utf-8 character at `pos`, returns the character and it's width in bytes
+#[inline(always)]
+fn next_char(body: &[u8], pos: usize) -> Result<(char, usize), NP_Error> {
+    let width = match body[pos] {
+        0x00..=0x7F => return Ok((body[pos] as char, 1)),
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return Err(NP_Error::new("JSON Parse Error: invalid utf-8"))
+    };
+
+    if pos + width > body.len() {
+        return Err(NP_Error::new("JSON Parse Error: invalid utf-8"));
+    }
+
+    match core::str::from_utf8(&body[pos..(pos + width)]) {
+        Ok(x) => Ok((NP_Error::unwrap(x.chars().next())?, width)),
+        Err(_e) => Err(NP_Error::new("JSON Parse Error: invalid utf-8"))
+    }
+}
+
+/// Parse JSON from a byte slice into a JSON object in memory
+/// 
+/// The bytes are read in place, they don't need to be copied into a `String` first.
+/// 
+/// Parse errors include the byte offset where parsing stopped.
+pub fn json_decode_bytes(body: &[u8]) -> Result<NP_JSON, NP_Error> {
 
     let mut pos: usize = 0;
 
+    match json_decode_at(body, &mut pos) {
+        Err(NP_Error::Custom { message }) => Err(NP_Error::new(format!("{} at byte {}", message, pos))),
+        result => result
+    }
+}
+
+/// Parse a JSON schema, parse errors point at the character in the schema where parsing stopped
+pub(crate) fn json_decode_schema(text: &str) -> Result<Box<NP_JSON>, NP_Error> {
+
+    let mut pos: usize = 0;
+
+    match json_decode_at(text.as_bytes(), &mut pos) {
+        Ok(json) => Ok(Box::new(json)),
+        Err(NP_Error::Custom { message }) => Err(NP_Error::schema_at(message, text, pos)),
+        Err(e) => Err(e)
+    }
+}
+
+/// Find the byte index of the value at `path` in a JSON document, each path segment is an object key or an array index
+/// 
+/// If only part of the path is in the document the index of the deepest value that was found is returned.
+pub fn json_find(body: &[u8], path: &[String]) -> usize {
+
+    let mut pos = json_skip_space(body, 0);
+
+    for segment in path.iter() {
+        let found = match body.get(pos) {
+            Some(b'{') => json_find_key(body, pos + 1, segment.as_bytes()),
+            Some(b'[') => match segment.parse::<usize>() {
+                Ok(index) => json_find_index(body, pos + 1, index),
+                Err(_) => None
+            },
+            _ => None
+        };
+
+        match found {
+            Some(x) => pos = x,
+            None => return pos
+        }
+    }
+
+    pos
+}
+
+fn json_skip_space(body: &[u8], mut pos: usize) -> usize {
+    while pos < body.len() && (body[pos] as char).is_ascii_whitespace() {
+        pos += 1;
+    }
+    pos
+}
+
+/// Get the byte index just past the JSON value that starts at `pos`
+fn json_skip_value(body: &[u8], mut pos: usize) -> usize {
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+
+    while pos < body.len() {
+        let c = body[pos];
+        pos += 1;
+
+        if in_string {
+            match c {
+                b'\\' => pos += 1,
+                b'"' => {
+                    in_string = false;
+                    if depth == 0 { return pos }
+                },
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                if depth == 0 { return pos - 1 }
+                depth -= 1;
+                if depth == 0 { return pos }
+            },
+            b',' | b':' if depth == 0 => return pos - 1,
+            c if depth == 0 && (c as char).is_ascii_whitespace() => return pos - 1,
+            _ => {}
+        }
+    }
+
+    pos
+}
+
+/// Find the value for `key` in the object whose contents start at `pos`
+fn json_find_key(body: &[u8], mut pos: usize, key: &[u8]) -> Option<usize> {
+    loop {
+        pos = json_skip_space(body, pos);
+        if body.get(pos) != Some(&b'"') {
+            return None
+        }
+
+        let key_end = json_skip_value(body, pos);
+        let this_key = &body[(pos + 1)..usize::max(pos + 1, key_end - 1)];
+
+        pos = json_skip_space(body, key_end);
+        if body.get(pos) != Some(&b':') {
+            return None
+        }
+        pos = json_skip_space(body, pos + 1);
+
+        if this_key == key {
+            return Some(pos)
+        }
+
+        pos = json_skip_space(body, json_skip_value(body, pos));
+        if body.get(pos) != Some(&b',') {
+            return None
+        }
+        pos += 1;
+    }
+}
+
+/// Find the value at `index` in the array whose contents start at `pos`
+fn json_find_index(body: &[u8], mut pos: usize, index: usize) -> Option<usize> {
+    let mut current = 0usize;
+    loop {
+        pos = json_skip_space(body, pos);
+        match body.get(pos) {
+            None | Some(b']') => return None,
+            _ => {}
+        }
+
+        if current == index {
+            return Some(pos)
+        }
+
+        pos = json_skip_space(body, json_skip_value(body, pos));
+        if body.get(pos) != Some(&b',') {
+            return None
+        }
+        pos += 1;
+        current += 1;
+    }
+}
+
+/// Parse JSON from a byte slice, `pos` is left at the byte being read when parsing stopped
+fn json_decode_at(body: &[u8], pos: &mut usize) -> Result<NP_JSON, NP_Error> {
+
+    let mut ret = Box::new(NP_JSON::Null);
+
     let mut chain: Vec<char> = Vec::new();
     let mut d_chain: Vec<String> = Vec::new();
     let mut a_chain: Vec<i64> = Vec::new();
@@ -513,12 +688,11 @@ pub fn json_decode<'json>(text: String) -> Result<Box<NP_JSON>, NP_Error> {
     let mut s_false: String = "".to_owned();
     let mut s_null: String = "".to_owned();
 
-    let body: Vec<char> = text.chars().collect();
     let size = body.len();
-    let mut done = false;
+    let mut done = size == 0;
     while !done {
 
-        let c: char = body[pos];
+        let (c, width) = next_char(body, *pos)?;
 
         match last_chain {
             's' => {
@@ -1805,8 +1979,8 @@ pub fn json_decode<'json>(text: String) -> Result<Box<NP_JSON>, NP_Error> {
             _ => {}
         };
 
-        pos += 1;
-        if pos >= size {
+        *pos += width;
+        if *pos >= size {
             done = true;
         }
 
@@ -1815,5 +1989,5 @@ pub fn json_decode<'json>(text: String) -> Result<Box<NP_JSON>, NP_Error> {
     }
 
 
-    Ok(ret)
+    Ok(*ret)
 }
\ No newline at end of file
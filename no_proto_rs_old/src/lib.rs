@@ -335,6 +335,10 @@ pub mod json_flex;
 pub mod format;
 pub mod memory;
 pub mod new_idl;
+pub mod visitor;
+pub mod msgpack;
+#[cfg(feature = "std")]
+pub mod pool;
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub mod hashmap;
@@ -343,16 +347,30 @@ mod utils;
 #[macro_use]
 extern crate alloc;
 
+#[cfg(all(feature = "std", not(test)))]
+extern crate std;
+
+#[cfg(feature = "serde")]
+extern crate serde_crate as serde;
+#[cfg(feature = "serde")]
+mod serde_bridge;
+
 use core::ops::{Deref, DerefMut};
 // use crate::buffer_ro::NP_Buffer_RO;
-use crate::memory::NP_Memory;
-use crate::json_flex::NP_JSON;
-use crate::schema::NP_Schema;
+use crate::memory::{NP_Allocator, NP_Memory};
+use crate::json_flex::{JSMAP, NP_JSON};
+use crate::schema::{NP_Enum_Data, NP_Struct_Data, NP_Schema, NP_Schema_Compat, NP_Schema_Info, NP_TypeKeys};
+use crate::pointer::option::NP_Enum;
 use crate::json_flex::json_decode;
+use crate::msgpack::msgpack_decode;
 use crate::error::NP_Error;
-use buffer::{NP_Buffer, DEFAULT_ROOT_PTR_ADDR};
+use buffer::{NP_Buffer, NP_Buffer_In, DEFAULT_ROOT_PTR_ADDR};
+use pointer::NP_Cursor;
+use hashmap::{crc32, murmurhash3_x86_32, SEED};
 use alloc::vec::Vec;
 use alloc::string::String;
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
 use schema::NP_Parsed_Schema;
 
 /// Generate a path from a string.  The path must use dot notation between the path segments.
@@ -396,6 +414,47 @@ macro_rules! np_path {
     }
 }
 
+/// Implement [`FromColumns`](./buffer/trait.FromColumns.html) for a struct whose fields are `Option`s named after the columns they read.
+///
+/// Each field is listed with the type to read from the buffer, it must be an owned type like `String` or `u32`.  Columns that aren't listed are skipped.
+///
+/// ```
+/// use no_proto::error::NP_Error;
+/// use no_proto::NP_Factory;
+/// use no_proto::columns;
+///
+/// #[derive(Default)]
+/// struct Point { x: Option<i32>, y: Option<i32> }
+///
+/// columns!(Point { x: i32, y: i32 });
+///
+/// let factory = NP_Factory::new("struct({fields: {x: i32(), y: i32(), label: string()}})")?;
+///
+/// let mut new_buffer = factory.new_buffer(None);
+/// new_buffer.set(&["x"], 5i32)?;
+/// new_buffer.set(&["y"], -2i32)?;
+///
+/// let point: Point = new_buffer.get_columns(&[])?;
+/// assert_eq!((point.x, point.y), (Some(5), Some(-2)));
+///
+/// # Ok::<(), NP_Error>(())
+/// ```
+///
+#[macro_export]
+macro_rules! columns {
+    ($name: ident { $($field: ident : $t: ty),* $(,)? }) => {
+        impl $crate::buffer::FromColumns for $name {
+            fn from_column(&mut self, column: &$crate::buffer::NP_Column) -> Result<(), $crate::error::NP_Error> {
+                match column.name {
+                    $(stringify!($field) => { self.$field = column.get::<$t>()?; },)*
+                    _ => { }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 
 /// Factories are created from schemas.  Once you have a factory you can use it to create new buffers or open existing ones.
 /// 
@@ -421,7 +480,7 @@ macro_rules! np_path {
 /// // user_factory can now be used to make or open buffers that contain the data in the schema.
 /// 
 /// // create new buffer
-/// let mut user_buffer = user_factory.new_buffer(None); // optional capacity, optional address size
+/// let mut user_buffer = user_factory.new_buffer(None); // optional capacity
 ///    
 /// // set the "name" field of the struct
 /// user_buffer.set(&["name"], "Billy Joel")?;
@@ -487,7 +546,7 @@ impl NP_Factory {
 
     /// Generate a new factory from an ES6 schema
     /// 
-    /// The operation will fail if the string can't be parsed or the schema is otherwise invalid.
+    /// The operation will fail if the string can't be parsed or the schema is otherwise invalid.  The error message includes the character position in the schema where the problem was found, along with a snippet of the schema at that position.
     /// 
     pub fn new<S>(es6_schema: S) -> Result<Self, NP_Error> where S: Into<String> {
         // let idl = JS_Schema::new(es6_schema.into())?;
@@ -505,26 +564,197 @@ impl NP_Factory {
         }) 
     }
 
+    /// Generate a new factory from a JSON schema
+    /// 
+    /// The operation will fail if the string can't be parsed or the schema is otherwise invalid.  Like `NP_Factory::new` the error includes the character position in the schema of the offending value and a snippet of the schema at that position, along with the path to the value.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new_json(r#"{"type": "list", "of": {"type": "string"}}"#)?;
+    /// assert_eq!(factory.export_schema_idl()?, "list({of: string()})");
+    /// 
+    /// match NP_Factory::new_json(r#"{"type": "list", "of": {"type": "u9"}}"#) {
+    ///     Err(NP_Error::BadSchema { path, position, .. }) => {
+    ///         assert_eq!(path, vec!["of", "type"]);
+    ///         assert_eq!(position, Some(32));
+    ///     },
+    ///     _ => panic!()
+    /// }
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn new_json<S>(json_schema: S) -> Result<Self, NP_Error> where S: AsRef<str> {
+        let source = json_schema.as_ref();
+
+        let json_schema = json_flex::json_decode_schema(source)?;
+
+        let json_schema = Box::new(NP_Schema::_canonical_json(&json_schema));
+
+        let (is_sortable, schema_bytes, schema) = NP_Schema::from_json(Vec::new(), &json_schema).map_err(|e| e.locate_json(source))?;
+
+        Ok(Self {
+            schema_bytes: schema_bytes,
+            schema:  NP_Schema {
+                is_sortable: is_sortable,
+                parsed: schema
+            }
+        })
+    }
+
     /// Create a new factory from a compiled schema byte array.
     /// The byte schemas are at least an order of magnitude faster to parse than JSON schemas.
     /// 
+    /// The bytes are validated before they're parsed, truncated or corrupt schemas produce an error instead of a panic.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory = NP_Factory::new("struct({fields: {name: string(), tags: list({of: u8()})}})")?;
+    /// let schema_bytes = factory.export_schema_bytes();
+    /// 
+    /// let factory2 = NP_Factory::new_bytes(schema_bytes)?;
+    /// assert_eq!(factory2.export_schema_bytes(), schema_bytes);
+    /// 
+    /// // truncated schema
+    /// assert!(NP_Factory::new_bytes(&schema_bytes[..(schema_bytes.len() - 1)]).is_err());
+    /// 
+    /// // unknown type key
+    /// assert!(NP_Factory::new_bytes(&[200]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
     pub fn new_bytes(schema_bytes: &[u8]) -> Result<Self, NP_Error> {
+
+        if NP_Schema::_validate_bytes(schema_bytes, 0, 0)? != schema_bytes.len() {
+            return Err(NP_Error::new("Schema bytes contain trailing data!"))
+        }
+
+        Ok(Self::new_bytes_unchecked(schema_bytes))
+    }
+
+    /// Create a new factory from a compiled schema byte array without validating it first.
+    /// 
+    /// Only use this with schema bytes from a trusted source, like the output of `export_schema_bytes`.  Invalid bytes will panic or produce a broken factory.
+    /// 
+    pub fn new_bytes_unchecked(schema_bytes: &[u8]) -> Self {
         
         let (is_sortable, mut schema) = NP_Schema::from_bytes(Vec::new(), 0, schema_bytes);
 
         // schema = NP_Schema::resolve_portals(schema)?;
 
-        Ok(Self {
+        Self {
             schema_bytes: Vec::from(schema_bytes),
             schema:  NP_Schema { 
                 is_sortable: is_sortable,
                 parsed: schema
             }
+        }
+    }
+
+    /// Create a new factory from the struct based IDL.
+    /// 
+    /// The first `struct` declaration is the root of the schema, other declarations can be used as field types by name.  The IDL produces the same compiled schema as the equivalent JSON or ES6 schema.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::from_idl(r#"
+    ///     struct user {
+    ///         name: string(size: 20),
+    ///         age: u8(default: 18),
+    ///         tags: list { string }
+    ///     }
+    /// "#)?;
+    /// 
+    /// let es6: NP_Factory = NP_Factory::new("struct({fields: {name: string({size: 20}), age: u8({default: 18}), tags: list({of: string()})}})")?;
+    /// assert_eq!(factory.export_schema_bytes(), es6.export_schema_bytes());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn from_idl<S>(idl: S) -> Result<Self, NP_Error> where S: AsRef<str> {
+        let source = idl.as_ref();
+
+        let ast = new_idl::AST::parse(source)?;
+
+        let json_schema = new_idl::idl_to_json_schema(source, &ast)?;
+
+        let (is_sortable, schema_bytes, schema) = NP_Schema::from_json(Vec::new(), &Box::new(NP_Schema::_canonical_json(&json_schema)))?;
+
+        Ok(Self {
+            schema_bytes: schema_bytes,
+            schema:  NP_Schema {
+                is_sortable: is_sortable,
+                parsed: schema
+            }
+        })
+    }
+
+    /// Create a new factory from a JSON schema with shared type definitions.
+    ///
+    /// Anywhere in the schema (or in another definition) `{"$ref": "name"}` is replaced with the named JSON definition before the schema is parsed, so common sub schemas only have to be written once.  Keys next to `$ref` override the definition's top level keys.
+    ///
+    /// Definitions that reference themselves are rejected, use a `portal` for recursive types.
+    ///
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    ///
+    /// let address = r#"{"type": "struct", "fields": [["street", {"type": "string"}], ["zip", {"type": "string", "size": 5}]]}"#;
+    ///
+    /// let factory: NP_Factory = NP_Factory::with_definitions(r#"{
+    ///     "type": "struct",
+    ///     "fields": [
+    ///         ["home", {"$ref": "address"}],
+    ///         ["work", {"$ref": "address"}]
+    ///     ]
+    /// }"#, &[("address", address)])?;
+    ///
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["work", "zip"], "90210")?;
+    /// assert_eq!(new_buffer.get::<&str>(&["work", "zip"])?, Some("90210"));
+    ///
+    /// // cyclic definitions are an error
+    /// let node = r#"{"type": "struct", "fields": [["next", {"$ref": "node"}]]}"#;
+    /// assert!(NP_Factory::with_definitions(r#"{"$ref": "node"}"#, &[("node", node)]).is_err());
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    ///
+    pub fn with_definitions<S>(json_schema: S, definitions: &[(&str, &str)]) -> Result<Self, NP_Error> where S: AsRef<str> {
+
+        let mut defs = JSMAP::new();
+        for (name, definition) in definitions.iter() {
+            defs.insert(String::from(*name), *json_decode(String::from(*definition))?);
+        }
+
+        let source = json_schema.as_ref();
+
+        let json_schema = json_flex::json_decode_schema(source)?;
+
+        let resolved = NP_Schema::resolve_refs(&json_schema, &defs, &mut Vec::new())?;
+
+        let (is_sortable, schema_bytes, schema) = NP_Schema::from_json(Vec::new(), &Box::new(NP_Schema::_canonical_json(&resolved))).map_err(|e| e.locate_json(source))?;
+
+        Ok(Self {
+            schema_bytes: schema_bytes,
+            schema:  NP_Schema {
+                is_sortable: is_sortable,
+                parsed: schema
+            }
         })
     }
 
     /// Get a copy of the compiled schema byte array
     /// 
+    /// The bytes are deterministic, equivalent JSON schemas give the same bytes even if their object keys are in a different order or numbers are written differently (`2` or `2.0`).  This makes the bytes (or `schema_id`) safe to use for content addressing schemas.
+    /// 
     pub fn export_schema_bytes(&self) -> &[u8] {
         &self.schema_bytes[..]
     }
@@ -535,162 +765,2154 @@ impl NP_Factory {
         self.schema.to_idl()
     }
 
-    /// Open existing Vec<u8> as buffer for this factory.  
+    /// Exports this factory's schema to JSON Schema (draft-07).
     /// 
-    pub fn open_buffer(&self, bytes: Vec<u8>) -> NP_Buffer {
-        NP_Buffer::_new(NP_Memory::existing_owned(bytes, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    /// The JSON Schema describes the JSON produced by `NP_Buffer::json_encode`, it can be used to validate JSON before it's put into a buffer or with other JSON Schema tooling.  This is different from the native JSON schema format NoProto uses.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string({size: 10}), age: u8(), tags: list({of: string()})}})")?;
+    /// 
+    /// let json_schema = factory.export_json_schema()?;
+    /// assert_eq!(json_schema.stringify(), r#"{"$schema":"http://json-schema.org/draft-07/schema#","type":"object","properties":{"name":{"type":"string","maxLength":10},"age":{"type":"integer","minimum":0,"maximum":255},"tags":{"type":"array","items":{"type":"string"}}}}"#);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn export_json_schema(&self) -> Result<NP_JSON, NP_Error> {
+        self.schema.to_json_schema()
     }
 
-    /// Open existing buffer as ready only ref, can much faster if you don't need to mutate anything.
+    /// Exports this factory's schema to the struct based IDL.  The output can be parsed again with `NP_Factory::from_idl`.
     /// 
-    /// All operations that would lead to mutation fail.  You can't perform any mutations on a buffer opened with this method.
+    /// The root of the schema must be a struct, it's exported as a declaration named `root`.
     /// 
-    /// Also, read only buffers are `Sync` and `Send` so good for multithreaded environments.
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
     /// 
-    pub fn open_buffer_ref<'buffer>(&'buffer self, bytes: &'buffer [u8]) -> NP_Buffer {
-        NP_Buffer::_new(NP_Memory::existing_ref(bytes, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string({size: 20}), tags: list({of: string()})}})")?;
+    /// 
+    /// let idl = factory.export_idl()?;
+    /// assert_eq!(idl, "struct root {\n    name: string(size: 20),\n    tags: list { string },\n}\n");
+    /// 
+    /// let factory2: NP_Factory = NP_Factory::from_idl(idl)?;
+    /// assert_eq!(factory.export_schema_bytes(), factory2.export_schema_bytes());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn export_idl(&self) -> Result<String, NP_Error> {
+        new_idl::json_schema_to_idl(&self.schema.to_json()?)
     }
 
-    /// Open existing buffer as mutable ref, can be much faster to skip copying.  The `data_len` property is how many bytes the data in the buffer is using up.
+    /// Get a fingerprint of this factory's schema.
     /// 
-    /// Some mutations cannot be done without appending bytes to the existing buffer.  Since it's impossible to append bytes to a `&mut [u8]` type, you should provide mutable slice with extra bytes on the end if you plan to mutate the buffer.
+    /// The id is a hash of the compiled schema bytes, so it's identical for factories created from the same schema with `NP_Factory::new` or `NP_Factory::new_bytes`.  Any change to the schema will (very likely) produce a different id.
     /// 
-    /// The `data_len` is at which byte the data ends in the buffer, this will be moved as needed by compaction and mutation operations.  
+    /// This is useful to tag stored buffers with the schema that created them.
     /// 
-    /// If the `&mut [u8]` type has the same length as `data_len`, mutations that require additional bytes will fail. `&mut [u8].len() - data_len` is how many bytes the buffer has for new allocations.
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
     /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string()}})")?;
+    /// let factory2: NP_Factory = NP_Factory::new_bytes(factory.export_schema_bytes())?;
+    /// assert_eq!(factory.schema_id(), factory2.schema_id());
     /// 
-    pub fn open_buffer_ref_mut<'buffer>(&'buffer self, bytes: &'buffer mut [u8], data_len: usize) -> NP_Buffer {
-        NP_Buffer::_new(NP_Memory::existing_ref_mut(bytes, data_len, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    /// let factory3: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u8()}})")?;
+    /// assert_ne!(factory.schema_id(), factory3.schema_id());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn schema_id(&self) -> u32 {
+        murmurhash3_x86_32(&self.schema_bytes, SEED)
     }
 
-    /// Generate a new empty buffer from this factory.
+    /// Open existing Vec<u8> as buffer for this factory.  
+    /// 
+    pub fn open_buffer(&self, bytes: Vec<u8>) -> NP_Buffer {
+        NP_Buffer::_new(NP_Memory::existing_owned(bytes, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    }
+
+    /// Open bytes created with `NP_Buffer::close_with_checksum`.
     /// 
-    /// The first opional argument, capacity, can be used to set the space of the underlying Vec<u8> when it's created.  If you know you're going to be putting lots of data into the buffer, it's a good idea to set this to a large number comparable to the amount of data you're putting in.  The default is 1,024 bytes.
+    /// The 4 byte checksum at the end is verified against the rest of the bytes and removed before the buffer is opened.  If the bytes have been changed or truncated an error is returned.
     /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
     /// 
-    pub fn new_buffer<'buffer>(&'buffer self, capacity: Option<usize>) -> NP_Buffer {
-        NP_Buffer::_new(NP_Memory::new(capacity, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    /// let factory: NP_Factory = NP_Factory::new("u32()")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&[], 2000u32)?;
+    /// let mut bytes = new_buffer.close_with_checksum();
+    /// 
+    /// // flip a bit in the value
+    /// bytes[9] ^= 0b0000_0100;
+    /// assert!(factory.open_buffer_checked(bytes).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn open_buffer_checked(&self, mut bytes: Vec<u8>) -> Result<NP_Buffer, NP_Error> {
+        Self::strip_checksum(&mut bytes)?;
+        Ok(self.open_buffer(bytes))
     }
 
-    /// Generate a new empty buffer from this factory.
+    /// Open bytes created with `NP_Buffer::close_with_checksum` from a tagged buffer.
     /// 
-    /// Make sure the mutable slice is large enough to fit all the data you plan on putting into it.
+    /// The checksum is verified and removed like `open_buffer_checked`, then the schema id is checked like `open_buffer_tagged`.
     /// 
-    pub fn new_buffer_ref_mut<'buffer>(&'buffer self, bytes: &'buffer mut [u8]) -> NP_Buffer {
-        NP_Buffer::_new(NP_Memory::new_ref_mut(bytes, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("u32()")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer_tagged(None);
+    /// new_buffer.set(&[], 2000u32)?;
+    /// let bytes = new_buffer.close_with_checksum();
+    /// assert_eq!(&bytes[0..4], &factory.schema_id().to_be_bytes());
+    /// 
+    /// let buffer = factory.open_buffer_tagged_checked(bytes)?;
+    /// assert_eq!(buffer.get::<u32>(&[])?, Some(2000));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn open_buffer_tagged_checked(&self, mut bytes: Vec<u8>) -> Result<NP_Buffer, NP_Error> {
+        Self::strip_checksum(&mut bytes)?;
+        self.open_buffer_tagged(bytes)
     }
 
-    // /// Convert a regular buffer into a packed buffer. A "packed" buffer contains the schema and the buffer data together.
-    // /// 
-    // /// You can optionally store buffers with their schema attached so you don't have to track the schema seperatly.
-    // /// 
-    // /// The schema is stored in a very compact, binary format.  A JSON version of the schema can be generated from the binary version at any time.
-    // /// 
-    // pub fn pack_buffer(&self, buffer: NP_Buffer) -> NP_Packed_Buffer {
-    //     NP_Packed_Buffer {
-    //         buffer: NP_Buffer::_new(NP_Memory::existing_owned(buffer.finish().bytes(), &self.schema.parsed as *const Vec<NP_Parsed_Schema>, DEFAULT_ROOT_PTR_ADDR)),
-    //         schema_bytes: self.export_schema_bytes().to_vec(),
-    //         schema: self.schema.clone()
-    //     }
-    // }
-}
+    /// Verify and remove the checksum at the end of the bytes
+    fn strip_checksum(bytes: &mut Vec<u8>) -> Result<(), NP_Error> {
+        if bytes.len() < 4 {
+            return Err(NP_Error::new("Buffer is too small to have a checksum!"))
+        }
 
-// /// Packed Buffer Container
-// pub struct NP_Packed_Buffer {
-//     buffer: NP_Buffer,
-//     schema_bytes: Vec<u8>,
-//     /// Schema data for this packed buffer
-//     pub schema: NP_Schema
-// }
+        let data_len = bytes.len() - 4;
+        let checksum = u32::from_be_bytes([bytes[data_len], bytes[data_len + 1], bytes[data_len + 2], bytes[data_len + 3]]);
 
-// impl NP_Packed_Buffer {
+        if crc32(&bytes[..data_len]) != checksum {
+            return Err(NP_Error::new("Buffer checksum doesn't match, the bytes are corrupt!"))
+        }
 
-//     /// Open a packed buffer
-//     pub fn open(buffer: Vec<u8>) -> Result<Self, NP_Error> {
-//         if buffer[0] != 1 {
-//             return Err(NP_Error::new("Trying to use NP_Packed_Buffer::open on non packed buffer!"))
-//         }
+        bytes.truncate(data_len);
+        Ok(())
+    }
 
-//         let schema_len = u16::from_be_bytes(unsafe { *((&buffer[1..3]) as *const [u8] as *const [u8; 2]) }) as usize;
+    /// Generate a new empty buffer that's tagged with this factory's `schema_id`.
+    /// 
+    /// The buffer works like any other buffer, but when it's finished the schema id is written into the first 4 bytes (big endian) in front of the buffer data.  Tagged bytes must be opened with `open_buffer_tagged`, which checks the id so a buffer can't be read with the wrong schema by accident.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string()}})")?;
+    /// let other: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u8()}})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer_tagged(None);
+    /// new_buffer.set(&["name"], "Bob")?;
+    /// let bytes = new_buffer.finish().bytes();
+    /// assert_eq!(bytes[0..4], factory.schema_id().to_be_bytes());
+    /// 
+    /// let buffer = factory.open_buffer_tagged(bytes.clone())?;
+    /// assert_eq!(buffer.get::<&str>(&["name"])?, Some("Bob"));
+    /// 
+    /// match other.open_buffer_tagged(bytes) {
+    ///     Err(NP_Error::SchemaMismatch { expected, found }) => {
+    ///         assert_eq!(expected, other.schema_id());
+    ///         assert_eq!(found, factory.schema_id());
+    ///     },
+    ///     _ => panic!()
+    /// }
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn new_buffer_tagged<'buffer>(&'buffer self, capacity: Option<usize>) -> NP_Buffer {
+        let mut buffer = self.new_buffer(capacity);
+        buffer._set_schema_tag(Some(self.schema_id()));
+        buffer
+    }
 
-//         let schema_bytes = &buffer[3..(3 + schema_len)];
+    /// Open bytes created by a buffer from `new_buffer_tagged`.
+    /// 
+    /// The first 4 bytes are compared to this factory's `schema_id` and removed before the buffer is opened, if they don't match `NP_Error::SchemaMismatch` is returned.  The opened buffer stays tagged, finishing it writes the id again.
+    /// 
+    pub fn open_buffer_tagged(&self, mut bytes: Vec<u8>) -> Result<NP_Buffer, NP_Error> {
+        if bytes.len() < 4 {
+            return Err(NP_Error::new("Buffer is too small to have a schema id!"))
+        }
 
-//         let (is_sortable, mut schema) = NP_Schema::from_bytes(Vec::new(), 0, schema_bytes);
+        let found = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let expected = self.schema_id();
 
-//         // schema = NP_Schema::resolve_portals(schema)?;
+        if found != expected {
+            return Err(NP_Error::SchemaMismatch { expected, found })
+        }
 
-//         let buffer_bytes = &buffer[(3 + schema_len)..];
+        bytes.drain(0..4);
+        let mut buffer = self.open_buffer(bytes);
+        buffer._set_schema_tag(Some(expected));
+        Ok(buffer)
+    }
 
-//         Ok(Self {
-//             buffer: NP_Buffer::_new(NP_Memory::existing_owned(buffer_bytes.to_vec(), &schema as *const Vec<NP_Parsed_Schema>, DEFAULT_ROOT_PTR_ADDR)),
-//             schema_bytes: schema_bytes.to_vec(),
-//             schema: NP_Schema {
-//                 is_sortable: is_sortable,
-//                 parsed: schema
-//             }
-//         })
-//     }
+    /// Check the bytes of a buffer against this factory's schema before trusting them.
+    /// 
+    /// Every pointer, vtable and collection item is checked to make sure it's inside the buffer, list and map chains must terminate and map keys must be valid utf-8.  The bytes are never mutated.
+    /// 
+    /// If something is wrong an `NP_Error::BadAddress` error is returned with the first offending address and what was wrong with it.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["3"], "hello")?;
+    /// let mut bytes = new_buffer.finish().bytes();
+    /// 
+    /// assert!(factory.validate_buffer(&bytes).is_ok());
+    /// 
+    /// // point the list somewhere that doesn't exist
+    /// bytes[2..6].copy_from_slice(&[0, 0, 255, 255]);
+    /// assert!(matches!(factory.validate_buffer(&bytes), Err(NP_Error::BadAddress { addr: 65535, context: "value is out of bounds" })));
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn validate_buffer(&self, bytes: &[u8]) -> Result<(), NP_Error> {
+        if bytes.len() < DEFAULT_ROOT_PTR_ADDR + 4 {
+            return Err(NP_Error::BadAddress { addr: bytes.len(), context: "buffer is too small" });
+        }
 
-//     /// Close this buffer and pack it
-//     pub fn close_packed(self) -> Vec<u8> {
-//         let mut new_buffer: Vec<u8> = Vec::new();
-//         new_buffer.push(1); // indicate this is a packed buffer
-//         let schema = self.export_schema_bytes();
-//         // schema size
-//         new_buffer.extend_from_slice(&(schema.len() as u16).to_be_bytes());
-//         // schema data
-//         new_buffer.extend_from_slice(self.export_schema_bytes());
-//         // buffer data
-//         new_buffer.extend(self.buffer.finish().bytes());
-//         new_buffer
-//     }
+        let memory = NP_Memory::existing_ref(bytes, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR);
 
-//     /// Convert this packed buffer into a regular buffer
-//     pub fn into_buffer(self) -> NP_Buffer {
-//         self.buffer
-//     }
+        NP_Cursor::validate(0, &NP_Cursor::new(DEFAULT_ROOT_PTR_ADDR, 0, 0), &memory)
+    }
 
-//     /// Get the schema bytes for this packed buffer
-//     pub fn export_schema_bytes(&self) -> &[u8] {
-//         &self.schema_bytes[..]
-//     }
+    /// Check if buffers created by the `other` factory can be safely read by this factory.
+    /// 
+    /// New columns at the end of a struct are fine, as are removed columns at the end of a struct.  Changing the type or storage properties (like `size`) of an existing value is not.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::schema::{NP_Schema_Compat, NP_TypeKeys};
+    /// 
+    /// let old_factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u8()}})")?;
+    /// 
+    /// // new column at the end
+    /// let new_factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u8(), tags: list({of: string()})}})")?;
+    /// assert_eq!(new_factory.is_compatible_with(&old_factory)?, NP_Schema_Compat::Compatible);
+    /// 
+    /// // type of existing column changed
+    /// let new_factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), age: u16()}})")?;
+    /// assert_eq!(new_factory.is_compatible_with(&old_factory)?, NP_Schema_Compat::TypeChanged {
+    ///     path: String::from("age"),
+    ///     from: NP_TypeKeys::Uint8,
+    ///     to: NP_TypeKeys::Uint16
+    /// });
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn is_compatible_with(&self, other: &NP_Factory) -> Result<NP_Schema_Compat, NP_Error> {
+        self.schema.is_compatible_with(&other.schema)
+    }
 
-//     /// Exports this schema to ES6 IDL.  This works regardless of how the initial buffer schema was created.
-//     /// 
-//     pub fn export_schema_idl(&self) -> Result<String, NP_Error> {
-//         self.schema.to_idl()
-//     }
+    /// Get the choices of the enum at the provided path, in the order they were declared in the schema.
+    /// 
+    /// Returns `None` if the path doesn't point to an enum.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     color: enum({choices: ["red", "green", "blue"]}),
+    ///     name: string()
+    /// }})"#)?;
+    /// 
+    /// assert_eq!(factory.enum_variants(&["color"])?, Some(vec!["red", "green", "blue"]));
+    /// assert_eq!(factory.enum_variants(&["name"])?, None);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn enum_variants(&self, path: &[&str]) -> Result<Option<Vec<&str>>, NP_Error> {
 
-//     /// Exports this schema to JSON.  This works regardless of how the initial buffer schema was created.
-//     /// 
-//     pub fn export_schema_json(&self) -> Result<NP_JSON, NP_Error> {
-//         self.schema.to_json()
-//     }
-// }
+        // schema queries never read the buffer bytes
+        let memory = NP_Memory::existing_ref(&[], &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR);
 
-// impl Deref for NP_Packed_Buffer {
-//     type Target = NP_Buffer;
+        let schema_addr = match NP_Cursor::select(&memory, NP_Cursor::new(DEFAULT_ROOT_PTR_ADDR, 0, 0), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Ok(None)
+        };
 
-//     fn deref(&self) -> &Self::Target {
-//         &self.buffer
-//     }
-// }
+        let schema = &self.schema.parsed[schema_addr];
 
-// impl DerefMut for NP_Packed_Buffer {
-//     fn deref_mut(&mut self) -> &mut Self::Target {
-//         &mut self.buffer
-//     }
-// }
+        if schema.i != NP_TypeKeys::Enum {
+            return Ok(None)
+        }
+
+        let data = unsafe { &*(*schema.data as *const NP_Enum_Data) };
+
+        Ok(Some(data.choices.iter().filter_map(|choice| {
+            match choice {
+                NP_Enum::Some(x) => Some(x.as_str()),
+                NP_Enum::None => None
+            }
+        }).collect()))
+    }
+
+    /// Get the field names of the struct at the provided path, in the order they were declared in the schema.
+    /// 
+    /// This is the same order used to store and iterate the fields, so it's a stable order for mapping rows to buffers.  Returns `None` if the path doesn't point to a struct.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     name: string(),
+    ///     address: struct({fields: {street: string(), city: string(), zip: u32()}})
+    /// }})"#)?;
+    /// 
+    /// assert_eq!(factory.schema_columns(&[])?, Some(vec!["name", "address"]));
+    /// assert_eq!(factory.schema_columns(&["address"])?, Some(vec!["street", "city", "zip"]));
+    /// assert_eq!(factory.schema_columns(&["name"])?, None);
+    /// assert_eq!(factory.schema_columns(&["nope"])?, None);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn schema_columns(&self, path: &[&str]) -> Result<Option<Vec<&str>>, NP_Error> {
+
+        // schema queries never read the buffer bytes
+        let memory = NP_Memory::existing_ref(&[], &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR);
+
+        let schema_addr = match NP_Cursor::select(&memory, NP_Cursor::new(DEFAULT_ROOT_PTR_ADDR, 0, 0), false, true, path)? {
+            Some(x) => x.schema_addr,
+            None => return Ok(None)
+        };
+
+        let schema = &self.schema.parsed[schema_addr];
+
+        if schema.i != NP_TypeKeys::Struct {
+            return Ok(None)
+        }
+
+        let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+
+        Ok(Some(data.fields.iter().map(|field| field.col.as_str()).collect()))
+    }
+
+    /// Describe the schema type at a given path without parsing the schema bytes.
+    /// 
+    /// Returns `None` if the path doesn't exist in the schema.  Portals are followed to the type they point to.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::schema::NP_TypeKeys;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     age: u8(),
+    ///     tags: list({of: string()}),
+    ///     point: tuple({values: [i32(), i32()]})
+    /// }})"#)?;
+    /// 
+    /// let root = factory.inspect(&[])?.unwrap();
+    /// assert_eq!(root.kind, NP_TypeKeys::Struct);
+    /// assert_eq!(root.fields, vec!["age", "tags", "point"]);
+    /// 
+    /// let age = factory.inspect(&["age"])?.unwrap();
+    /// assert_eq!(age.is_collection, false);
+    /// assert_eq!((age.min, age.max), (Some(0), Some(255)));
+    /// 
+    /// assert_eq!(factory.inspect(&["tags"])?.unwrap().of, Some(NP_TypeKeys::UTF8String));
+    /// assert_eq!(factory.inspect(&["point"])?.unwrap().values, vec![NP_TypeKeys::Int32, NP_TypeKeys::Int32]);
+    /// assert_eq!(factory.inspect(&["point", "1"])?.unwrap().kind, NP_TypeKeys::Int32);
+    /// assert_eq!(factory.inspect(&["nope"])?, None);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn inspect(&self, path: &[&str]) -> Result<Option<NP_Schema_Info>, NP_Error> {
+
+        // schema queries never read the buffer bytes
+        let memory = NP_Memory::existing_ref(&[], &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR);
+
+        match NP_Cursor::select(&memory, NP_Cursor::new(DEFAULT_ROOT_PTR_ADDR, 0, 0), false, true, path)? {
+            Some(x) => Ok(Some(NP_Schema::_type_info(&self.schema.parsed, x.schema_addr))),
+            None => Ok(None)
+        }
+    }
+
+    /// Get every type used anywhere in the schema.
+    /// 
+    /// Struct fields, tuple values, list and map items are all included, and portals are followed to the types they point to.  Enum choices are stored by name so they only add `Enum` to the set.
+    /// 
+    /// Useful for code generators that only want to emit handlers for the types a schema actually uses.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::schema::NP_TypeKeys;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new(r#"struct({fields: {
+    ///     age: u8(),
+    ///     color: enum({choices: ["red", "green"]}),
+    ///     tags: list({of: map({value: string()})}),
+    ///     point: tuple({values: [i32(), f64()]})
+    /// }})"#)?;
+    /// 
+    /// let types: Vec<NP_TypeKeys> = factory.types_used().into_iter().collect();
+    /// assert_eq!(types, vec![
+    ///     NP_TypeKeys::UTF8String,
+    ///     NP_TypeKeys::Int32,
+    ///     NP_TypeKeys::Uint8,
+    ///     NP_TypeKeys::Double,
+    ///     NP_TypeKeys::Enum,
+    ///     NP_TypeKeys::Struct,
+    ///     NP_TypeKeys::Map,
+    ///     NP_TypeKeys::List,
+    ///     NP_TypeKeys::Tuple
+    /// ]);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn types_used(&self) -> BTreeSet<NP_TypeKeys> {
+        let mut types = BTreeSet::new();
+        NP_Schema::_types_used(&self.schema.parsed, 0, &mut types);
+        types
+    }
+
+    /// Read a buffer written with `NP_Buffer::write_to` from a `std::io::Read` source, like a file or socket.
+    /// 
+    /// A 4 byte big endian length is read first, followed by that many bytes of buffer data.  The bytes aren't checked against the schema, use `validate_buffer` on the result if the source isn't trusted.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    #[cfg(feature = "std")]
+    pub fn read_from<R: std::io::Read>(&self, r: &mut R) -> Result<NP_Buffer, NP_Error> {
+        Ok(self.open_buffer(Self::read_frame(r)?))
+    }
+
+    /// Read a tagged buffer written with `NP_Buffer::write_to`, the schema id is checked like `open_buffer_tagged`.
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    #[cfg(feature = "std")]
+    pub fn read_from_tagged<R: std::io::Read>(&self, r: &mut R) -> Result<NP_Buffer, NP_Error> {
+        self.open_buffer_tagged(Self::read_frame(r)?)
+    }
+
+    /// Read the length and bytes written by `NP_Buffer::write_to`
+    #[cfg(feature = "std")]
+    fn read_frame<R: std::io::Read>(r: &mut R) -> Result<Vec<u8>, NP_Error> {
+        let mut length = [0u8; 4];
+        r.read_exact(&mut length)?;
+        let length = u32::from_be_bytes(length) as usize;
+
+        // don't trust the length for the allocation, a bad length would reserve up to 4GB
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut limited = std::io::Read::take(&mut *r, length as u64);
+        std::io::Read::read_to_end(&mut limited, &mut bytes)?;
+
+        if bytes.len() != length {
+            return Err(NP_Error::new("Stream ended before the end of the buffer!"))
+        }
+
+        Ok(bytes)
+    }
+
+    /// Build a new buffer from any value that implements serde's `Serialize`.
+    /// 
+    /// The value is first converted into `NP_JSON`, then set into the buffer with `buffer_from_json`, so the top level must match the root of the schema and data that doesn't fit is ignored.
+    /// 
+    /// `NP_Buffer` also implements `Serialize`, producing the same structure as `json_encode`.
+    /// 
+    /// Requires the `serde` feature.
+    /// 
+    #[cfg(feature = "serde")]
+    pub fn from_serde<T: serde::Serialize>(&self, value: &T) -> Result<NP_Buffer, NP_Error> {
+        let json = serde_bridge::to_json(value)?;
+        self.buffer_from_json(&json, None)
+    }
+
+    /// Open existing buffer as ready only ref, can much faster if you don't need to mutate anything.
+    /// 
+    /// All operations that would lead to mutation fail.  You can't perform any mutations on a buffer opened with this method.
+    /// 
+    /// Also, read only buffers are `Sync` and `Send` so good for multithreaded environments.
+    /// 
+    pub fn open_buffer_ref<'buffer>(&'buffer self, bytes: &'buffer [u8]) -> NP_Buffer {
+        NP_Buffer::_new(NP_Memory::existing_ref(bytes, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    }
+
+    /// Open existing buffer as mutable ref, can be much faster to skip copying.  The `data_len` property is how many bytes the data in the buffer is using up.
+    /// 
+    /// Some mutations cannot be done without appending bytes to the existing buffer.  Since it's impossible to append bytes to a `&mut [u8]` type, you should provide mutable slice with extra bytes on the end if you plan to mutate the buffer.
+    /// 
+    /// The `data_len` is at which byte the data ends in the buffer, this will be moved as needed by compaction and mutation operations.  
+    /// 
+    /// If the `&mut [u8]` type has the same length as `data_len`, mutations that require additional bytes will fail. `&mut [u8].len() - data_len` is how many bytes the buffer has for new allocations.
+    /// 
+    /// 
+    pub fn open_buffer_ref_mut<'buffer>(&'buffer self, bytes: &'buffer mut [u8], data_len: usize) -> NP_Buffer {
+        NP_Buffer::_new(NP_Memory::existing_ref_mut(bytes, data_len, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    }
+
+    /// Generate a new empty buffer from this factory.
+    /// 
+    /// The first opional argument, capacity, can be used to set the space of the underlying Vec<u8> when it's created.  If you know you're going to be putting lots of data into the buffer, it's a good idea to set this to a large number comparable to the amount of data you're putting in.  The default is the size from `estimate_capacity`.
+    /// 
+    /// Older versions always started with 1024 bytes when no capacity was given, buffers for small schemas now start much smaller and grow as values are set.  Pass `Some(1024)` to keep the old behavior.
+    /// 
+    pub fn new_buffer<'buffer>(&'buffer self, capacity: Option<usize>) -> NP_Buffer {
+        let capacity = match capacity {
+            Some(x) => x,
+            None => self.estimate_capacity()
+        };
+        NP_Buffer::_new(NP_Memory::new(Some(capacity), &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    }
+
+    /// Estimate how many bytes a buffer from this factory will use.
+    /// 
+    /// This is a heuristic used as the default capacity for `new_buffer` to reduce reallocations while a buffer is being filled.  Fixed size values, struct vtables and tuples are counted as if every value is set.  Lists, maps, portals, and strings or bytes without a `size` can't be known ahead of time so each one adds a small fixed amount.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {id: u64(), age: u8()}})")?;
+    /// // 6 byte header, 20 byte vtable, 9 bytes of values
+    /// assert_eq!(factory.estimate_capacity(), 35);
+    /// 
+    /// let mut new_buffer = factory.new_buffer(None);
+    /// new_buffer.set(&["id"], 20u64)?;
+    /// new_buffer.set(&["age"], 30u8)?;
+    /// assert_eq!(new_buffer.data_length(), 35);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn estimate_capacity(&self) -> usize {
+        // is_packed, size, root pointer
+        6 + NP_Schema::_estimate_size(&self.schema.parsed, 0, 0)
+    }
+
+    /// Calculate exactly how many bytes a buffer holding this JSON value will use, without building the buffer.
+    /// 
+    /// The JSON is walked against the schema the same way `buffer_from_json` sets it, counting struct vtables, list and map item pointers, map keys, string and bytes length prefixes and the values themselves.  JSON that doesn't line up with the schema is ignored, just like it is when it's set.
+    /// 
+    /// The result is the size of a compacted buffer, it's a good `capacity` to avoid reallocations while the JSON is being set.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::json_flex::json_decode;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), tags: list({of: string()}), meta: map({value: u32()})}})")?;
+    /// 
+    /// let json = json_decode(String::from(r#"{"name": "Jeb Kermin", "tags": ["pilot", "kerbal"], "meta": {"age": 30}}"#))?;
+    /// let size = factory.size_of_json(&json)?;
+    /// 
+    /// let new_buffer = factory.buffer_from_json(&json, Some(size))?;
+    /// assert_eq!(new_buffer.calc_bytes()?.after_compaction, size);
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn size_of_json(&self, json: &NP_JSON) -> Result<usize, NP_Error> {
+        // is_packed, size, root pointer
+        Ok(6 + NP_Schema::_json_size(&self.schema.parsed, 0, json, 0)?.unwrap_or(0))
+    }
+
+    /// Generate a new buffer from a JSON value.
+    /// 
+    /// This works like `new_buffer` followed by `set_json` at the root and is the inverse of `NP_Buffer::json_encode`.  If no capacity is provided the buffer is sized with `size_of_json` so it doesn't have to grow while the JSON is set.
+    /// 
+    /// The top level of the JSON must match the root of the schema, objects for structs and maps and arrays for lists and tuples.  Data that doesn't align with the schema is ignored.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::json_flex::json_decode;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), tags: list({of: string()})}})")?;
+    /// 
+    /// let json = json_decode(String::from(r#"{"name": "Jeb Kermin", "tags": ["pilot", "kerbal"]}"#))?;
+    /// let new_buffer = factory.buffer_from_json(&json, Some(128))?;
+    /// 
+    /// assert_eq!(new_buffer.get::<&str>(&["name"])?, Some("Jeb Kermin"));
+    /// assert_eq!(new_buffer.get::<&str>(&["tags", "1"])?, Some("kerbal"));
+    /// 
+    /// let wrong_shape = json_decode(String::from(r#"["Jeb Kermin"]"#))?;
+    /// assert!(factory.buffer_from_json(&wrong_shape, None).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn buffer_from_json<'buffer>(&'buffer self, json: &NP_JSON, capacity: Option<usize>) -> Result<NP_Buffer, NP_Error> {
+
+        let root_type = self.schema.parsed[0].i;
+
+        let matches = match (root_type, json) {
+            (NP_TypeKeys::Struct, NP_JSON::Dictionary(_)) => true,
+            (NP_TypeKeys::Map, NP_JSON::Dictionary(_)) => true,
+            (NP_TypeKeys::List, NP_JSON::Array(_)) => true,
+            (NP_TypeKeys::Tuple, NP_JSON::Array(_)) => true,
+            (NP_TypeKeys::Struct, _) | (NP_TypeKeys::Map, _) | (NP_TypeKeys::List, _) | (NP_TypeKeys::Tuple, _) => false,
+            _ => true
+        };
+
+        if matches == false {
+            let mut err = String::from("JSON doesn't match the root schema type (");
+            err.push_str(root_type.into_type_idx().0);
+            err.push_str(")!");
+            return Err(NP_Error::new(err))
+        }
+
+        let capacity = match capacity {
+            Some(x) => x,
+            None => self.size_of_json(json)?
+        };
+
+        let mut buffer = self.new_buffer(Some(capacity));
+        buffer.set_json(&[], json, false)?;
+        Ok(buffer)
+    }
+
+    /// Generate a new buffer from MessagePack bytes.
+    /// 
+    /// The bytes are parsed with [`msgpack_decode`](msgpack/fn.msgpack_decode.html) and then set with `buffer_from_json`, so values are stored the same way JSON would be and the buffer is the inverse of `NP_Buffer::to_msgpack`.  Unlike `buffer_from_json` the whole value is checked against the schema first.  Fields that aren't in the schema, values of the wrong type and enum strings that aren't a choice return an error with the path to the value instead of being ignored.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string(), tags: list({of: u16()})}})")?;
+    /// 
+    /// // {"name": "Bob", "tags": [1, 300]}
+    /// let new_buffer = factory.buffer_from_msgpack(&[
+    ///     0x82,
+    ///     0xa4, b'n', b'a', b'm', b'e', 0xa3, b'B', b'o', b'b',
+    ///     0xa4, b't', b'a', b'g', b's', 0x92, 0x01, 0xcd, 0x01, 0x2c
+    /// ])?;
+    /// 
+    /// assert_eq!(new_buffer.get::<&str>(&["name"])?, Some("Bob"));
+    /// assert_eq!(new_buffer.get::<u16>(&["tags", "1"])?, Some(300));
+    /// 
+    /// // {"name": 5}
+    /// assert!(factory.buffer_from_msgpack(&[0x81, 0xa4, b'n', b'a', b'm', b'e', 0x05]).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn buffer_from_msgpack<'buffer>(&'buffer self, bytes: &[u8]) -> Result<NP_Buffer, NP_Error> {
+        let json = msgpack_decode(bytes)?;
+        NP_Schema::_check_json(&self.schema.parsed, 0, &json, &mut Vec::new(), 0)?;
+        self.buffer_from_json(&json, None)
+    }
+
+    /// Generate a new empty buffer from this factory.
+    /// 
+    /// Make sure the mutable slice is large enough to fit all the data you plan on putting into it.
+    /// 
+    pub fn new_buffer_ref_mut<'buffer>(&'buffer self, bytes: &'buffer mut [u8]) -> NP_Buffer {
+        NP_Buffer::_new(NP_Memory::new_ref_mut(bytes, &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR))
+    }
+
+    /// Create a pool that hands out empty buffers from this factory and takes them back when they're dropped.
+    /// 
+    /// The optional capacity is used for new buffers the pool has to make, it works like the one for `new_buffer`.  [Learn about buffer pools here.](./pool/index.html)
+    /// 
+    /// Requires the `std` feature.
+    /// 
+    #[cfg(feature = "std")]
+    pub fn buffer_pool<'pool>(&'pool self, capacity: Option<usize>) -> pool::NP_BufferPool<'pool> {
+        pool::NP_BufferPool::new(self, capacity)
+    }
+
+    /// Generate a new empty buffer that gets its memory from a custom allocator.
+    /// 
+    /// Every allocation the buffer makes, including compaction, goes through the allocator instead of the global one.  The optional capacity works like the one for `new_buffer`.  If the allocator can't provide the initial block an error is returned.
+    /// 
+    /// The buffer is returned as an [`NP_Buffer_In`](buffer/struct.NP_Buffer_In.html) that borrows the allocator, it can be used like any other buffer.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::NP_Factory;
+    /// use no_proto::memory::NP_Allocator;
+    /// use core::cell::{Cell, UnsafeCell};
+    /// 
+    /// // a tiny bump arena
+    /// struct Arena { bytes: UnsafeCell<[u8; 1024]>, used: Cell<usize> }
+    /// 
+    /// unsafe impl NP_Allocator for Arena {
+    ///     fn alloc(&self, size: usize) -> *mut u8 {
+    ///         let start = self.used.get();
+    ///         if start + size > 1024 { return core::ptr::null_mut() }
+    ///         self.used.set(start + size);
+    ///         unsafe { (*self.bytes.get()).as_mut_ptr().add(start) }
+    ///     }
+    ///     fn grow(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> *mut u8 {
+    ///         let new_ptr = self.alloc(new_size);
+    ///         if new_ptr.is_null() { return new_ptr }
+    ///         unsafe { core::ptr::copy_nonoverlapping(ptr, new_ptr, old_size) };
+    ///         new_ptr
+    ///     }
+    /// }
+    /// 
+    /// let arena = Arena { bytes: UnsafeCell::new([0; 1024]), used: Cell::new(0) };
+    /// 
+    /// let factory: NP_Factory = NP_Factory::new("list({of: string()})")?;
+    /// 
+    /// let mut new_buffer = factory.new_buffer_in(&arena, Some(16))?;
+    /// new_buffer.list_push(&[], "hello")?;
+    /// new_buffer.list_push(&[], "world")?;
+    /// assert_eq!(new_buffer.get::<&str>(&["1"])?, Some("world"));
+    /// 
+    /// // the buffer grew inside the arena
+    /// assert!(arena.used.get() > 16);
+    /// 
+    /// // out of arena space
+    /// assert!(factory.new_buffer_in(&arena, Some(2048)).is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn new_buffer_in<'buffer, A: NP_Allocator>(&'buffer self, alloc: &'buffer A, capacity: Option<usize>) -> Result<NP_Buffer_In<'buffer, A>, NP_Error> {
+        let capacity = match capacity {
+            Some(x) => x,
+            None => self.estimate_capacity()
+        };
+        let alloc: *const (dyn NP_Allocator + 'buffer) = alloc;
+        // NP_Buffer_In carries the borrow and never hands out the inner buffer mutably, so the memory can't be used once the allocator is gone
+        let alloc: *const dyn NP_Allocator = unsafe { core::mem::transmute(alloc) };
+        Ok(NP_Buffer_In::_new(NP_Buffer::_new(NP_Memory::new_in(alloc, Some(capacity), &self.schema.parsed, DEFAULT_ROOT_PTR_ADDR)?)))
+    }
+
+    // /// Convert a regular buffer into a packed buffer. A "packed" buffer contains the schema and the buffer data together.
+    // /// 
+    // /// You can optionally store buffers with their schema attached so you don't have to track the schema seperatly.
+    // /// 
+    // /// The schema is stored in a very compact, binary format.  A JSON version of the schema can be generated from the binary version at any time.
+    // /// 
+    // pub fn pack_buffer(&self, buffer: NP_Buffer) -> NP_Packed_Buffer {
+    //     NP_Packed_Buffer {
+    //         buffer: NP_Buffer::_new(NP_Memory::existing_owned(buffer.finish().bytes(), &self.schema.parsed as *const Vec<NP_Parsed_Schema>, DEFAULT_ROOT_PTR_ADDR)),
+    //         schema_bytes: self.export_schema_bytes().to_vec(),
+    //         schema: self.schema.clone()
+    //     }
+    // }
+}
+
+// /// Packed Buffer Container
+// pub struct NP_Packed_Buffer {
+//     buffer: NP_Buffer,
+//     schema_bytes: Vec<u8>,
+//     /// Schema data for this packed buffer
+//     pub schema: NP_Schema
+// }
+
+// impl NP_Packed_Buffer {
+
+//     /// Open a packed buffer
+//     pub fn open(buffer: Vec<u8>) -> Result<Self, NP_Error> {
+//         if buffer[0] != 1 {
+//             return Err(NP_Error::new("Trying to use NP_Packed_Buffer::open on non packed buffer!"))
+//         }
+
+//         let schema_len = u16::from_be_bytes(unsafe { *((&buffer[1..3]) as *const [u8] as *const [u8; 2]) }) as usize;
+
+//         let schema_bytes = &buffer[3..(3 + schema_len)];
+
+//         let (is_sortable, mut schema) = NP_Schema::from_bytes(Vec::new(), 0, schema_bytes);
+
+//         // schema = NP_Schema::resolve_portals(schema)?;
+
+//         let buffer_bytes = &buffer[(3 + schema_len)..];
+
+//         Ok(Self {
+//             buffer: NP_Buffer::_new(NP_Memory::existing_owned(buffer_bytes.to_vec(), &schema as *const Vec<NP_Parsed_Schema>, DEFAULT_ROOT_PTR_ADDR)),
+//             schema_bytes: schema_bytes.to_vec(),
+//             schema: NP_Schema {
+//                 is_sortable: is_sortable,
+//                 parsed: schema
+//             }
+//         })
+//     }
+
+//     /// Close this buffer and pack it
+//     pub fn close_packed(self) -> Vec<u8> {
+//         let mut new_buffer: Vec<u8> = Vec::new();
+//         new_buffer.push(1); // indicate this is a packed buffer
+//         let schema = self.export_schema_bytes();
+//         // schema size
+//         new_buffer.extend_from_slice(&(schema.len() as u16).to_be_bytes());
+//         // schema data
+//         new_buffer.extend_from_slice(self.export_schema_bytes());
+//         // buffer data
+//         new_buffer.extend(self.buffer.finish().bytes());
+//         new_buffer
+//     }
+
+//     /// Convert this packed buffer into a regular buffer
+//     pub fn into_buffer(self) -> NP_Buffer {
+//         self.buffer
+//     }
+
+//     /// Get the schema bytes for this packed buffer
+//     pub fn export_schema_bytes(&self) -> &[u8] {
+//         &self.schema_bytes[..]
+//     }
+
+//     /// Exports this schema to ES6 IDL.  This works regardless of how the initial buffer schema was created.
+//     /// 
+//     pub fn export_schema_idl(&self) -> Result<String, NP_Error> {
+//         self.schema.to_idl()
+//     }
+
+//     /// Exports this schema to JSON.  This works regardless of how the initial buffer schema was created.
+//     /// 
+//     pub fn export_schema_json(&self) -> Result<NP_JSON, NP_Error> {
+//         self.schema.to_json()
+//     }
+// }
+
+// impl Deref for NP_Packed_Buffer {
+//     type Target = NP_Buffer;
+
+//     fn deref(&self) -> &Self::Target {
+//         &self.buffer
+//     }
+// }
+
+// impl DerefMut for NP_Packed_Buffer {
+//     fn deref_mut(&mut self) -> &mut Self::Target {
+//         &mut self.buffer
+//     }
+// }
+
+#[test]
+fn threading_works() {
+    let fact = NP_Factory::new("string()").unwrap();
+    let buffer = fact.new_buffer(None);
+    std::thread::spawn(move || {
+        let f = fact.export_schema_bytes();
+        let b = buffer;
+        assert_eq!(6, b.calc_bytes().unwrap().current_buffer);
+        assert_eq!(8, f.len());
+    }).join().unwrap()
+}
+
+#[test]
+fn validate_buffer_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        name: string(),
+        tags: map({value: string()}),
+        scores: list({of: u32()})
+    }})"#)?;
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["name"], "hello")?;
+    buffer.set(&["tags", "foo"], "bar")?;
+    buffer.set(&["tags", "foo2"], "bar2")?;
+    buffer.set(&["scores", "2"], 20u32)?;
+    buffer.set(&["scores", "5"], 50u32)?;
+    let bytes = buffer.finish().bytes();
+    assert!(factory.validate_buffer(&bytes).is_ok());
+
+    // truncated buffer
+    assert!(factory.validate_buffer(&bytes[..(bytes.len() - 2)]).is_err());
+
+    // map items are stored right before their keys, point the last item back at the head
+    let find = |needle: &[u8]| bytes.windows(needle.len()).position(|w| w == needle).unwrap();
+    let head_addr = find(&[4, b'f', b'o', b'o', b'2']) - 12;
+    let tail_addr = find(&[3, b'f', b'o', b'o', 0]) - 12;
+    let mut cyclic = bytes.clone();
+    cyclic[(tail_addr + 4)..(tail_addr + 8)].copy_from_slice(&(head_addr as u32).to_be_bytes());
+    assert!(matches!(factory.validate_buffer(&cyclic), Err(NP_Error::BadAddress { context: "map items contain a cycle", .. })));
+
+    assert!(matches!(factory.validate_buffer(&[0, 0, 0]), Err(NP_Error::BadAddress { addr: 3, context: "buffer is too small" })));
+
+    Ok(())
+}
+
+#[test]
+fn is_compatible_with_works() -> Result<(), NP_Error> {
+    let old = NP_Factory::new("struct({fields: {name: string(), pair: tuple({values: [u8(), dec({exp: 2})]}), color: enum({choices: [\"red\", \"blue\"]})}})")?;
+
+    // renamed and removed trailing columns
+    let new = NP_Factory::new("struct({fields: {title: string(), pair: tuple({values: [u8(), dec({exp: 2})]})}})")?;
+    assert_eq!(new.is_compatible_with(&old)?, NP_Schema_Compat::Compatible);
+
+    // new enum choices at the end
+    let new = NP_Factory::new("struct({fields: {name: string(), pair: tuple({values: [u8(), dec({exp: 2})]}), color: enum({choices: [\"red\", \"blue\", \"green\"]})}})")?;
+    assert_eq!(new.is_compatible_with(&old)?, NP_Schema_Compat::Compatible);
+
+    let new = NP_Factory::new("struct({fields: {name: string(), pair: tuple({values: [u8(), dec({exp: 3})]})}})")?;
+    assert_eq!(new.is_compatible_with(&old)?, NP_Schema_Compat::PropertyChanged { path: String::from("pair.1"), property: String::from("exp") });
+
+    let new = NP_Factory::new("struct({fields: {name: string(), pair: tuple({values: [u8()]})}})")?;
+    assert_eq!(new.is_compatible_with(&old)?, NP_Schema_Compat::TupleLengthChanged { path: String::from("pair"), from: 2, to: 1 });
+
+    let new = NP_Factory::new("struct({fields: {name: string(), pair: tuple({values: [u8(), dec({exp: 2})]}), color: enum({choices: [\"blue\", \"red\"]})}})")?;
+    assert_eq!(new.is_compatible_with(&old)?, NP_Schema_Compat::PropertyChanged { path: String::from("color"), property: String::from("choices") });
+
+    let new = NP_Factory::new("string()")?;
+    assert_eq!(new.is_compatible_with(&old)?, NP_Schema_Compat::TypeChanged { path: String::from(""), from: NP_TypeKeys::Struct, to: NP_TypeKeys::UTF8String });
+
+    Ok(())
+}
+
+#[test]
+fn inspect_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("struct({fields: {value: i64(), scores: map({value: u64()}), next: portal({to: \"\"})}})")?;
+
+    let info = factory.inspect(&["scores"])?.unwrap();
+    assert_eq!(info, NP_Schema_Info { kind: NP_TypeKeys::Map, is_collection: true, fields: Vec::new(), of: Some(NP_TypeKeys::Uint64), values: Vec::new(), min: None, max: None });
+    assert_eq!(factory.inspect(&["scores", "any key"])?.unwrap().max, Some(u64::MAX as i128));
+
+    // portals are followed
+    assert_eq!(factory.inspect(&["next"])?, factory.inspect(&[])?);
+    assert_eq!(factory.inspect(&["next", "next", "value"])?.unwrap().min, Some(i64::MIN as i128));
+
+    assert_eq!(factory.inspect(&["value", "deeper"])?, None);
+
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn write_to_read_from_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("list({of: string()})")?;
+
+    let mut stream: Vec<u8> = Vec::new();
+    for x in 0..3 {
+        let mut buffer = factory.new_buffer(None);
+        buffer.set(&["0"], format!("hello {}", x).as_str())?;
+        assert_eq!(buffer.write_to(&mut stream)?, buffer.data_length() + 4);
+    }
+
+    let mut reader = &stream[..];
+    for x in 0..3 {
+        let buffer = factory.read_from(&mut reader)?;
+        assert_eq!(buffer.get::<&str>(&["0"])?, Some(format!("hello {}", x).as_str()));
+    }
+
+    // nothing left to read
+    assert!(factory.read_from(&mut reader).is_err());
+
+    // length is longer than the data
+    let mut reader = &[0u8, 0, 0, 200, 0, 0][..];
+    assert!(factory.read_from(&mut reader).is_err());
+
+    // tagged buffers write the schema id after the length
+    let mut stream: Vec<u8> = Vec::new();
+    let mut buffer = factory.new_buffer_tagged(None);
+    buffer.set(&["0"], "tagged")?;
+    assert_eq!(buffer.write_to(&mut stream)?, buffer.data_length() + 8);
+    assert_eq!(stream[4..8], factory.schema_id().to_be_bytes());
+    let buffer = factory.read_from_tagged(&mut &stream[..])?;
+    assert_eq!(buffer.get::<&str>(&["0"])?, Some("tagged"));
+
+    Ok(())
+}
+
+#[test]
+fn checksum_works() -> Result<(), NP_Error> {
+    // standard CRC32 check value
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+
+    let factory = NP_Factory::new("struct({fields: {name: string(), age: u8()}})")?;
+
+    let mut bytes = [0u8; 256];
+    let mut buffer = factory.new_buffer_ref_mut(&mut bytes);
+    buffer.set(&["name"], "Jeb Kermin")?;
+    buffer.set(&["age"], 30u8)?;
+    let data_len = buffer.data_length();
+
+    // unused space at the end of ref mut buffers isn't part of the checksum
+    let checked = buffer.close_with_checksum();
+    assert_eq!(checked.len(), data_len + 4);
+
+    let buffer = factory.open_buffer_checked(checked.clone())?;
+    assert_eq!(buffer.get::<&str>(&["name"])?, Some("Jeb Kermin"));
+    assert_eq!(buffer.get::<u8>(&["age"])?, Some(30));
+
+    // truncated
+    assert!(factory.open_buffer_checked(checked[..checked.len() - 1].to_vec()).is_err());
+    assert!(factory.open_buffer_checked(vec![0, 0]).is_err());
+
+    // every single bit flip is caught
+    for idx in 0..checked.len() {
+        let mut corrupt = checked.clone();
+        corrupt[idx] ^= 1;
+        assert!(factory.open_buffer_checked(corrupt).is_err());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn value_eq_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("struct({fields: {name: string(), age: u8({default: 20}), tags: list({of: string()}), meta: map({value: u32()})}})")?;
+
+    let mut buffer_a = factory.new_buffer(None);
+    buffer_a.set(&["name"], "Jeb")?;
+    buffer_a.set(&["tags", "2"], "pilot")?;
+    buffer_a.set(&["meta", "flights"], 20u32)?;
+    buffer_a.set(&["meta", "crashes"], 3u32)?;
+    buffer_a.set(&["name"], "Jeb Kermin")?;
+
+    let mut buffer_b = factory.new_buffer(None);
+    buffer_b.set(&["meta", "crashes"], 3u32)?;
+    buffer_b.set(&["meta", "flights"], 20u32)?;
+    buffer_b.set(&["tags", "2"], "pilot")?;
+    buffer_b.set(&["name"], "Jeb Kermin")?;
+
+    assert_ne!(buffer_a.read_bytes(), buffer_b.read_bytes());
+    assert!(buffer_a.value_eq(&buffer_b)?);
+    assert!(buffer_b.value_eq(&buffer_a)?);
+    assert!(buffer_a.value_eq(&buffer_a.deep_clone()?)?);
+
+    // list index matters
+    let mut buffer_c = buffer_b.clone();
+    buffer_c.del(&["tags", "2"])?;
+    buffer_c.set(&["tags", "1"], "pilot")?;
+    assert!(!buffer_a.value_eq(&buffer_c)?);
+
+    // extra map key
+    let mut buffer_c = buffer_b.clone();
+    buffer_c.set(&["meta", "launches"], 1u32)?;
+    assert!(!buffer_a.value_eq(&buffer_c)?);
+    assert!(!buffer_c.value_eq(&buffer_a)?);
+
+    // unset isn't the same as the default
+    let mut buffer_c = buffer_b.clone();
+    buffer_c.set(&["age"], 20u8)?;
+    assert!(!buffer_a.value_eq(&buffer_c)?);
+
+    // sortable tuples
+    let factory = NP_Factory::new("tuple({sorted: true, values: [u8(), string({size: 5})]})")?;
+    let mut buffer_a = factory.new_buffer(None);
+    buffer_a.set(&["0"], 2u8)?;
+    buffer_a.set(&["1"], "hello")?;
+    let mut buffer_b = factory.new_buffer(None);
+    buffer_b.set(&["1"], "hello")?;
+    buffer_b.set(&["0"], 2u8)?;
+    assert!(buffer_a.value_eq(&buffer_b)?);
+    buffer_b.set(&["0"], 3u8)?;
+    assert!(!buffer_a.value_eq(&buffer_b)?);
+
+    Ok(())
+}
+
+#[test]
+fn schema_id_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("struct({fields: {name: string({size: 10}), tags: list({of: string()})}})")?;
+    let json_factory = NP_Factory::new_json(r#"{"type": "struct", "fields": [["name", {"type": "string", "size": 10}], ["tags", {"type": "list", "of": {"type": "string"}}]]}"#)?;
+    let bytes_factory = NP_Factory::new_bytes(factory.export_schema_bytes())?;
+
+    assert_eq!(factory.schema_id(), json_factory.schema_id());
+    assert_eq!(factory.schema_id(), bytes_factory.schema_id());
+
+    let other = NP_Factory::new("struct({fields: {name: string({size: 11}), tags: list({of: string()})}})")?;
+    assert_ne!(factory.schema_id(), other.schema_id());
+
+    Ok(())
+}
+
+#[test]
+fn from_idl_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::from_idl(r#"
+        struct user {
+            name: string(size: 10, default: "bob"),
+            age: u8,
+            address: address,
+            tags: list { string },
+            scores: map { f32 },
+            point: tuple { i32, i32 },
+            color: enum(default: "red") { red, green, blue }
+        }
+
+        struct address {
+            street: string,
+            zip: u32(default: 1_000)
+        }
+    "#)?;
+
+    let es6 = NP_Factory::new(r#"struct({fields: {
+        name: string({size: 10, default: "bob"}),
+        age: u8(),
+        address: struct({fields: {street: string(), zip: u32({default: 1000})}}),
+        tags: list({of: string()}),
+        scores: map({value: f32()}),
+        point: tuple({values: [i32(), i32()]}),
+        color: enum({choices: ["red", "green", "blue"], default: "red"})
+    }})"#)?;
+
+    assert_eq!(factory.export_schema_bytes(), es6.export_schema_bytes());
+    assert_eq!(factory.export_schema_idl()?, es6.export_schema_idl()?);
+
+    assert!(NP_Factory::from_idl("list { string }").is_err());
+    assert!(NP_Factory::from_idl("").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn export_idl_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        name: string({size: 10, default: "hello"}),
+        age: i16({default: -20}),
+        price: dec({exp: 2, default: 10.5}),
+        location: geo4({default: {lat: 20.5, lng: -10.25}}),
+        address: struct({fields: {street: string(), zip: u32({default: 1000})}}),
+        tags: list({of: list({of: string()})}),
+        scores: map({value: f32({default: 2.0})}),
+        point: tuple({sorted: true, values: [i32(), u8()]}),
+        color: enum({choices: ["red", "green", "light blue"], default: "red"})
+    }})"#)?;
+
+    let idl = factory.export_idl()?;
+    let from_idl = NP_Factory::from_idl(idl.as_str())?;
+
+    assert_eq!(factory.export_schema_bytes(), from_idl.export_schema_bytes());
+    assert_eq!(idl, from_idl.export_idl()?);
+
+    // root must be a struct
+    let list = NP_Factory::new("list({of: string()})")?;
+    assert!(list.export_idl().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn export_json_schema_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        count: i16({default: 5}),
+        scores: map({value: f64()}),
+        point: tuple({values: [geo4(), bool()]}),
+        color: enum({choices: ["red", "blue"], default: "blue"}),
+        bytes: bytes({size: 4})
+    }})"#)?;
+
+    let json_schema = factory.export_json_schema()?;
+    assert_eq!(json_schema["properties"]["count"].stringify(), r#"{"type":"integer","minimum":-32768,"maximum":32767,"default":5}"#);
+    assert_eq!(json_schema["properties"]["scores"].stringify(), r#"{"type":"object","additionalProperties":{"type":"number"}}"#);
+    assert_eq!(json_schema["properties"]["point"]["items"][1].stringify(), r#"{"type":"boolean"}"#);
+    assert_eq!(json_schema["properties"]["point"]["additionalItems"], NP_JSON::False);
+    assert_eq!(json_schema["properties"]["color"].stringify(), r#"{"type":"string","enum":["red","blue"],"default":"blue"}"#);
+    assert_eq!(json_schema["properties"]["bytes"]["maxItems"], NP_JSON::Integer(4));
+
+    // portals to a parent become references
+    let factory = NP_Factory::new(r#"struct({fields: {
+        value: u8(),
+        next: portal({to: ""})
+    }})"#)?;
+    assert_eq!(factory.export_json_schema()?["properties"]["next"].stringify(), r##"{"$ref":"#"}"##);
+
+    Ok(())
+}
+
+#[test]
+fn schema_bytes_validation_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        name: string({default: "hello"}),
+        data: bytes({size: 4}),
+        age: u16({default: 20}),
+        price: dec({exp: 2, default: 1.50}),
+        active: bool(),
+        loc: geo8(),
+        id: uuid(),
+        created: date(),
+        color: enum({choices: ["red", "blue"], default: "blue"}),
+        tags: list({of: string()}),
+        meta: map({value: i32()}),
+        pair: tuple({values: [u8(), string({size: 3})]})
+    }})"#)?;
+    let schema_bytes = factory.export_schema_bytes();
+
+    let factory2 = NP_Factory::new_bytes(schema_bytes)?;
+    assert_eq!(factory2.export_schema_bytes(), schema_bytes);
+
+    // every truncated prefix is rejected without panicking
+    for len in 0..schema_bytes.len() {
+        assert!(NP_Factory::new_bytes(&schema_bytes[..len]).is_err());
+    }
+
+    // trailing data
+    let mut extra = schema_bytes.to_vec();
+    extra.push(0);
+    assert!(NP_Factory::new_bytes(&extra).is_err());
+
+    // unknown type keys and invalid scalar settings
+    assert!(NP_Factory::new_bytes(&[26]).is_err());
+    assert!(NP_Factory::new_bytes(&[NP_TypeKeys::Boolean as u8, 3]).is_err());
+    assert!(NP_Factory::new_bytes(&[NP_TypeKeys::Geo as u8, 5, 0]).is_err());
+    assert!(NP_Factory::new_bytes(&[NP_TypeKeys::List as u8, 200]).is_err());
+    NP_Factory::new_bytes(&[NP_TypeKeys::List as u8, NP_TypeKeys::Uuid as u8])?;
+
+    Ok(())
+}
 
 #[test]
-fn threading_works() {
-    let fact = NP_Factory::new("string()").unwrap();
-    let buffer = fact.new_buffer(None);
-    std::thread::spawn(move || {
-        let f = fact.export_schema_bytes();
-        let b = buffer;
-        assert_eq!(6, b.calc_bytes().unwrap().current_buffer);
-        assert_eq!(8, f.len());
-    }).join().unwrap()
-}
\ No newline at end of file
+fn address_space_guard_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("list({of: string()})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    // same guard as the u32 address limit, just a smaller boundary
+    buffer.set_max_data_length(u16::MAX as usize);
+
+    let value = "x".repeat(1000);
+    let mut pushed = 0usize;
+    let err = loop {
+        match buffer.list_push(&[], value.as_str()) {
+            Ok(_) => pushed += 1,
+            Err(e) => break e
+        }
+    };
+
+    assert!(matches!(err, NP_Error::MemoryOutOfSpace));
+    assert!(buffer.data_length() < u16::MAX as usize);
+
+    // nothing before the failed allocation was corrupted
+    for idx in 0..pushed {
+        assert_eq!(buffer.get::<&str>(&[format!("{}", idx).as_str()])?, Some(value.as_str()));
+    }
+    buffer.compact(None)?;
+    assert_eq!(buffer.get::<&str>(&[format!("{}", pushed - 1).as_str()])?, Some(value.as_str()));
+
+    Ok(())
+}
+
+#[test]
+fn size_of_json_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        name: string(),
+        code: string({size: 4}),
+        data: bytes(),
+        active: bool(),
+        count: u32(),
+        price: dec({exp: 2}),
+        point: geo8(),
+        created: date(),
+        color: enum({choices: ["red", "blue"]}),
+        pair: tuple({values: [u8(), string()]}),
+        tags: list({of: string()}),
+        meta: map({value: struct({fields: {a: u8(), b: u64(), c: u8(), d: u8(), e: string()}})})
+    }})"#)?;
+
+    let docs = [
+        r#"{"active": false}"#,
+        r#"{"name": "Jeb Kermin", "code": "ab", "data": [1, 2, 3], "active": true, "count": 20}"#,
+        r#"{"price": 20.25, "point": {"lat": 20.5, "lng": -10.2}, "created": 1604965249484, "color": "blue"}"#,
+        r#"{"pair": [2, "hello"], "tags": ["pilot", null, "kerbal"]}"#,
+        r#"{"meta": {"first": {"a": 1}, "second": {"e": "far field"}, "third": {"a": null}, "fourth": {"b": "wrong type"}}}"#,
+        r#"{"name": null, "tags": [null], "pair": [null]}"#,
+        r#"{"name": 20, "count": "wrong type", "tags": "also wrong", "meta": null}"#
+    ];
+
+    for doc in docs.iter() {
+        let json = json_decode(String::from(*doc))?;
+        let size = factory.size_of_json(&json)?;
+        let mut buffer = factory.buffer_from_json(&json, None)?;
+        assert_eq!(buffer.calc_bytes()?.after_compaction, size);
+        buffer.compact(None)?;
+        assert_eq!(buffer.data_length(), size);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn scalar_and_list_roots_work() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("u32({default: 5})")?;
+    let mut buffer = factory.new_buffer(None);
+    assert_eq!(buffer.get::<u32>(&[])?, Some(5));
+    buffer.set(&[], 20u32)?;
+    assert_eq!(buffer.get::<u32>(&[])?, Some(20));
+    // scalars have no children
+    assert_eq!(buffer.get::<u32>(&["0"])?, None);
+    assert_eq!(buffer.set(&["0"], 2u32)?, false);
+    buffer.set(&[], 30u32)?;
+    buffer.compact(None)?;
+    assert_eq!(buffer.data_length(), 10);
+    let buffer = factory.open_buffer(buffer.finish().bytes());
+    assert_eq!(buffer.get::<u32>(&[])?, Some(30));
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":30}"#);
+
+    let factory = NP_Factory::new("list({of: u32()})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["0"], 1u32)?;
+    buffer.set(&["2"], 3u32)?;
+    buffer.list_push(&[], 4u32)?;
+    assert_eq!(buffer.get::<u32>(&["0"])?, Some(1));
+    assert_eq!(buffer.get::<u32>(&["1"])?, None);
+    assert_eq!(buffer.get::<u32>(&["3"])?, Some(4));
+    assert_eq!(buffer.get_length(&[])?, Some(4));
+    // the root itself isn't a u32
+    assert!(buffer.get::<u32>(&[]).is_err());
+    buffer.compact(None)?;
+    let buffer = factory.open_buffer(buffer.finish().bytes());
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":[1,null,3,4]}"#);
+
+    Ok(())
+}
+
+#[test]
+fn get_columns_works() -> Result<(), NP_Error> {
+    #[derive(Default)]
+    struct Inner { a: Option<u8>, e: Option<String> }
+
+    columns!(Inner { a: u8, e: String });
+
+    let factory = NP_Factory::new(r#"
+        struct({fields: {
+            inner: struct({fields: {
+                a: u8({default: 7}), b: u8(), c: u8(), d: u8(), e: string()
+            }}),
+            other: u8()
+        }})
+    "#)?;
+
+    let mut buffer = factory.new_buffer(None);
+
+    // nested struct not in the buffer yet, columns get their defaults
+    let inner: Inner = buffer.get_columns(&["inner"])?;
+    assert_eq!((inner.a, inner.e), (Some(7), None));
+
+    // "e" lives in the second vtable
+    buffer.set(&["inner", "a"], 2u8)?;
+    buffer.set(&["inner", "e"], "hello")?;
+    let inner: Inner = buffer.get_columns(&["inner"])?;
+    assert_eq!((inner.a, inner.e), (Some(2), Some(String::from("hello"))));
+
+    // not a struct
+    assert!(buffer.get_columns::<Inner>(&["other"]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn set_json_str_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("struct({fields: {name: string(), tags: list({of: string()})}})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    buffer.set_json_str(&["tags"], r#"["a", "b"]"#, true)?;
+    assert_eq!(buffer.get::<&str>(&["tags", "1"])?, Some("b"));
+
+    match buffer.set_json_str(&[], r#"{"name": tru}"#, true) {
+        Err(NP_Error::Custom { message }) => assert_eq!(message, "JSON Parse Error at byte 12"),
+        _ => panic!("expected a parse error")
+    }
+
+    Ok(())
+}
+
+#[test]
+fn reset_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("struct({fields: {name: string(), tags: list({of: string()})}})")?;
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["name"], "hello")?;
+    buffer.set(&["tags", "3"], "world")?;
+    buffer.reset()?;
+    assert_eq!(buffer.finish().bytes(), factory.new_buffer(None).finish().bytes());
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["tags", "3"], "world")?;
+    let handle = buffer.cursor_at(&["tags"])?.unwrap();
+    buffer.reset()?;
+    assert!(buffer.get_at::<&str>(&handle, &["3"]).is_err());
+    assert_eq!(buffer.memory_stats().malloc_count, 0);
+    buffer.set(&["tags", "0"], "again")?;
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":{"name":null,"tags":["again"]}}"#);
+
+    // ref mut buffers go back to the header
+    let mut bytes = [0u8; 256];
+    let mut buffer = factory.new_buffer_ref_mut(&mut bytes);
+    buffer.set(&["name"], "hello")?;
+    buffer.reset()?;
+    assert_eq!(buffer.data_length(), 6);
+    assert_eq!(buffer.get::<&str>(&["name"])?, None);
+
+    // read only buffers can't be reset
+    let bytes = factory.new_buffer(None).finish().bytes();
+    let mut buffer = factory.open_buffer_ref(&bytes);
+    assert!(buffer.reset().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn set_paths_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"
+        struct({fields: {
+            pair: tuple({values: [u8(), string({size: 4}), u8()]}),
+            list: list({of: struct({fields: {a: u8(), b: u8()}})}),
+            next: portal({to: "list.0"})
+        }})
+    "#)?;
+
+    let mut buffer = factory.new_buffer(None);
+    assert_eq!(buffer.set_paths()?, Vec::<Vec<String>>::new());
+
+    buffer.set(&["pair", "1"], "abcd")?;
+    buffer.set(&["list", "3", "b"], 2u8)?;
+    buffer.set(&["list", "1", "a"], 1u8)?;
+    // set then cleared, the list item is still there but empty
+    buffer.set(&["list", "0", "a"], 1u8)?;
+    buffer.del(&["list", "0", "a"])?;
+
+    assert_eq!(buffer.set_paths()?, vec![
+        vec!["pair", "1"],
+        vec!["list", "1", "a"],
+        vec!["list", "3", "b"]
+    ]);
+
+    buffer.move_cursor(&["list"])?;
+    assert_eq!(buffer.set_paths()?, vec![vec!["1", "a"], vec!["3", "b"]]);
+
+    Ok(())
+}
+
+#[test]
+fn access_stats_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"
+        struct({fields: {
+            pair: tuple({values: [u8(), string()]}),
+            list: list({of: struct({fields: {a: u8(), b: u8()}})}),
+            tags: map({value: bool()})
+        }})
+    "#)?;
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["pair", "0"], 3u8)?;
+    buffer.set(&["list", "0", "a"], 1u8)?;
+    buffer.set(&["list", "2", "a"], 2u8)?;
+    buffer.set(&["tags", "hot"], true)?;
+
+    // nothing is counted until tracking is enabled
+    buffer.get::<u8>(&["pair", "0"])?;
+    assert_eq!(buffer.access_stats(), Vec::new());
+
+    buffer.enable_access_tracking();
+    buffer.get::<u8>(&["pair", "0"])?;
+    buffer.get::<u8>(&["pair", "0"])?;
+    buffer.get::<u8>(&["list", "0", "a"])?;
+    buffer.get::<u8>(&["list", "2", "a"])?;
+    buffer.get::<bool>(&["tags", "hot"])?;
+    // values that aren't in the buffer aren't counted
+    buffer.get::<bool>(&["tags", "cold"])?;
+    // type errors still count as a read
+    assert!(buffer.get::<u16>(&["pair", "0"]).is_err());
+
+    // enabling again keeps the counts
+    buffer.enable_access_tracking();
+
+    assert_eq!(buffer.access_stats(), vec![
+        (vec![String::from("pair"), String::from("0")], 3),
+        (vec![String::from("list"), String::from("*"), String::from("a")], 2),
+        (vec![String::from("tags"), String::from("*")], 1)
+    ]);
+
+    // counts belong to this buffer only
+    let other = factory.new_buffer(None);
+    assert_eq!(other.access_stats(), Vec::new());
+
+    Ok(())
+}
+
+#[test]
+fn set_checked_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"
+        struct({fields: {
+            age: u16(),
+            tags: list({of: map({value: string()})}),
+            pair: tuple({values: [u8(), string()]})
+        }})
+    "#)?;
+
+    let message = |result: Result<(), NP_Error>| match result {
+        Err(NP_Error::Custom { message }) => message,
+        _ => String::new()
+    };
+
+    let mut buffer = factory.new_buffer(None);
+
+    // plain set makes the path before it finds the wrong type
+    assert!(buffer.set(&["tags", "2", "x"], 5u8).is_err());
+    assert!(buffer.data_length() > 6);
+
+    buffer.reset()?;
+    assert_eq!(message(buffer.set_checked(&["age"], "thirty")), "TypeError: `age` is uint16, got string");
+    assert_eq!(message(buffer.set_checked(&["tags", "2", "x"], 5u8)), "TypeError: `tags.2.x` is string, got uint8");
+    assert_eq!(message(buffer.set_checked(&["pair", "1"], 5u8)), "TypeError: `pair.1` is string, got uint8");
+    assert_eq!(message(buffer.set_checked(&["other"], 5u8)), "TypeError: `other` isn't in the schema");
+    assert_eq!(message(buffer.set_checked(&[], 5u8)), "TypeError: root is struct, got uint8");
+    assert_eq!(buffer.data_length(), 6);
+
+    buffer.set_checked(&["tags", "2", "x"], "hello")?;
+    buffer.set_checked(&["pair", "0"], 1u8)?;
+    assert_eq!(buffer.get::<&str>(&["tags", "2", "x"])?, Some("hello"));
+    assert_eq!(buffer.get::<u8>(&["pair", "0"])?, Some(1));
+
+    Ok(())
+}
+
+#[test]
+fn freeze_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("struct({fields: {name: string(), tags: list({of: string()})}})")?;
+
+    fn is_send_sync<T: Send + Sync>(_t: &T) { }
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["name"], "config")?;
+    buffer.set(&["tags", "2"], "c")?;
+    buffer.enable_access_tracking();
+
+    let reader = buffer.freeze();
+    is_send_sync(&reader);
+
+    std::thread::scope(|s| {
+        for _x in 0..4 {
+            let reader = reader.clone();
+            s.spawn(move || {
+                assert_eq!(reader.get::<&str>(&["name"]).unwrap(), Some("config"));
+                assert_eq!(reader.get::<&str>(&["tags", "2"]).unwrap(), Some("c"));
+            });
+        }
+    });
+
+    assert_eq!(reader.contains(&["tags", "1"])?, false);
+    assert_eq!(reader.set_paths()?, vec![vec!["name"], vec!["tags", "2"]]);
+    assert_eq!(reader.json_encode(&["tags"])?.stringify(), r#"{"value":[null,null,"c"]}"#);
+
+    // borrowed bytes are copied, the reader keeps working after they're gone
+    let reader = {
+        let mut bytes = vec![0u8; 64];
+        let mut buffer = factory.new_buffer_ref_mut(&mut bytes);
+        buffer.set(&["name"], "borrowed")?;
+        let length = buffer.data_length();
+        let reader = buffer.freeze();
+        assert_eq!(reader.read_bytes().len(), length);
+        reader
+    };
+    assert_eq!(reader.get::<&str>(&["name"])?, Some("borrowed"));
+
+    Ok(())
+}
+
+#[test]
+fn freeze_calc_bytes_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("list({of: map({value: string(), intern_keys: true})})")?;
+
+    let mut buffer = factory.new_buffer(None);
+    for x in 0..8usize {
+        let index = format!("{}", x);
+        buffer.set(&[index.as_str(), "color"], "red")?;
+        buffer.set(&[index.as_str(), "size"], "large")?;
+    }
+    let expected = buffer.calc_bytes()?;
+
+    let reader = buffer.freeze();
+
+    std::thread::scope(|s| {
+        for _x in 0..2 {
+            s.spawn(|| {
+                for _y in 0..100 {
+                    assert_eq!(reader.calc_bytes().unwrap(), expected);
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+#[test]
+fn count_where_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("struct({fields: {scores: map({value: u32()}), items: list({of: i8()}), name: string()}})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    // collections that aren't in the buffer
+    assert_eq!(buffer.count_where(&["scores"], |_x: &u32| true)?, 0);
+    assert_eq!(buffer.count_where(&["items"], |_x: &i8| true)?, 0);
+
+    buffer.set(&["scores", "a"], 5u32)?;
+    buffer.set(&["scores", "b"], 500u32)?;
+    buffer.set(&["scores", "c"], 50u32)?;
+    buffer.del(&["scores", "b"])?;
+    buffer.set(&["items", "0"], -1i8)?;
+    buffer.set(&["items", "3"], 3i8)?;
+    buffer.set(&["items", "9"], -9i8)?;
+
+    assert_eq!(buffer.count_where(&["scores"], |x: &u32| *x > 1)?, 2);
+    assert_eq!(buffer.count_where(&["scores"], |x: &u32| *x > 100)?, 0);
+    // unset items aren't counted
+    assert_eq!(buffer.count_where(&["items"], |_x: &i8| true)?, 3);
+    assert_eq!(buffer.count_where(&["items"], |x: &i8| *x < 0)?, 2);
+
+    assert!(buffer.count_where(&["scores"], |_x: &u8| true).is_err());
+    assert!(buffer.count_where(&["name"], |_x: &&str| true).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn tagged_buffers_work() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("list({of: string()})")?;
+    let other = NP_Factory::new("list({of: u8()})")?;
+
+    let mut buffer = factory.new_buffer_tagged(None);
+    buffer.set(&["1"], "hello")?;
+    let length = buffer.data_length();
+    let bytes = buffer.finish().bytes();
+    assert_eq!(bytes.len(), length + 4);
+    assert_eq!(factory.new_buffer_tagged(None).finish().data_len(), 10);
+
+    // still tagged after opening, editing and compacting
+    let mut buffer = factory.open_buffer_tagged(bytes)?;
+    buffer.set(&["0"], "world")?;
+    buffer.compact(None)?;
+    let mut out = Vec::new();
+    buffer.close_into(&mut out);
+    assert_eq!(out[0..4], factory.schema_id().to_be_bytes());
+
+    let buffer = factory.open_buffer_tagged(out.clone())?;
+    assert_eq!(buffer.get::<&str>(&["0"])?, Some("world"));
+    assert_eq!(buffer.get::<&str>(&["1"])?, Some("hello"));
+
+    assert!(matches!(other.open_buffer_tagged(out.clone()), Err(NP_Error::SchemaMismatch { .. })));
+    assert!(factory.open_buffer_tagged(vec![0, 0]).is_err());
+
+    // copies and checksums keep the id
+    let mut buffer = factory.open_buffer_tagged(out.clone())?;
+    assert_eq!(buffer.deep_clone()?.finish().bytes()[0..4], factory.schema_id().to_be_bytes());
+    assert_eq!(buffer.copy_buffer().finish().bytes()[0..4], factory.schema_id().to_be_bytes());
+    assert_eq!(buffer.compact_into(None)?.finish().bytes()[0..4], factory.schema_id().to_be_bytes());
+    let checked = buffer.close_with_checksum();
+    assert!(other.open_buffer_tagged_checked(checked.clone()).is_err());
+    assert_eq!(factory.open_buffer_tagged_checked(checked)?.get::<&str>(&["1"])?, Some("hello"));
+
+    // untagged buffers don't have the id
+    let plain = factory.new_buffer(None).finish().bytes();
+    assert_eq!(plain.len(), 6);
+    assert!(factory.open_buffer_tagged(plain).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn get_all_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        users: map({value: struct({fields: {name: string(), tags: list({of: string()}), age: u8({default: 18})}})}),
+        scores: list({of: u16()})
+    }})"#)?;
+
+    let mut buffer = factory.new_buffer(None);
+    assert_eq!(buffer.get_all::<&str>(&["users"], &["name"])?, vec![]);
+
+    buffer.set(&["users", "jeb", "name"], "Jeb")?;
+    buffer.set(&["users", "jeb", "tags", "1"], "pilot")?;
+    buffer.set(&["users", "bill", "age"], 30u8)?;
+    buffer.set(&["users", "bob", "name"], "Bob")?;
+    buffer.del(&["users", "bob"])?;
+    buffer.set(&["scores", "1"], 10u16)?;
+    buffer.set(&["scores", "4"], 40u16)?;
+
+    let mut ages = buffer.get_all::<u8>(&["users"], &["age"])?;
+    ages.sort();
+    assert_eq!(ages, vec![(String::from("bill"), Some(30)), (String::from("jeb"), Some(18))]);
+
+    let mut tags = buffer.get_all::<&str>(&["users"], &["tags", "1"])?;
+    tags.sort();
+    assert_eq!(tags, vec![(String::from("bill"), None), (String::from("jeb"), Some("pilot"))]);
+
+    // empty sub path gets the items themselves
+    assert_eq!(buffer.get_all::<u16>(&["scores"], &[])?, vec![(String::from("1"), Some(10)), (String::from("4"), Some(40))]);
+
+    assert!(buffer.get_all::<u16>(&["users"], &["name"]).is_err());
+    assert!(buffer.get_all::<&str>(&["users"], &["missing"]).is_err());
+    assert!(buffer.get_all::<&str>(&["users", "jeb"], &["name"]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn get_as_f64_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        a: i16(),
+        b: f32(),
+        c: u64(),
+        d: dec({exp: 3}),
+        e: bool(),
+        f: list({of: i8()})
+    }})"#)?;
+    let mut buffer = factory.new_buffer(None);
+
+    // nothing set and no defaults
+    assert_eq!(buffer.get_as_f64(&["a"])?, None);
+    assert_eq!(buffer.get_as_f64(&["f", "2"])?, None);
+    assert_eq!(buffer.get_as_f64(&["nope"])?, None);
+
+    buffer.set(&["a"], -300i16)?;
+    buffer.set(&["b"], 2.5f32)?;
+    buffer.set(&["c"], u64::MAX)?;
+    buffer.set(&["d"], pointer::dec::NP_Dec::new(-1500, 3))?;
+    buffer.set(&["f", "2"], 7i8)?;
+
+    assert_eq!(buffer.get_as_f64(&["a"])?, Some(-300f64));
+    assert_eq!(buffer.get_as_f64(&["b"])?, Some(2.5f64));
+    assert_eq!(buffer.get_as_f64(&["c"])?, Some(u64::MAX as f64));
+    assert_eq!(buffer.get_as_f64(&["d"])?, Some(-1.5f64));
+    assert_eq!(buffer.get_as_f64(&["f", "2"])?, Some(7f64));
+
+    match buffer.get_as_f64(&["e"]) {
+        Err(NP_Error::Custom { message }) => assert_eq!(message, "TypeError: Attempted to get f64 for type (bool), only number types can be read as f64\n"),
+        _ => panic!()
+    }
+    assert!(buffer.get_as_f64(&["f"]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn byte_range_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        id: u32(),
+        tags: list({of: string()}),
+        pair: tuple({values: [u8(), string({size: 4})], sorted: true})
+    }})"#)?;
+    let mut buffer = factory.new_buffer(None);
+
+    assert_eq!(buffer.byte_range(&["id"])?, None);
+    assert_eq!(buffer.byte_range(&["tags", "1"])?, None);
+
+    buffer.set(&["id"], 7u32)?;
+    buffer.set(&["tags", "1"], "blue")?;
+    buffer.set(&["pair", "1"], "abcd")?;
+
+    for path in [&["id"][..], &["tags", "1"][..], &["pair", "1"][..]].iter() {
+        let (start, end) = buffer.byte_range(path)?.unwrap();
+        assert_eq!(&buffer.read_bytes()[start..end], buffer.get_raw_bytes(path)?.unwrap());
+    }
+    let (start, end) = buffer.byte_range(&["id"])?.unwrap();
+    assert_eq!(&buffer.read_bytes()[start..end], &7u32.to_be_bytes()[..]);
+
+    assert!(buffer.byte_range(&["tags"]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn set_enum_lenient_works() -> Result<(), NP_Error> {
+    use crate::pointer::option::NP_Enum;
+
+    let factory = NP_Factory::new(r#"struct({fields: {
+        size: enum({choices: ["small", "Small", "LARGE"]}),
+        name: string()
+    }})"#)?;
+    let mut buffer = factory.new_buffer(None);
+
+    // exact matches win
+    assert_eq!(buffer.match_enum(&["size"], "Small")?, Some(NP_Enum::new("Small")));
+    assert_eq!(buffer.match_enum(&["size"], "  SMALL")?, Some(NP_Enum::new("small")));
+    assert_eq!(buffer.match_enum(&["size"], "large\t")?, Some(NP_Enum::new("LARGE")));
+    assert_eq!(buffer.match_enum(&["size"], "")?, None);
+
+    assert_eq!(buffer.set_enum_lenient(&["size"], "medium")?, false);
+    assert_eq!(buffer.get::<NP_Enum>(&["size"])?, None);
+    assert_eq!(buffer.set_enum_lenient(&["size"], " Large ")?, true);
+    assert_eq!(buffer.get::<NP_Enum>(&["size"])?, Some(NP_Enum::new("LARGE")));
+
+    // strict set is unchanged
+    assert!(buffer.set(&["size"], NP_Enum::new("large")).is_err());
+
+    assert!(buffer.match_enum(&["name"], "small").is_err());
+    assert!(buffer.set_enum_lenient(&["other"], "small").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn tuple_to_json_vec_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        pair: tuple({values: [u8(), list({of: string()})]}),
+        sorted: tuple({values: [u16(), string({size: 2})], sorted: true}),
+        name: string()
+    }})"#)?;
+    let mut buffer = factory.new_buffer(None);
+
+    assert_eq!(buffer.tuple_to_json_vec(&["pair"])?, Vec::new());
+
+    buffer.set(&["pair", "0"], 3u8)?;
+    buffer.set(&["pair", "1", "0"], "a")?;
+    buffer.set(&["sorted", "0"], 9u16)?;
+
+    let pair = buffer.tuple_to_json_vec(&["pair"])?;
+    assert_eq!(pair.len(), 2);
+    assert_eq!(pair[0].stringify(), "3");
+    assert_eq!(pair[1].stringify(), r#"["a"]"#);
+
+    let sorted = buffer.tuple_to_json_vec(&["sorted"])?;
+    assert_eq!(sorted[0].stringify(), "9");
+
+    // matches the array from json_encode
+    assert_eq!(NP_JSON::Array(pair), buffer.json_encode(&["pair"])?["value"].clone());
+
+    match buffer.tuple_to_json_vec(&["name"]) {
+        Err(NP_Error::Custom { message }) => assert_eq!(message, "TypeError: Attempted to get tuple values from schema of type (string)\n"),
+        _ => panic!()
+    }
+    assert!(buffer.tuple_to_json_vec(&["other"]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn equivalent_json_schemas_compile_the_same() -> Result<(), NP_Error> {
+    let a = NP_Factory::with_definitions(r#"{
+        "type": "struct",
+        "fields": [
+            ["name", {"type": "string", "size": 10, "default": "bob"}],
+            ["price", {"type": "dec", "exp": 2, "default": 3}],
+            ["score", {"type": "f64", "default": 5}],
+            ["tags", {"type": "map", "value": {"type": "u8", "default": 1}}],
+            ["loc", {"type": "geo4", "default": {"lat": 2, "lng": -1.5}}]
+        ]
+    }"#, &[])?;
+    let b = NP_Factory::with_definitions(r#"{
+        "fields": [
+            ["name", {"default": "bob", "size": 10.0, "type": "string"}],
+            ["price", {"default": 3.0, "exp": 2.0, "type": "dec"}],
+            ["score", {"default": 5.0, "type": "f64"}],
+            ["tags", {"value": {"default": 1.0, "type": "u8"}, "type": "map"}],
+            ["loc", {"default": {"lng": -1.5, "lat": 2.0}, "type": "geo4"}]
+        ],
+        "type": "struct"
+    }"#, &[])?;
+
+    assert_eq!(a.export_schema_bytes(), b.export_schema_bytes());
+    assert_eq!(a.schema_id(), b.schema_id());
+
+    // field order is part of the schema
+    let c = NP_Factory::with_definitions(r#"{"type": "struct", "fields": [["b", {"type": "u8"}], ["a", {"type": "u8"}]]}"#, &[])?;
+    let d = NP_Factory::with_definitions(r#"{"type": "struct", "fields": [["a", {"type": "u8"}], ["b", {"type": "u8"}]]}"#, &[])?;
+    assert_ne!(c.export_schema_bytes(), d.export_schema_bytes());
+
+    // negative zero defaults keep their sign
+    let e = NP_Factory::with_definitions(r#"{"type": "f64", "default": -0.0}"#, &[])?;
+    assert!(e.new_buffer(None).get::<f64>(&[])?.unwrap().is_sign_negative());
+
+    // the same canonical form comes out of every factory
+    let f = NP_Factory::new_json(r#"{"default": 3.0, "exp": 2, "type": "dec"}"#)?;
+    let g = NP_Factory::new_json(r#"{"type": "dec", "exp": 2.0, "default": 3}"#)?;
+    assert_eq!(f.export_schema_bytes(), g.export_schema_bytes());
+
+    // whole number defaults that overflow once they're shifted by `exp`
+    assert!(NP_Factory::new_json(r#"{"type": "dec", "exp": 4, "default": 1e16}"#).is_err());
+    assert!(NP_Factory::new_json(r#"{"type": "dec", "exp": 4, "default": 10000000000000000}"#).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn extract_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        id: u32(),
+        profile: struct({fields: {
+            name: string(),
+            scores: map({value: list({of: u16()})}),
+            home: geo4()
+        }}),
+        other: string()
+    }})"#)?;
+    let profile_factory = NP_Factory::new(r#"struct({fields: {
+        name: string(),
+        scores: map({value: list({of: u16()})}),
+        home: geo4()
+    }})"#)?;
+    let id_factory = NP_Factory::new("u32()")?;
+
+    let mut buffer = factory.new_buffer(None);
+
+    // nothing here yet
+    let empty = buffer.extract(&["profile"], &profile_factory)?;
+    assert_eq!(empty.get::<&str>(&["name"])?, None);
+
+    buffer.set(&["id"], 12u32)?;
+    buffer.set(&["other"], "not copied")?;
+    buffer.set(&["profile", "name"], "first")?;
+    buffer.set(&["profile", "name"], "Jeb")?;
+    buffer.set(&["profile", "scores", "math", "1"], 90u16)?;
+    buffer.set(&["profile", "scores", "art", "0"], 75u16)?;
+
+    let profile = buffer.extract(&["profile"], &profile_factory)?;
+    assert_eq!(profile.get::<&str>(&["name"])?, Some("Jeb"));
+    assert_eq!(profile.get::<u16>(&["scores", "math", "1"])?, Some(90));
+    assert_eq!(profile.get::<u16>(&["scores", "art", "0"])?, Some(75));
+    // already compacted
+    assert_eq!(profile.calc_bytes()?.wasted_bytes, 0);
+    assert!(profile.data_length() < buffer.data_length());
+
+    let id = buffer.extract(&["id"], &id_factory)?;
+    assert_eq!(id.get::<u32>(&[])?, Some(12));
+
+    // the source isn't changed
+    assert_eq!(buffer.get::<&str>(&["profile", "name"])?, Some("Jeb"));
+
+    assert!(buffer.extract(&["profile", "scores"], &profile_factory).is_err());
+    assert!(buffer.extract(&["other"], &id_factory).is_err());
+    assert!(buffer.extract(&["nope"], &id_factory).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn graft_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new(r#"struct({fields: {
+        id: u32(),
+        people: list({of: struct({fields: {
+            name: string(),
+            tags: map({value: bool()})
+        }})})
+    }})"#)?;
+    let person_factory = NP_Factory::new(r#"struct({fields: {
+        name: string(),
+        tags: map({value: bool()})
+    }})"#)?;
+    let id_factory = NP_Factory::new("u32()")?;
+
+    let mut person = person_factory.new_buffer(None);
+    person.set(&["name"], "Jeb")?;
+    person.set(&["tags", "pilot"], true)?;
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["people", "0", "name"], "Bill")?;
+    buffer.set(&["people", "2", "name"], "Bob")?;
+    buffer.set(&["people", "2", "tags", "engineer"], true)?;
+
+    // new list item
+    buffer.graft(&["people", "1"], &person)?;
+    // existing data is replaced
+    buffer.graft(&["people", "2"], &person)?;
+
+    for index in ["1", "2"].iter() {
+        assert_eq!(buffer.get::<&str>(&["people", index, "name"])?, Some("Jeb"));
+        assert_eq!(buffer.get::<bool>(&["people", index, "tags", "pilot"])?, Some(true));
+    }
+    assert_eq!(buffer.get::<bool>(&["people", "2", "tags", "engineer"])?, None);
+    assert_eq!(buffer.get::<&str>(&["people", "0", "name"])?, Some("Bill"));
+
+    // extract gives the same thing back
+    let copy = buffer.extract(&["people", "1"], &person_factory)?;
+    assert_eq!(copy.logical_hash()?, person.logical_hash()?);
+
+    let mut id = id_factory.new_buffer(None);
+    id.set(&[], 99u32)?;
+    buffer.graft(&["id"], &id)?;
+    assert_eq!(buffer.get::<u32>(&["id"])?, Some(99));
+
+    // grafting an empty buffer deletes
+    buffer.graft(&["people", "0"], &person_factory.new_buffer(None))?;
+    assert_eq!(buffer.get::<&str>(&["people", "0", "name"])?, None);
+
+    assert!(buffer.graft(&["people"], &person).is_err());
+    assert!(buffer.graft(&["nope"], &id).is_err());
+
+    // the bytes stay valid after compaction
+    buffer.compact(None)?;
+    assert_eq!(buffer.get::<&str>(&["people", "2", "name"])?, Some("Jeb"));
+
+    Ok(())
+}
+
+#[test]
+fn error_display_works() -> Result<(), NP_Error> {
+    assert_eq!(format!("{}", NP_Error::new("Something broke!")), "Something broke!");
+    assert_eq!(format!("{}", NP_Error::BadAddress { addr: 20, context: "value is out of bounds" }), "Invalid buffer at address 20: value is out of bounds");
+    assert_eq!(format!("{}", NP_Error::SchemaMismatch { expected: 1, found: 2 }), "Schema mismatch, expected schema id 1 but found 2");
+    assert!(format!("{}", NP_Error::RecursionLimit).contains("nested too deeply"));
+    assert!(format!("{}", NP_Error::Unreachable).len() > 0);
+    assert!(format!("{}", NP_Error::MemoryReadOnly).contains("read only"));
+    Ok(())
+}
+
+#[test]
+fn types_used_works() -> Result<(), NP_Error> {
+    // recursive portals only get walked once
+    let factory = NP_Factory::new(r#"struct({fields: {
+        name: string(),
+        child: struct({fields: {
+            id: uuid(),
+            next: portal({to: "child"})
+        }}),
+        dates: list({of: date()})
+    }})"#)?;
+
+    let types = factory.types_used();
+    assert_eq!(types.len(), 6);
+    for kind in [NP_TypeKeys::Struct, NP_TypeKeys::UTF8String, NP_TypeKeys::Uuid, NP_TypeKeys::Portal, NP_TypeKeys::List, NP_TypeKeys::Date].iter() {
+        assert!(types.contains(kind));
+    }
+
+    assert_eq!(NP_Factory::new("u8()")?.types_used().into_iter().collect::<Vec<NP_TypeKeys>>(), vec![NP_TypeKeys::Uint8]);
+
+    Ok(())
+}
+
+#[test]
+fn estimate_capacity_portals_works() -> Result<(), NP_Error> {
+    // every portal would multiply the work if they were followed
+    let factory = NP_Factory::new(r#"struct({fields: {
+        id: u32(),
+        a: portal({to: ""}),
+        b: portal({to: ""}),
+        c: portal({to: ""}),
+        d: portal({to: ""}),
+        e: portal({to: ""}),
+        f: portal({to: ""})
+    }})"#)?;
+    // header, two vtables, the u32 and six portals
+    assert_eq!(factory.estimate_capacity(), 6 + 40 + 4 + (6 * 20));
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["a", "b", "id"], 5u32)?;
+    assert_eq!(buffer.get::<u32>(&["a", "b", "id"])?, Some(5));
+
+    Ok(())
+}
+
+#[test]
+fn new_buffer_in_estimate_works() -> Result<(), NP_Error> {
+    use core::cell::{Cell, UnsafeCell};
+
+    // bump arena that can only hand out `limit` bytes
+    struct Arena { bytes: UnsafeCell<[u8; 64]>, used: Cell<usize>, limit: usize }
+
+    unsafe impl memory::NP_Allocator for Arena {
+        fn alloc(&self, size: usize) -> *mut u8 {
+            let start = self.used.get();
+            if start + size > self.limit { return core::ptr::null_mut() }
+            self.used.set(start + size);
+            unsafe { (*self.bytes.get()).as_mut_ptr().add(start) }
+        }
+        fn grow(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> *mut u8 {
+            let new_ptr = self.alloc(new_size);
+            if new_ptr.is_null() { return new_ptr }
+            unsafe { core::ptr::copy_nonoverlapping(ptr, new_ptr, old_size) };
+            new_ptr
+        }
+    }
+
+    let factory = NP_Factory::new("struct({fields: {id: u64(), age: u8()}})")?;
+    let estimate = factory.estimate_capacity();
+
+    // the whole record fits in the first block
+    let arena = Arena { bytes: UnsafeCell::new([0; 64]), used: Cell::new(0), limit: estimate };
+    let mut buffer = factory.new_buffer_in(&arena, None)?;
+    buffer.set(&["id"], 20u64)?;
+    buffer.set(&["age"], 30u8)?;
+    assert_eq!(buffer.data_length(), estimate);
+    assert_eq!(arena.used.get(), estimate);
+    assert_eq!(buffer.get::<u8>(&["age"])?, Some(30));
+
+    // the estimate is asked for up front
+    let arena = Arena { bytes: UnsafeCell::new([0; 64]), used: Cell::new(0), limit: estimate - 1 };
+    assert!(factory.new_buffer_in(&arena, None).is_err());
+    assert!(factory.new_buffer_in(&arena, Some(16)).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn json_schema_errors_have_position() -> Result<(), NP_Error> {
+    let message = |result: Result<NP_Factory, NP_Error>| match result {
+        Err(e @ NP_Error::BadSchema { .. }) => format!("{}", e),
+        _ => String::new()
+    };
+
+    let schema = r#"{
+    "type": "struct",
+    "fields": [
+        ["name", {"type": "string"}],
+        ["age", {"type": "u9"}]
+    ]
+}"#;
+    assert_eq!(message(NP_Factory::new_json(schema)), "Can't find a type that matches this schema! u9 (at character 103: `\"u9\"}]\n    ]\n}`)");
+
+    match NP_Factory::new_json(schema) {
+        Err(NP_Error::BadSchema { path, .. }) => assert_eq!(path, vec!["fields", "1", "1", "type"]),
+        _ => panic!()
+    }
+
+    // errors from type parsers point at the schema object
+    assert_eq!(message(NP_Factory::new_json(r#"{"type": "list", "of": {"type": "dec"}}"#)), "Decimal type requires 'exp' property! (at character 23: `{\"type\": \"dec\"}}`)");
+    assert_eq!(message(NP_Factory::new_json(r#"{"type": "tuple", "values": [{"type": "u8"}, {"size": 2}]}"#)), "Schemas must have a 'type' property! (at character 45: `{\"size\": 2}]}`)");
+
+    // JSON parse errors point at where parsing stopped
+    assert_eq!(message(NP_Factory::new_json(r#"{"type": "list", "of": {"type": string}}"#)), "JSON Parse Error (at character 38: `}}`)");
+
+    // paths into a definition point at the `$ref`
+    assert_eq!(message(NP_Factory::with_definitions(r#"{"type": "map", "value": {"$ref": "bad"}}"#, &[("bad", r#"{"type": "nope"}"#)])), "Can't find a type that matches this schema! nope (at character 25: `{\"$ref\": \"bad\"}}`)");
+
+    // the IDL path has no source positions for the JSON it generates, so only the path is given
+    assert_eq!(message(NP_Factory::from_idl("struct user { tags: list { dec } }")), "Decimal type requires 'exp' property! (at `fields.0.1.of`)");
+
+    Ok(())
+}
+
+#[test]
+fn cursor_handle_invalidation_works() -> Result<(), NP_Error> {
+    let factory = NP_Factory::new("struct({fields: {a: list({of: string()}), b: list({of: string()}), m: map({value: string()})}})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["a", "0"], "x")?;
+    buffer.set(&["m", "k"], "v")?;
+
+    let refresh = |buffer: &NP_Buffer| buffer.cursor_at(&["a"]).map(|x| x.unwrap());
+
+    // setting values doesn't move anything
+    let handle = refresh(&buffer)?;
+    buffer.set(&["a", "1"], "y")?;
+    buffer.set(&["b", "0"], "z")?;
+    assert_eq!(buffer.get_at::<&str>(&handle, &["1"])?, Some("y"));
+
+    let handle = refresh(&buffer)?;
+    buffer.move_value(&["b"], &["a"])?;
+    assert!(buffer.get_at::<&str>(&handle, &["0"]).is_err());
+
+    let handle = refresh(&buffer)?;
+    buffer.set_list(&["a"], &["p", "q"])?;
+    assert!(buffer.get_at::<&str>(&handle, &["0"]).is_err());
+
+    let handle = refresh(&buffer)?;
+    buffer.list_remove(&["a"], 0)?;
+    assert!(buffer.get_at::<&str>(&handle, &["0"]).is_err());
+
+    let handle = refresh(&buffer)?;
+    buffer.map_rename_key(&["m"], "k", "j")?;
+    assert!(buffer.get_at::<&str>(&handle, &["0"]).is_err());
+
+    let handle = refresh(&buffer)?;
+    buffer.clear_collection(&["a"])?;
+    assert!(buffer.get_at::<&str>(&handle, &["0"]).is_err());
+
+    Ok(())
+}
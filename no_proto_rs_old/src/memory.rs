@@ -2,15 +2,34 @@
 
 use crate::{schema::NP_Parsed_Schema};
 use crate::{error::NP_Error};
-use core::cell::UnsafeCell;
+use crate::buffer::DEFAULT_MAX_DEPTH;
+use core::cell::{Cell, RefCell, UnsafeCell};
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 
+/// Custom allocator for buffer memory, used with `NP_Factory::new_buffer_in`.
+/// 
+/// Buffers normally keep their bytes in a `Vec<u8>` from the global allocator.  Implementing this trait lets a buffer get its bytes from somewhere else, like an arena that's reset after every request.
+/// 
+/// The buffer never frees the memory it gets from the allocator, reclaiming it (for example by resetting the arena) is up to the allocator once the buffer is dropped.
+/// 
+/// # Safety
+/// Pointers returned from `alloc` and `grow` must be valid for reads and writes of the requested size for as long as the allocator is borrowed by the buffer, or null if the allocation failed.
+/// 
+pub unsafe trait NP_Allocator {
+    /// Allocate a new block of `size` bytes, returns null if the allocation failed.
+    fn alloc(&self, size: usize) -> *mut u8;
+    /// Grow a block from `alloc` or `grow` to `new_size` bytes, the first `old_size` bytes must be kept.  The returned pointer can be the same block or a new one, returns null if the allocation failed.
+    fn grow(&self, ptr: *mut u8, old_size: usize, new_size: usize) -> *mut u8;
+}
+
 #[doc(hidden)]
 #[derive(PartialEq, Debug)]
 pub enum NP_Memory_Kind {
     Owned { vec: Vec<u8> },
     Ref { vec: *const [u8] },
-    RefMut { vec: *mut [u8], len: usize }
+    RefMut { vec: *mut [u8], len: usize },
+    Alloc { ptr: *mut u8, cap: usize, len: usize, alloc: *const dyn NP_Allocator }
 }
 
 
@@ -22,7 +41,12 @@ pub struct NP_Memory {
     pub root: usize,
     pub schema: *const Vec<NP_Parsed_Schema>,
     pub max_size: usize,
+    pub max_depth: usize,
     pub is_mutable: bool,
+    pub malloc_count: Cell<usize>,
+    pub bytes_allocated: Cell<usize>,
+    /// murmur hash of a map key -> address of the key bytes, used by maps with `intern_keys`
+    pub interned_keys: RefCell<BTreeMap<u32, u32>>
 }
 
 unsafe impl Send for NP_Memory {}
@@ -32,9 +56,13 @@ impl Clone for NP_Memory {
         Self {
             root: self.root,
             max_size: self.max_size,
+            max_depth: self.max_depth,
             bytes: UnsafeCell::new(NP_Memory_Kind::Owned { vec: self.read_bytes().to_vec() }),
             schema: self.schema.clone(),
-            is_mutable: true
+            is_mutable: true,
+            malloc_count: self.malloc_count.clone(),
+            bytes_allocated: self.bytes_allocated.clone(),
+            interned_keys: self.interned_keys.clone()
         }
     }
 }
@@ -48,9 +76,13 @@ impl NP_Memory {
         Self {
             root,
             max_size: u32::MAX as usize,
+            max_depth: DEFAULT_MAX_DEPTH,
             bytes: UnsafeCell::new(NP_Memory_Kind::Owned { vec: bytes }),
             schema: schema,
-            is_mutable: true
+            is_mutable: true,
+            malloc_count: Cell::new(0),
+            bytes_allocated: Cell::new(0),
+            interned_keys: RefCell::new(BTreeMap::new())
         }
     }
 
@@ -60,9 +92,13 @@ impl NP_Memory {
         Self {
             root,
             max_size: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
             bytes: UnsafeCell::new(NP_Memory_Kind::Ref { vec: bytes }),
             schema: schema,
-            is_mutable: false
+            is_mutable: false,
+            malloc_count: Cell::new(0),
+            bytes_allocated: Cell::new(0),
+            interned_keys: RefCell::new(BTreeMap::new())
         }
     }
 
@@ -72,9 +108,13 @@ impl NP_Memory {
         Self {
             root,
             max_size: usize::min(u32::MAX as usize, len),
+            max_depth: DEFAULT_MAX_DEPTH,
             bytes: UnsafeCell::new(NP_Memory_Kind::RefMut { vec: bytes, len: len }),
             schema: schema,
-            is_mutable: true
+            is_mutable: true,
+            malloc_count: Cell::new(0),
+            bytes_allocated: Cell::new(0),
+            interned_keys: RefCell::new(BTreeMap::new())
         }
     }
 
@@ -93,9 +133,13 @@ impl NP_Memory {
         Self {
             root,
             max_size: u32::MAX as usize,
+            max_depth: DEFAULT_MAX_DEPTH,
             bytes: UnsafeCell::new(NP_Memory_Kind::Owned { vec: new_bytes }),
             schema: schema,
-            is_mutable: true
+            is_mutable: true,
+            malloc_count: Cell::new(0),
+            bytes_allocated: Cell::new(0),
+            interned_keys: RefCell::new(BTreeMap::new())
         }
     }
 
@@ -105,13 +149,51 @@ impl NP_Memory {
         Self {
             root,
             max_size: u32::MAX as usize,
+            max_depth: DEFAULT_MAX_DEPTH,
             bytes: UnsafeCell::new(NP_Memory_Kind::RefMut { vec: bytes, len: 6 }),
             schema: schema,
-            is_mutable: true
+            is_mutable: true,
+            malloc_count: Cell::new(0),
+            bytes_allocated: Cell::new(0),
+            interned_keys: RefCell::new(BTreeMap::new())
         }
     }
 
+    /// Create a new empty buffer memory that gets its bytes from a custom allocator
+    pub fn new_in(alloc: *const dyn NP_Allocator, capacity: Option<usize>, schema: *const Vec<NP_Parsed_Schema>, root: usize) -> Result<Self, NP_Error> {
+        let use_size = usize::max(capacity.unwrap_or(1024), 6);
+
+        let ptr = unsafe { &*alloc }.alloc(use_size);
+
+        if ptr.is_null() {
+            return Err(NP_Error::MemoryOutOfSpace)
+        }
+
+        // is_packed, size, root pointer
+        unsafe { core::ptr::write_bytes(ptr, 0, 6) };
+
+        Ok(Self {
+            root,
+            max_size: u32::MAX as usize,
+            max_depth: DEFAULT_MAX_DEPTH,
+            bytes: UnsafeCell::new(NP_Memory_Kind::Alloc { ptr, cap: use_size, len: 6, alloc }),
+            schema: schema,
+            is_mutable: true,
+            malloc_count: Cell::new(0),
+            bytes_allocated: Cell::new(0),
+            interned_keys: RefCell::new(BTreeMap::new())
+        })
+    }
+
     pub fn new_empty(&self, capacity: Option<usize>) -> Result<Self, NP_Error> {
+
+        // compacted buffers stay in the same allocator
+        if let NP_Memory_Kind::Alloc { alloc, .. } = unsafe { &*self.bytes.get() } {
+            let mut memory = Self::new_in(*alloc, capacity, self.schema, self.root)?;
+            memory.max_depth = self.max_depth;
+            return Ok(memory)
+        }
+
         let use_size = match capacity {
             Some(x) => x,
             None => 1024
@@ -125,9 +207,13 @@ impl NP_Memory {
         Ok(Self {
             root: self.root,
             max_size: u32::MAX as usize,
+            max_depth: self.max_depth,
             bytes: UnsafeCell::new(NP_Memory_Kind::Owned { vec: new_bytes }),
             schema: self.schema,
-            is_mutable: true
+            is_mutable: true,
+            malloc_count: Cell::new(0),
+            bytes_allocated: Cell::new(0),
+            interned_keys: RefCell::new(BTreeMap::new())
         })
     }
 
@@ -157,6 +243,10 @@ impl NP_Memory {
                 *len = new_len;
 
                 Ok(())
+            },
+            NP_Memory_Kind::Alloc { .. } => {
+                // NO OP
+                Err(NP_Error::Unreachable)
             }
         }
         
@@ -174,21 +264,83 @@ impl NP_Memory {
             },
             NP_Memory_Kind::RefMut { .. } => {
                 self.max_size = usize::min(u32::MAX as usize, len);
+            },
+            NP_Memory_Kind::Alloc { .. } => {
+                self.max_size = usize::min(u32::MAX as usize, len);
             }
         }
         
     }
 
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.max_depth = depth;
+    }
+
     #[inline(always)]
     pub fn length(&self) -> usize {
         let self_bytes = unsafe { &*self.bytes.get() };
         match self_bytes {
             NP_Memory_Kind::Owned { vec} => vec.len(),
             NP_Memory_Kind::Ref { .. } => 0,
-            NP_Memory_Kind::RefMut { len, .. } => *len
+            NP_Memory_Kind::RefMut { len, .. } => *len,
+            NP_Memory_Kind::Alloc { len, .. } => *len
         }
     }
 
+    /// How many bytes the memory can hold before it has to grow
+    pub fn capacity(&self) -> usize {
+        let self_bytes = unsafe { &*self.bytes.get() };
+        match self_bytes {
+            NP_Memory_Kind::Owned { vec } => vec.capacity(),
+            NP_Memory_Kind::Ref { vec } => unsafe { &**vec }.len(),
+            NP_Memory_Kind::RefMut { vec, .. } => unsafe { &**vec }.len(),
+            NP_Memory_Kind::Alloc { cap, .. } => *cap
+        }
+    }
+
+    /// Release unused capacity, only owned memory can be shrunk
+    pub fn shrink_to_fit(&mut self) {
+        let self_bytes = unsafe { &mut *self.bytes.get() };
+        match self_bytes {
+            NP_Memory_Kind::Owned { vec } => vec.shrink_to_fit(),
+            NP_Memory_Kind::Ref { .. } => { /* NO OP */ },
+            NP_Memory_Kind::RefMut { .. } => { /* NO OP */ },
+            NP_Memory_Kind::Alloc { .. } => { /* NO OP */ }
+        }
+    }
+
+    /// Drop all the data and go back to an empty root, the capacity is kept
+    pub fn reset(&mut self) -> Result<(), NP_Error> {
+        let self_bytes = unsafe { &mut *self.bytes.get() };
+        match self_bytes {
+            NP_Memory_Kind::Owned { vec } => {
+                vec.clear();
+                // is_packed, size, root pointer
+                vec.extend(&[0u8; 6]);
+            },
+            NP_Memory_Kind::Ref { .. } => {
+                return Err(NP_Error::MemoryReadOnly)
+            },
+            NP_Memory_Kind::RefMut { vec, len } => {
+                if unsafe { &**vec }.len() < 6 {
+                    return Err(NP_Error::MemoryOutOfSpace)
+                }
+                unsafe { core::ptr::write_bytes(*vec as *mut u8, 0, 6) };
+                *len = 6;
+            },
+            NP_Memory_Kind::Alloc { ptr, len, .. } => {
+                unsafe { core::ptr::write_bytes(*ptr, 0, 6) };
+                *len = 6;
+            }
+        }
+
+        self.malloc_count.set(0);
+        self.bytes_allocated.set(0);
+        self.interned_keys.borrow_mut().clear();
+
+        Ok(())
+    }
+
     #[inline(always)]
     pub fn get_schemas(&self) -> &Vec<NP_Parsed_Schema> {
         unsafe { &*self.schema }
@@ -209,6 +361,9 @@ impl NP_Memory {
             return Err(NP_Error::MemoryOutOfSpace)
         }
 
+        self.malloc_count.set(self.malloc_count.get() + 1);
+        self.bytes_allocated.set(self.bytes_allocated.get() + bytes.len());
+
         let self_bytes = unsafe { &mut *self.bytes.get() };
 
         match self_bytes {
@@ -225,6 +380,19 @@ impl NP_Memory {
                     v[location + x] = *b;
                 }
 
+            },
+            NP_Memory_Kind::Alloc { ptr, cap, len, alloc } => {
+                if *len + bytes.len() > *cap {
+                    let new_cap = usize::max(*cap * 2, *len + bytes.len());
+                    let new_ptr = unsafe { &**alloc }.grow(*ptr, *cap, new_cap);
+                    if new_ptr.is_null() {
+                        return Err(NP_Error::MemoryOutOfSpace)
+                    }
+                    *ptr = new_ptr;
+                    *cap = new_cap;
+                }
+                unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.add(location), bytes.len()) };
+                *len += bytes.len();
             }
         }
 
@@ -244,9 +412,22 @@ impl NP_Memory {
             NP_Memory_Kind::Owned { vec } => &vec[..],
             NP_Memory_Kind::Ref { vec } => unsafe { &**vec },
             NP_Memory_Kind::RefMut { vec, .. } => unsafe { &**vec },
+            NP_Memory_Kind::Alloc { ptr, len, .. } => unsafe { core::slice::from_raw_parts(*ptr, *len) },
         }
     }   
 
+    /// Only the bytes in use, without the unused space at the end of RefMut buffers
+    #[inline(always)]
+    pub fn read_data(&self) -> &[u8] {
+        let self_bytes = unsafe { &*self.bytes.get() };
+        match self_bytes {
+            NP_Memory_Kind::Owned { vec } => &vec[..],
+            NP_Memory_Kind::Ref { vec } => unsafe { &**vec },
+            NP_Memory_Kind::RefMut { vec, len } => unsafe { &(&**vec)[..usize::min(*len, (&**vec).len())] },
+            NP_Memory_Kind::Alloc { ptr, len, .. } => unsafe { core::slice::from_raw_parts(*ptr, *len) },
+        }
+    }
+
     #[inline(always)]
     pub fn write_bytes(&self) -> &mut [u8] {
         let self_bytes = unsafe { &mut *self.bytes.get() };
@@ -258,6 +439,7 @@ impl NP_Memory {
                 &mut *mut_ptr
             },
             NP_Memory_Kind::RefMut { vec, .. } => unsafe { &mut **vec },
+            NP_Memory_Kind::Alloc { ptr, len, .. } => unsafe { core::slice::from_raw_parts_mut(*ptr, *len) },
         }
     }
 
@@ -367,12 +549,35 @@ impl NP_Memory {
         Some(unsafe { &*(slice as *const [u8] as *const [u8; 32]) })
     }
 
+    /// Move the bytes into owned memory that can't be written to, borrowed bytes are copied
+    pub fn into_read_only(self) -> NP_Memory {
+        let bytes = match self.bytes.into_inner() {
+            NP_Memory_Kind::Owned { vec } => vec,
+            NP_Memory_Kind::Ref { vec } => Vec::from(unsafe { &*vec }),
+            NP_Memory_Kind::RefMut { vec, len } => Vec::from(unsafe { &(&*vec)[..usize::min(len, (&*vec).len())] }),
+            NP_Memory_Kind::Alloc { ptr, len, .. } => Vec::from(unsafe { core::slice::from_raw_parts(ptr, len) })
+        };
+
+        NP_Memory {
+            bytes: UnsafeCell::new(NP_Memory_Kind::Owned { vec: bytes }),
+            root: self.root,
+            schema: self.schema,
+            max_size: self.max_size,
+            max_depth: self.max_depth,
+            is_mutable: false,
+            malloc_count: self.malloc_count,
+            bytes_allocated: self.bytes_allocated,
+            interned_keys: self.interned_keys
+        }
+    }
+
     pub fn dump(self) -> Vec<u8> {
         let bytes = self.bytes.into_inner();
         match bytes {
             NP_Memory_Kind::Owned { vec } => vec,
             NP_Memory_Kind::Ref { vec } => Vec::from(unsafe { &*vec }),
-            NP_Memory_Kind::RefMut { vec, ..  } => Vec::from(unsafe { &*vec })
+            NP_Memory_Kind::RefMut { vec, ..  } => Vec::from(unsafe { &*vec }),
+            NP_Memory_Kind::Alloc { ptr, len, .. } => Vec::from(unsafe { core::slice::from_raw_parts(ptr, len) })
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,212 @@
+//! MessagePack decoder
+//!
+//! Parses MessagePack bytes into an [`NP_JSON`](../json_flex/enum.NP_JSON.html) value so it can be set into a buffer the same way JSON is.  Use [`NP_Factory::buffer_from_msgpack`](../struct.NP_Factory.html#method.buffer_from_msgpack) to check the value against the schema and build a buffer in one step.  Exporting to MessagePack is done with [`NP_Buffer::to_msgpack`](../buffer/struct.NP_Buffer.html#method.to_msgpack).
+//!
+//! Values are converted like this:
+//! - `nil`, booleans, strings, arrays and maps become the matching JSON type.  Map keys must be strings.
+//! - Integers become `NP_JSON::Integer`, unsigned values above `i64::MAX` wrap around and come back out unchanged when set into a `u64`.
+//! - `float 32` and `float 64` become `NP_JSON::Float`.
+//! - `bin` values become an array of integers, the same shape `bytes` values use in JSON.
+//! - Extension types aren't supported and return an error.
+//!
+//! ```
+//! use no_proto::error::NP_Error;
+//! use no_proto::msgpack::msgpack_decode;
+//! use no_proto::json_flex::NP_JSON;
+//!
+//! // {"a": [1, -1, nil]}
+//! let json = msgpack_decode(&[0x81, 0xa1, b'a', 0x93, 0x01, 0xff, 0xc0])?;
+//! assert_eq!(json["a"], NP_JSON::Array(vec![NP_JSON::Integer(1), NP_JSON::Integer(-1), NP_JSON::Null]));
+//!
+//! # Ok::<(), NP_Error>(())
+//! ```
+//!
+
+use crate::buffer::DEFAULT_MAX_DEPTH;
+use crate::error::NP_Error;
+use crate::json_flex::{JSMAP, NP_JSON};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Parse MessagePack bytes into a JSON value.
+///
+/// The bytes must hold exactly one value, trailing bytes are an error.  Errors include the byte offset where the data went wrong.
+///
+pub fn msgpack_decode(bytes: &[u8]) -> Result<NP_JSON, NP_Error> {
+    let mut pos = 0usize;
+    let value = decode_at(bytes, &mut pos, 0)?;
+
+    if pos != bytes.len() {
+        return Err(NP_Error::new(format!("MessagePack Error: trailing bytes at byte {}", pos)))
+    }
+
+    Ok(value)
+}
+
+fn take<'bytes>(bytes: &'bytes [u8], pos: &mut usize, len: usize) -> Result<&'bytes [u8], NP_Error> {
+    if bytes.len() - *pos < len {
+        return Err(NP_Error::new(format!("MessagePack Error: unexpected end of data at byte {}", bytes.len())))
+    }
+    let start = *pos;
+    *pos += len;
+    Ok(&bytes[start..*pos])
+}
+
+fn take_len(bytes: &[u8], pos: &mut usize, size: usize) -> Result<usize, NP_Error> {
+    let b = take(bytes, pos, size)?;
+    Ok(match size {
+        1 => b[0] as usize,
+        2 => u16::from_be_bytes([b[0], b[1]]) as usize,
+        _ => u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize
+    })
+}
+
+fn take_str(bytes: &[u8], pos: &mut usize, len: usize) -> Result<String, NP_Error> {
+    let start = *pos;
+    match core::str::from_utf8(take(bytes, pos, len)?) {
+        Ok(s) => Ok(String::from(s)),
+        Err(_e) => Err(NP_Error::new(format!("MessagePack Error: invalid utf-8 string at byte {}", start)))
+    }
+}
+
+fn decode_array(bytes: &[u8], pos: &mut usize, len: usize, depth: usize) -> Result<NP_JSON, NP_Error> {
+    // every value takes at least one byte, don't trust the length for the allocation
+    let mut items: Vec<NP_JSON> = Vec::with_capacity(usize::min(len, bytes.len() - *pos));
+    for _x in 0..len {
+        items.push(decode_at(bytes, pos, depth + 1)?);
+    }
+    Ok(NP_JSON::Array(items))
+}
+
+fn decode_map(bytes: &[u8], pos: &mut usize, len: usize, depth: usize) -> Result<NP_JSON, NP_Error> {
+    let mut map = JSMAP::new();
+    for _x in 0..len {
+        let key_pos = *pos;
+        let key = match decode_at(bytes, pos, depth + 1)? {
+            NP_JSON::String(key) => key,
+            _ => return Err(NP_Error::new(format!("MessagePack Error: map keys must be strings at byte {}", key_pos)))
+        };
+        let value = decode_at(bytes, pos, depth + 1)?;
+        map.insert(key, value);
+    }
+    Ok(NP_JSON::Dictionary(map))
+}
+
+fn decode_bin(bytes: &[u8], pos: &mut usize, len: usize) -> Result<NP_JSON, NP_Error> {
+    Ok(NP_JSON::Array(take(bytes, pos, len)?.iter().map(|x| NP_JSON::Integer(*x as i64)).collect()))
+}
+
+fn decode_at(bytes: &[u8], pos: &mut usize, depth: usize) -> Result<NP_JSON, NP_Error> {
+
+    if depth > DEFAULT_MAX_DEPTH { return Err(NP_Error::RecursionLimit) }
+
+    let marker_pos = *pos;
+    let marker = take(bytes, pos, 1)?[0];
+
+    Ok(match marker {
+        0x00..=0x7f => NP_JSON::Integer(marker as i64),
+        0x80..=0x8f => return decode_map(bytes, pos, (marker & 0x0f) as usize, depth),
+        0x90..=0x9f => return decode_array(bytes, pos, (marker & 0x0f) as usize, depth),
+        0xa0..=0xbf => NP_JSON::String(take_str(bytes, pos, (marker & 0x1f) as usize)?),
+        0xc0 => NP_JSON::Null,
+        0xc2 => NP_JSON::False,
+        0xc3 => NP_JSON::True,
+        0xc4 => { let len = take_len(bytes, pos, 1)?; return decode_bin(bytes, pos, len) },
+        0xc5 => { let len = take_len(bytes, pos, 2)?; return decode_bin(bytes, pos, len) },
+        0xc6 => { let len = take_len(bytes, pos, 4)?; return decode_bin(bytes, pos, len) },
+        0xca => {
+            let b = take(bytes, pos, 4)?;
+            NP_JSON::Float(f32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f64)
+        },
+        0xcb => {
+            let b = take(bytes, pos, 8)?;
+            NP_JSON::Float(f64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+        },
+        0xcc => NP_JSON::Integer(take_len(bytes, pos, 1)? as i64),
+        0xcd => NP_JSON::Integer(take_len(bytes, pos, 2)? as i64),
+        0xce => NP_JSON::Integer(take_len(bytes, pos, 4)? as i64),
+        0xcf => {
+            let b = take(bytes, pos, 8)?;
+            NP_JSON::Integer(u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as i64)
+        },
+        0xd0 => NP_JSON::Integer(take(bytes, pos, 1)?[0] as i8 as i64),
+        0xd1 => {
+            let b = take(bytes, pos, 2)?;
+            NP_JSON::Integer(i16::from_be_bytes([b[0], b[1]]) as i64)
+        },
+        0xd2 => {
+            let b = take(bytes, pos, 4)?;
+            NP_JSON::Integer(i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as i64)
+        },
+        0xd3 => {
+            let b = take(bytes, pos, 8)?;
+            NP_JSON::Integer(i64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+        },
+        0xd9 => { let len = take_len(bytes, pos, 1)?; NP_JSON::String(take_str(bytes, pos, len)?) },
+        0xda => { let len = take_len(bytes, pos, 2)?; NP_JSON::String(take_str(bytes, pos, len)?) },
+        0xdb => { let len = take_len(bytes, pos, 4)?; NP_JSON::String(take_str(bytes, pos, len)?) },
+        0xdc => { let len = take_len(bytes, pos, 2)?; return decode_array(bytes, pos, len, depth) },
+        0xdd => { let len = take_len(bytes, pos, 4)?; return decode_array(bytes, pos, len, depth) },
+        0xde => { let len = take_len(bytes, pos, 2)?; return decode_map(bytes, pos, len, depth) },
+        0xdf => { let len = take_len(bytes, pos, 4)?; return decode_map(bytes, pos, len, depth) },
+        0xe0..=0xff => NP_JSON::Integer(marker as i8 as i64),
+        0xc7..=0xc9 | 0xd4..=0xd8 => return Err(NP_Error::new(format!("MessagePack Error: extension types aren't supported at byte {}", marker_pos))),
+        0xc1 => return Err(NP_Error::new(format!("MessagePack Error: invalid marker at byte {}", marker_pos)))
+    })
+}
+
+#[cfg(test)]
+fn error_message<T>(result: Result<T, NP_Error>) -> String {
+    match result {
+        Err(NP_Error::Custom { message }) => message,
+        _ => String::new()
+    }
+}
+
+#[test]
+fn msgpack_round_trip_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new(r#"struct({fields: {
+        a: i8(), b: i64(), c: u64(), d: f32(), e: f64(), f: dec({exp: 2}), g: bool(),
+        h: string(), i: bytes(), j: geo8(), k: uuid(), l: date(), m: enum({choices: ["red", "green"]}),
+        n: map({value: list({of: u16()})}), o: tuple({values: [u8(), string()]})
+    }})"#)?;
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["a"], -100i8)?;
+    buffer.set(&["b"], i64::MIN)?;
+    buffer.set(&["c"], u64::MAX)?;
+    buffer.set(&["d"], 1.5f32)?;
+    buffer.set(&["e"], -2.25f64)?;
+    buffer.set(&["f"], crate::pointer::dec::NP_Dec::new(-150, 2))?;
+    buffer.set(&["g"], true)?;
+    buffer.set(&["h"], "hello")?;
+    buffer.set(&["i"], &[1u8, 2, 255] as &[u8])?;
+    buffer.set(&["j"], crate::pointer::geo::NP_Geo::new(8, 45.5, -122.75))?;
+    buffer.set(&["k"], &crate::pointer::uuid::NP_UUID::generate(4))?;
+    buffer.set(&["l"], crate::pointer::date::NP_Date::new(1604965249484))?;
+    buffer.set(&["m"], crate::pointer::option::NP_Enum::new("green"))?;
+    buffer.set(&["n", "x", "1"], 300u16)?;
+    buffer.set(&["n", "y", "0"], 7u16)?;
+    buffer.set(&["o", "1"], "b")?;
+
+    let copy = factory.buffer_from_msgpack(&buffer.to_msgpack()?)?;
+    // unset values come back set to their default, so compare what they read as
+    assert_eq!(copy.logical_hash()?, buffer.logical_hash()?);
+    assert_eq!(copy.get::<u64>(&["c"])?, Some(u64::MAX));
+
+    // bad data
+    assert_eq!(error_message(msgpack_decode(&[0x92, 0x01])), "MessagePack Error: unexpected end of data at byte 2");
+    assert_eq!(error_message(msgpack_decode(&[0x01, 0x02])), "MessagePack Error: trailing bytes at byte 1");
+    assert_eq!(error_message(msgpack_decode(&[0x81, 0x01, 0x01])), "MessagePack Error: map keys must be strings at byte 1");
+    assert_eq!(error_message(msgpack_decode(&[0xd4, 0x01, 0x01])), "MessagePack Error: extension types aren't supported at byte 0");
+    assert!(msgpack_decode(&[0xdd, 0xff, 0xff, 0xff, 0xff]).is_err());
+
+    // values that don't match the schema
+    assert_eq!(error_message(factory.buffer_from_msgpack(&[0x81, 0xa1, b'g', 0x01])), "TypeError: Expected (bool) at `g`, found integer");
+    assert_eq!(error_message(factory.buffer_from_msgpack(&[0x81, 0xa1, b'n', 0x81, 0xa1, b'x', 0x91, 0xa0])), "TypeError: Expected (uint16) at `n.x.0`, found string");
+    assert_eq!(error_message(factory.buffer_from_msgpack(&[0x81, 0xa1, b'm', 0xa4, b'b', b'l', b'u', b'e'])), "TypeError: `blue` isn't one of the enum choices at `m`");
+    assert_eq!(error_message(factory.buffer_from_msgpack(&[0x81, 0xa1, b'z', 0xc3])), "TypeError: Unknown field `z` at root");
+    assert_eq!(error_message(factory.buffer_from_msgpack(&[0x90])), "TypeError: Expected (struct) at root, found array");
+
+    Ok(())
+}
@@ -4,6 +4,7 @@
 //! 
 use alloc::prelude::v1::Box;
 use crate::error::NP_Error;
+use crate::json_flex::{JSMAP, NP_JSON};
 use alloc::string::String;
 use alloc::vec::Vec;
 
@@ -14,6 +15,13 @@ pub struct AST_STR {
     end: usize 
 }
 
+impl AST_STR {
+    /// Read this string out of the source it was parsed from
+    pub fn read(&self, source: &str) -> String {
+        source.chars().skip(self.start).take(self.end - self.start).collect()
+    }
+}
+
 
 
 /// AST object of schema
@@ -134,6 +142,9 @@ impl AST {
                             if cursor.end + 1 < ast.end && chars[cursor.end + 1] == '>' { // >
                                 result.push(AST::arrow);
                                 cursor.end +=1;
+                            } else if cursor.end + 1 < ast.end && chars[cursor.end + 1] >= '0' && chars[cursor.end + 1] <= '9' { // negative number
+                                cursor.start = cursor.end;
+                                cursor.state = ast_cursor_state::number;
                             }
                         }
                         '<' => { // <
@@ -324,6 +335,457 @@ impl AST {
     }
 }
 
+/// Convert a parsed IDL document into a JSON schema.
+/// 
+/// Only `struct` declarations are supported.  The first declaration is the root of the schema, the other declarations can be used as field types by name.
+/// 
+pub fn idl_to_json_schema(source: &str, ast: &Vec<AST>) -> Result<NP_JSON, NP_Error> {
+
+    let mut declarations: Vec<(String, &Vec<AST>)> = Vec::new();
+
+    let mut idx = 0usize;
+
+    while idx < ast.len() {
+        match &ast[idx] {
+            AST::token { addr } if addr.read(source) == "struct" => {
+                let name = match ast.get(idx + 1) {
+                    Some(AST::token { addr }) => addr.read(source),
+                    _ => return Err(NP_Error::new("IDL Error: Expected a name after 'struct'!"))
+                };
+
+                // optional (), not used yet
+                let mut body_idx = idx + 2;
+                if let Some(AST::args { .. }) = ast.get(body_idx) {
+                    body_idx += 1;
+                }
+
+                match ast.get(body_idx) {
+                    Some(AST::closure { items }) => declarations.push((name, items)),
+                    _ => return Err(NP_Error::new("IDL Error: Expected '{' after struct name!"))
+                }
+
+                idx = body_idx + 1;
+            },
+            AST::comma => { idx += 1; },
+            _ => return Err(NP_Error::new("IDL Error: Only struct declarations are supported!"))
+        }
+    }
+
+    if declarations.len() == 0 {
+        return Err(NP_Error::new("IDL Error: No struct declarations found!"))
+    }
+
+    idl_struct_to_json(0, source, &declarations, declarations[0].1)
+}
+
+fn idl_struct_to_json(depth: usize, source: &str, declarations: &Vec<(String, &Vec<AST>)>, items: &Vec<AST>) -> Result<NP_JSON, NP_Error> {
+
+    if depth > 255 {
+        return Err(NP_Error::RecursionLimit)
+    }
+
+    let mut fields: Vec<NP_JSON> = Vec::new();
+
+    let mut idx = 0usize;
+
+    while idx < items.len() {
+        match &items[idx] {
+            AST::comma => { idx += 1; },
+            AST::token { addr } => {
+                let field_name = addr.read(source);
+                match items.get(idx + 1) {
+                    Some(AST::colon) => { },
+                    _ => {
+                        let mut err = String::from("IDL Error: Expected ':' after field ");
+                        err.push_str(field_name.as_str());
+                        return Err(NP_Error::new(err))
+                    }
+                }
+                idx += 2;
+                let field_type = idl_type_to_json(depth + 1, source, declarations, items, &mut idx)?;
+                fields.push(NP_JSON::Array(vec![NP_JSON::String(field_name), field_type]));
+            },
+            _ => return Err(NP_Error::new("IDL Error: Expected a field name!"))
+        }
+    }
+
+    let mut schema = JSMAP::new();
+    schema.insert(String::from("type"), NP_JSON::String(String::from("struct")));
+    schema.insert(String::from("fields"), NP_JSON::Array(fields));
+    Ok(NP_JSON::Dictionary(schema))
+}
+
+fn idl_type_to_json(depth: usize, source: &str, declarations: &Vec<(String, &Vec<AST>)>, items: &Vec<AST>, idx: &mut usize) -> Result<NP_JSON, NP_Error> {
+
+    if depth > 255 {
+        return Err(NP_Error::RecursionLimit)
+    }
+
+    let type_name = match items.get(*idx) {
+        Some(AST::token { addr }) => addr.read(source),
+        _ => return Err(NP_Error::new("IDL Error: Expected a type!"))
+    };
+    *idx += 1;
+
+    // type(key: value, ...)
+    let mut schema = JSMAP::new();
+    schema.insert(String::from("type"), NP_JSON::String(type_name.clone()));
+
+    if let Some(AST::args { items: args }) = items.get(*idx) {
+        idl_args_to_json(source, args, &mut schema)?;
+        *idx += 1;
+    }
+
+    // type { ... }
+    let body = match items.get(*idx) {
+        Some(AST::closure { items: body }) => {
+            *idx += 1;
+            Some(body)
+        },
+        _ => None
+    };
+
+    match (type_name.as_str(), body) {
+        ("struct", Some(body)) => {
+            let mut result = idl_struct_to_json(depth + 1, source, declarations, body)?;
+            // keep any properties set in the args
+            if let NP_JSON::Dictionary(map) = &mut result {
+                for (key, value) in schema.values.into_iter() {
+                    if key != "type" {
+                        map.insert(key, value);
+                    }
+                }
+            }
+            return Ok(result)
+        },
+        ("list", Some(body)) => {
+            let mut body_idx = 0usize;
+            schema.insert(String::from("of"), idl_type_to_json(depth + 1, source, declarations, body, &mut body_idx)?);
+        },
+        ("map", Some(body)) => {
+            let mut body_idx = 0usize;
+            schema.insert(String::from("value"), idl_type_to_json(depth + 1, source, declarations, body, &mut body_idx)?);
+        },
+        ("tuple", Some(body)) => {
+            let mut values: Vec<NP_JSON> = Vec::new();
+            let mut body_idx = 0usize;
+            while body_idx < body.len() {
+                if let AST::comma = &body[body_idx] {
+                    body_idx += 1;
+                    continue;
+                }
+                values.push(idl_type_to_json(depth + 1, source, declarations, body, &mut body_idx)?);
+            }
+            schema.insert(String::from("values"), NP_JSON::Array(values));
+        },
+        ("enum", Some(body)) | ("option", Some(body)) => {
+            let mut choices: Vec<NP_JSON> = Vec::new();
+            for choice in body.iter() {
+                match choice {
+                    AST::token { addr } | AST::string { addr } => choices.push(NP_JSON::String(addr.read(source))),
+                    AST::comma => { },
+                    _ => return Err(NP_Error::new("IDL Error: Enum choices must be names or strings!"))
+                }
+            }
+            schema.insert(String::from("choices"), NP_JSON::Array(choices));
+        },
+        (_, Some(_)) => {
+            let mut err = String::from("IDL Error: Type doesn't accept a body: ");
+            err.push_str(type_name.as_str());
+            return Err(NP_Error::new(err))
+        },
+        (_, None) => {
+            // reference to another declaration
+            if let Some((_, decl)) = declarations.iter().find(|(name, _)| *name == type_name) {
+                return idl_struct_to_json(depth + 1, source, declarations, decl)
+            }
+        }
+    }
+
+    Ok(NP_JSON::Dictionary(schema))
+}
+
+fn idl_args_to_json(source: &str, args: &Vec<AST>, schema: &mut JSMAP) -> Result<(), NP_Error> {
+
+    let mut idx = 0usize;
+
+    while idx < args.len() {
+        match &args[idx] {
+            AST::comma => { idx += 1; },
+            AST::token { addr } => {
+                let key = addr.read(source);
+                match (args.get(idx + 1), args.get(idx + 2)) {
+                    (Some(AST::colon), Some(value)) => {
+                        schema.insert(key, idl_value_to_json(source, value)?);
+                    },
+                    _ => {
+                        let mut err = String::from("IDL Error: Expected a value for property ");
+                        err.push_str(key.as_str());
+                        return Err(NP_Error::new(err))
+                    }
+                }
+                idx += 3;
+            },
+            _ => return Err(NP_Error::new("IDL Error: Expected a property name!"))
+        }
+    }
+
+    Ok(())
+}
+
+fn idl_value_to_json(source: &str, value: &AST) -> Result<NP_JSON, NP_Error> {
+    match value {
+        AST::string { addr } => {
+            let mut string = String::new();
+            let mut escaped = false;
+            for c in addr.read(source).chars() {
+                if c == '\\' && escaped == false {
+                    escaped = true;
+                } else {
+                    string.push(c);
+                    escaped = false;
+                }
+            }
+            Ok(NP_JSON::String(string))
+        },
+        AST::number { addr } => {
+            // numbers can contain _ and , seperators
+            let number: String = addr.read(source).chars().filter(|c| *c != '_' && *c != ',').collect();
+            if number.contains('.') || number.contains('e') {
+                match number.parse::<f64>() {
+                    Ok(x) => Ok(NP_JSON::Float(x)),
+                    Err(_e) => Err(NP_Error::new("IDL Error: Invalid number!"))
+                }
+            } else {
+                match number.parse::<i64>() {
+                    Ok(x) => Ok(NP_JSON::Integer(x)),
+                    Err(_e) => Err(NP_Error::new("IDL Error: Invalid number!"))
+                }
+            }
+        },
+        AST::token { addr } => {
+            let token = addr.read(source);
+            match token.as_str() {
+                "true" => Ok(NP_JSON::True),
+                "false" => Ok(NP_JSON::False),
+                _ => Ok(NP_JSON::String(token))
+            }
+        },
+        AST::list { items } => {
+            let mut values: Vec<NP_JSON> = Vec::new();
+            for item in items.iter() {
+                if let AST::comma = item {
+                    continue;
+                }
+                values.push(idl_value_to_json(source, item)?);
+            }
+            Ok(NP_JSON::Array(values))
+        },
+        AST::closure { items } => {
+            let mut values = JSMAP::new();
+            idl_args_to_json(source, items, &mut values)?;
+            Ok(NP_JSON::Dictionary(values))
+        },
+        _ => Err(NP_Error::new("IDL Error: Unsupported property value!"))
+    }
+}
+
+/// Convert a JSON schema into the struct based IDL.
+/// 
+/// The root of the schema must be a struct, it's exported as a declaration named `root`.  The result can be parsed again with `idl_to_json_schema`.
+/// 
+pub fn json_schema_to_idl(json_schema: &NP_JSON) -> Result<String, NP_Error> {
+
+    let root_type = match &json_schema["type"] {
+        NP_JSON::String(x) if x == "struct" || x == "table" => x.len(),
+        _ => return Err(NP_Error::new("IDL Error: The root of the schema must be a struct!"))
+    };
+
+    let mut result = String::from("struct root");
+    result.push_str(&json_type_to_idl(0, json_schema)?[root_type..]);
+    result.push_str("\n");
+    Ok(result)
+}
+
+fn idl_is_token(value: &str) -> bool {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => { },
+        _ => return false
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn idl_indent(result: &mut String, depth: usize) {
+    for _ in 0..depth {
+        result.push_str("    ");
+    }
+}
+
+fn json_type_to_idl(depth: usize, json_schema: &NP_JSON) -> Result<String, NP_Error> {
+
+    if depth > 255 {
+        return Err(NP_Error::RecursionLimit)
+    }
+
+    let type_name = match &json_schema["type"] {
+        NP_JSON::String(x) => x.clone(),
+        _ => return Err(NP_Error::new("Schemas must have a 'type' property!"))
+    };
+
+    let body_key = match type_name.as_str() {
+        "struct" | "table" => "fields",
+        "list" | "array" => "of",
+        "map" => "value",
+        "tuple" => "values",
+        "enum" | "option" => "choices",
+        _ => ""
+    };
+
+    let mut result = type_name.clone();
+
+    // properties go in the args
+    if let NP_JSON::Dictionary(map) = json_schema {
+        let mut args: Vec<String> = Vec::new();
+        for (key, value) in map.values.iter() {
+            if key == "type" || key == body_key {
+                continue;
+            }
+            if let NP_JSON::Null = value {
+                continue;
+            }
+            let mut arg = key.clone();
+            arg.push_str(": ");
+            arg.push_str(json_value_to_idl(depth + 1, value)?.as_str());
+            args.push(arg);
+        }
+        if args.len() > 0 {
+            result.push('(');
+            result.push_str(args.join(", ").as_str());
+            result.push(')');
+        }
+    }
+
+    match (body_key, &json_schema[body_key]) {
+        ("fields", NP_JSON::Array(fields)) => {
+            result.push_str(" {\n");
+            for field in fields.iter() {
+                match (&field[0], &field[1]) {
+                    (NP_JSON::String(name), field_schema) if idl_is_token(name) => {
+                        idl_indent(&mut result, depth + 1);
+                        result.push_str(name);
+                        result.push_str(": ");
+                        result.push_str(json_type_to_idl(depth + 1, field_schema)?.as_str());
+                        result.push_str(",\n");
+                    },
+                    (NP_JSON::String(name), _) => {
+                        let mut err = String::from("IDL Error: Field name can't be used in the IDL: ");
+                        err.push_str(name);
+                        return Err(NP_Error::new(err))
+                    },
+                    _ => return Err(NP_Error::new("IDL Error: Invalid struct field!"))
+                }
+            }
+            idl_indent(&mut result, depth);
+            result.push('}');
+        },
+        ("of", of) | ("value", of) => {
+            result.push_str(" { ");
+            result.push_str(json_type_to_idl(depth + 1, of)?.as_str());
+            result.push_str(" }");
+        },
+        ("values", NP_JSON::Array(values)) => {
+            let mut items: Vec<String> = Vec::new();
+            for value in values.iter() {
+                items.push(json_type_to_idl(depth + 1, value)?);
+            }
+            result.push_str(" { ");
+            result.push_str(items.join(", ").as_str());
+            result.push_str(" }");
+        },
+        ("choices", NP_JSON::Array(choices)) => {
+            let mut items: Vec<String> = Vec::new();
+            for choice in choices.iter() {
+                match choice {
+                    NP_JSON::String(x) if idl_is_token(x) => items.push(x.clone()),
+                    _ => items.push(json_value_to_idl(depth + 1, choice)?)
+                }
+            }
+            result.push_str(" { ");
+            result.push_str(items.join(", ").as_str());
+            result.push_str(" }");
+        },
+        ("", _) => { },
+        _ => {
+            let mut err = String::from("IDL Error: Missing schema property: ");
+            err.push_str(body_key);
+            return Err(NP_Error::new(err))
+        }
+    }
+
+    Ok(result)
+}
+
+fn json_value_to_idl(depth: usize, value: &NP_JSON) -> Result<String, NP_Error> {
+
+    if depth > 255 {
+        return Err(NP_Error::RecursionLimit)
+    }
+
+    match value {
+        NP_JSON::String(x) => {
+            let mut result = String::from("\"");
+            for c in x.chars() {
+                if c == '"' || c == '\\' {
+                    result.push('\\');
+                }
+                result.push(c);
+            }
+            result.push('"');
+            Ok(result)
+        },
+        NP_JSON::Integer(x) => Ok(format!("{}", x)),
+        NP_JSON::Float(x) => {
+            let mut result = format!("{}", x);
+            if result.contains('.') == false {
+                result.push_str(".0");
+            }
+            Ok(result)
+        },
+        NP_JSON::True => Ok(String::from("true")),
+        NP_JSON::False => Ok(String::from("false")),
+        NP_JSON::Array(values) => {
+            let mut items: Vec<String> = Vec::new();
+            for value in values.iter() {
+                items.push(json_value_to_idl(depth + 1, value)?);
+            }
+            let mut result = String::from("[");
+            result.push_str(items.join(", ").as_str());
+            result.push(']');
+            Ok(result)
+        },
+        NP_JSON::Dictionary(map) => {
+            let mut items: Vec<String> = Vec::new();
+            for (key, value) in map.values.iter() {
+                if idl_is_token(key) == false {
+                    let mut err = String::from("IDL Error: Property name can't be used in the IDL: ");
+                    err.push_str(key);
+                    return Err(NP_Error::new(err))
+                }
+                let mut item = key.clone();
+                item.push_str(": ");
+                item.push_str(json_value_to_idl(depth + 1, value)?.as_str());
+                items.push(item);
+            }
+            let mut result = String::from("{");
+            result.push_str(items.join(", ").as_str());
+            result.push('}');
+            Ok(result)
+        },
+        NP_JSON::Null => Err(NP_Error::new("IDL Error: Null values can't be used in the IDL!"))
+    }
+}
+
 #[test]
 fn test() {
     // println!("HELLO {:?}", );
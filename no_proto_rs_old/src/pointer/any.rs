@@ -1,4 +1,5 @@
 use alloc::{string::String, sync::Arc};
+use alloc::collections::BTreeSet;
 use crate::{idl::{JS_AST, JS_Schema}, json_flex::{JSMAP}, schema::{NP_Parsed_Schema, NP_Value_Kind, NULL}};
 use alloc::vec::Vec;
 use crate::error::NP_Error;
@@ -48,7 +49,7 @@ impl<'value> NP_Value<'value> for NP_Any {
     fn to_json(depth:usize, cursor: &NP_Cursor, memory: &'value NP_Memory) -> NP_JSON {
         NP_JSON::Null
     }
-    fn get_size(depth:usize, _cursor: &NP_Cursor, _memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(depth:usize, _cursor: &NP_Cursor, _memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
         Ok(0)
     }
     fn do_compact(depth:usize, from_cursor: NP_Cursor, from_memory: &'value NP_Memory, to_cursor: NP_Cursor, to_memory: &'value NP_Memory) -> Result<NP_Cursor, NP_Error> where Self: 'value + Sized {
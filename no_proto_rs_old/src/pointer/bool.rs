@@ -16,6 +16,7 @@
 //! ```
 
 use alloc::sync::Arc;
+use alloc::collections::BTreeSet;
 use alloc::string::String;
 use crate::{idl::{JS_AST, JS_Schema}, json_flex::JSMAP, schema::{NP_Bool_Data, NP_Parsed_Schema, NP_Value_Kind}};
 use crate::error::NP_Error;
@@ -172,7 +173,7 @@ impl<'value> NP_Value<'value> for bool {
         }
     }
 
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
         let c_value = || { cursor.get_value(memory) };
         if c_value().get_addr_value() == 0 {
             Ok(0) 
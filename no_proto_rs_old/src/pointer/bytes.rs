@@ -17,6 +17,7 @@
 //! 
 
 use alloc::{string::String, sync::Arc};
+use alloc::collections::BTreeSet;
 use crate::{idl::{JS_AST, JS_Schema}, json_flex::JSMAP, schema::{NP_Bytes_Data, NP_Parsed_Schema, NP_Value_Kind}};
 use crate::error::NP_Error;
 use crate::{schema::{NP_TypeKeys}, pointer::NP_Value, json_flex::NP_JSON};
@@ -324,7 +325,7 @@ impl<'value> NP_Value<'value> for NP_Bytes {
             }
         }
     }
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
         let value_addr = c_value().get_addr_value() as usize;
@@ -581,7 +582,7 @@ impl<'value> NP_Value<'value> for NP_Borrow_Bytes<'value> {
             if str_size > core::u32::MAX as usize {
                 return Err(NP_Error::new("String too large!"));
             }
-            let size_bytes = (str_size as u16).to_be_bytes();
+            let size_bytes = (str_size as u32).to_be_bytes();
             // set string size
             for x in 0..size_bytes.len() {
                 write_bytes[(addr_value + x)] = size_bytes[x];
@@ -607,9 +608,10 @@ impl<'value> NP_Value<'value> for NP_Borrow_Bytes<'value> {
                 memory.malloc_borrow(&size_bytes)?
             };
     
-            cursor.get_value_mut(memory).set_addr_value(new_addr as u32);
-    
             memory.malloc_borrow(bytes)?;
+
+            // only point to the new value once all of it is in the buffer
+            cursor.get_value_mut(memory).set_addr_value(new_addr as u32);
     
             return Ok(cursor);
         }
@@ -653,8 +655,8 @@ impl<'value> NP_Value<'value> for NP_Borrow_Bytes<'value> {
         NP_Bytes::to_json(depth, cursor, memory)
     }
 
-    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
-        NP_Bytes::get_size(depth, cursor, memory)
+    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
+        NP_Bytes::get_size(depth, cursor, memory, sized_keys)
     }
 
     fn from_json_to_schema(schema: Vec<NP_Parsed_Schema>, json_schema: &Box<NP_JSON>) -> Result<(bool, Vec<u8>, Vec<NP_Parsed_Schema>), NP_Error> {
@@ -748,4 +750,71 @@ fn set_clear_value_and_compaction_works() -> Result<(), NP_Error> {
     assert_eq!(buffer.calc_bytes()?.current_buffer, 6usize);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn fixed_size_round_trip_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("struct({fields: {hash: bytes({size: 32}), tag: tuple({values: [bytes({size: 3}), u8()]})}})")?;
+    let mut buffer = factory.new_buffer(None);
+
+    let hash: Vec<u8> = (0..32u8).collect();
+    buffer.set(&["hash"], &hash[..])?;
+    // short values are padded with zeros, long values are cut off
+    buffer.set(&["tag", "0"], &[9u8] as &[u8])?;
+    assert_eq!(buffer.get::<&[u8]>(&["tag", "0"])?, Some(&[9u8, 0, 0] as &[u8]));
+    buffer.set(&["tag", "0"], &[1u8, 2, 3, 4, 5] as &[u8])?;
+    assert_eq!(buffer.get::<&[u8]>(&["tag", "0"])?, Some(&[1u8, 2, 3] as &[u8]));
+
+    // no length prefix, updates happen in place
+    let size = buffer.data_length();
+    buffer.set(&["hash"], &[255u8; 40] as &[u8])?;
+    assert_eq!(buffer.data_length(), size);
+    buffer.set(&["hash"], &hash[..])?;
+
+    buffer.compact(None)?;
+    let buffer = factory.open_buffer(buffer.finish().bytes());
+    assert_eq!(buffer.get::<&[u8]>(&["hash"])?, Some(&hash[..]));
+    assert_eq!(buffer.get::<NP_Bytes>(&["tag", "0"])?, Some(vec![1u8, 2, 3]));
+
+    // fixed bytes sort by their bytes
+    let factory = crate::NP_Factory::new("tuple({values: [bytes({size: 2})], sorted: true})")?;
+    let mut low = factory.new_buffer(None);
+    low.set(&["0"], &[1u8, 200] as &[u8])?;
+    let mut high = factory.new_buffer(None);
+    high.set(&["0"], &[2u8] as &[u8])?;
+    assert!(low.finish().bytes() < high.finish().bytes());
+
+    Ok(())
+}
+
+#[test]
+fn shorter_value_reuses_memory() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new("bytes()")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&[], &[1u8; 13] as &[u8])?;
+    buffer.set(&[], &[2u8; 5] as &[u8])?;
+    assert_eq!(buffer.get::<&[u8]>(&[])?, Some(&[2u8; 5] as &[u8]));
+
+    Ok(())
+}
+
+#[test]
+fn get_string_lossy_works() -> Result<(), NP_Error> {
+    let schema = r#"{"type":"list","of":{"type":"bytes","default":[111,107]}}"#;
+    let factory = crate::NP_Factory::new_json(schema)?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["0"], &[0xF0u8, 0x9F, 0x9A, 0x80][..])?;
+    // cut off in the middle of a character
+    buffer.set(&["1"], &[0x61u8, 0xF0, 0x9F, 0x9A][..])?;
+    buffer.set(&["2"], &[0xC0u8, 0x62][..])?;
+    assert_eq!(buffer.get_string_lossy(&["0"])?, Some(String::from("🚀")));
+    assert_eq!(buffer.get_string_lossy(&["1"])?, Some(String::from("a\u{FFFD}")));
+    assert_eq!(buffer.get_string_lossy(&["2"])?, Some(String::from("\u{FFFD}b")));
+    // the exact bytes are still there
+    assert_eq!(buffer.get::<&[u8]>(&["1"])?, Some(&[0x61u8, 0xF0, 0x9F, 0x9A] as &[u8]));
+    // defaults are used for empty values
+    assert_eq!(buffer.get_string_lossy(&["3"])?, Some(String::from("ok")));
+    assert!(buffer.get_string_lossy(&[]).is_err());
+
+    Ok(())
+}
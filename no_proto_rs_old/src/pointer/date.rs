@@ -13,12 +13,16 @@
 //! new_buffer.set(&[], NP_Date::new(1604965249484))?;
 //! 
 //! assert_eq!(NP_Date::new(1604965249484), new_buffer.get::<NP_Date>(&[])?.unwrap());
+//! assert_eq!("2020-11-09T23:40:49.484Z", new_buffer.get::<NP_Date>(&[])?.unwrap().to_rfc3339());
 //!
 //! # Ok::<(), NP_Error>(()) 
 //! ```
 //! 
+//! When setting a date from JSON an RFC 3339 string can be used in place of the epoch value.
+//! 
 
 use alloc::{string::String, sync::Arc};
+use alloc::collections::BTreeSet;
 use crate::{idl::{JS_AST, JS_Schema}, schema::{NP_Parsed_Schema, NP_Value_Kind, NP_u64_Data}};
 use alloc::vec::Vec;
 use crate::json_flex::{JSMAP, NP_JSON};
@@ -64,6 +68,171 @@ impl NP_Date {
     pub fn new(time_ms: u64) -> Self {
         NP_Date { value: time_ms }
     }
+
+    /// Format the date as an RFC 3339 string in UTC, including milliseconds.
+    /// 
+    /// Dates past the year 9999 are formatted with more than 4 year digits, `from_rfc3339` reads them back.
+    /// 
+    /// ```
+    /// use no_proto::pointer::date::NP_Date;
+    /// 
+    /// assert_eq!(NP_Date::new(1604965249484).to_rfc3339(), "2020-11-09T23:40:49.484Z");
+    /// assert_eq!(NP_Date::new(0).to_rfc3339(), "1970-01-01T00:00:00.000Z");
+    /// ```
+    /// 
+    pub fn to_rfc3339(&self) -> String {
+        let millis = self.value % 1000;
+        let seconds = self.value / 1000;
+        let days = seconds / 86400;
+        let day_seconds = seconds % 86400;
+
+        let (year, month, day) = civil_from_days(days as i64);
+
+        format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z", year, month, day, day_seconds / 3600, (day_seconds % 3600) / 60, day_seconds % 60, millis)
+    }
+
+    /// Parse an RFC 3339 string into a date.
+    /// 
+    /// Fractional seconds past milliseconds are dropped.  Years with more than 4 digits are accepted so every date `to_rfc3339` formats can be read back.  Time zone offsets are applied, so the stored value is always UTC.  Dates before the unix epoch can't be stored and will return an error.
+    /// 
+    /// ```
+    /// use no_proto::error::NP_Error;
+    /// use no_proto::pointer::date::NP_Date;
+    /// 
+    /// assert_eq!(NP_Date::from_rfc3339("2020-11-09T23:40:49.484Z")?, NP_Date::new(1604965249484));
+    /// assert_eq!(NP_Date::from_rfc3339("2020-11-09T18:40:49.484-05:00")?, NP_Date::new(1604965249484));
+    /// assert!(NP_Date::from_rfc3339("2020-13-09T23:40:49Z").is_err());
+    /// 
+    /// # Ok::<(), NP_Error>(()) 
+    /// ```
+    /// 
+    pub fn from_rfc3339(value: &str) -> Result<NP_Date, NP_Error> {
+        let bad_date = || { NP_Error::new(format!("Can't parse RFC 3339 date: {}", value)) };
+
+        // years past 9999 have more than 4 digits, like `to_rfc3339` writes them
+        let year_len = value.as_bytes().iter().take_while(|x| x.is_ascii_digit()).count();
+        if year_len < 4 || year_len > 9 {
+            return Err(bad_date())
+        }
+        let year = value.as_bytes()[..year_len].iter().fold(0i64, |year, x| year * 10 + (*x - b'0') as i64);
+
+        // the rest of the string lines up with a 4 digit year
+        let bytes = &value.as_bytes()[(year_len - 4)..];
+
+        let digits = |start: usize, len: usize| -> Result<i64, NP_Error> {
+            if start + len > bytes.len() {
+                return Err(bad_date())
+            }
+            let mut result = 0i64;
+            for x in &bytes[start..(start + len)] {
+                if *x < b'0' || *x > b'9' {
+                    return Err(bad_date())
+                }
+                result = result * 10 + (*x - b'0') as i64;
+            }
+            Ok(result)
+        };
+
+        if bytes.len() < 20 || bytes[4] != b'-' || bytes[7] != b'-' || bytes[13] != b':' || bytes[16] != b':' {
+            return Err(bad_date())
+        }
+
+        if bytes[10] != b'T' && bytes[10] != b't' && bytes[10] != b' ' {
+            return Err(bad_date())
+        }
+
+        let month = digits(5, 2)?;
+        let day = digits(8, 2)?;
+        let hour = digits(11, 2)?;
+        let minute = digits(14, 2)?;
+        let second = digits(17, 2)?;
+
+        if month < 1 || month > 12 || day < 1 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 60 {
+            return Err(bad_date())
+        }
+
+        // fractional seconds
+        let mut index = 19;
+        let mut millis = 0i64;
+        if bytes[index] == b'.' {
+            index += 1;
+            let mut places = 0;
+            while index < bytes.len() && bytes[index] >= b'0' && bytes[index] <= b'9' {
+                if places < 3 {
+                    millis = millis * 10 + (bytes[index] - b'0') as i64;
+                    places += 1;
+                }
+                index += 1;
+            }
+            if places == 0 {
+                return Err(bad_date())
+            }
+            while places < 3 {
+                millis *= 10;
+                places += 1;
+            }
+        }
+
+        // time zone offset
+        let offset = match bytes.get(index) {
+            Some(b'Z') | Some(b'z') if index + 1 == bytes.len() => 0,
+            Some(b'+') | Some(b'-') if index + 6 == bytes.len() && bytes[index + 3] == b':' => {
+                let offset_hours = digits(index + 1, 2)?;
+                let offset_minutes = digits(index + 4, 2)?;
+                if offset_hours > 23 || offset_minutes > 59 {
+                    return Err(bad_date())
+                }
+                let offset = (offset_hours * 3600 + offset_minutes * 60) * 1000;
+                if bytes[index] == b'+' { offset } else { -offset }
+            },
+            _ => return Err(bad_date())
+        };
+
+        let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+        let time = seconds as i128 * 1000 + (millis - offset) as i128;
+
+        if time < 0 {
+            return Err(NP_Error::new("Dates before the unix epoch can't be stored!"))
+        }
+
+        if time > u64::MAX as i128 {
+            return Err(NP_Error::new("Date is too far in the future to be stored!"))
+        }
+
+        Ok(NP_Date::new(time as u64))
+    }
+}
+
+// Days since the unix epoch to (year, month, day), from Howard Hinnant's `civil_from_days` algorithm
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// (year, month, day) to days since the unix epoch, the inverse of `civil_from_days`
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 => if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 { 29 } else { 28 },
+        _ => 31
+    }
 }
 
 impl Default for NP_Date {
@@ -113,6 +282,7 @@ impl<'value> NP_Value<'value> for NP_Date {
         let value = match &**value {
             NP_JSON::Integer(x) => *x as u64,
             NP_JSON::Float(x) => *x as u64,
+            NP_JSON::String(x) => NP_Date::from_rfc3339(x)?.value,
             _ => 0
         };
 
@@ -191,7 +361,7 @@ impl<'value> NP_Value<'value> for NP_Date {
         }
     }
 
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
 
@@ -265,7 +435,7 @@ impl<'value> NP_Value<'value> for NP_Date {
             val: NP_Value_Kind::Fixed(8),
             i: NP_TypeKeys::Date,
             sortable: true,
-            data: Arc::new(Box::into_raw(Box::new(NP_u64_Data { default })) as *const u8)
+            data: Arc::new(Box::into_raw(Box::new(NP_u64_Data { default, desc: false })) as *const u8)
         });
 
         return Ok((true, schema_data, schema));
@@ -292,7 +462,7 @@ impl<'value> NP_Value<'value> for NP_Date {
         schema.push(NP_Parsed_Schema {
             val: NP_Value_Kind::Fixed(8),
             i: NP_TypeKeys::Date,
-            data: Arc::new(Box::into_raw(Box::new(NP_u64_Data { default })) as *const u8),
+            data: Arc::new(Box::into_raw(Box::new(NP_u64_Data { default, desc: false })) as *const u8),
             sortable: true
         });
 
@@ -317,7 +487,7 @@ impl<'value> NP_Value<'value> for NP_Date {
             val: NP_Value_Kind::Fixed(8),
             i: NP_TypeKeys::Date,
             sortable: true,
-            data: Arc::new(Box::into_raw(Box::new(NP_u64_Data { default })) as *const u8)
+            data: Arc::new(Box::into_raw(Box::new(NP_u64_Data { default, desc: false })) as *const u8)
         });
         (true, schema)
     }
@@ -360,4 +530,32 @@ fn set_clear_value_and_compaction_works() -> Result<(), NP_Error> {
     assert_eq!(buffer.calc_bytes()?.current_buffer, 6usize);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn rfc3339_works() -> Result<(), NP_Error> {
+    for value in [0u64, 951782400000, 951868799999, 1604965249484, 253402300799999, 253402300800000, u64::MAX] {
+        let date = NP_Date::new(value);
+        assert_eq!(NP_Date::from_rfc3339(&date.to_rfc3339())?, date);
+    }
+
+    assert_eq!(NP_Date::new(951782400000).to_rfc3339(), "2000-02-29T00:00:00.000Z");
+    assert_eq!(NP_Date::from_rfc3339("2000-02-29T00:00:00.123456Z")?, NP_Date::new(951782400123));
+    assert_eq!(NP_Date::from_rfc3339("2000-02-29 01:30:00.1+01:30")?, NP_Date::new(951782400100));
+    assert!(NP_Date::from_rfc3339("2001-02-29T00:00:00Z").is_err());
+    assert!(NP_Date::from_rfc3339("1969-12-31T23:59:59Z").is_err());
+
+    // years past 9999
+    assert_eq!(NP_Date::new(253402300800000).to_rfc3339(), "10000-01-01T00:00:00.000Z");
+    assert_eq!(NP_Date::from_rfc3339("10000-01-01T00:00:00Z")?, NP_Date::new(253402300800000));
+    assert!(NP_Date::from_rfc3339("999999999-01-01T00:00:00Z").is_err());
+    assert!(NP_Date::from_rfc3339("999-01-01T00:00:00Z").is_err());
+    assert!(NP_Date::from_rfc3339("2000-02-29T00:00:00").is_err());
+
+    let factory = crate::NP_Factory::new("date()")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set_json(&[], &NP_JSON::String(String::from("2020-11-09T23:40:49.484Z")), false)?;
+    assert_eq!(buffer.get::<NP_Date>(&[])?, Some(NP_Date::new(1604965249484)));
+
+    Ok(())
+}
@@ -59,8 +59,26 @@
 //! # Ok::<(), NP_Error>(()) 
 //! ```
 //!
+//! By default decimals are exported to JSON as an object with both the `value` and the `parts`, so no precision is lost.  Set the `json` schema property to `"number"` to export them as plain JSON numbers instead.
+//! 
+//! ```
+//! use no_proto::error::NP_Error;
+//! use no_proto::NP_Factory;
+//! use no_proto::pointer::dec::NP_Dec;
+//! 
+//! let factory: NP_Factory = NP_Factory::new("dec({exp: 2, json: \"number\"})")?;
+//!
+//! let mut new_buffer = factory.new_buffer(None);
+//! new_buffer.set(&[], NP_Dec::new(1234, 2))?;
+//! 
+//! assert_eq!("12.34", new_buffer.json_encode(&[])?["value"].stringify());
+//!
+//! # Ok::<(), NP_Error>(()) 
+//! ```
+//!
 
 use alloc::{string::String, sync::Arc};
+use alloc::collections::BTreeSet;
 use alloc::prelude::v1::Box;
 use crate::{idl::{JS_AST, JS_Schema}, schema::{NP_Dec_Data, NP_Value_Kind}, utils::to_signed};
 use crate::schema::{NP_Parsed_Schema};
@@ -129,6 +147,39 @@ impl NP_Dec {
         m / s
     }
 
+    /// Convert to a JSON number.
+    /// 
+    /// The decimal string is built from the digits of `num` before it's parsed, so large `exp` values don't pick up rounding errors from `to_float`.
+    fn to_json_number(&self) -> NP_JSON {
+        if self.exp == 0 {
+            return NP_JSON::Integer(self.num)
+        }
+
+        let exp = self.exp as usize;
+        let mut digits = self.num.to_string().replace("-", "");
+        while digits.len() <= exp {
+            digits.insert(0, '0');
+        }
+        digits.insert(digits.len() - exp, '.');
+        if self.num < 0 {
+            digits.insert(0, '-');
+        }
+
+        match digits.parse::<f64>() {
+            Ok(x) => NP_JSON::Float(x),
+            Err(_e) => NP_JSON::Float(self.to_float())
+        }
+    }
+
+    /// Parse the `json` schema property
+    fn parse_json_option(value: &str) -> Result<bool, NP_Error> {
+        match value {
+            "number" => Ok(true),
+            "struct" => Ok(false),
+            _ => Err(NP_Error::new("Decimal 'json' property must be \"number\" or \"struct\"!"))
+        }
+    }
+
     /// Shift the exponent of this NP_Dec to a new value.
     /// 
     /// If the new `exp` value is higher than the old `exp` value, there may be an overflow of the i64 value.
@@ -199,6 +250,73 @@ impl NP_Dec {
         NP_Dec { num, exp }
     }
 
+    /// Parse a decimal string like `"-12.34"` into an NP_Dec with the given `exp`.
+    ///
+    /// The digits are added to `num` one at a time, the value never passes through a float so there's no precision loss for large numbers.  Fails if the string has more decimal places than `exp` can hold (trailing zeros are fine) or if the value doesn't fit in an `i64`.
+    ///
+    /// ```
+    /// use no_proto::pointer::dec::NP_Dec;
+    /// use no_proto::error::NP_Error;
+    ///
+    /// assert_eq!(NP_Dec::from_str("12.34", 2)?, NP_Dec::new(1234, 2));
+    /// assert_eq!(NP_Dec::from_str("-5.1", 3)?, NP_Dec::new(-5100, 3));
+    /// assert_eq!(NP_Dec::from_str("922337203685477.5807", 4)?, NP_Dec::new(i64::MAX, 4));
+    ///
+    /// assert!(NP_Dec::from_str("1.234", 2).is_err());
+    ///
+    /// # Ok::<(), NP_Error>(())
+    /// ```
+    pub fn from_str(s: &str, exp: u8) -> Result<NP_Dec, NP_Error> {
+
+        let s = s.trim();
+
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s)
+        };
+
+        let (whole, fraction) = match digits.find('.') {
+            Some(idx) => (&digits[..idx], &digits[(idx + 1)..]),
+            None => (digits, "")
+        };
+
+        if whole.len() == 0 && fraction.len() == 0 {
+            return Err(NP_Error::new("Decimal string has no digits!"))
+        }
+
+        let fraction = fraction.trim_end_matches('0');
+
+        if fraction.len() > exp as usize {
+            return Err(NP_Error::new("Decimal string has more decimal places than the exp allows!"))
+        }
+
+        let overflow = || NP_Error::new("Decimal string is too large for an NP_Dec!");
+
+        // work in negative numbers so i64::MIN can be parsed
+        let mut num: i64 = 0;
+        let mut places = 0usize;
+        for (i, c) in whole.chars().chain(fraction.chars()).enumerate() {
+            let digit = match c.to_digit(10) {
+                Some(d) => d as i64,
+                None => return Err(NP_Error::new("Decimal string contains an invalid character!"))
+            };
+            num = num.checked_mul(10).and_then(|x| x.checked_sub(digit)).ok_or_else(overflow)?;
+            if i >= whole.len() { places += 1; }
+        }
+
+        while places < exp as usize {
+            num = num.checked_mul(10).ok_or_else(overflow)?;
+            places += 1;
+        }
+
+        if negative == false {
+            num = num.checked_neg().ok_or_else(overflow)?;
+        }
+
+        Ok(NP_Dec { num, exp })
+    }
+
     /// Given another NP_Dec value, match the `exp` value of this NP_Dec to the other one.  Returns a copy of the other NP_Dec.
     /// 
     /// This creates a copy of the other NP_Dec then shifts it's `exp` value to whatever self is, then returns that copy.
@@ -700,6 +818,10 @@ impl<'value> NP_Value<'value> for NP_Dec {
             schema_json.insert("default".to_owned(), NP_JSON::Float(value.into()));
         }
 
+        if data.json_number {
+            schema_json.insert("json".to_owned(), NP_JSON::String("number".to_owned()));
+        }
+
         Ok(NP_JSON::Dictionary(schema_json))
          
     }
@@ -732,6 +854,16 @@ impl<'value> NP_Value<'value> for NP_Dec {
                     return Err(NP_Error::new("Decimal types require a `parts` property!"))
                 }
             },
+            &NP_JSON::Integer(num) => {
+                Self::set_value(cursor, memory, NP_Dec::new(num, 0))?;
+            },
+            &NP_JSON::Float(num) => {
+                Self::set_value(cursor, memory, num.into())?;
+            },
+            NP_JSON::String(num) => {
+                let data = unsafe { &*(*memory.get_schema(cursor.schema_addr).data as *const NP_Dec_Data) };
+                Self::set_value(cursor, memory, NP_Dec::from_str(num.as_str(), data.exp)?)?;
+            },
             _ => {}
         }
 
@@ -815,6 +947,7 @@ impl<'value> NP_Value<'value> for NP_Dec {
         match Self::into_value(cursor, memory) {
             Ok(x) => {
                 match x {
+                    Some(y) if data.json_number => y.to_json_number(),
                     Some(y) => {
                         let mut object = JSMAP::new();
 
@@ -830,7 +963,9 @@ impl<'value> NP_Value<'value> for NP_Dec {
                     None => {
                         let data = unsafe { &*(*memory.get_schema(cursor.schema_addr).data as *const NP_Dec_Data) };
 
-                        if let Some(d) = data.default {
+                        if let (Some(d), true) = (data.default, data.json_number) {
+                            d.to_json_number()
+                        } else if let Some(d) = data.default {
                             let mut object = JSMAP::new();
                             let mut parts = JSMAP::new();
 
@@ -853,7 +988,7 @@ impl<'value> NP_Value<'value> for NP_Dec {
         }
     }
 
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
         
         let c_value = || { cursor.get_value(memory) };
 
@@ -874,6 +1009,9 @@ impl<'value> NP_Value<'value> for NP_Dec {
             result.push_str(", default: ");
             result.push_str(x.to_float().to_string().as_str());
         }
+        if data.json_number {
+            result.push_str(", json: \"number\"");
+        }
         result.push_str("})");
         Ok(result)
        
@@ -883,6 +1021,7 @@ impl<'value> NP_Value<'value> for NP_Dec {
 
         let mut exp: Option<u8> = None;
         let mut default: Option<f64> = None;
+        let mut json_number = false;
         if args.len() > 0 {
             match &args[0] {
                 JS_AST::object { properties } => {
@@ -914,6 +1053,14 @@ impl<'value> NP_Value<'value> for NP_Dec {
                                     _ => { }
                                 }
                             },
+                            "json" => {
+                                match value {
+                                    JS_AST::string { addr } => {
+                                        json_number = NP_Dec::parse_json_option(idl.get_str(addr))?;
+                                    },
+                                    _ => { }
+                                }
+                            },
                             _ => { }
                         }
                     }
@@ -936,15 +1083,17 @@ impl<'value> NP_Value<'value> for NP_Dec {
 
         let mult = 10i64.pow(exp as u32);
 
+        let json_flag = if json_number { 2 } else { 0 };
+
         let default = match default {
             Some(x) => {
-                schema_data.push(1);
+                schema_data.push(1 | json_flag);
                 let value = x * (mult as f64);
                 schema_data.extend((value as i64).to_be_bytes().to_vec());
                 Some(NP_Dec::new(value as i64, exp))
             },
             _ => {
-                schema_data.push(0);
+                schema_data.push(json_flag);
                 None
             }
         };
@@ -953,7 +1102,7 @@ impl<'value> NP_Value<'value> for NP_Dec {
             val: NP_Value_Kind::Fixed(8),
             i: NP_TypeKeys::Decimal,
             sortable: true,
-            data: Arc::new(Box::into_raw(Box::new(NP_Dec_Data { exp, default })) as *const u8)
+            data: Arc::new(Box::into_raw(Box::new(NP_Dec_Data { exp, default, json_number })) as *const u8)
         });
 
         return Ok((true, schema_data, schema))
@@ -980,23 +1129,33 @@ impl<'value> NP_Value<'value> for NP_Dec {
             }
         }
 
+        let json_number = match &json_schema["json"] {
+            NP_JSON::String(x) => NP_Dec::parse_json_option(x.as_str())?,
+            _ => false
+        };
+
+        let json_flag = if json_number { 2 } else { 0 };
+
         let mult = 10i64.pow(exp as u32);
 
         let default = match json_schema["default"] {
             NP_JSON::Float(x) => {
-                schema_data.push(1);
+                schema_data.push(1 | json_flag);
                 let value = x * (mult as f64);
                 schema_data.extend((value as i64).to_be_bytes().to_vec());
                 Some(NP_Dec::new(value as i64, exp))
             },
             NP_JSON::Integer(x) => {
-                schema_data.push(1);
-                let value = x * (mult as i64);
+                let value = match 10i64.checked_pow(exp as u32).and_then(|mult| x.checked_mul(mult)) {
+                    Some(value) => value,
+                    None => return Err(NP_Error::new("Decimal 'default' property is too large for the 'exp' property!"))
+                };
+                schema_data.push(1 | json_flag);
                 schema_data.extend((value as i64).to_be_bytes().to_vec());
                 Some(NP_Dec::new(value as i64, exp))
             },
             _ => {
-                schema_data.push(0);
+                schema_data.push(json_flag);
                 // schema_data.extend(0i64.to_be_bytes().to_vec())
                 None
             }
@@ -1006,7 +1165,7 @@ impl<'value> NP_Value<'value> for NP_Dec {
             val: NP_Value_Kind::Fixed(8),
             i: NP_TypeKeys::Decimal,
             sortable: true,
-            data: Arc::new(Box::into_raw(Box::new(NP_Dec_Data { exp, default })) as *const u8)
+            data: Arc::new(Box::into_raw(Box::new(NP_Dec_Data { exp, default, json_number })) as *const u8)
         });
 
         return Ok((true, schema_data, schema))
@@ -1016,7 +1175,9 @@ impl<'value> NP_Value<'value> for NP_Dec {
     fn from_bytes_to_schema(mut schema: Vec<NP_Parsed_Schema>, address: usize, bytes: &[u8]) -> (bool, Vec<NP_Parsed_Schema>) {
         let exp = bytes[address + 1];
 
-        let default = if bytes[address + 2] == 0 {
+        let json_number = bytes[address + 2] & 2 == 2;
+
+        let default = if bytes[address + 2] & 1 == 0 {
             None
         } else {
             let mut slice = 0i64.to_be_bytes();
@@ -1029,7 +1190,7 @@ impl<'value> NP_Value<'value> for NP_Dec {
             val: NP_Value_Kind::Fixed(8),
             i: NP_TypeKeys::Decimal,
             sortable: true,
-            data: Arc::new(Box::into_raw(Box::new(NP_Dec_Data { exp, default })) as *const u8)
+            data: Arc::new(Box::into_raw(Box::new(NP_Dec_Data { exp, default, json_number })) as *const u8)
         });
 
         (true, schema)
@@ -1070,6 +1231,45 @@ fn schema_parsing_works() -> Result<(), NP_Error> {
     Ok(())
 }
 
+#[test]
+fn json_number_works() -> Result<(), NP_Error> {
+    let schema = "{\"type\":\"decimal\",\"exp\":3,\"default\":203.293,\"json\":\"number\"}";
+    let factory = crate::NP_Factory::new_json(schema)?;
+    assert_eq!(schema, factory.schema.to_json()?.stringify());
+    let factory2 = crate::NP_Factory::new_bytes(factory.export_schema_bytes())?;
+    assert_eq!(schema, factory2.schema.to_json()?.stringify());
+    let factory3 = crate::NP_Factory::new(factory.schema.to_idl()?)?;
+    assert_eq!(schema, factory3.schema.to_json()?.stringify());
+
+    let mut buffer = factory.new_buffer(None);
+    assert_eq!("203.293", buffer.json_encode(&[])?["value"].stringify());
+    buffer.set(&[], NP_Dec::new(-5, 3))?;
+    assert_eq!("-0.005", buffer.json_encode(&[])?["value"].stringify());
+
+    // numbers round trip through JSON
+    let json = buffer.json_encode(&[])?;
+    buffer.set_json(&[], &json["value"], false)?;
+    assert_eq!(buffer.get::<NP_Dec>(&[])?.unwrap(), NP_Dec::new(-5, 3));
+
+    // large exponents
+    let factory = crate::NP_Factory::new("dec({exp: 18, json: \"number\"})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&[], NP_Dec::new(7, 18))?;
+    assert_eq!(NP_JSON::Float(7e-18), buffer.json_encode(&[])?["value"]);
+    buffer.set(&[], NP_Dec::new(i64::MAX, 18))?;
+    assert_eq!(NP_JSON::Float(9.223372036854776), buffer.json_encode(&[])?["value"]);
+
+    // structured output is still the default
+    let factory = crate::NP_Factory::new("dec({exp: 2})")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&[], NP_Dec::new(1234, 2))?;
+    assert_eq!("{\"value\":12.34,\"parts\":{\"num\":1234,\"exp\":2}}", buffer.json_encode(&[])?["value"].stringify());
+
+    assert!(crate::NP_Factory::new("dec({exp: 2, json: \"float\"})").is_err());
+
+    Ok(())
+}
+
 #[test]
 fn default_value_works() -> Result<(), NP_Error> {
     let schema = "{\"type\":\"decimal\",\"exp\":3,\"default\":203.293}";
@@ -1097,4 +1297,24 @@ fn set_clear_value_and_compaction_works() -> Result<(), NP_Error> {
     assert_eq!(buffer.calc_bytes()?.current_buffer, 6usize);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn from_str_works() -> Result<(), NP_Error> {
+    // 2^53 + 1 can't be represented by an f64
+    assert_eq!(NP_Dec::from_str("9007199254740993", 0)?.num, 9007199254740993);
+    assert_eq!(NP_Dec::from_str("90071992547409.93", 2)?.num, 9007199254740993);
+    assert_eq!(NP_Dec::from_str("-922337203685477.5808", 4)?.num, i64::MIN);
+    assert_eq!(NP_Dec::from_str(".5", 1)?, NP_Dec::new(5, 1));
+    assert_eq!(NP_Dec::from_str("3.1400", 2)?, NP_Dec::new(314, 2));
+    assert!(NP_Dec::from_str("922337203685477.5808", 4).is_err());
+    assert!(NP_Dec::from_str("12a", 0).is_err());
+    assert!(NP_Dec::from_str("-", 0).is_err());
+
+    let factory = crate::NP_Factory::new_json("{\"type\":\"decimal\",\"exp\": 2}")?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set_json(&[], &NP_JSON::String("90071992547409.93".to_owned()), true)?;
+    assert_eq!(buffer.get::<NP_Dec>(&[])?.unwrap().num, 9007199254740993);
+
+    Ok(())
+}
@@ -19,6 +19,7 @@
 //! 
 
 use alloc::sync::Arc;
+use alloc::collections::BTreeSet;
 use alloc::string::String;
 use crate::{idl::{JS_AST, JS_Schema}, schema::{NP_Geo_Data, NP_Parsed_Schema, NP_Value_Kind}};
 use alloc::vec::Vec;
@@ -162,7 +163,7 @@ impl<'value> NP_Value<'value> for NP_Geo_Bytes {
     fn to_json(depth:usize, cursor: &NP_Cursor, memory: &'value NP_Memory) -> NP_JSON {
         NP_Geo::to_json(depth, cursor, memory)
     }
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
 
@@ -282,6 +283,48 @@ impl NP_Geo {
         NP_JSON::Dictionary(result_json)
      }
 
+     /// Check if this point is inside the box made by the south west (`sw`) and north east (`ne`) corners, edges included.
+     ///
+     /// Coordinates are compared at the resolution of this point so bounds with more precision than the point can hold don't cause false negatives.  If the west edge is east of the east edge the box is treated as crossing the 180° meridian.
+     ///
+     /// ```
+     /// use no_proto::pointer::geo::NP_Geo;
+     ///
+     /// let portland = NP_Geo::new(8, 45.509616, -122.714625);
+     /// assert!(portland.in_bounds(&NP_Geo::new(8, 45.0, -123.0), &NP_Geo::new(8, 46.0, -122.0)));
+     /// assert!(!portland.in_bounds(&NP_Geo::new(8, 46.0, -123.0), &NP_Geo::new(8, 47.0, -122.0)));
+     ///
+     /// // box crossing the antimeridian
+     /// let fiji = NP_Geo::new(8, -17.7, 178.0);
+     /// assert!(fiji.in_bounds(&NP_Geo::new(8, -20.0, 175.0), &NP_Geo::new(8, -15.0, -178.0)));
+     /// ```
+     pub fn in_bounds(&self, sw: &NP_Geo, ne: &NP_Geo) -> bool {
+        let dev = NP_Geo::get_deviser(self.size as i64);
+
+        // round to the nearest step of the point's resolution
+        let quantize = |value: f64| -> f64 {
+            if dev == 0.0 { return value }
+            let scaled = value * dev;
+            (if scaled < 0.0 { scaled - 0.5 } else { scaled + 0.5 }) as i64 as f64
+        };
+
+        let lat = quantize(self.lat);
+        let lng = quantize(self.lng);
+        let (south, west) = (quantize(sw.lat), quantize(sw.lng));
+        let (north, east) = (quantize(ne.lat), quantize(ne.lng));
+
+        if lat < south || lat > north {
+            return false
+        }
+
+        if west <= east {
+            lng >= west && lng <= east
+        } else {
+            // box wraps around the 180° meridian
+            lng >= west || lng <= east
+        }
+     }
+
      /// Get the bytes that represent this geographic coordinate
      pub fn get_bytes(&self) -> Option<NP_Geo_Bytes> {
         if self.size == 0 {
@@ -806,7 +849,7 @@ impl<'value> NP_Value<'value> for NP_Geo {
         Ok((false, schema_data, schema))
     }
 
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
 
@@ -1088,4 +1131,30 @@ fn set_clear_value_and_compaction_works() -> Result<(), NP_Error> {
     assert_eq!(buffer.calc_bytes()?.current_buffer, 6usize);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn in_bounds_works() -> Result<(), NP_Error> {
+    let schema = r#"{"type":"geo4"}"#;
+    let factory = crate::NP_Factory::new_json(schema)?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&[], NP_Geo::new(4, 20.23, -12.21))?;
+    let point = buffer.get::<NP_Geo>(&[])?.unwrap();
+
+    assert!(point.in_bounds(&NP_Geo::new(4, 20.0, -13.0), &NP_Geo::new(4, 21.0, -12.0)));
+    // edges are inside, even with more precision than geo4 stores
+    assert!(point.in_bounds(&NP_Geo::new(16, 20.230000001, -12.210000001), &NP_Geo::new(16, 20.230000001, -12.210000001)));
+    assert!(!point.in_bounds(&NP_Geo::new(4, 20.24, -13.0), &NP_Geo::new(4, 21.0, -12.0)));
+    assert!(!point.in_bounds(&NP_Geo::new(4, 20.0, -12.0), &NP_Geo::new(4, 21.0, -11.0)));
+
+    // box spanning the 180° meridian
+    let sw = NP_Geo::new(8, -20.0, 170.0);
+    let ne = NP_Geo::new(8, -10.0, -170.0);
+    assert!(NP_Geo::new(8, -15.0, 175.0).in_bounds(&sw, &ne));
+    assert!(NP_Geo::new(8, -15.0, -175.0).in_bounds(&sw, &ne));
+    assert!(NP_Geo::new(8, -15.0, 180.0).in_bounds(&sw, &ne));
+    assert!(!NP_Geo::new(8, -15.0, 0.0).in_bounds(&sw, &ne));
+    assert!(!NP_Geo::new(8, -25.0, 175.0).in_bounds(&sw, &ne));
+
+    Ok(())
+}
@@ -28,14 +28,15 @@ pub mod portal;
 use core::{fmt::{Debug}};
 
 use alloc::prelude::v1::Box;
-use crate::{idl::{JS_AST, JS_Schema}, pointer::dec::NP_Dec, schema::{NP_Portal_Data, NP_Schema_Addr}, utils::opt_err};
+use alloc::collections::BTreeSet;
+use crate::{idl::{JS_AST, JS_Schema}, pointer::dec::NP_Dec, schema::{NP_Map_List_Data, NP_Portal_Data, NP_Schema_Addr, NP_Struct_Data, NP_Tuple_Data, NP_Value_Kind}, utils::opt_err};
 use crate::NP_Parsed_Schema;
-use crate::{json_flex::NP_JSON};
+use crate::{buffer::{NP_JSON_Opts, VTABLE_BYTES, VTABLE_SIZE}, json_flex::{JSMAP, NP_JSON}};
 use crate::memory::{NP_Memory};
 use crate::NP_Error;
 use crate::{schema::{NP_TypeKeys}, collection::{map::NP_Map, struc::NP_Struct, list::NP_List, tuple::NP_Tuple}};
 
-use alloc::{string::String, vec::Vec, borrow::ToOwned};
+use alloc::{string::{String, ToString}, vec::Vec, borrow::ToOwned};
 use bytes::NP_Bytes;
 
 use self::{date::NP_Date, geo::NP_Geo, option::NP_Enum, portal::NP_Portal, ulid::{NP_ULID}, uuid::{NP_UUID}};
@@ -293,7 +294,7 @@ impl<'cursor> NP_Cursor {
     
         let mut path_index = 0usize;
         
-        let mut loop_count = 0u16;
+        let mut loop_count = 0usize;
     
         loop {
     
@@ -303,7 +304,7 @@ impl<'cursor> NP_Cursor {
                 return Ok(Some(loop_cursor));
             }
     
-            if loop_count > 256 {
+            if loop_count > memory.max_depth {
                 return Err(NP_Error::RecursionLimit)
             }
 
@@ -511,7 +512,7 @@ impl<'cursor> NP_Cursor {
     /// 
     pub fn json_encode(depth: usize, cursor: &NP_Cursor, memory: &NP_Memory) -> NP_JSON {
 
-        if depth > 255 { return NP_JSON::Null }
+        if depth > memory.max_depth { return NP_JSON::Null }
 
         match memory.get_schema(cursor.schema_addr).i {
             NP_TypeKeys::None           => { NP_JSON::Null },
@@ -545,11 +546,243 @@ impl<'cursor> NP_Cursor {
 
     }
 
+    /// Is there no data at this cursor?
+    ///
+    pub fn is_absent(cursor: &NP_Cursor, memory: &NP_Memory) -> bool {
+        if cursor.value_bytes.is_some() { // fixed tuple values always have an address, check the presence byte
+            memory.read_bytes()[cursor.buff_addr - 1] == 0
+        } else {
+            cursor.get_value(memory).get_addr_value() == 0
+        }
+    }
+
+    /// Exports this pointer and all it's descendants into a JSON object, using the provided options to handle values that aren't in the buffer.
+    ///
+    pub fn json_encode_with(depth: usize, cursor: &NP_Cursor, memory: &NP_Memory, opts: &NP_JSON_Opts) -> NP_JSON {
+
+        if depth > memory.max_depth { return NP_JSON::Null }
+
+        let schema = memory.get_schema(cursor.schema_addr);
+
+        if schema.i != NP_TypeKeys::Portal && NP_Cursor::is_absent(cursor, memory) {
+            if opts.include_absent == false || opts.absent_as_null {
+                return NP_JSON::Null
+            }
+            return NP_Cursor::json_encode(depth, cursor, memory)
+        }
+
+        match schema.i {
+            NP_TypeKeys::Struct => {
+                let mut json_map = JSMAP::new();
+
+                let mut struc = NP_Struct::new_iter(&cursor, memory);
+
+                while let Some((_index, key, item)) = struc.step_iter(memory) {
+                    match item {
+                        Some(real) => {
+                            if opts.include_absent || NP_Cursor::is_absent(&real, memory) == false {
+                                json_map.insert(String::from(key), NP_Cursor::json_encode_with(depth + 1, &real, memory, opts));
+                            }
+                        },
+                        None => {
+                            if opts.include_absent {
+                                json_map.insert(String::from(key), NP_JSON::Null);
+                            }
+                        }
+                    }
+                }
+
+                NP_JSON::Dictionary(json_map)
+            },
+            NP_TypeKeys::Tuple => {
+                let mut json_list = Vec::new();
+
+                let mut tuple = NP_Tuple::new_iter(&cursor, memory);
+
+                while let Some((_idx, item)) = tuple.step_iter(memory, false) {
+                    if let Some(x) = item {
+                        json_list.push(NP_Cursor::json_encode_with(depth + 1, &x, memory, opts));
+                    } else {
+                        json_list.push(NP_JSON::Null);
+                    }
+                }
+
+                NP_JSON::Array(json_list)
+            },
+            NP_TypeKeys::List => {
+                let mut json_list = Vec::new();
+
+                let mut list_iter = NP_List::new_iter(&cursor, memory, false, 0);
+
+                while let Some((_index, item)) = NP_List::step_iter(&mut list_iter, memory) {
+                    if let Some(item_cursor) = &item {
+                        json_list.push(NP_Cursor::json_encode_with(depth + 1, item_cursor, memory, opts));
+                    } else {
+                        json_list.push(NP_JSON::Null);
+                    }
+                }
+
+                NP_JSON::Array(json_list)
+            },
+            NP_TypeKeys::Map => {
+                let mut json_map = JSMAP::new();
+
+                let mut map_iter = NP_Map::new_iter(&cursor, memory);
+
+                while let Some((key, item)) = map_iter.step_iter(memory) {
+                    json_map.insert(String::from(key), NP_Cursor::json_encode_with(depth + 1, &item, memory, opts));
+                }
+
+                NP_JSON::Dictionary(json_map)
+            },
+            NP_TypeKeys::Portal => {
+                let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+
+                let mut next = cursor.clone();
+                next.schema_addr = data.schema;
+                next.parent_schema_addr = data.parent_schema;
+                NP_Cursor::json_encode_with(depth + 1, &next, memory, opts)
+            },
+            _ => NP_Cursor::json_encode(depth, cursor, memory)
+        }
+    }
+
+    /// Compare the values at two cursors, possibly in different buffers with the same schema.
+    ///
+    /// Values are decoded and compared, so the layout of the bytes doesn't matter.  Maps are compared without regard to key order, lists are compared by index. An unset value is only equal to another unset value.
+    ///
+    pub fn value_eq(depth: usize, a_cursor: &NP_Cursor, a_memory: &NP_Memory, b_cursor: &NP_Cursor, b_memory: &NP_Memory) -> Result<bool, NP_Error> {
+
+        if depth > a_memory.max_depth { return Err(NP_Error::RecursionLimit) }
+
+        let a_schema = a_memory.get_schema(a_cursor.schema_addr);
+        let b_schema = b_memory.get_schema(b_cursor.schema_addr);
+
+        if a_schema.i != b_schema.i {
+            return Ok(false)
+        }
+
+        if a_schema.i == NP_TypeKeys::Portal {
+            let a_data = unsafe { &*(*a_schema.data as *const NP_Portal_Data) };
+            let b_data = unsafe { &*(*b_schema.data as *const NP_Portal_Data) };
+
+            let mut a_next = a_cursor.clone();
+            a_next.schema_addr = a_data.schema;
+            a_next.parent_schema_addr = a_data.parent_schema;
+
+            let mut b_next = b_cursor.clone();
+            b_next.schema_addr = b_data.schema;
+            b_next.parent_schema_addr = b_data.parent_schema;
+
+            return NP_Cursor::value_eq(depth + 1, &a_next, a_memory, &b_next, b_memory)
+        }
+
+        match (NP_Cursor::is_absent(a_cursor, a_memory), NP_Cursor::is_absent(b_cursor, b_memory)) {
+            (true, true) => return Ok(true),
+            (false, false) => { },
+            _ => return Ok(false)
+        }
+
+        // only the cursors that point to a value
+        let present = |cursor: Option<NP_Cursor>, memory: &NP_Memory| -> Option<NP_Cursor> {
+            match cursor {
+                Some(x) => if NP_Cursor::is_absent(&x, memory) { None } else { Some(x) },
+                None => None
+            }
+        };
+
+        match a_schema.i {
+            NP_TypeKeys::Struct => {
+                let mut a_struc = NP_Struct::new_iter(a_cursor, a_memory);
+                let mut b_struc = NP_Struct::new_iter(b_cursor, b_memory);
+
+                while let (Some((_, _, a_item)), Some((_, _, b_item))) = (a_struc.step_iter(a_memory), b_struc.step_iter(b_memory)) {
+                    match (present(a_item, a_memory), present(b_item, b_memory)) {
+                        (Some(a), Some(b)) => if NP_Cursor::value_eq(depth + 1, &a, a_memory, &b, b_memory)? == false { return Ok(false) },
+                        (None, None) => { },
+                        _ => return Ok(false)
+                    }
+                }
+
+                Ok(true)
+            },
+            NP_TypeKeys::Tuple => {
+                let mut a_tuple = NP_Tuple::new_iter(a_cursor, a_memory);
+                let mut b_tuple = NP_Tuple::new_iter(b_cursor, b_memory);
+
+                while let (Some((_, a_item)), Some((_, b_item))) = (a_tuple.step_iter(a_memory, false), b_tuple.step_iter(b_memory, false)) {
+                    match (present(a_item, a_memory), present(b_item, b_memory)) {
+                        (Some(a), Some(b)) => if NP_Cursor::value_eq(depth + 1, &a, a_memory, &b, b_memory)? == false { return Ok(false) },
+                        (None, None) => { },
+                        _ => return Ok(false)
+                    }
+                }
+
+                Ok(true)
+            },
+            NP_TypeKeys::List => {
+                let mut a_items: Vec<(usize, NP_Cursor)> = Vec::new();
+                let mut a_iter = NP_List::new_iter(a_cursor, a_memory, true, 0);
+                while let Some((index, item)) = NP_List::step_iter(&mut a_iter, a_memory) {
+                    if let Some(x) = present(item, a_memory) { a_items.push((index, x)); }
+                }
+
+                let mut b_items: Vec<(usize, NP_Cursor)> = Vec::new();
+                let mut b_iter = NP_List::new_iter(b_cursor, b_memory, true, 0);
+                while let Some((index, item)) = NP_List::step_iter(&mut b_iter, b_memory) {
+                    if let Some(x) = present(item, b_memory) { b_items.push((index, x)); }
+                }
+
+                if a_items.len() != b_items.len() {
+                    return Ok(false)
+                }
+
+                for ((a_index, a), (b_index, b)) in a_items.iter().zip(b_items.iter()) {
+                    if a_index != b_index || NP_Cursor::value_eq(depth + 1, a, a_memory, b, b_memory)? == false {
+                        return Ok(false)
+                    }
+                }
+
+                Ok(true)
+            },
+            NP_TypeKeys::Map => {
+                let mut a_items: Vec<(&str, NP_Cursor)> = Vec::new();
+                let mut a_iter = NP_Map::new_iter(a_cursor, a_memory);
+                while let Some((key, item)) = a_iter.step_iter(a_memory) {
+                    if let Some(x) = present(Some(item), a_memory) { a_items.push((key, x)); }
+                }
+
+                let mut b_items: Vec<(&str, NP_Cursor)> = Vec::new();
+                let mut b_iter = NP_Map::new_iter(b_cursor, b_memory);
+                while let Some((key, item)) = b_iter.step_iter(b_memory) {
+                    if let Some(x) = present(Some(item), b_memory) { b_items.push((key, x)); }
+                }
+
+                if a_items.len() != b_items.len() {
+                    return Ok(false)
+                }
+
+                // keys can be in any order
+                for (a_key, a) in a_items.iter() {
+                    match b_items.iter().find(|(b_key, _)| b_key == a_key) {
+                        Some((_, b)) => if NP_Cursor::value_eq(depth + 1, a, a_memory, b, b_memory)? == false { return Ok(false) },
+                        None => return Ok(false)
+                    }
+                }
+
+                Ok(true)
+            },
+            _ => {
+                Ok(NP_Cursor::json_encode(depth, a_cursor, a_memory) == NP_Cursor::json_encode(depth, b_cursor, b_memory))
+            }
+        }
+    }
+
     /// Compact from old cursor and memory into new cursor and memory
     /// 
     pub fn compact(depth: usize, from_cursor: NP_Cursor, from_memory: &NP_Memory, to_cursor: NP_Cursor, to_memory: &NP_Memory) -> Result<NP_Cursor, NP_Error> {
 
-        if depth > 255 { return Err(NP_Error::RecursionLimit)}
+        if depth > from_memory.max_depth { return Err(NP_Error::RecursionLimit) }
 
         match from_memory.get_schema(from_cursor.schema_addr).i {
             NP_TypeKeys::Any           => { Ok(to_cursor) }
@@ -622,17 +855,87 @@ impl<'cursor> NP_Cursor {
         Ok(())
     }
 
+    /// Write the schema `default` into every unset scalar at or below this cursor.
+    ///
+    /// Structs and tuples are recursed into, lists and maps are left alone.
+    ///
+    pub fn fill_defaults(depth: usize, cursor: NP_Cursor, memory: &NP_Memory) -> Result<(), NP_Error> {
+
+        if depth > memory.max_depth { return Err(NP_Error::RecursionLimit) }
+
+        let schemas = memory.get_schemas();
+        let schema = &schemas[cursor.schema_addr];
+
+        match schema.i {
+            NP_TypeKeys::Struct => {
+                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                for field in data.fields.iter() {
+                    if let Some(item) = NP_Struct::select(cursor, schema, &field.col, true, false, memory)? {
+                        NP_Cursor::fill_defaults(depth + 1, item, memory)?;
+                    }
+                }
+                return Ok(())
+            },
+            NP_TypeKeys::Tuple => {
+                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                for idx in 0..data.values.len() {
+                    if let Some(item) = NP_Tuple::select(cursor, schema, idx, true, false, memory)? {
+                        NP_Cursor::fill_defaults(depth + 1, item, memory)?;
+                    }
+                }
+                return Ok(())
+            },
+            _ => { }
+        }
+
+        // only unset values get the default
+        if NP_Cursor::is_absent(&cursor, memory) == false {
+            return Ok(())
+        }
+
+        let addr = cursor.schema_addr;
+
+        let did_set = match schema.i {
+            NP_TypeKeys::UTF8String  => { if let Some(x) =   String::default_value(0, addr, schemas) {   String::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Bytes       => { if let Some(x) = NP_Bytes::default_value(0, addr, schemas) { NP_Bytes::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Int8        => { if let Some(x) =       i8::default_value(0, addr, schemas) {       i8::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Int16       => { if let Some(x) =      i16::default_value(0, addr, schemas) {      i16::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Int32       => { if let Some(x) =      i32::default_value(0, addr, schemas) {      i32::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Int64       => { if let Some(x) =      i64::default_value(0, addr, schemas) {      i64::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Uint8       => { if let Some(x) =       u8::default_value(0, addr, schemas) {       u8::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Uint16      => { if let Some(x) =      u16::default_value(0, addr, schemas) {      u16::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Uint32      => { if let Some(x) =      u32::default_value(0, addr, schemas) {      u32::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Uint64      => { if let Some(x) =      u64::default_value(0, addr, schemas) {      u64::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Float       => { if let Some(x) =      f32::default_value(0, addr, schemas) {      f32::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Double      => { if let Some(x) =      f64::default_value(0, addr, schemas) {      f64::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Decimal     => { if let Some(x) =   NP_Dec::default_value(0, addr, schemas) {   NP_Dec::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Boolean     => { if let Some(x) =     bool::default_value(0, addr, schemas) {     bool::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Geo         => { if let Some(x) =   NP_Geo::default_value(0, addr, schemas) {   NP_Geo::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Uuid        => { if let Some(x) =  NP_UUID::default_value(0, addr, schemas) {  NP_UUID::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Ulid        => { if let Some(x) =  NP_ULID::default_value(0, addr, schemas) {  NP_ULID::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Date        => { if let Some(x) =  NP_Date::default_value(0, addr, schemas) {  NP_Date::set_value(cursor, memory, x)?; true } else { false } },
+            NP_TypeKeys::Enum        => { if let Some(x) =  NP_Enum::default_value(0, addr, schemas) {  NP_Enum::set_value(cursor, memory, x)?; true } else { false } },
+            _ => false
+        };
+
+        if did_set && cursor.parent_type == NP_Cursor_Parent::Tuple {
+            memory.write_bytes()[cursor.buff_addr - 1] = 1;
+        }
+
+        Ok(())
+    }
+
     /// Set a JSON value into the buffer
     pub fn set_from_json(depth: usize, apply_null: bool, cursor: NP_Cursor, memory: &NP_Memory, json: &Box<NP_JSON>) -> Result<(), NP_Error> {
 
 
-        if depth > 255 { return Err(NP_Error::RecursionLimit) }
+        if depth > memory.max_depth { return Err(NP_Error::RecursionLimit) }
 
         // if apply_null is true, we should delete values where we find "null" or "undefined"
-        // if apply_null && **json == NP_JSON::Null {
-        //     NP_Cursor::delete(cursor, memory)?;
-        //     return Ok(())
-        // }
+        if apply_null && **json == NP_JSON::Null {
+            NP_Cursor::delete(cursor, memory)?;
+            return Ok(())
+        }
 
         if cursor.parent_type == NP_Cursor_Parent::Tuple {
             memory.write_bytes()[cursor.buff_addr - 1] = 1;
@@ -689,12 +992,251 @@ impl<'cursor> NP_Cursor {
         Ok(true)
     }
 
+    /// Remove every item or field from the collection at this cursor, the collection itself stays in the buffer.
+    /// 
+    /// Returns `true` if anything was cleared.
+    /// 
+    pub fn clear(depth: usize, cursor: NP_Cursor, memory: &NP_Memory) -> Result<bool, NP_Error> {
+
+        if depth > memory.max_depth { return Err(NP_Error::RecursionLimit) }
+
+        let schema = memory.get_schema(cursor.schema_addr);
+
+        let addr = cursor.get_value(memory).get_addr_value() as usize;
+
+        match schema.i {
+            NP_TypeKeys::Portal => {
+                let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+                let mut next = cursor.clone();
+                next.schema_addr = data.schema;
+                next.parent_schema_addr = data.parent_schema;
+                NP_Cursor::clear(depth + 1, next, memory)
+            },
+            NP_TypeKeys::List => {
+                if addr == 0 { return Ok(false) }
+                let list = NP_List::get_list(addr, memory);
+                let cleared = list.get_head() != 0;
+                list.set_head(0);
+                list.set_tail(0);
+                Ok(cleared)
+            },
+            NP_TypeKeys::Map => {
+                if addr == 0 { return Ok(false) }
+                // the map value points straight at the newest item
+                cursor.get_value_mut(memory).set_addr_value(0);
+                Ok(true)
+            },
+            NP_TypeKeys::Struct => {
+                let mut cleared = false;
+                let mut vtable_addr = addr;
+                let mut loop_max = 64usize;
+                while vtable_addr != 0 && loop_max > 0 {
+                    let vtable = NP_Struct::get_vtable(vtable_addr, memory);
+                    for slot in vtable.values.iter_mut() {
+                        if slot.get_addr_value() != 0 {
+                            slot.set_addr_value(0);
+                            cleared = true;
+                        }
+                    }
+                    vtable_addr = vtable.get_next() as usize;
+                    loop_max -= 1;
+                }
+                Ok(cleared)
+            },
+            NP_TypeKeys::Tuple => {
+                if addr == 0 { return Ok(false) }
+                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                let mut cleared = false;
+                for idx in 0..data.values.len() {
+                    if let Some(item) = NP_Tuple::select(cursor, schema, idx, false, false, memory)? {
+                        cleared |= NP_Cursor::delete(item, memory)?;
+                    }
+                }
+                Ok(cleared)
+            },
+            _ => Err(NP_Error::new("Only lists, maps, structs and tuples can be cleared!"))
+        }
+    }
+
+    fn invalid_at(context: &'static str, addr: usize) -> NP_Error {
+        NP_Error::BadAddress { addr, context }
+    }
+
+    /// Check that this pointer and all it's descendants only reference valid locations in the buffer.
+    ///
+    /// Every address is bounds checked before it is followed and nothing is mutated.  The first problem found is returned as an `NP_Error::BadAddress` error.
+    ///
+    pub fn validate(depth: usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<(), NP_Error> {
+
+        if depth > memory.max_depth { return Err(NP_Error::RecursionLimit) }
+
+        let bytes = memory.read_bytes();
+        let schema = memory.get_schema(cursor.schema_addr);
+
+        let read_u32 = |addr: usize| -> u32 {
+            u32::from_be_bytes([bytes[addr], bytes[addr + 1], bytes[addr + 2], bytes[addr + 3]])
+        };
+
+        if schema.i == NP_TypeKeys::Portal {
+            let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+            let mut next = cursor.clone();
+            next.schema_addr = data.schema;
+            next.parent_schema_addr = data.parent_schema;
+            return NP_Cursor::validate(depth + 1, &next, memory)
+        }
+
+        let value_addr = if cursor.value_bytes.is_some() { // fixed tuple values are stored in place
+            if bytes[cursor.buff_addr - 1] == 0 {
+                return Ok(())
+            }
+            cursor.buff_addr
+        } else {
+            if cursor.buff_addr + 4 > bytes.len() {
+                return Err(NP_Cursor::invalid_at("pointer is out of bounds", cursor.buff_addr))
+            }
+            read_u32(cursor.buff_addr) as usize
+        };
+
+        // no value here
+        if value_addr == 0 {
+            return Ok(())
+        }
+
+        if value_addr >= bytes.len() {
+            return Err(NP_Cursor::invalid_at("value is out of bounds", value_addr))
+        }
+
+        match schema.i {
+            NP_TypeKeys::None | NP_TypeKeys::Any => { },
+            NP_TypeKeys::Struct => {
+                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                let max_vtables = (data.fields.len() + VTABLE_SIZE - 1) / VTABLE_SIZE;
+
+                let mut vtable_addr = value_addr;
+                let mut vtable_idx = 0usize;
+                while vtable_addr != 0 {
+                    if vtable_idx >= max_vtables {
+                        return Err(NP_Cursor::invalid_at("too many vtables in struct", vtable_addr))
+                    }
+                    if vtable_addr + VTABLE_BYTES > bytes.len() {
+                        return Err(NP_Cursor::invalid_at("vtable is out of bounds", vtable_addr))
+                    }
+                    for x in 0..VTABLE_SIZE {
+                        let field_idx = (vtable_idx * VTABLE_SIZE) + x;
+                        if field_idx < data.fields.len() {
+                            let item = NP_Cursor::new(vtable_addr + (x * 4), data.fields[field_idx].schema, cursor.schema_addr);
+                            NP_Cursor::validate(depth + 1, &item, memory)?;
+                        }
+                    }
+                    vtable_addr = read_u32(vtable_addr + (VTABLE_SIZE * 4)) as usize;
+                    vtable_idx += 1;
+                }
+            },
+            NP_TypeKeys::Tuple => {
+                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                if value_addr + data.empty.len() > bytes.len() {
+                    return Err(NP_Cursor::invalid_at("tuple is out of bounds", value_addr))
+                }
+                for idx in 0..data.values.len() {
+                    if let Some(item) = NP_Tuple::select(*cursor, schema, idx, false, false, memory)? {
+                        NP_Cursor::validate(depth + 1, &item, memory)?;
+                    }
+                }
+            },
+            NP_TypeKeys::List => {
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                if value_addr + 8 > bytes.len() {
+                    return Err(NP_Cursor::invalid_at("list is out of bounds", value_addr))
+                }
+                let tail_addr = read_u32(value_addr + 4) as usize;
+
+                let mut item_addr = read_u32(value_addr) as usize;
+                let mut last_index: Option<u16> = None;
+                while item_addr != 0 {
+                    if item_addr + 10 > bytes.len() {
+                        return Err(NP_Cursor::invalid_at("list item is out of bounds", item_addr))
+                    }
+                    // indexes always increase along the list, this also guarantees the chain terminates
+                    let index = u16::from_be_bytes([bytes[item_addr + 8], bytes[item_addr + 9]]);
+                    if let Some(last) = last_index {
+                        if index <= last {
+                            return Err(NP_Cursor::invalid_at("list items are out of order", item_addr))
+                        }
+                    }
+                    last_index = Some(index);
+
+                    NP_Cursor::validate(depth + 1, &NP_Cursor::new(item_addr, data.child, cursor.schema_addr), memory)?;
+
+                    let next_addr = read_u32(item_addr + 4) as usize;
+                    if next_addr == 0 && item_addr != tail_addr {
+                        return Err(NP_Cursor::invalid_at("list tail doesn't match last item", item_addr))
+                    }
+                    item_addr = next_addr;
+                }
+            },
+            NP_TypeKeys::Map => {
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                // each map item uses at least 13 bytes, so any chain longer than this has a cycle
+                let max_items = bytes.len() / 13;
+
+                let mut item_addr = value_addr;
+                let mut count = 0usize;
+                while item_addr != 0 {
+                    if count > max_items {
+                        return Err(NP_Cursor::invalid_at("map items contain a cycle", item_addr))
+                    }
+                    if item_addr + 12 > bytes.len() {
+                        return Err(NP_Cursor::invalid_at("map item is out of bounds", item_addr))
+                    }
+                    let key_addr = read_u32(item_addr + 8) as usize;
+                    if key_addr == 0 || key_addr >= bytes.len() || key_addr + 1 + (bytes[key_addr] as usize) > bytes.len() {
+                        return Err(NP_Cursor::invalid_at("map key is out of bounds", key_addr))
+                    }
+                    let key_len = bytes[key_addr] as usize;
+                    if core::str::from_utf8(&bytes[(key_addr + 1)..(key_addr + 1 + key_len)]).is_err() {
+                        return Err(NP_Cursor::invalid_at("map key is not valid utf-8", key_addr))
+                    }
+
+                    NP_Cursor::validate(depth + 1, &NP_Cursor::new(item_addr, data.child, cursor.schema_addr), memory)?;
+
+                    item_addr = read_u32(item_addr + 4) as usize;
+                    count += 1;
+                }
+            },
+            _ => { // scalars
+                match schema.val {
+                    NP_Value_Kind::Fixed(size) => {
+                        if value_addr + (size as usize) > bytes.len() {
+                            return Err(NP_Cursor::invalid_at("value is out of bounds", value_addr))
+                        }
+                    },
+                    NP_Value_Kind::Pointer => { // dynamic size, 4 length bytes then the data
+                        if value_addr + 4 > bytes.len() {
+                            return Err(NP_Cursor::invalid_at("value is out of bounds", value_addr))
+                        }
+                        let size = read_u32(value_addr) as usize;
+                        if value_addr + 4 + size > bytes.len() {
+                            return Err(NP_Cursor::invalid_at("value is out of bounds", value_addr))
+                        }
+                        if schema.i == NP_TypeKeys::UTF8String && core::str::from_utf8(&bytes[(value_addr + 4)..(value_addr + 4 + size)]).is_err() {
+                            return Err(NP_Cursor::invalid_at("string is not valid utf-8", value_addr))
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Calculate the number of bytes used by this pointer and it's descendants.
     /// 
-    pub fn calc_size(depth: usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    /// `sized_keys` holds the addresses of the interned map keys already counted, so each one is only counted once per calculation.
+    /// 
+    pub fn calc_size(depth: usize, cursor: &NP_Cursor, memory: &NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
+
+        if depth > memory.max_depth { return Err(NP_Error::RecursionLimit) }
 
-        if depth > 255 { return Err(NP_Error::new("Depth error!")) }
-        
         let value = cursor.get_value(memory);
 
         let type_key = memory.get_schema(cursor.schema_addr).i;
@@ -711,35 +1253,109 @@ impl<'cursor> NP_Cursor {
         let type_size = match type_key {
             NP_TypeKeys::None         => { Ok(0) },
             NP_TypeKeys::Any          => { Ok(0) },
-            NP_TypeKeys::UTF8String   => {    String::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Bytes        => {  NP_Bytes::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Int8         => {        i8::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Int16        => {       i16::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Int32        => {       i32::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Int64        => {       i64::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Uint8        => {        u8::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Uint16       => {       u16::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Uint32       => {       u32::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Uint64       => {       u64::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Float        => {       f32::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Double       => {       f64::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Decimal      => {    NP_Dec::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Boolean      => {      bool::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Geo          => {    NP_Geo::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Uuid         => {   NP_UUID::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Ulid         => {   NP_ULID::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Date         => {   NP_Date::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Enum         => {   NP_Enum::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Struct       => { NP_Struct::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Map          => {    NP_Map::get_size(depth, cursor, memory) },
-            NP_TypeKeys::List         => {   NP_List::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Tuple        => {  NP_Tuple::get_size(depth, cursor, memory) },
-            NP_TypeKeys::Portal       => { NP_Portal::get_size(depth, cursor, memory) },
-            // NP_TypeKeys::Union        => {  NP_Union::get_size(depth, cursor, memory) },
+            NP_TypeKeys::UTF8String   => {    String::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Bytes        => {  NP_Bytes::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Int8         => {        i8::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Int16        => {       i16::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Int32        => {       i32::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Int64        => {       i64::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Uint8        => {        u8::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Uint16       => {       u16::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Uint32       => {       u32::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Uint64       => {       u64::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Float        => {       f32::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Double       => {       f64::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Decimal      => {    NP_Dec::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Boolean      => {      bool::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Geo          => {    NP_Geo::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Uuid         => {   NP_UUID::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Ulid         => {   NP_ULID::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Date         => {   NP_Date::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Enum         => {   NP_Enum::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Struct       => { NP_Struct::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Map          => {    NP_Map::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::List         => {   NP_List::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Tuple        => {  NP_Tuple::get_size(depth, cursor, memory, sized_keys) },
+            NP_TypeKeys::Portal       => { NP_Portal::get_size(depth, cursor, memory, sized_keys) },
+            // NP_TypeKeys::Union        => {  NP_Union::get_size(depth, cursor, memory, sized_keys) },
         }?;
 
         Ok(type_size + base_size)
     }
+
+    /// Collect the path of every scalar below this cursor that has a value in the buffer.
+    ///
+    /// Struct fields and tuple columns are visited in schema order, list items by index and map keys in the order they're stored.
+    ///
+    pub fn collect_paths(depth: usize, cursor: &NP_Cursor, memory: &NP_Memory, path: &mut Vec<String>, paths: &mut Vec<Vec<String>>) -> Result<(), NP_Error> {
+
+        if depth > memory.max_depth { return Err(NP_Error::RecursionLimit) }
+
+        let schema = memory.get_schema(cursor.schema_addr);
+
+        if schema.i != NP_TypeKeys::Portal && NP_Cursor::is_absent(cursor, memory) {
+            return Ok(())
+        }
+
+        match schema.i {
+            NP_TypeKeys::None | NP_TypeKeys::Any => { },
+            NP_TypeKeys::Struct => {
+                let mut struc = NP_Struct::new_iter(&cursor, memory);
+
+                while let Some((_index, key, item)) = struc.step_iter(memory) {
+                    if let Some(real) = item {
+                        path.push(String::from(key));
+                        NP_Cursor::collect_paths(depth + 1, &real, memory, path, paths)?;
+                        path.pop();
+                    }
+                }
+            },
+            NP_TypeKeys::Tuple => {
+                let mut tuple = NP_Tuple::new_iter(&cursor, memory);
+
+                while let Some((index, item)) = tuple.step_iter(memory, false) {
+                    if let Some(real) = item {
+                        path.push(index.to_string());
+                        NP_Cursor::collect_paths(depth + 1, &real, memory, path, paths)?;
+                        path.pop();
+                    }
+                }
+            },
+            NP_TypeKeys::List => {
+                let mut list_iter = NP_List::new_iter(&cursor, memory, true, 0);
+
+                while let Some((index, item)) = NP_List::step_iter(&mut list_iter, memory) {
+                    if let Some(real) = item {
+                        path.push(index.to_string());
+                        NP_Cursor::collect_paths(depth + 1, &real, memory, path, paths)?;
+                        path.pop();
+                    }
+                }
+            },
+            NP_TypeKeys::Map => {
+                let mut map_iter = NP_Map::new_iter(&cursor, memory);
+
+                while let Some((key, item)) = map_iter.step_iter(memory) {
+                    path.push(String::from(key));
+                    NP_Cursor::collect_paths(depth + 1, &item, memory, path, paths)?;
+                    path.pop();
+                }
+            },
+            NP_TypeKeys::Portal => {
+                let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+
+                let mut next = cursor.clone();
+                next.schema_addr = data.schema;
+                next.parent_schema_addr = data.parent_schema;
+                NP_Cursor::collect_paths(depth + 1, &next, memory, path, paths)?;
+            },
+            _ => {
+                paths.push(path.clone());
+            }
+        }
+
+        Ok(())
+    }
 }
 
 
@@ -757,6 +1373,19 @@ pub trait NP_Scalar<'scalar> {
 
 }
 
+/// Scalar types that don't borrow from the buffer, used by `NP_Buffer::get_owned`.
+/// 
+/// Strings and bytes are read as `String` and `Vec<u8>` copies instead of `&str` and `&[u8]`.
+pub trait NP_Value_Owned: 'static + for<'a> NP_Value<'a> + for<'a> NP_Scalar<'a> { }
+
+macro_rules! owned_value {
+    ($($t:ty),*) => {
+        $( impl NP_Value_Owned for $t { } )*
+    }
+}
+
+owned_value!(String, NP_Bytes, i8, i16, i32, i64, u8, u16, u32, u64, f32, f64, bool, NP_Dec, NP_Geo, NP_UUID, NP_ULID, NP_Date, NP_Enum);
+
 /// This trait is used to implement types as NoProto buffer types.
 /// This includes all the type data, encoding and decoding methods.
 #[doc(hidden)]
@@ -818,7 +1447,7 @@ pub trait NP_Value<'value> {
 
     /// Calculate the size of this pointer and it's children (recursive for collections)
     /// 
-    fn get_size(depth: usize, cursor: &'value NP_Cursor, memory: &'value NP_Memory) -> Result<usize, NP_Error>;
+    fn get_size(depth: usize, cursor: &'value NP_Cursor, memory: &'value NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error>;
     
     /// Handle copying from old pointer/buffer to new pointer/buffer (recursive for collections)
     /// 
@@ -28,9 +28,10 @@
 //! 
 //! 
 
-use crate::schema::NULL;
+use crate::schema::{NULL, NP_Schema, DESC_FLAG};
 use crate::schema::{NP_i8_Data, NP_i16_Data, NP_i32_Data, NP_i64_Data, NP_u8_Data, NP_u16_Data, NP_u32_Data, NP_u64_Data, NP_f32_Data, NP_f64_Data};
 use alloc::sync::Arc;
+use alloc::collections::BTreeSet;
 use crate::schema::NP_Value_Kind;
 use core::{str::FromStr};
 
@@ -119,6 +120,10 @@ macro_rules! noproto_number {
                     };
                     
                 }
+
+                if NP_Schema::_is_desc(schema, address) {
+                    schema_json.insert("sort".to_owned(), NP_JSON::String("desc".to_owned()));
+                }
         
                 Ok(NP_JSON::Dictionary(schema_json))
             }
@@ -126,12 +131,24 @@ macro_rules! noproto_number {
             fn schema_to_idl(schema: &Vec<NP_Parsed_Schema>, address: usize)-> Result<String, NP_Error> {
                 let mut result = String::from($str2);
 
+                let mut properties: Vec<String> = Vec::new();
+
                 if let Some(default) = <$t>::np_get_default(address, &schema) {
-                    result.push_str("({default: ");
-                    result.push_str(default.to_string().as_str());
-                    result.push_str("})");
-                } else {
+                    let mut def = String::from("default: ");
+                    def.push_str(default.to_string().as_str());
+                    properties.push(def);
+                }
+
+                if NP_Schema::_is_desc(schema, address) {
+                    properties.push(String::from("sort: \"desc\""));
+                }
+
+                if properties.len() == 0 {
                     result.push_str("()");
+                } else {
+                    result.push_str("({");
+                    result.push_str(properties.join(", ").as_str());
+                    result.push_str("})");
                 }
 
                 Ok(result)
@@ -141,6 +158,7 @@ macro_rules! noproto_number {
                 
                 let mut default: Option<$t> = None;
                 let mut default_str: Option<String> = None;
+                let mut desc = false;
 
                 if args.len() > 0 {
                     match &args[0] {
@@ -162,6 +180,14 @@ macro_rules! noproto_number {
                                             _ => { }
                                         }
                                     },
+                                    "sort" => {
+                                        match value {
+                                            JS_AST::string { addr } => {
+                                                desc = NP_Schema::_parse_sort(idl.get_str(addr).trim())?;
+                                            },
+                                            _ => { return Err(NP_Error::new("sort property must be 'asc' or 'desc'!")) }
+                                        }
+                                    },
                                     _ => { }
                                 }
                             }
@@ -170,14 +196,20 @@ macro_rules! noproto_number {
                     }
                 }
 
+                if desc && matches!($numType, NP_NumType::floating) {
+                    return Err(NP_Error::new("sort property can only be used with integers!"))
+                }
+
                 let mut schema_data: Vec<u8> = Vec::new();
                 schema_data.push($tkey as u8);
 
+                let desc_flag = if desc { DESC_FLAG } else { 0 };
+
                 if let Some(x) = default {
-                    schema_data.push(1);
+                    schema_data.push(1 | desc_flag);
                     schema_data.extend_from_slice(&(x as $t).to_be_bytes());
                 } else {
-                    schema_data.push(0);
+                    schema_data.push(desc_flag);
                 }
 
                 let use_schema = NP_Parsed_Schema {
@@ -189,34 +221,34 @@ macro_rules! noproto_number {
                     },
                     data: Arc::new(match $tkey {
                         NP_TypeKeys::Int8 => {
-                            Box::into_raw(Box::new(NP_i8_Data { default: i8::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_i8_Data { default: i8::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         NP_TypeKeys::Int16 => {
-                            Box::into_raw(Box::new(NP_i16_Data { default: i16::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_i16_Data { default: i16::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         NP_TypeKeys::Int32 => {
-                            Box::into_raw(Box::new(NP_i32_Data { default: i32::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_i32_Data { default: i32::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         NP_TypeKeys::Int64 => {
-                            Box::into_raw(Box::new(NP_i64_Data { default: i64::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_i64_Data { default: i64::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint8 => {
-                            Box::into_raw(Box::new(NP_u8_Data { default: u8::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_u8_Data { default: u8::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint16 => {
-                            Box::into_raw(Box::new(NP_u16_Data { default: u16::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_u16_Data { default: u16::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint32 => {
-                            Box::into_raw(Box::new(NP_u32_Data { default: u32::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_u32_Data { default: u32::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint64 => {
-                            Box::into_raw(Box::new(NP_u64_Data { default: u64::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_u64_Data { default: u64::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         NP_TypeKeys::Float => {
-                            Box::into_raw(Box::new(NP_f32_Data { default: f32::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_f32_Data { default: f32::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         NP_TypeKeys::Double => {
-                            Box::into_raw(Box::new(NP_f64_Data { default: f64::np_unwrap_default(default_str) })) as *const u8
+                            Box::into_raw(Box::new(NP_f64_Data { default: f64::np_unwrap_default(default_str), desc })) as *const u8
                         },
                         _ => { NULL() }
                     })
@@ -237,6 +269,8 @@ macro_rules! noproto_number {
 
                 let mut value_address = c_value().get_addr_value() as usize;
 
+                let desc = NP_Schema::_is_desc(memory.get_schemas(), cursor.schema_addr);
+
                 if value_address != 0 { // existing value, replace
                     let mut bytes = value.to_be_bytes();
 
@@ -246,6 +280,10 @@ macro_rules! noproto_number {
                         },
                         _ => {}
                     };
+
+                    if desc {
+                        for byte in bytes.iter_mut() { *byte = !*byte; }
+                    }
         
                     let write_bytes = memory.write_bytes();
         
@@ -264,6 +302,10 @@ macro_rules! noproto_number {
                         },
                         _ => {}
                     };
+
+                    if desc {
+                        for byte in bytes.iter_mut() { *byte = !*byte; }
+                    }
         
                     value_address = memory.malloc_borrow(&bytes)?;
                     cursor.get_value_mut(memory).set_addr_value(value_address as u32);
@@ -290,6 +332,10 @@ macro_rules! noproto_number {
                     be_bytes[x] = read_memory[value_addr + x];
                 }
 
+                if NP_Schema::_is_desc(memory.get_schemas(), cursor.schema_addr) {
+                    for byte in be_bytes.iter_mut() { *byte = !*byte; }
+                }
+
                 match $numType {
                     NP_NumType::signed => {
                         be_bytes[0] = to_signed(be_bytes[0]);
@@ -330,7 +376,7 @@ macro_rules! noproto_number {
                 }
             }
 
-            fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+            fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
                 let c_value = || { cursor.get_value(memory) };
 
@@ -343,20 +389,32 @@ macro_rules! noproto_number {
 
             fn from_json_to_schema(mut schema: Vec<NP_Parsed_Schema>, json_schema: &Box<NP_JSON>) -> Result<(bool, Vec<u8>, Vec<NP_Parsed_Schema>), NP_Error> {
         
+                let desc = match &json_schema["sort"] {
+                    NP_JSON::String(x) => NP_Schema::_parse_sort(x.as_str())?,
+                    NP_JSON::Null => false,
+                    _ => { return Err(NP_Error::new("sort property must be 'asc' or 'desc'!")) }
+                };
+
+                if desc && matches!($numType, NP_NumType::floating) {
+                    return Err(NP_Error::new("sort property can only be used with integers!"))
+                }
+
+                let desc_flag = if desc { DESC_FLAG } else { 0 };
+
                 let mut schema_data: Vec<u8> = Vec::new();
                 schema_data.push($tkey as u8);
     
                 match json_schema["default"] {
                     NP_JSON::Float(x) => {
-                        schema_data.push(1);
+                        schema_data.push(1 | desc_flag);
                         schema_data.extend((x as $t).to_be_bytes().to_vec());
                     },
                     NP_JSON::Integer(x) => {
-                        schema_data.push(1);
+                        schema_data.push(1 | desc_flag);
                         schema_data.extend((x as $t).to_be_bytes().to_vec());
                     },
                     _ => {
-                        schema_data.push(0);
+                        schema_data.push(desc_flag);
                     }
                 };
 
@@ -369,34 +427,34 @@ macro_rules! noproto_number {
                     },
                     data: Arc::new(match $tkey {
                         NP_TypeKeys::Int8 => {
-                            Box::into_raw(Box::new(NP_i8_Data { default: i8::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_i8_Data { default: i8::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         NP_TypeKeys::Int16 => {
-                            Box::into_raw(Box::new(NP_i16_Data { default: i16::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_i16_Data { default: i16::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         NP_TypeKeys::Int32 => {
-                            Box::into_raw(Box::new(NP_i32_Data { default: i32::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_i32_Data { default: i32::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         NP_TypeKeys::Int64 => {
-                            Box::into_raw(Box::new(NP_i64_Data { default: i64::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_i64_Data { default: i64::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint8 => {
-                            Box::into_raw(Box::new(NP_u8_Data { default: u8::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_u8_Data { default: u8::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint16 => {
-                            Box::into_raw(Box::new(NP_u16_Data { default: u16::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_u16_Data { default: u16::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint32 => {
-                            Box::into_raw(Box::new(NP_u32_Data { default: u32::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_u32_Data { default: u32::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint64 => {
-                            Box::into_raw(Box::new(NP_u64_Data { default: u64::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_u64_Data { default: u64::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         NP_TypeKeys::Float => {
-                            Box::into_raw(Box::new(NP_f32_Data { default: f32::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_f32_Data { default: f32::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         NP_TypeKeys::Double => {
-                            Box::into_raw(Box::new(NP_f64_Data { default: f64::np_get_default_from_json(&json_schema["default"]) })) as *const u8
+                            Box::into_raw(Box::new(NP_f64_Data { default: f64::np_get_default_from_json(&json_schema["default"]), desc })) as *const u8
                         },
                         _ => { NULL() }
                     })
@@ -410,6 +468,8 @@ macro_rules! noproto_number {
 
             fn from_bytes_to_schema(mut schema: Vec<NP_Parsed_Schema>, address: usize, bytes: &[u8]) -> (bool, Vec<NP_Parsed_Schema>) {
 
+                let desc = bytes[address + 1] & DESC_FLAG != 0;
+
                 let use_schema = NP_Parsed_Schema {
                     i: $tkey,
                     val: NP_Value_Kind::Fixed(core::mem::size_of::<Self>() as u32),
@@ -419,34 +479,34 @@ macro_rules! noproto_number {
                     },
                     data: Arc::new(match $tkey {
                         NP_TypeKeys::Int8 => {
-                            Box::into_raw(Box::new(NP_i8_Data { default: i8::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_i8_Data { default: i8::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         NP_TypeKeys::Int16 => {
-                            Box::into_raw(Box::new(NP_i16_Data { default: i16::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_i16_Data { default: i16::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         NP_TypeKeys::Int32 => {
-                            Box::into_raw(Box::new(NP_i32_Data { default: i32::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_i32_Data { default: i32::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         NP_TypeKeys::Int64 => {
-                            Box::into_raw(Box::new(NP_i64_Data { default: i64::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_i64_Data { default: i64::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint8 => {
-                            Box::into_raw(Box::new(NP_u8_Data { default: u8::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_u8_Data { default: u8::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint16 => {
-                            Box::into_raw(Box::new(NP_u16_Data { default: u16::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_u16_Data { default: u16::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint32 => {
-                            Box::into_raw(Box::new(NP_u32_Data { default: u32::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_u32_Data { default: u32::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         NP_TypeKeys::Uint64 => {
-                            Box::into_raw(Box::new(NP_u64_Data { default: u64::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_u64_Data { default: u64::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         NP_TypeKeys::Float => {
-                            Box::into_raw(Box::new(NP_f32_Data { default: f32::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_f32_Data { default: f32::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         NP_TypeKeys::Double => {
-                            Box::into_raw(Box::new(NP_f64_Data { default: f64::np_get_default_from_bytes(address, bytes) })) as *const u8
+                            Box::into_raw(Box::new(NP_f64_Data { default: f64::np_get_default_from_bytes(address, bytes), desc })) as *const u8
                         },
                         _ => { NULL() }
                     })
@@ -515,7 +575,7 @@ impl NP_BigEndian for i8 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 1] = Default::default();
@@ -606,7 +666,7 @@ impl NP_BigEndian for i16 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 2] = Default::default();
@@ -681,7 +741,7 @@ impl NP_BigEndian for i32 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 4] = Default::default();
@@ -754,7 +814,7 @@ impl NP_BigEndian for i64 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 8] = Default::default();
@@ -829,7 +889,7 @@ impl NP_BigEndian for u8 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 1] = Default::default();
@@ -902,7 +962,7 @@ impl NP_BigEndian for u16 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 2] = Default::default();
@@ -975,7 +1035,7 @@ impl NP_BigEndian for u32 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 4] = Default::default();
@@ -1048,7 +1108,7 @@ impl NP_BigEndian for u64 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 8] = Default::default();
@@ -1120,7 +1180,7 @@ impl NP_BigEndian for f32 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 4] = Default::default();
@@ -1193,7 +1253,7 @@ impl NP_BigEndian for f64 {
         }
     }
     fn np_get_default_from_bytes<'default>(address: usize, bytes: &'default [u8]) -> Option<Self> {
-        if bytes[address + 1] == 0 {
+        if bytes[address + 1] & 1 == 0 {
             None
         } else {
             let mut slice: [u8; 8] = Default::default();
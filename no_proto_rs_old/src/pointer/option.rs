@@ -19,6 +19,7 @@
 use crate::{JS_Schema, idl::JS_AST, schema::{NP_Enum_Data, NP_Value_Kind}};
 use crate::{memory::NP_Memory, schema::{NP_Parsed_Schema}};
 use alloc::{sync::Arc, vec::Vec};
+use alloc::collections::BTreeSet;
 use crate::json_flex::{JSMAP, NP_JSON};
 use crate::schema::{NP_TypeKeys};
 use crate::{pointer::NP_Value, error::NP_Error};
@@ -402,7 +403,7 @@ impl<'value> NP_Value<'value> for NP_Enum {
         }
     }
 
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
         let c_value = || { cursor.get_value(memory) };
 
         let value_address = c_value().get_addr_value() as usize;
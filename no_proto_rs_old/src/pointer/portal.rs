@@ -53,6 +53,7 @@
 
 use crate::{idl::{JS_AST, JS_Schema}, memory::NP_Memory, schema::{NP_Parsed_Schema, NP_Portal_Data, NP_Value_Kind}};
 use alloc::{sync::Arc, vec::Vec};
+use alloc::collections::BTreeSet;
 
 use crate::json_flex::{JSMAP, NP_JSON};
 use crate::schema::{NP_TypeKeys};
@@ -217,12 +218,12 @@ impl<'value> NP_Value<'value> for NP_Portal {
        
     }
 
-    fn get_size(depth:usize, cursor: &'value NP_Cursor, memory: &'value NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(depth:usize, cursor: &'value NP_Cursor, memory: &'value NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
         let data = unsafe { &*(*memory.get_schema(cursor.schema_addr).data as *const NP_Portal_Data) };
         let mut next = cursor.clone();
         next.schema_addr = data.schema;
         next.parent_schema_addr = data.parent_schema;
-        NP_Cursor::calc_size(depth + 1, &next, memory)
+        NP_Cursor::calc_size(depth + 1, &next, memory, sized_keys)
          
     }
 
@@ -339,4 +340,33 @@ fn set_clear_value_and_compaction_works() -> Result<(), NP_Error> {
     assert_eq!("hello", buffer.get::<&str>(&["address", "more", "more","more", "more","more", "more","more", "more", "street"])?.unwrap());
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn max_depth_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new(r#"struct({fields: {
+        node: u32(),
+        child: portal({ to: "" })
+    }})"#)?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["node"], 1u32)?;
+    buffer.set(&["child", "node"], 2u32)?;
+    buffer.set(&["child", "child", "node"], 3u32)?;
+
+    buffer.set_max_depth(4);
+    assert_eq!(buffer.get::<u32>(&["child", "node"])?, Some(2u32));
+    assert!(matches!(buffer.get::<u32>(&["child", "child", "node"]), Err(NP_Error::RecursionLimit)));
+    assert_eq!(buffer.json_encode(&[])?.stringify(), r#"{"value":{"node":1,"child":{"node":2,"child":{"node":null,"child":null}}}}"#);
+
+    // every other walk stops at the same depth
+    assert!(matches!(buffer.calc_bytes(), Err(NP_Error::RecursionLimit)));
+    assert!(matches!(buffer.deep_clone(), Err(NP_Error::RecursionLimit)));
+    assert!(matches!(buffer.value_eq(&buffer), Err(NP_Error::RecursionLimit)));
+    assert!(matches!(buffer.compact(None), Err(NP_Error::RecursionLimit)));
+
+    buffer.set_max_depth(255);
+    buffer.compact(None)?;
+    assert_eq!(buffer.get::<u32>(&["child", "child", "node"])?, Some(3u32));
+
+    Ok(())
+}
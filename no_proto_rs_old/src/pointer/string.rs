@@ -15,9 +15,10 @@
 //! ```
 
 use alloc::sync::Arc;
+use alloc::collections::BTreeSet;
 use alloc::string::String;
 use alloc::prelude::v1::Box;
-use crate::{error::NP_Error, idl::{JS_AST, JS_Schema}, schema::{NP_String_Data, NP_Value_Kind, String_Case}};
+use crate::{error::NP_Error, idl::{JS_AST, JS_Schema}, schema::{NP_Schema, NP_String_Data, NP_Value_Kind, String_Case, String_Overflow, DESC_FLAG}};
 use crate::{
     json_flex::JSMAP,
     memory::NP_Memory,
@@ -34,6 +35,14 @@ use alloc::string::ToString;
 /// &str type alias
 pub type NP_String<'string> = &'string str;
 
+fn parse_overflow(value: &str) -> Result<String_Overflow, NP_Error> {
+    match value {
+        "error" => Ok(String_Overflow::Error),
+        "truncate" => Ok(String_Overflow::Truncate),
+        _ => Err(NP_Error::new("overflow property must be 'error' or 'truncate'!"))
+    }
+}
+
 // impl<'value> NP_Scalar<'value> for &'value str {
 //     fn schema_default(_schema: &NP_Parsed_Schema) -> Option<Self> where Self: Sized {
 //         None
@@ -135,6 +144,14 @@ impl<'value> NP_Value<'value> for String {
             schema_json.insert("size".to_owned(), NP_JSON::Integer(data.size.clone().into()));
         }
 
+        if data.overflow == String_Overflow::Error {
+            schema_json.insert("overflow".to_owned(), NP_JSON::String("error".to_owned()));
+        }
+
+        if data.desc {
+            schema_json.insert("sort".to_owned(), NP_JSON::String("desc".to_owned()));
+        }
+
         if let Some(default_value) = &data.default {
             schema_json.insert(
                 "default".to_owned(),
@@ -167,6 +184,14 @@ impl<'value> NP_Value<'value> for String {
             properties.push(def);
         }
 
+        if data.overflow == String_Overflow::Error {
+            properties.push(String::from("overflow: \"error\""));
+        }
+
+        if data.desc {
+            properties.push(String::from("sort: \"desc\""));
+        }
+
         match data.case {
             String_Case::Uppercase => {
                 properties.push(String::from("uppercase: true"));
@@ -200,6 +225,10 @@ impl<'value> NP_Value<'value> for String {
 
         let mut default: Option<String> = Option::None;
 
+        let mut overflow = String_Overflow::Truncate;
+
+        let mut desc = false;
+
         if args.len() > 0 {
 
             match &args[0] {
@@ -214,6 +243,35 @@ impl<'value> NP_Value<'value> for String {
                                 case_byte = String_Case::Uppercase; 
                                 set += 1;
                             },
+                            "case" => {
+                                match value {
+                                    JS_AST::string { addr } => {
+                                        match idl.get_str(addr).trim() {
+                                            "lower" => { case_byte = String_Case::Lowercase; },
+                                            "upper" => { case_byte = String_Case::Uppercase; },
+                                            _ => { return Err(NP_Error::new("case property must be 'lower' or 'upper'!")) }
+                                        }
+                                        set += 1;
+                                    },
+                                    _ => { return Err(NP_Error::new("case property must be 'lower' or 'upper'!")) }
+                                }
+                            },
+                            "overflow" => {
+                                match value {
+                                    JS_AST::string { addr } => {
+                                        overflow = parse_overflow(idl.get_str(addr).trim())?;
+                                    },
+                                    _ => { return Err(NP_Error::new("overflow property must be 'error' or 'truncate'!")) }
+                                }
+                            },
+                            "sort" => {
+                                match value {
+                                    JS_AST::string { addr } => {
+                                        desc = NP_Schema::_parse_sort(idl.get_str(addr).trim())?;
+                                    },
+                                    _ => { return Err(NP_Error::new("sort property must be 'asc' or 'desc'!")) }
+                                }
+                            },
                             "size" => {
                                 match value {
                                     JS_AST::number { addr } => {
@@ -245,11 +303,19 @@ impl<'value> NP_Value<'value> for String {
         }
         
 
-        if set == 2 {
+        if set >= 2 {
             return Err(NP_Error::new("Only one of uppercase or lowercase can be set!"));
         }
 
-        schema_data.push(case_byte as u8);
+        if overflow == String_Overflow::Error && has_fixed_size == false {
+            return Err(NP_Error::new("overflow property can only be used with fixed size strings!"));
+        }
+
+        if desc && has_fixed_size == false {
+            return Err(NP_Error::new("sort property can only be used with fixed size strings!"));
+        }
+
+        schema_data.push(case_byte as u8 | overflow.to_flag() | if desc { DESC_FLAG } else { 0 });
 
         if has_fixed_size {
             schema_data.extend_from_slice(&size.to_be_bytes());
@@ -279,7 +345,7 @@ impl<'value> NP_Value<'value> for String {
             },
             i: NP_TypeKeys::UTF8String,
             sortable: has_fixed_size,
-            data:  Arc::new(Box::into_raw(Box::new(NP_String_Data { size: size, default, case: case_byte, empty })) as *const u8)
+            data:  Arc::new(Box::into_raw(Box::new(NP_String_Data { size: size, default, case: case_byte, overflow, empty, desc })) as *const u8)
         });
 
         return Ok((has_fixed_size, schema_data, schema));
@@ -300,6 +366,8 @@ impl<'value> NP_Value<'value> for String {
 
         // case byte
         let case_byte = String_Case::from(bytes[address + 1]);
+        let overflow = String_Overflow::from(bytes[address + 1]);
+        let desc = bytes[address + 1] & DESC_FLAG != 0;
 
         // fixed size
         let fixed_size = u32::from_be_bytes([bytes[address + 2], bytes[address + 3],bytes[address + 4], bytes[address + 5]]);
@@ -322,7 +390,7 @@ impl<'value> NP_Value<'value> for String {
                 },
                 i: NP_TypeKeys::UTF8String,
                 sortable: fixed_size > 0,
-                data:  Arc::new(Box::into_raw(Box::new(NP_String_Data { size: fixed_size, default: None, case: case_byte, empty })) as *const u8)
+                data:  Arc::new(Box::into_raw(Box::new(NP_String_Data { size: fixed_size, default: None, case: case_byte, overflow, empty, desc })) as *const u8)
             })
         } else {
             let default_bytes = str::from_utf8(&bytes[(address + 8)..(address + 8 + (default_size - 1))]).unwrap_or_default();
@@ -335,7 +403,7 @@ impl<'value> NP_Value<'value> for String {
                 },
                 i: NP_TypeKeys::UTF8String,
                 sortable: fixed_size > 0,
-                data:  Arc::new(Box::into_raw(Box::new(NP_String_Data { size: fixed_size, default: Some(default_bytes.to_string()), case: case_byte, empty })) as *const u8)
+                data:  Arc::new(Box::into_raw(Box::new(NP_String_Data { size: fixed_size, default: Some(default_bytes.to_string()), case: case_byte, overflow, empty, desc })) as *const u8)
             })
         }
 
@@ -347,6 +415,25 @@ impl<'value> NP_Value<'value> for String {
     }
 
     fn into_value(cursor: &NP_Cursor, memory: &'value NP_Memory) -> Result<Option<Self>, NP_Error> where Self: Sized {
+
+        let data = unsafe { &*(*memory.get_schema(cursor.schema_addr).data as *const NP_String_Data) };
+
+        // descending strings are stored inverted, they have to be copied out
+        if data.desc {
+            let value_addr = cursor.get_value(memory).get_addr_value() as usize;
+
+            if value_addr == 0 {
+                return Ok(None);
+            }
+
+            let bytes: Vec<u8> = memory.read_bytes()[value_addr..(value_addr + (data.size as usize))].iter().map(|byte| !*byte).collect();
+
+            return match String::from_utf8(bytes) {
+                Ok(x) => Ok(Some(x)),
+                Err(_e) => Err(NP_Error::new("Descending string is not valid utf-8!"))
+            }
+        }
+
         match NP_String::into_value(cursor, memory)? {
             Some(x) => Ok(Some(String::from(x))),
             None => Ok(None)
@@ -354,7 +441,7 @@ impl<'value> NP_Value<'value> for String {
     }
 
 
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
         let value_addr = c_value().get_addr_value() as usize;
@@ -398,11 +485,36 @@ impl<'value> NP_Value<'value> for String {
             _ => {}
         }
 
-        if set == 2 {
+        match &json_schema["case"] {
+            NP_JSON::String(x) => {
+                match x.as_str() {
+                    "lower" => { case_byte = String_Case::Lowercase; },
+                    "upper" => { case_byte = String_Case::Uppercase; },
+                    _ => { return Err(NP_Error::new("case property must be 'lower' or 'upper'!")) }
+                }
+                set += 1;
+            },
+            NP_JSON::Null => {},
+            _ => { return Err(NP_Error::new("case property must be 'lower' or 'upper'!")) }
+        }
+
+        if set >= 2 {
             return Err(NP_Error::new("Only one of uppercase and lowercase can be set!"));
         }
 
-        schema_data.push(case_byte as u8);
+        let overflow = match &json_schema["overflow"] {
+            NP_JSON::String(x) => parse_overflow(x.as_str())?,
+            NP_JSON::Null => String_Overflow::Truncate,
+            _ => { return Err(NP_Error::new("overflow property must be 'error' or 'truncate'!")) }
+        };
+
+        let desc = match &json_schema["sort"] {
+            NP_JSON::String(x) => NP_Schema::_parse_sort(x.as_str())?,
+            NP_JSON::Null => false,
+            _ => { return Err(NP_Error::new("sort property must be 'asc' or 'desc'!")) }
+        };
+
+        schema_data.push(case_byte as u8 | overflow.to_flag() | if desc { DESC_FLAG } else { 0 });
 
         let mut has_fixed_size = false;
 
@@ -444,6 +556,14 @@ impl<'value> NP_Value<'value> for String {
             }
         };
 
+        if overflow == String_Overflow::Error && has_fixed_size == false {
+            return Err(NP_Error::new("overflow property can only be used with fixed size strings!"));
+        }
+
+        if desc && has_fixed_size == false {
+            return Err(NP_Error::new("sort property can only be used with fixed size strings!"));
+        }
+
         let default = match &json_schema["default"] {
             NP_JSON::String(bytes) => {
                 let str_bytes = bytes.clone().into_bytes();
@@ -476,7 +596,7 @@ impl<'value> NP_Value<'value> for String {
             },
             i: NP_TypeKeys::UTF8String,
             sortable: has_fixed_size,
-            data:  Arc::new(Box::into_raw(Box::new(NP_String_Data { size, default, case: case_byte, empty })) as *const u8)
+            data:  Arc::new(Box::into_raw(Box::new(NP_String_Data { size, default, case: case_byte, overflow, empty, desc })) as *const u8)
         });
 
         return Ok((has_fixed_size, schema_data, schema));
@@ -563,10 +683,26 @@ impl<'value> NP_Value<'value> for NP_String<'value> {
             _ => {}
         }
     
-        let str_size = bytes.len() as usize;
+        let mut str_size = bytes.len() as usize;
 
         if size > 0 {
             // fixed size bytes
+
+            if str_size > size as usize {
+                match data.overflow {
+                    String_Overflow::Error => {
+                        return Err(NP_Error::new("String is larger than the fixed size!"));
+                    },
+                    String_Overflow::Truncate => {
+                        // don't split a multi byte character
+                        str_size = size as usize;
+                        while str_size > 0 && (bytes[str_size] & 0b1100_0000) == 0b1000_0000 {
+                            str_size -= 1;
+                        }
+                        bytes = &bytes[..str_size];
+                    }
+                }
+            }
     
             if c_value().get_addr_value() == 0 {
                 // malloc new bytes
@@ -585,6 +721,11 @@ impl<'value> NP_Value<'value> for NP_String<'value> {
                     // rest is white space
                     write_bytes[(addr + x)] = 32;
                 }
+
+                // invert for descending sort
+                if data.desc {
+                    write_bytes[addr + x] = !write_bytes[addr + x];
+                }
             }
     
             return Ok(cursor);
@@ -636,9 +777,10 @@ impl<'value> NP_Value<'value> for NP_String<'value> {
                 memory.malloc_borrow(&size_bytes)?
             };
     
-            cursor.get_value_mut(memory).set_addr_value(new_addr as u32);
-    
             memory.malloc_borrow(bytes)?;
+
+            // only point to the new value once all of it is in the buffer
+            cursor.get_value_mut(memory).set_addr_value(new_addr as u32);
     
             return Ok(cursor);
         }
@@ -675,6 +817,10 @@ impl<'value> NP_Value<'value> for NP_String<'value> {
 
         let data = unsafe { &*(*memory.get_schema(cursor.schema_addr).data as *const NP_String_Data) };
 
+        if data.desc {
+            return Err(NP_Error::new("Descending strings are stored inverted and can't be borrowed, get a String instead!"));
+        }
+
         if data.size > 0 {
             // fixed size
 
@@ -701,8 +847,8 @@ impl<'value> NP_Value<'value> for NP_String<'value> {
         String::to_json(depth, cursor, memory)
     }
 
-    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
-        String::get_size(depth, cursor, memory)
+    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
+        String::get_size(depth, cursor, memory, sized_keys)
     }
 
     fn from_json_to_schema(schema: Vec<NP_Parsed_Schema>, _json_schema: &Box<NP_JSON>) -> Result<(bool, Vec<u8>, Vec<NP_Parsed_Schema>), NP_Error> {
@@ -807,6 +953,49 @@ fn fixed_size_works() -> Result<(), NP_Error> {
     Ok(())
 }
 
+#[test]
+fn fixed_size_overflow_works() -> Result<(), NP_Error> {
+    for schema in [r#"string({size: 6, overflow: "error"})"#, r#"string({size: 6})"#].iter() {
+        let factory = crate::NP_Factory::new(*schema)?;
+        assert_eq!(*schema, factory.schema.to_idl()?);
+        let factory2 = crate::NP_Factory::new_bytes(factory.export_schema_bytes())?;
+        assert_eq!(*schema, factory2.schema.to_idl()?);
+    }
+
+    let schema = "{\"type\":\"string\",\"size\":6,\"overflow\":\"error\"}";
+    let factory = crate::NP_Factory::new_json(schema)?;
+    assert_eq!(schema, factory.schema.to_json()?.stringify());
+
+    // overflow requires a fixed size
+    assert!(crate::NP_Factory::new(r#"string({overflow: "error"})"#).is_err());
+    assert!(crate::NP_Factory::new(r#"string({size: 6, overflow: "wrap"})"#).is_err());
+
+    // error mode
+    let factory = crate::NP_Factory::new(r#"string({size: 6, overflow: "error"})"#)?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&[], "abcdef")?;
+    assert_eq!(buffer.get::<&str>(&[])?, Some("abcdef"));
+    buffer.set(&[], "hello")?;
+    assert_eq!(buffer.get::<&str>(&[])?, Some("hello "));
+    assert!(buffer.set(&[], "hello world").is_err());
+    assert_eq!(buffer.get::<&str>(&[])?, Some("hello "));
+
+    // truncate mode
+    let factory = crate::NP_Factory::new(r#"string({size: 6, overflow: "truncate"})"#)?;
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&[], "abcdef")?;
+    assert_eq!(buffer.get::<&str>(&[])?, Some("abcdef"));
+    buffer.set(&[], "hello")?;
+    assert_eq!(buffer.get::<&str>(&[])?, Some("hello "));
+    buffer.set(&[], "hello world")?;
+    assert_eq!(buffer.get::<&str>(&[])?, Some("hello "));
+    // multi byte characters aren't split
+    buffer.set(&[], "hell☃")?;
+    assert_eq!(buffer.get::<&str>(&[])?, Some("hell  "));
+
+    Ok(())
+}
+
 #[test]
 fn set_clear_value_and_compaction_works() -> Result<(), NP_Error> {
     let schema = "{\"type\":\"string\"}";
@@ -841,6 +1030,21 @@ fn uppercase_lowercase_works() -> Result<(), NP_Error> {
     buffer.set(&[], "hello")?;
     assert_eq!(buffer.get::<&str>(&[])?.unwrap(),"HELLO");
 
+    let schema = "{\"type\":\"string\",\"case\": \"lower\"}";
+    let factory = crate::NP_Factory::new_json(schema)?;
+    assert_eq!("{\"type\":\"string\",\"lowercase\":true}", factory.schema.to_json()?.stringify());
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&[], "HELLO")?;
+    assert_eq!(buffer.get::<&str>(&[])?.unwrap(),"hello");
+
+    let factory = crate::NP_Factory::new(r#"string({case: "upper"})"#)?;
+    assert_eq!("string({uppercase: true})", factory.schema.to_idl()?);
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&[], "hello")?;
+    assert_eq!(buffer.get::<&str>(&[])?.unwrap(),"HELLO");
+
+    assert!(crate::NP_Factory::new(r#"string({case: "title"})"#).is_err());
+    assert!(crate::NP_Factory::new(r#"string({case: "upper", lowercase: true})"#).is_err());
 
     Ok(())
 }
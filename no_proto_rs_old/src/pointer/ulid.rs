@@ -21,6 +21,7 @@
 
 use crate::{idl::{JS_AST, JS_Schema}, memory::NP_Memory, schema::{NP_Parsed_Schema, NP_Value_Kind, NULL}, utils::from_base32};
 use alloc::{sync::Arc, vec::Vec};
+use alloc::collections::BTreeSet;
 use crate::utils::to_base32;
 use crate::json_flex::{JSMAP, NP_JSON};
 use crate::schema::{NP_TypeKeys};
@@ -248,7 +249,7 @@ impl<'value> NP_Value<'value> for NP_ULID {
         }
     }
 
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
 
@@ -374,8 +375,8 @@ impl<'value> NP_Value<'value> for &NP_ULID {
         NP_ULID::to_json(depth, cursor, memory)
     }
 
-    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
-        NP_ULID::get_size(depth, cursor, memory)
+    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
+        NP_ULID::get_size(depth, cursor, memory, sized_keys)
     }
 
     fn from_json_to_schema(schema: Vec<NP_Parsed_Schema>, _json_schema: &Box<NP_JSON>) -> Result<(bool, Vec<u8>, Vec<NP_Parsed_Schema>), NP_Error> {
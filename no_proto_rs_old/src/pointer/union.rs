@@ -5,6 +5,7 @@ use crate::{idl::{JS_AST, JS_Schema}, schema::{NP_Schema_Addr, NP_Value_Kind}};
 use crate::NP_Schema;
 use crate::{memory::NP_Memory, schema::{NP_Parsed_Schema}};
 use alloc::vec::Vec;
+use alloc::collections::BTreeSet;
 
 use crate::json_flex::{JSMAP, NP_JSON};
 use crate::schema::{NP_TypeKeys};
@@ -307,13 +308,13 @@ impl<'value> NP_Value<'value> for NP_Union {
         todo!()
     }
 
-    fn get_size(_depth:usize, _cursor: &'value NP_Cursor, _memory: &'value NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, _cursor: &'value NP_Cursor, _memory: &'value NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
         // match memory.get_schema(cursor.schema_addr) {
         //     NP_TypeKeys::Portal { schema, parent_schema, .. } => {
         //         let mut next = cursor.clone();
         //         next.schema_addr = *schema;
         //         next.parent_schema_addr = *parent_schema;
-        //         NP_Cursor::calc_size(depth + 1, &next, memory)
+        //         NP_Cursor::calc_size(depth + 1, &next, memory, sized_keys)
         //     },
         //     _ => Err(NP_Error::Unreachable)
         // }
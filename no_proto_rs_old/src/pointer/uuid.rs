@@ -23,6 +23,7 @@
 //! 
 
 use alloc::sync::Arc;
+use alloc::collections::BTreeSet;
 use crate::{JS_Schema, idl::JS_AST, schema::{NP_Value_Kind, NULL}};
 use alloc::prelude::v1::Box;
 use crate::pointer::NP_Scalar;
@@ -228,7 +229,7 @@ impl<'value> NP_Value<'value> for NP_UUID {
         }
     }
 
-    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
+    fn get_size(_depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, _sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
 
         let c_value = || { cursor.get_value(memory) };
 
@@ -352,8 +353,8 @@ impl<'value> NP_Value<'value> for &NP_UUID {
         NP_UUID::to_json(depth, cursor, memory)
     }
 
-    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory) -> Result<usize, NP_Error> {
-        NP_UUID::get_size(depth, cursor, memory)
+    fn get_size(depth:usize, cursor: &NP_Cursor, memory: &NP_Memory, sized_keys: &mut BTreeSet<usize>) -> Result<usize, NP_Error> {
+        NP_UUID::get_size(depth, cursor, memory, sized_keys)
     }
 
     fn from_json_to_schema(schema: Vec<NP_Parsed_Schema>, _json_schema: &Box<NP_JSON>) -> Result<(bool, Vec<u8>, Vec<NP_Parsed_Schema>), NP_Error> {
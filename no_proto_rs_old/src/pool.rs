@@ -0,0 +1,176 @@
+//! Reuse buffers across requests
+//!
+//! An [`NP_BufferPool`](struct.NP_BufferPool.html) keeps buffers from a factory around after they're used, so a server encoding many records doesn't allocate a new buffer for each one.  Create one with [`NP_Factory::buffer_pool`](../struct.NP_Factory.html#method.buffer_pool).
+//!
+//! The pool is `Send + Sync`, it can be shared between threads with a reference or an `Arc`.
+//!
+//! Requires the `std` feature.
+//!
+//! ```
+//! use no_proto::error::NP_Error;
+//! use no_proto::NP_Factory;
+//!
+//! let factory: NP_Factory = NP_Factory::new("struct({fields: {name: string()}})")?;
+//! let pool = factory.buffer_pool(Some(256));
+//!
+//! for name in ["Jeb", "Bill", "Bob"].iter() {
+//!     let mut buffer = pool.acquire();
+//!     buffer.set(&["name"], *name)?;
+//!     assert_eq!(buffer.get::<&str>(&["name"])?, Some(*name));
+//!     // buffer goes back to the pool here
+//! }
+//!
+//! // the same buffer was used every time
+//! assert_eq!(pool.idle(), 1);
+//!
+//! # Ok::<(), NP_Error>(())
+//! ```
+//!
+
+use crate::NP_Factory;
+use crate::buffer::NP_Buffer;
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+/// A pool of buffers from one factory.
+///
+/// Buffers handed out by `acquire` are always empty, like a new buffer from the factory.
+///
+#[derive(Debug)]
+pub struct NP_BufferPool<'pool> {
+    factory: &'pool NP_Factory,
+    capacity: Option<usize>,
+    buffers: Mutex<Vec<NP_Buffer>>
+}
+
+impl<'pool> NP_BufferPool<'pool> {
+
+    #[doc(hidden)]
+    pub fn new(factory: &'pool NP_Factory, capacity: Option<usize>) -> Self {
+        Self { factory, capacity, buffers: Mutex::new(Vec::new()) }
+    }
+
+    /// Take an empty buffer from the pool, a new buffer is made if the pool doesn't have one.
+    ///
+    /// The buffer goes back to the pool when the returned `NP_PooledBuffer` is dropped.
+    ///
+    pub fn acquire(&self) -> NP_PooledBuffer<'_, 'pool> {
+        let existing = self.buffers.lock().unwrap_or_else(|e| e.into_inner()).pop();
+
+        let buffer = match existing {
+            Some(mut buffer) => {
+                // buffers in the pool own their memory, reset can't fail
+                match buffer.reset() {
+                    Ok(()) => buffer,
+                    Err(_e) => self.factory.new_buffer(self.capacity)
+                }
+            },
+            None => self.factory.new_buffer(self.capacity)
+        };
+
+        NP_PooledBuffer { pool: self, buffer: Some(buffer) }
+    }
+
+    /// How many buffers are waiting in the pool
+    pub fn idle(&self) -> usize {
+        self.buffers.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Drop all the buffers waiting in the pool
+    pub fn clear(&self) {
+        self.buffers.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+/// A buffer borrowed from an `NP_BufferPool`, derefs to `NP_Buffer`.
+///
+/// Dropping it puts the buffer back into the pool.  Use `detach` to keep the buffer instead.
+///
+#[derive(Debug)]
+pub struct NP_PooledBuffer<'borrow, 'pool> {
+    pool: &'borrow NP_BufferPool<'pool>,
+    buffer: Option<NP_Buffer>
+}
+
+impl<'borrow, 'pool> NP_PooledBuffer<'borrow, 'pool> {
+    /// Take the buffer out of the pool for good, it won't be returned when dropped
+    pub fn detach(mut self) -> NP_Buffer {
+        match self.buffer.take() {
+            Some(buffer) => buffer,
+            None => unreachable!()
+        }
+    }
+}
+
+impl<'borrow, 'pool> Deref for NP_PooledBuffer<'borrow, 'pool> {
+    type Target = NP_Buffer;
+
+    fn deref(&self) -> &NP_Buffer {
+        match &self.buffer {
+            Some(buffer) => buffer,
+            None => unreachable!()
+        }
+    }
+}
+
+impl<'borrow, 'pool> DerefMut for NP_PooledBuffer<'borrow, 'pool> {
+    fn deref_mut(&mut self) -> &mut NP_Buffer {
+        match &mut self.buffer {
+            Some(buffer) => buffer,
+            None => unreachable!()
+        }
+    }
+}
+
+impl<'borrow, 'pool> Drop for NP_PooledBuffer<'borrow, 'pool> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.pool.buffers.lock().unwrap_or_else(|e| e.into_inner()).push(buffer);
+        }
+    }
+}
+
+#[test]
+fn buffer_pool_works() -> Result<(), crate::error::NP_Error> {
+    let factory = NP_Factory::new("list({of: string()})")?;
+    let pool = factory.buffer_pool(None);
+
+    fn is_send_sync<T: Send + Sync>(_t: &T) { }
+    is_send_sync(&pool);
+
+    {
+        let mut a = pool.acquire();
+        let mut b = pool.acquire();
+        a.set(&["0"], "a")?;
+        b.set(&["3"], "b")?;
+        assert_eq!(pool.idle(), 0);
+    }
+    assert_eq!(pool.idle(), 2);
+
+    // returned buffers come back empty
+    let c = pool.acquire();
+    assert_eq!(c.get_length(&[])?, None);
+    assert_eq!(c.data_length(), 6);
+
+    let kept = c.detach();
+    assert_eq!(kept.data_length(), 6);
+    assert_eq!(pool.idle(), 1);
+
+    std::thread::scope(|s| {
+        for x in 0..4 {
+            let pool = &pool;
+            s.spawn(move || {
+                let mut buffer = pool.acquire();
+                buffer.set(&["0"], format!("thread {}", x).as_str()).unwrap();
+                assert_eq!(buffer.get::<&str>(&["0"]).unwrap(), Some(format!("thread {}", x).as_str()));
+            });
+        }
+    });
+    assert!(pool.idle() >= 1);
+
+    pool.clear();
+    assert_eq!(pool.idle(), 0);
+
+    Ok(())
+}
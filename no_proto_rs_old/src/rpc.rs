@@ -579,7 +579,7 @@ impl<'fact> NP_RPC_Factory<'fact> {
                 for jspec in json_spec.iter() {
                     match &jspec["msg"] { // msg type
                         NP_JSON::String(msg_name) => {
-                            let schema = NP_Schema::from_json(Vec::new(), &Box::new(jspec.clone()))?;
+                            let schema = NP_Schema::from_json(Vec::new(), &Box::new(NP_Schema::_canonical_json(jspec)))?;
                             let factory = NP_Factory {
                                 schema: NP_Schema { is_sortable: schema.0, parsed: schema.2 },
                                 schema_bytes: schema.1
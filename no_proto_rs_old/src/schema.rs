@@ -330,6 +330,22 @@
 //! map({value: string()})
 //! ```
 //! 
+//! **Interned Keys**<br/>
+//! If the same keys show up in many maps, like a list of maps, set the `intern_keys` property to `true`.  New map items will point to key bytes already in the buffer instead of storing another copy of the key.  Compaction keeps the keys shared.
+//! 
+//! ```text
+//! // JSON
+//! {
+//!     "type": "map",
+//!     "intern_keys": true,
+//!     "value": {
+//!         "type": "string"
+//!     }
+//! }
+//! // ES6
+//! map({value: string(), intern_keys: true})
+//! ```
+//! 
 //! 
 //! ## tuple
 //! A tuple is a fixed size list of items.  Each item has it's own type and index.  Tuples support up to 255 items.
@@ -345,6 +361,8 @@
 //! 
 //! When `sorted` is true the order of values is gauranteed to be constant in every buffer and all buffers will be identical in size.
 //! 
+//! Values sort ascending by default.  Integers and fixed size strings accept a `sort` property, set it to `"desc"` to store the value with its bytes inverted so that value sorts descending.  Values are inverted back when they're read, but descending strings have to be read as `String` since they can't be borrowed from the buffer.
+//! 
 //! ```text
 //! // JSON
 //! {
@@ -376,6 +394,13 @@
 //!     u8(), 
 //!     i64()
 //! ]})
+//! 
+//! // sort by the string, then the largest i64 first
+//! // ES6
+//! tuple({sorted: true, values: [
+//!     string({size: 25}), 
+//!     i64({sort: "desc"})
+//! ]})
 //! ```
 //!
 //! 
@@ -407,6 +432,16 @@
 //! // ES6
 //! string({size: 20})
 //! 
+//! // fixed size, return an error instead of truncating values that are too long
+//! // JSON
+//! {
+//!     "type": "string",
+//!     "size": 20,
+//!     "overflow": "error"
+//! }
+//! // ES6
+//! string({size: 20, overflow: "error"})
+//! 
 //! // with default value
 //! // JSON
 //! {
@@ -416,6 +451,16 @@
 //! 
 //! // ES6
 //! string({default: "Default string value"})
+//! 
+//! // lowercase all values before they're saved, use "upper" to uppercase them instead
+//! // JSON
+//! {
+//!     "type": "string",
+//!     "case": "lower"
+//! }
+//! 
+//! // ES6
+//! string({case: "lower"})
 //! ```
 //! 
 //! More Details:
@@ -642,6 +687,19 @@
 //! decimal({exp: 3, default: 20.293})
 //! ```
 //! 
+//! Decimal values are exported to JSON as an object with `value` and `parts` properties.  Set the optional `json` property to `"number"` to export plain JSON numbers instead.
+//! 
+//! ```text
+//! // JSON
+//! {
+//!     "type": "decimal",
+//!     "exp": 3,
+//!     "json": "number"
+//! }
+//! // ES6
+//! decimal({exp: 3, json: "number"})
+//! ```
+//! 
 //! More Details:
 //! - [Using NP_Dec data type](../pointer/dec/index.html)
 //! 
@@ -815,21 +873,23 @@ use crate::{hashmap::NP_HashMap, idl::{JS_AST, JS_Schema}};
 use crate::{np_path, pointer::{NP_Cursor}};
 use alloc::{string::String, sync::Arc};
 use core::{fmt::Debug};
-use crate::{buffer::DEFAULT_ROOT_PTR_ADDR, json_flex::NP_JSON, memory::NP_Memory, pointer::{portal::{NP_Portal}, ulid::NP_ULID, uuid::NP_UUID}};
+use crate::{buffer::{DEFAULT_ROOT_PTR_ADDR, DEFAULT_MAX_DEPTH, LIST_MAX_SIZE, VTABLE_BYTES, VTABLE_SIZE}, json_flex::{JSMAP, NP_JSON}, memory::NP_Memory, pointer::{portal::{NP_Portal}, ulid::NP_ULID, uuid::NP_UUID}};
 use crate::pointer::any::NP_Any;
 use crate::pointer::date::NP_Date;
 use crate::pointer::geo::NP_Geo;
 use crate::pointer::dec::NP_Dec;
 use crate::collection::tuple::NP_Tuple;
 use crate::pointer::bytes::NP_Bytes;
-use crate::collection::{list::NP_List, struc::NP_Struct, map::NP_Map};
+use crate::collection::{list::NP_List, struc::NP_Struct, map::{NP_Map, INTERN_KEYS_FLAG}};
 use crate::pointer::{option::NP_Enum, NP_Value};
 use crate::error::NP_Error;
 use alloc::vec::Vec;
 use alloc::boxed::Box;
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeSet;
 
 /// Simple enum to store the schema types
-#[derive(Debug, Clone, Eq, PartialEq, Copy)]
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Copy)]
 #[repr(u8)]
 #[allow(missing_docs)]
 pub enum NP_TypeKeys {
@@ -919,11 +979,44 @@ pub enum String_Case {
 
 impl From<u8> for String_Case {
     fn from(value: u8) -> Self {
+        // high bits hold the overflow mode and sort order
+        let value = value & !(String_Overflow::FLAG | DESC_FLAG);
         if value > 2 { return String_Case::None; }
         unsafe { core::mem::transmute(value) }
     }
 }
 
+/// Set in the flag byte of number and string schemas when values are stored inverted, so byte-wise sorting is descending
+#[doc(hidden)]
+pub const DESC_FLAG: u8 = 0b0100_0000;
+
+#[derive(Debug, Clone, Eq, PartialEq, Copy)]
+#[doc(hidden)]
+#[allow(missing_docs)]
+pub enum String_Overflow {
+    Truncate,
+    Error
+}
+
+#[allow(missing_docs)]
+impl String_Overflow {
+    /// Stored in the high bit of the string case byte
+    pub const FLAG: u8 = 0b1000_0000;
+
+    pub fn to_flag(&self) -> u8 {
+        match self {
+            String_Overflow::Truncate => 0,
+            String_Overflow::Error => String_Overflow::FLAG
+        }
+    }
+}
+
+impl From<u8> for String_Overflow {
+    fn from(value: u8) -> Self {
+        if value & String_Overflow::FLAG == 0 { String_Overflow::Truncate } else { String_Overflow::Error }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Copy)]
 #[doc(hidden)]
 #[allow(missing_docs)]
@@ -1002,70 +1095,90 @@ impl Default for NP_Parsed_Schema {
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_u8_Data {
-    pub default: Option<u8>
+    pub default: Option<u8>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_u16_Data {
-    pub default: Option<u16>
+    pub default: Option<u16>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_u32_Data {
-    pub default: Option<u32>
+    pub default: Option<u32>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_u64_Data {
-    pub default: Option<u64>
+    pub default: Option<u64>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_i8_Data {
-    pub default: Option<i8>
+    pub default: Option<i8>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_i16_Data {
-    pub default: Option<i16>
+    pub default: Option<i16>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_i32_Data {
-    pub default: Option<i32>
+    pub default: Option<i32>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_i64_Data {
-    pub default: Option<i64>
+    pub default: Option<i64>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_f32_Data {
-    pub default: Option<f32>
+    pub default: Option<f32>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_f64_Data {
-    pub default: Option<f64>
+    pub default: Option<f64>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
@@ -1082,7 +1195,10 @@ pub struct NP_String_Data {
     pub default: Option<String>,
     pub size: u32,
     pub case: String_Case,
-    pub empty: Vec<u8>
+    pub overflow: String_Overflow,
+    pub empty: Vec<u8>,
+    /// stored inverted for descending byte-wise sorting
+    pub desc: bool
 }
 
 #[allow(missing_docs)]
@@ -1106,7 +1222,8 @@ pub struct NP_Geo_Data {
 #[derive(Debug, Clone)]
 pub struct NP_Dec_Data {
     pub default: Option<NP_Dec>,
-    pub exp: u8
+    pub exp: u8,
+    pub json_number: bool
 }
 
 #[allow(missing_docs)]
@@ -1129,7 +1246,9 @@ pub struct NP_Struct_Data {
 #[doc(hidden)]
 #[derive(Debug, Clone)]
 pub struct NP_Map_List_Data {
-    pub child: usize
+    pub child: usize,
+    /// maps only, reuse key bytes already in the buffer
+    pub intern_keys: bool
 }
 
 #[allow(missing_docs)]
@@ -1283,9 +1402,632 @@ pub struct NP_Schema {
     pub parsed: Vec<NP_Parsed_Schema>
 }
 
+/// Result of comparing two schemas with `NP_Factory::is_compatible_with`.
+/// 
+/// Paths are the dot separated keys to the offending type, the root of the schema is an empty string.
+/// 
+#[derive(Debug, Clone, PartialEq)]
+pub enum NP_Schema_Compat {
+    /// Buffers made with the old schema can be safely read with the new schema
+    Compatible,
+    /// The type at this path is different
+    TypeChanged {
+        /// Path to the changed type
+        path: String,
+        /// Type in the old schema
+        from: NP_TypeKeys,
+        /// Type in the new schema
+        to: NP_TypeKeys
+    },
+    /// A property that changes how the value is stored is different, like `size` or `exp`
+    PropertyChanged {
+        /// Path to the changed type
+        path: String,
+        /// The property that changed
+        property: String
+    },
+    /// The number of values in a tuple is different
+    TupleLengthChanged {
+        /// Path to the tuple
+        path: String,
+        /// Length in the old schema
+        from: usize,
+        /// Length in the new schema
+        to: usize
+    }
+}
+
+/// Description of a single type in a schema, returned by `NP_Factory::inspect`.
+/// 
+#[derive(Debug, Clone, PartialEq)]
+pub struct NP_Schema_Info {
+    /// The data type
+    pub kind: NP_TypeKeys,
+    /// If the type is a struct, tuple, list or map
+    pub is_collection: bool,
+    /// Field names of a struct in schema order, empty for every other type
+    pub fields: Vec<String>,
+    /// Type of list items or map values
+    pub of: Option<NP_TypeKeys>,
+    /// Types of the values in a tuple, empty for every other type
+    pub values: Vec<NP_TypeKeys>,
+    /// Smallest value the type can store, only for integer types
+    pub min: Option<i128>,
+    /// Largest value the type can store, only for integer types
+    pub max: Option<i128>
+}
 
 impl NP_Schema {
 
+    /// Estimate how many bytes a value of the type at `address` uses when every value is set.
+    /// 
+    /// Lists, maps, portals and variable length strings/bytes can't be known ahead of time so they add a small fixed amount.
+    #[doc(hidden)]
+    pub fn _estimate_size(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize, depth: usize) -> usize {
+
+        if depth > DEFAULT_MAX_DEPTH {
+            return 0
+        }
+
+        let schema = &parsed_schema[address];
+
+        match schema.i {
+            NP_TypeKeys::Struct => {
+                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                let vtables = (data.fields.len() + VTABLE_SIZE - 1) / VTABLE_SIZE;
+                data.fields.iter().fold(vtables * VTABLE_BYTES, |size, field| {
+                    size + NP_Schema::_estimate_size(parsed_schema, field.schema, depth + 1)
+                })
+            },
+            NP_TypeKeys::Tuple => {
+                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                // fixed size values are stored inside the tuple
+                data.values.iter().fold(data.empty.len(), |size, value| {
+                    if value.fixed {
+                        size
+                    } else {
+                        size + NP_Schema::_estimate_size(parsed_schema, value.schema, depth + 1)
+                    }
+                })
+            },
+            NP_TypeKeys::List | NP_TypeKeys::Map => 64,
+            // portals are often recursive, following them can blow up quickly, count a single vtable instead
+            NP_TypeKeys::Portal => VTABLE_BYTES,
+            NP_TypeKeys::None | NP_TypeKeys::Any => 0,
+            _ => {
+                match schema.val {
+                    NP_Value_Kind::Fixed(size) => size as usize,
+                    // length prefix and a few bytes of data
+                    NP_Value_Kind::Pointer => 20
+                }
+            }
+        }
+    }
+
+    /// Count how many bytes a JSON value takes up once it's set at `address`, not counting the pointer to it.
+    ///
+    /// Mirrors what `set_from_json` stores and what compaction keeps, JSON that doesn't match the schema returns `None` since it's ignored.
+    #[doc(hidden)]
+    pub fn _json_size(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize, json: &NP_JSON, depth: usize) -> Result<Option<usize>, NP_Error> {
+
+        if depth > DEFAULT_MAX_DEPTH { return Err(NP_Error::RecursionLimit) }
+
+        if let NP_JSON::Null = json {
+            return Ok(None)
+        }
+
+        let schema = &parsed_schema[address];
+
+        let size = match (schema.i, json) {
+            (NP_TypeKeys::Struct, NP_JSON::Dictionary(map)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                // any field that isn't null makes the first vtable, even if the value is ignored
+                let mut has_vtable = false;
+                let mut live_vtables = 1usize;
+                let mut acc_size = 0usize;
+                for (index, field) in data.fields.iter().enumerate() {
+                    match map.get(field.col.as_str()) {
+                        Some(NP_JSON::Null) | None => { },
+                        Some(value) => {
+                            has_vtable = true;
+                            if let Some(size) = NP_Schema::_json_size(parsed_schema, field.schema, value, depth + 1)? {
+                                live_vtables = usize::max(live_vtables, (index / VTABLE_SIZE) + 1);
+                                acc_size += size;
+                            }
+                        }
+                    }
+                }
+                if has_vtable { Some(acc_size + (live_vtables * VTABLE_BYTES)) } else { None }
+            },
+            // empty collections are never allocated
+            (NP_TypeKeys::List, NP_JSON::Array(items)) | (NP_TypeKeys::Tuple, NP_JSON::Array(items)) if items.len() == 0 => None,
+            (NP_TypeKeys::Map, NP_JSON::Dictionary(map)) if map.values.len() == 0 => None,
+            (NP_TypeKeys::List, NP_JSON::Array(items)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                // head + tail
+                let mut acc_size = 8usize;
+                for item in items.iter() {
+                    // every item gets a list item pointer, even if it's value is empty
+                    acc_size += 10 + NP_Schema::_json_size(parsed_schema, data.child, item, depth + 1)?.unwrap_or(0);
+                }
+                Some(acc_size)
+            },
+            (NP_TypeKeys::Map, NP_JSON::Dictionary(map)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                let mut acc_size = 0usize;
+                for (key, value) in map.values.iter() {
+                    // map item pointer, key length byte and key, even if the value is empty
+                    acc_size += 12 + 1 + key.len() + NP_Schema::_json_size(parsed_schema, data.child, value, depth + 1)?.unwrap_or(0);
+                }
+                Some(acc_size)
+            },
+            (NP_TypeKeys::Tuple, NP_JSON::Array(items)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                if items.len() > data.values.len() {
+                    return Err(NP_Error::new("Failed to find column value!"))
+                }
+                // every column is in the tuple with its presence byte
+                let mut acc_size = 0usize;
+                for (index, value) in data.values.iter().enumerate() {
+                    acc_size += 1;
+                    if value.fixed {
+                        acc_size += value.size;
+                    } else {
+                        acc_size += 4;
+                        if let Some(item) = items.get(index) {
+                            acc_size += NP_Schema::_json_size(parsed_schema, value.schema, item, depth + 1)?.unwrap_or(0);
+                        }
+                    }
+                }
+                Some(acc_size)
+            },
+            (NP_TypeKeys::Portal, _) => {
+                let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+                return NP_Schema::_json_size(parsed_schema, data.schema, json, depth + 1)
+            },
+            (NP_TypeKeys::UTF8String, NP_JSON::String(value)) => {
+                let data = unsafe { &*(*schema.data as *const NP_String_Data) };
+                if data.size > 0 { Some(data.size as usize) } else { Some(4 + value.len()) }
+            },
+            (NP_TypeKeys::Bytes, NP_JSON::Array(value)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Bytes_Data) };
+                if data.size > 0 { Some(data.size as usize) } else { Some(4 + value.len()) }
+            },
+            (NP_TypeKeys::Int8, NP_JSON::Integer(_)) | (NP_TypeKeys::Int8, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Int16, NP_JSON::Integer(_)) | (NP_TypeKeys::Int16, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Int32, NP_JSON::Integer(_)) | (NP_TypeKeys::Int32, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Int64, NP_JSON::Integer(_)) | (NP_TypeKeys::Int64, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Uint8, NP_JSON::Integer(_)) | (NP_TypeKeys::Uint8, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Uint16, NP_JSON::Integer(_)) | (NP_TypeKeys::Uint16, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Uint32, NP_JSON::Integer(_)) | (NP_TypeKeys::Uint32, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Uint64, NP_JSON::Integer(_)) | (NP_TypeKeys::Uint64, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Float, NP_JSON::Integer(_)) | (NP_TypeKeys::Float, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Double, NP_JSON::Integer(_)) | (NP_TypeKeys::Double, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Decimal, NP_JSON::Integer(_)) | (NP_TypeKeys::Decimal, NP_JSON::Float(_)) | (NP_TypeKeys::Decimal, NP_JSON::Dictionary(_)) | (NP_TypeKeys::Decimal, NP_JSON::String(_)) |
+            (NP_TypeKeys::Boolean, NP_JSON::True) | (NP_TypeKeys::Boolean, NP_JSON::False) |
+            (NP_TypeKeys::Geo, NP_JSON::Dictionary(_)) |
+            (NP_TypeKeys::Uuid, NP_JSON::String(_)) |
+            (NP_TypeKeys::Ulid, NP_JSON::String(_)) |
+            (NP_TypeKeys::Enum, NP_JSON::String(_)) |
+            // dates are set from any JSON value
+            (NP_TypeKeys::Date, _) => {
+                match schema.val {
+                    NP_Value_Kind::Fixed(size) => Some(size as usize),
+                    NP_Value_Kind::Pointer => None
+                }
+            },
+            _ => None
+        };
+
+        Ok(size)
+    }
+
+    fn _check_path(path: &Vec<String>) -> String {
+        if path.len() == 0 {
+            return String::from("root")
+        }
+        let mut result = String::from("`");
+        result.push_str(&path.join("."));
+        result.push_str("`");
+        result
+    }
+
+    /// Check that a JSON value lines up with the schema at `address` before it's set.
+    ///
+    /// `set_from_json` skips anything that doesn't match, this returns an error that names the path instead.  `null` is accepted anywhere.
+    #[doc(hidden)]
+    pub fn _check_json(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize, json: &NP_JSON, path: &mut Vec<String>, depth: usize) -> Result<(), NP_Error> {
+
+        if depth > DEFAULT_MAX_DEPTH { return Err(NP_Error::RecursionLimit) }
+
+        let schema = &parsed_schema[address];
+
+        let matches = match (schema.i, json) {
+            (_, NP_JSON::Null) => true,
+            (NP_TypeKeys::Any, _) | (NP_TypeKeys::None, _) => true,
+            (NP_TypeKeys::Portal, _) => {
+                let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+                return NP_Schema::_check_json(parsed_schema, data.schema, json, path, depth + 1)
+            },
+            (NP_TypeKeys::Struct, NP_JSON::Dictionary(map)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                for (key, value) in map.values.iter() {
+                    match data.fields.iter().find(|field| &field.col == key) {
+                        Some(field) => {
+                            path.push(key.clone());
+                            NP_Schema::_check_json(parsed_schema, field.schema, value, path, depth + 1)?;
+                            path.pop();
+                        },
+                        None => {
+                            return Err(NP_Error::new(format!("TypeError: Unknown field `{}` at {}", key, NP_Schema::_check_path(path))))
+                        }
+                    }
+                }
+                true
+            },
+            (NP_TypeKeys::Map, NP_JSON::Dictionary(map)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                for (key, value) in map.values.iter() {
+                    path.push(key.clone());
+                    NP_Schema::_check_json(parsed_schema, data.child, value, path, depth + 1)?;
+                    path.pop();
+                }
+                true
+            },
+            (NP_TypeKeys::List, NP_JSON::Array(items)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                if items.len() > LIST_MAX_SIZE {
+                    return Err(NP_Error::new(format!("TypeError: List at {} is longer than 2^16!", NP_Schema::_check_path(path))))
+                }
+                for (index, item) in items.iter().enumerate() {
+                    path.push(format!("{}", index));
+                    NP_Schema::_check_json(parsed_schema, data.child, item, path, depth + 1)?;
+                    path.pop();
+                }
+                true
+            },
+            (NP_TypeKeys::Tuple, NP_JSON::Array(items)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                if items.len() > data.values.len() {
+                    return Err(NP_Error::new(format!("TypeError: Tuple at {} has {} values, expected at most {}", NP_Schema::_check_path(path), items.len(), data.values.len())))
+                }
+                for (index, (item, value)) in items.iter().zip(data.values.iter()).enumerate() {
+                    path.push(format!("{}", index));
+                    NP_Schema::_check_json(parsed_schema, value.schema, item, path, depth + 1)?;
+                    path.pop();
+                }
+                true
+            },
+            (NP_TypeKeys::Enum, NP_JSON::String(value)) => {
+                let data = unsafe { &*(*schema.data as *const NP_Enum_Data) };
+                if data.choices.iter().any(|choice| match choice { NP_Enum::Some(x) => x == value, NP_Enum::None => false }) == false {
+                    return Err(NP_Error::new(format!("TypeError: `{}` isn't one of the enum choices at {}", value, NP_Schema::_check_path(path))))
+                }
+                true
+            },
+            (NP_TypeKeys::Int8, NP_JSON::Integer(_)) | (NP_TypeKeys::Int8, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Int16, NP_JSON::Integer(_)) | (NP_TypeKeys::Int16, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Int32, NP_JSON::Integer(_)) | (NP_TypeKeys::Int32, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Int64, NP_JSON::Integer(_)) | (NP_TypeKeys::Int64, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Uint8, NP_JSON::Integer(_)) | (NP_TypeKeys::Uint8, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Uint16, NP_JSON::Integer(_)) | (NP_TypeKeys::Uint16, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Uint32, NP_JSON::Integer(_)) | (NP_TypeKeys::Uint32, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Uint64, NP_JSON::Integer(_)) | (NP_TypeKeys::Uint64, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Float, NP_JSON::Integer(_)) | (NP_TypeKeys::Float, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Double, NP_JSON::Integer(_)) | (NP_TypeKeys::Double, NP_JSON::Float(_)) |
+            (NP_TypeKeys::Decimal, NP_JSON::Integer(_)) | (NP_TypeKeys::Decimal, NP_JSON::Float(_)) | (NP_TypeKeys::Decimal, NP_JSON::Dictionary(_)) | (NP_TypeKeys::Decimal, NP_JSON::String(_)) |
+            (NP_TypeKeys::Boolean, NP_JSON::True) | (NP_TypeKeys::Boolean, NP_JSON::False) |
+            (NP_TypeKeys::UTF8String, NP_JSON::String(_)) |
+            (NP_TypeKeys::Bytes, NP_JSON::Array(_)) |
+            (NP_TypeKeys::Geo, NP_JSON::Dictionary(_)) |
+            (NP_TypeKeys::Uuid, NP_JSON::String(_)) |
+            (NP_TypeKeys::Ulid, NP_JSON::String(_)) |
+            (NP_TypeKeys::Date, NP_JSON::Integer(_)) | (NP_TypeKeys::Date, NP_JSON::Float(_)) | (NP_TypeKeys::Date, NP_JSON::String(_)) => true,
+            _ => false
+        };
+
+        if matches == false {
+            let found = match json {
+                NP_JSON::String(_) => "string",
+                NP_JSON::Integer(_) => "integer",
+                NP_JSON::Float(_) => "float",
+                NP_JSON::Dictionary(_) => "map",
+                NP_JSON::Array(_) => "array",
+                NP_JSON::True | NP_JSON::False => "boolean",
+                NP_JSON::Null => "null"
+            };
+            return Err(NP_Error::new(format!("TypeError: Expected ({}) at {}, found {}", schema.i.into_type_idx().0, NP_Schema::_check_path(path), found)))
+        }
+
+        Ok(())
+    }
+
+    /// Collect every type reachable from `address` into `types`, including collection items and the targets of portals.
+    #[doc(hidden)]
+    pub fn _types_used(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize, types: &mut BTreeSet<NP_TypeKeys>) {
+
+        // portals can point back at their parents, so each address is only walked once
+        let mut visited = vec![false; parsed_schema.len()];
+        let mut stack = vec![address];
+
+        while let Some(address) = stack.pop() {
+            if address >= parsed_schema.len() || visited[address] {
+                continue;
+            }
+            visited[address] = true;
+
+            let schema = &parsed_schema[address];
+            types.insert(schema.i);
+
+            match schema.i {
+                NP_TypeKeys::Struct => {
+                    let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                    stack.extend(data.fields.iter().map(|field| field.schema));
+                },
+                NP_TypeKeys::Tuple => {
+                    let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                    stack.extend(data.values.iter().map(|value| value.schema));
+                },
+                NP_TypeKeys::List | NP_TypeKeys::Map => {
+                    let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                    stack.push(data.child);
+                },
+                NP_TypeKeys::Portal => {
+                    let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+                    stack.push(data.schema);
+                },
+                _ => { }
+            }
+        }
+    }
+
+    /// Describe the type at `address`, portals are followed to the type they point to
+    #[doc(hidden)]
+    pub fn _type_info(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize) -> NP_Schema_Info {
+
+        let mut address = address;
+        let mut loop_count = 0usize;
+        while parsed_schema[address].i == NP_TypeKeys::Portal && loop_count < parsed_schema.len() {
+            address = unsafe { &*(*parsed_schema[address].data as *const NP_Portal_Data) }.schema;
+            loop_count += 1;
+        }
+
+        let schema = &parsed_schema[address];
+
+        let mut info = NP_Schema_Info {
+            kind: schema.i,
+            is_collection: false,
+            fields: Vec::new(),
+            of: None,
+            values: Vec::new(),
+            min: None,
+            max: None
+        };
+
+        match schema.i {
+            NP_TypeKeys::Int8   => { info.min = Some(i8::MIN as i128);  info.max = Some(i8::MAX as i128); },
+            NP_TypeKeys::Int16  => { info.min = Some(i16::MIN as i128); info.max = Some(i16::MAX as i128); },
+            NP_TypeKeys::Int32  => { info.min = Some(i32::MIN as i128); info.max = Some(i32::MAX as i128); },
+            NP_TypeKeys::Int64  => { info.min = Some(i64::MIN as i128); info.max = Some(i64::MAX as i128); },
+            NP_TypeKeys::Uint8  => { info.min = Some(0);                info.max = Some(u8::MAX as i128); },
+            NP_TypeKeys::Uint16 => { info.min = Some(0);                info.max = Some(u16::MAX as i128); },
+            NP_TypeKeys::Uint32 => { info.min = Some(0);                info.max = Some(u32::MAX as i128); },
+            NP_TypeKeys::Uint64 => { info.min = Some(0);                info.max = Some(u64::MAX as i128); },
+            NP_TypeKeys::Struct => {
+                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                info.is_collection = true;
+                info.fields = data.fields.iter().map(|field| field.col.clone()).collect();
+            },
+            NP_TypeKeys::Tuple => {
+                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                info.is_collection = true;
+                info.values = data.values.iter().map(|value| parsed_schema[value.schema].i).collect();
+            },
+            NP_TypeKeys::List | NP_TypeKeys::Map => {
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                info.is_collection = true;
+                info.of = Some(parsed_schema[data.child].i);
+            },
+            _ => { }
+        }
+
+        info
+    }
+
+    /// Check if buffers made with the `old` schema can be safely read with this schema.
+    /// 
+    /// Struct columns are stored by position, so columns can be renamed, new columns can be added to the end and columns can be removed from the end.  Changing the type of a column is not safe.
+    /// 
+    pub fn is_compatible_with(&self, old: &NP_Schema) -> Result<NP_Schema_Compat, NP_Error> {
+        NP_Schema::_compat(&self.parsed, 0, &old.parsed, 0, String::from(""))
+    }
+
+    /// Parse the `sort` property of number and string types, true means descending
+    #[doc(hidden)]
+    pub fn _parse_sort(value: &str) -> Result<bool, NP_Error> {
+        match value {
+            "asc" => Ok(false),
+            "desc" => Ok(true),
+            _ => Err(NP_Error::new("sort property must be 'asc' or 'desc'!"))
+        }
+    }
+
+    /// Is this type stored inverted for descending byte-wise sorting?
+    #[doc(hidden)]
+    pub fn _is_desc(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize) -> bool {
+        let data = *parsed_schema[address].data;
+        match parsed_schema[address].i {
+            NP_TypeKeys::Int8 => unsafe { &*(data as *const NP_i8_Data) }.desc,
+            NP_TypeKeys::Int16 => unsafe { &*(data as *const NP_i16_Data) }.desc,
+            NP_TypeKeys::Int32 => unsafe { &*(data as *const NP_i32_Data) }.desc,
+            NP_TypeKeys::Int64 => unsafe { &*(data as *const NP_i64_Data) }.desc,
+            NP_TypeKeys::Uint8 => unsafe { &*(data as *const NP_u8_Data) }.desc,
+            NP_TypeKeys::Uint16 => unsafe { &*(data as *const NP_u16_Data) }.desc,
+            NP_TypeKeys::Uint32 => unsafe { &*(data as *const NP_u32_Data) }.desc,
+            NP_TypeKeys::Uint64 => unsafe { &*(data as *const NP_u64_Data) }.desc,
+            NP_TypeKeys::UTF8String => unsafe { &*(data as *const NP_String_Data) }.desc,
+            _ => false
+        }
+    }
+
+    fn _compat_path(path: &String, key: &str) -> String {
+        let mut next = path.clone();
+        if next.len() > 0 {
+            next.push_str(".");
+        }
+        next.push_str(key);
+        next
+    }
+
+    /// Recursive function to compare two schemas
+    #[doc(hidden)]
+    pub fn _compat(new_schema: &Vec<NP_Parsed_Schema>, new_addr: usize, old_schema: &Vec<NP_Parsed_Schema>, old_addr: usize, path: String) -> Result<NP_Schema_Compat, NP_Error> {
+
+        let new_type = &new_schema[new_addr];
+        let old_type = &old_schema[old_addr];
+
+        if new_type.i != old_type.i {
+            return Ok(NP_Schema_Compat::TypeChanged { path, from: old_type.i, to: new_type.i })
+        }
+
+        if new_type.sortable != old_type.sortable || new_type.val != old_type.val {
+            return Ok(NP_Schema_Compat::PropertyChanged { path, property: String::from("size") })
+        }
+
+        // inverted bytes can't be read with the other sort order
+        if NP_Schema::_is_desc(new_schema, new_addr) != NP_Schema::_is_desc(old_schema, old_addr) {
+            return Ok(NP_Schema_Compat::PropertyChanged { path, property: String::from("sort") })
+        }
+
+        match new_type.i {
+            NP_TypeKeys::Struct => {
+                let new_data = unsafe { &*(*new_type.data as *const NP_Struct_Data) };
+                let old_data = unsafe { &*(*old_type.data as *const NP_Struct_Data) };
+
+                // columns are matched by position, anything past the shorter schema is fine
+                for (new_field, old_field) in new_data.fields.iter().zip(old_data.fields.iter()) {
+                    let result = NP_Schema::_compat(new_schema, new_field.schema, old_schema, old_field.schema, NP_Schema::_compat_path(&path, &new_field.col))?;
+                    if result != NP_Schema_Compat::Compatible {
+                        return Ok(result)
+                    }
+                }
+            },
+            NP_TypeKeys::Tuple => {
+                let new_data = unsafe { &*(*new_type.data as *const NP_Tuple_Data) };
+                let old_data = unsafe { &*(*old_type.data as *const NP_Tuple_Data) };
+
+                // tuples are packed into a single block, the layout has to be identical
+                if new_data.values.len() != old_data.values.len() {
+                    return Ok(NP_Schema_Compat::TupleLengthChanged { path, from: old_data.values.len(), to: new_data.values.len() })
+                }
+
+                for (idx, (new_value, old_value)) in new_data.values.iter().zip(old_data.values.iter()).enumerate() {
+                    let result = NP_Schema::_compat(new_schema, new_value.schema, old_schema, old_value.schema, NP_Schema::_compat_path(&path, format!("{}", idx).as_str()))?;
+                    if result != NP_Schema_Compat::Compatible {
+                        return Ok(result)
+                    }
+                }
+            },
+            NP_TypeKeys::List | NP_TypeKeys::Map => {
+                let new_data = unsafe { &*(*new_type.data as *const NP_Map_List_Data) };
+                let old_data = unsafe { &*(*old_type.data as *const NP_Map_List_Data) };
+
+                return NP_Schema::_compat(new_schema, new_data.child, old_schema, old_data.child, path)
+            },
+            NP_TypeKeys::UTF8String => {
+                let new_data = unsafe { &*(*new_type.data as *const NP_String_Data) };
+                let old_data = unsafe { &*(*old_type.data as *const NP_String_Data) };
+
+                if new_data.size != old_data.size {
+                    return Ok(NP_Schema_Compat::PropertyChanged { path, property: String::from("size") })
+                }
+            },
+            NP_TypeKeys::Bytes => {
+                let new_data = unsafe { &*(*new_type.data as *const NP_Bytes_Data) };
+                let old_data = unsafe { &*(*old_type.data as *const NP_Bytes_Data) };
+
+                if new_data.size != old_data.size {
+                    return Ok(NP_Schema_Compat::PropertyChanged { path, property: String::from("size") })
+                }
+            },
+            NP_TypeKeys::Geo => {
+                let new_data = unsafe { &*(*new_type.data as *const NP_Geo_Data) };
+                let old_data = unsafe { &*(*old_type.data as *const NP_Geo_Data) };
+
+                if new_data.size != old_data.size {
+                    return Ok(NP_Schema_Compat::PropertyChanged { path, property: String::from("size") })
+                }
+            },
+            NP_TypeKeys::Decimal => {
+                let new_data = unsafe { &*(*new_type.data as *const NP_Dec_Data) };
+                let old_data = unsafe { &*(*old_type.data as *const NP_Dec_Data) };
+
+                if new_data.exp != old_data.exp {
+                    return Ok(NP_Schema_Compat::PropertyChanged { path, property: String::from("exp") })
+                }
+            },
+            NP_TypeKeys::Enum => {
+                let new_data = unsafe { &*(*new_type.data as *const NP_Enum_Data) };
+                let old_data = unsafe { &*(*old_type.data as *const NP_Enum_Data) };
+
+                // options are stored by index, new choices can only be added to the end
+                if new_data.choices.len() < old_data.choices.len() || new_data.choices[..old_data.choices.len()] != old_data.choices[..] {
+                    return Ok(NP_Schema_Compat::PropertyChanged { path, property: String::from("choices") })
+                }
+            },
+            NP_TypeKeys::Portal => {
+                let new_data = unsafe { &*(*new_type.data as *const NP_Portal_Data) };
+                let old_data = unsafe { &*(*old_type.data as *const NP_Portal_Data) };
+
+                // portal targets are checked where they are defined
+                if new_data.path != old_data.path {
+                    return Ok(NP_Schema_Compat::PropertyChanged { path, property: String::from("to") })
+                }
+            },
+            _ => { }
+        }
+
+        Ok(NP_Schema_Compat::Compatible)
+    }
+
+    /// Recursive function to find the path to every schema address, list items and map values use `*` as their key
+    #[doc(hidden)]
+    pub fn _schema_paths(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize, path: &mut Vec<String>, paths: &mut Vec<Option<Vec<String>>>) {
+
+        if address >= paths.len() || paths[address].is_some() {
+            return;
+        }
+
+        paths[address] = Some(path.clone());
+
+        match parsed_schema[address].i {
+            NP_TypeKeys::Struct => {
+                let data = unsafe { &*(*parsed_schema[address].data as *const NP_Struct_Data) };
+                for field in data.fields.iter() {
+                    path.push(field.col.clone());
+                    NP_Schema::_schema_paths(parsed_schema, field.schema, path, paths);
+                    path.pop();
+                }
+            },
+            NP_TypeKeys::Tuple => {
+                let data = unsafe { &*(*parsed_schema[address].data as *const NP_Tuple_Data) };
+                for (idx, value) in data.values.iter().enumerate() {
+                    path.push(format!("{}", idx));
+                    NP_Schema::_schema_paths(parsed_schema, value.schema, path, paths);
+                    path.pop();
+                }
+            },
+            NP_TypeKeys::List | NP_TypeKeys::Map => {
+                let data = unsafe { &*(*parsed_schema[address].data as *const NP_Map_List_Data) };
+                path.push(String::from("*"));
+                NP_Schema::_schema_paths(parsed_schema, data.child, path, paths);
+                path.pop();
+            },
+            // portal targets get the path where they are defined
+            _ => { }
+        }
+    }
+
     /// Get a IDL represenatation of this schema
     pub fn to_idl(&self) -> Result<String, NP_Error> {
         NP_Schema::_type_to_idl(&self.parsed, 0)
@@ -1330,6 +2072,195 @@ impl NP_Schema {
         NP_Schema::_type_to_json(&self.parsed, 0)
     }
 
+    /// Get a JSON Schema (draft-07) represenatation of this schema.
+    /// 
+    /// The JSON Schema describes the JSON that `NP_Buffer::json_encode` produces for the value.  Portals that point to one of their parents are exported as a `$ref`.
+    pub fn to_json_schema(&self) -> Result<NP_JSON, NP_Error> {
+        let mut result = match NP_Schema::_type_to_json_schema(&self.parsed, 0, &mut Vec::new(), String::from("#"))? {
+            NP_JSON::Dictionary(map) => map,
+            _ => JSMAP::new()
+        };
+        let mut with_version = JSMAP::new();
+        with_version.insert(String::from("$schema"), NP_JSON::String(String::from("http://json-schema.org/draft-07/schema#")));
+        for (key, value) in result.values.drain(..) {
+            with_version.insert(key, value);
+        }
+        Ok(NP_JSON::Dictionary(with_version))
+    }
+
+    /// Recursive function to convert schema into JSON Schema
+    #[doc(hidden)]
+    pub fn _type_to_json_schema(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize, parents: &mut Vec<(usize, String)>, pointer: String) -> Result<NP_JSON, NP_Error> {
+
+        if parents.len() > 255 {
+            return Err(NP_Error::RecursionLimit)
+        }
+
+        let schema = &parsed_schema[address];
+
+        // properties from the native JSON of this type
+        let native = NP_Schema::_type_to_json_shallow(parsed_schema, address)?;
+
+        // decimals set to export as JSON numbers
+        let dec_number = schema.i == NP_TypeKeys::Decimal && unsafe { &*(*schema.data as *const NP_Dec_Data) }.json_number;
+
+        let mut json_schema = JSMAP::new();
+
+        let type_str = |kind: &str| { NP_JSON::String(String::from(kind)) };
+
+        let number_bounds = |json_schema: &mut JSMAP, min: i64, max: Option<i64>| {
+            json_schema.insert(String::from("type"), NP_JSON::String(String::from("integer")));
+            json_schema.insert(String::from("minimum"), NP_JSON::Integer(min));
+            if let Some(max) = max {
+                json_schema.insert(String::from("maximum"), NP_JSON::Integer(max));
+            }
+        };
+
+        parents.push((address, pointer.clone()));
+
+        match schema.i {
+            NP_TypeKeys::None | NP_TypeKeys::Any => { },
+            NP_TypeKeys::UTF8String => {
+                json_schema.insert(String::from("type"), type_str("string"));
+                if let NP_JSON::Integer(size) = &native["size"] {
+                    json_schema.insert(String::from("maxLength"), NP_JSON::Integer(*size));
+                }
+            },
+            NP_TypeKeys::Bytes => {
+                json_schema.insert(String::from("type"), type_str("array"));
+                let mut items = JSMAP::new();
+                number_bounds(&mut items, 0, Some(u8::MAX as i64));
+                json_schema.insert(String::from("items"), NP_JSON::Dictionary(items));
+                if let NP_JSON::Integer(size) = &native["size"] {
+                    json_schema.insert(String::from("maxItems"), NP_JSON::Integer(*size));
+                }
+            },
+            NP_TypeKeys::Int8   => number_bounds(&mut json_schema, i8::MIN as i64, Some(i8::MAX as i64)),
+            NP_TypeKeys::Int16  => number_bounds(&mut json_schema, i16::MIN as i64, Some(i16::MAX as i64)),
+            NP_TypeKeys::Int32  => number_bounds(&mut json_schema, i32::MIN as i64, Some(i32::MAX as i64)),
+            NP_TypeKeys::Int64  => number_bounds(&mut json_schema, i64::MIN, Some(i64::MAX)),
+            NP_TypeKeys::Uint8  => number_bounds(&mut json_schema, 0, Some(u8::MAX as i64)),
+            NP_TypeKeys::Uint16 => number_bounds(&mut json_schema, 0, Some(u16::MAX as i64)),
+            NP_TypeKeys::Uint32 => number_bounds(&mut json_schema, 0, Some(u32::MAX as i64)),
+            NP_TypeKeys::Uint64 => number_bounds(&mut json_schema, 0, None), // u64::MAX doesn't fit in JSON integers
+            NP_TypeKeys::Float | NP_TypeKeys::Double => {
+                json_schema.insert(String::from("type"), type_str("number"));
+            },
+            NP_TypeKeys::Decimal if dec_number => {
+                json_schema.insert(String::from("type"), type_str("number"));
+            },
+            NP_TypeKeys::Decimal => {
+                json_schema.insert(String::from("type"), type_str("object"));
+                let mut parts = JSMAP::new();
+                let mut integer = JSMAP::new();
+                integer.insert(String::from("type"), type_str("integer"));
+                parts.insert(String::from("num"), NP_JSON::Dictionary(integer.clone()));
+                parts.insert(String::from("exp"), NP_JSON::Dictionary(integer));
+                let mut parts_schema = JSMAP::new();
+                parts_schema.insert(String::from("type"), type_str("object"));
+                parts_schema.insert(String::from("properties"), NP_JSON::Dictionary(parts));
+                let mut number = JSMAP::new();
+                number.insert(String::from("type"), type_str("number"));
+                let mut properties = JSMAP::new();
+                properties.insert(String::from("value"), NP_JSON::Dictionary(number));
+                properties.insert(String::from("parts"), NP_JSON::Dictionary(parts_schema));
+                json_schema.insert(String::from("properties"), NP_JSON::Dictionary(properties));
+            },
+            NP_TypeKeys::Boolean => {
+                json_schema.insert(String::from("type"), type_str("boolean"));
+            },
+            NP_TypeKeys::Geo => {
+                json_schema.insert(String::from("type"), type_str("object"));
+                let mut number = JSMAP::new();
+                number.insert(String::from("type"), type_str("number"));
+                let mut properties = JSMAP::new();
+                properties.insert(String::from("lat"), NP_JSON::Dictionary(number.clone()));
+                properties.insert(String::from("lng"), NP_JSON::Dictionary(number));
+                json_schema.insert(String::from("properties"), NP_JSON::Dictionary(properties));
+                json_schema.insert(String::from("required"), NP_JSON::Array(vec![type_str("lat"), type_str("lng")]));
+            },
+            NP_TypeKeys::Uuid | NP_TypeKeys::Ulid => {
+                json_schema.insert(String::from("type"), type_str("string"));
+            },
+            NP_TypeKeys::Date => {
+                number_bounds(&mut json_schema, 0, None);
+            },
+            NP_TypeKeys::Enum => {
+                json_schema.insert(String::from("type"), type_str("string"));
+                if let NP_JSON::Array(choices) = &native["choices"] {
+                    json_schema.insert(String::from("enum"), NP_JSON::Array(choices.clone()));
+                }
+            },
+            NP_TypeKeys::Struct => {
+                let data = unsafe { &*(*schema.data as *const NP_Struct_Data) };
+                json_schema.insert(String::from("type"), type_str("object"));
+                let mut properties = JSMAP::new();
+                for field in data.fields.iter() {
+                    let mut field_pointer = pointer.clone();
+                    field_pointer.push_str("/properties/");
+                    field_pointer.push_str(&field.col.replace("~", "~0").replace("/", "~1"));
+                    properties.insert(field.col.clone(), NP_Schema::_type_to_json_schema(parsed_schema, field.schema, parents, field_pointer)?);
+                }
+                json_schema.insert(String::from("properties"), NP_JSON::Dictionary(properties));
+            },
+            NP_TypeKeys::List => {
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                json_schema.insert(String::from("type"), type_str("array"));
+                let mut items_pointer = pointer.clone();
+                items_pointer.push_str("/items");
+                json_schema.insert(String::from("items"), NP_Schema::_type_to_json_schema(parsed_schema, data.child, parents, items_pointer)?);
+            },
+            NP_TypeKeys::Map => {
+                let data = unsafe { &*(*schema.data as *const NP_Map_List_Data) };
+                json_schema.insert(String::from("type"), type_str("object"));
+                let mut value_pointer = pointer.clone();
+                value_pointer.push_str("/additionalProperties");
+                json_schema.insert(String::from("additionalProperties"), NP_Schema::_type_to_json_schema(parsed_schema, data.child, parents, value_pointer)?);
+            },
+            NP_TypeKeys::Tuple => {
+                let data = unsafe { &*(*schema.data as *const NP_Tuple_Data) };
+                json_schema.insert(String::from("type"), type_str("array"));
+                let mut items: Vec<NP_JSON> = Vec::new();
+                for (idx, value) in data.values.iter().enumerate() {
+                    let item_pointer = format!("{}/items/{}", pointer, idx);
+                    items.push(NP_Schema::_type_to_json_schema(parsed_schema, value.schema, parents, item_pointer)?);
+                }
+                json_schema.insert(String::from("items"), NP_JSON::Array(items));
+                json_schema.insert(String::from("additionalItems"), NP_JSON::False);
+            },
+            NP_TypeKeys::Portal => {
+                let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+                parents.pop();
+                // portals to a parent would recurse forever, point to the parent instead
+                if let Some((_, parent_pointer)) = parents.iter().find(|(addr, _)| *addr == data.schema) {
+                    json_schema.insert(String::from("$ref"), NP_JSON::String(parent_pointer.clone()));
+                    return Ok(NP_JSON::Dictionary(json_schema))
+                }
+                return NP_Schema::_type_to_json_schema(parsed_schema, data.schema, parents, pointer)
+            }
+        }
+
+        parents.pop();
+
+        match (schema.i, &native["default"]) {
+            (_, NP_JSON::Null) => { },
+            (NP_TypeKeys::Decimal, _) if dec_number == false => { }, // decimal defaults aren't in the same format as structured decimal values
+            (_, default) => {
+                json_schema.insert(String::from("default"), default.clone());
+            }
+        }
+
+        Ok(NP_JSON::Dictionary(json_schema))
+    }
+
+    /// Native JSON of a single schema, without the children of collections
+    fn _type_to_json_shallow(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize) -> Result<NP_JSON, NP_Error> {
+        match parsed_schema[address].i {
+            NP_TypeKeys::Struct | NP_TypeKeys::List | NP_TypeKeys::Map | NP_TypeKeys::Tuple | NP_TypeKeys::Portal => Ok(NP_JSON::Null),
+            _ => NP_Schema::_type_to_json(parsed_schema, address)
+        }
+    }
+
     /// Recursive function parse schema into JSON
     #[doc(hidden)]
     pub fn _type_to_json(parsed_schema: &Vec<NP_Parsed_Schema>, address: usize) -> Result<NP_JSON, NP_Error> {
@@ -1425,7 +2356,7 @@ impl NP_Schema {
             JS_AST::method { name, args } => {
                 let type_name = idl.get_str(name).trim();
 
-                match type_name {
+                let result = match type_name {
                     "any"      => {    NP_Any::from_idl_to_schema(parsed, type_name, idl, args) },
                     "string"   => {    String::from_idl_to_schema(parsed, type_name, idl, args) },
                     "utf8"     => {    String::from_idl_to_schema(parsed, type_name, idl, args) },
@@ -1472,9 +2403,15 @@ impl NP_Schema {
                     // "union"    => {  NP_Union::from_idl_to_schema(parsed, type_name, idl, args) },
                     _ => {
                         let mut err_msg = String::from("Can't find a type that matches this schema! ");
-                        err_msg.push_str(idl.get_str(name));
-                        Err(NP_Error::new(err_msg.as_str()))
+                        err_msg.push_str(type_name);
+                        return Err(idl.error_at(err_msg.as_str(), name))
                     }
+                };
+
+                match result {
+                    // errors from nested types already point at the nested type
+                    Err(NP_Error::Custom { message }) => Err(idl.error_at(message.as_str(), name)),
+                    _ => result
                 }
             },
             _ => { Err(NP_Error::new("Error parsing IDL Schema!")) }
@@ -1515,6 +2452,264 @@ impl NP_Schema {
         }
     }
 
+    /// Validate compiled schema bytes before they're parsed
+    /// 
+    /// Walks the schema bytes starting at `address` and makes sure every type key is known, every length or offset stays inside the byte array and every collection definition is complete.  Returns the address just past the end of the schema.
+    /// 
+    pub fn _validate_bytes(bytes: &[u8], address: usize, depth: usize) -> Result<usize, NP_Error> {
+
+        if depth > DEFAULT_MAX_DEPTH {
+            return Err(NP_Error::RecursionLimit)
+        }
+
+        let read = |addr: usize, len: usize| -> Result<&[u8], NP_Error> {
+            match addr.checked_add(len) {
+                Some(end) if end <= bytes.len() => Ok(&bytes[addr..end]),
+                _ => Err(NP_Error::new("Schema bytes are truncated!"))
+            }
+        };
+
+        let type_key = read(address, 1)?[0];
+
+        if type_key > NP_TypeKeys::Portal as u8 {
+            return Err(NP_Error::new(format!("Schema bytes contain unknown type key {}!", type_key)))
+        }
+
+        match NP_TypeKeys::from(type_key) {
+            NP_TypeKeys::None | NP_TypeKeys::Any | NP_TypeKeys::Uuid | NP_TypeKeys::Ulid => Ok(address + 1),
+            NP_TypeKeys::UTF8String => {
+                let header = read(address + 1, 7)?;
+                let default_size = u16::from_be_bytes([header[5], header[6]]) as usize;
+                if default_size == 0 {
+                    return Ok(address + 8)
+                }
+                if core::str::from_utf8(read(address + 8, default_size - 1)?).is_err() {
+                    return Err(NP_Error::new("Schema bytes contain a string default that isn't valid utf-8!"))
+                }
+                Ok(address + 8 + default_size - 1)
+            },
+            NP_TypeKeys::Bytes => {
+                let header = read(address + 1, 6)?;
+                let default_size = u16::from_be_bytes([header[4], header[5]]) as usize;
+                if default_size == 0 {
+                    return Ok(address + 7)
+                }
+                read(address + 7, default_size - 1)?;
+                Ok(address + 7 + default_size - 1)
+            },
+            NP_TypeKeys::Int8 | NP_TypeKeys::Uint8 => { Self::_validate_number_bytes(bytes, address, 1) },
+            NP_TypeKeys::Int16 | NP_TypeKeys::Uint16 => { Self::_validate_number_bytes(bytes, address, 2) },
+            NP_TypeKeys::Int32 | NP_TypeKeys::Uint32 | NP_TypeKeys::Float => { Self::_validate_number_bytes(bytes, address, 4) },
+            NP_TypeKeys::Int64 | NP_TypeKeys::Uint64 | NP_TypeKeys::Double => { Self::_validate_number_bytes(bytes, address, 8) },
+            NP_TypeKeys::Decimal => {
+                let flags = read(address + 2, 1)?[0];
+                if flags & 1 == 1 {
+                    read(address + 3, 8)?;
+                    Ok(address + 11)
+                } else {
+                    Ok(address + 3)
+                }
+            },
+            NP_TypeKeys::Boolean => {
+                if read(address + 1, 1)?[0] > 2 {
+                    return Err(NP_Error::new("Schema bytes contain an invalid bool default!"))
+                }
+                Ok(address + 2)
+            },
+            NP_TypeKeys::Geo => {
+                let header = read(address + 1, 2)?;
+                let size = header[0] as usize;
+                if size != 4 && size != 8 && size != 16 {
+                    return Err(NP_Error::new("Schema bytes contain an invalid geo size!"))
+                }
+                if header[1] == 0 {
+                    return Ok(address + 3)
+                }
+                read(address + 3, size)?;
+                Ok(address + 3 + size)
+            },
+            NP_TypeKeys::Date => {
+                if read(address + 1, 1)?[0] == 0 {
+                    return Ok(address + 2)
+                }
+                read(address + 2, 8)?;
+                Ok(address + 10)
+            },
+            NP_TypeKeys::Enum => {
+                let header = read(address + 1, 2)?;
+                let (default_index, choices_len) = (header[0] as usize, header[1] as usize);
+                if default_index > choices_len {
+                    return Err(NP_Error::new("Schema bytes contain an enum default outside of the choices!"))
+                }
+                let mut offset = address + 3;
+                for _x in 0..choices_len {
+                    let choice_size = read(offset, 1)?[0] as usize;
+                    if core::str::from_utf8(read(offset + 1, choice_size)?).is_err() {
+                        return Err(NP_Error::new("Schema bytes contain an enum choice that isn't valid utf-8!"))
+                    }
+                    offset += 1 + choice_size;
+                }
+                Ok(offset)
+            },
+            NP_TypeKeys::Struct => {
+                let fields_count = read(address + 1, 1)?[0] as usize;
+                let mut offset = address + 2;
+                for _x in 0..fields_count {
+                    let col_name_len = read(offset, 1)?[0] as usize;
+                    if core::str::from_utf8(read(offset + 1, col_name_len)?).is_err() {
+                        return Err(NP_Error::new("Schema bytes contain a struct field name that isn't valid utf-8!"))
+                    }
+                    offset += 1 + col_name_len;
+                    offset = Self::_validate_sized_bytes(bytes, offset, depth)?;
+                }
+                Ok(offset)
+            },
+            NP_TypeKeys::Map => {
+                // maps with interned keys have a flag byte before the value schema
+                if read(address + 1, 1)?[0] == INTERN_KEYS_FLAG {
+                    Self::_validate_bytes(bytes, address + 2, depth + 1)
+                } else {
+                    Self::_validate_bytes(bytes, address + 1, depth + 1)
+                }
+            },
+            NP_TypeKeys::List => {
+                Self::_validate_bytes(bytes, address + 1, depth + 1)
+            },
+            NP_TypeKeys::Tuple => {
+                let column_len = read(address + 1, 2)?[1] as usize;
+                let mut offset = address + 3;
+                for _x in 0..column_len {
+                    offset = Self::_validate_sized_bytes(bytes, offset, depth)?;
+                }
+                Ok(offset)
+            },
+            NP_TypeKeys::Portal => {
+                let path_size = read(address + 1, 2)?;
+                let path_size = u16::from_be_bytes([path_size[0], path_size[1]]) as usize;
+                if core::str::from_utf8(read(address + 3, path_size)?).is_err() {
+                    return Err(NP_Error::new("Schema bytes contain a portal path that isn't valid utf-8!"))
+                }
+                Ok(address + 3 + path_size)
+            }
+        }
+    }
+
+    /// Validate a child schema that's prefixed with its u16 byte length, used by struct fields and tuple values
+    fn _validate_sized_bytes(bytes: &[u8], address: usize, depth: usize) -> Result<usize, NP_Error> {
+        if address + 2 > bytes.len() {
+            return Err(NP_Error::new("Schema bytes are truncated!"))
+        }
+        let schema_size = u16::from_be_bytes([bytes[address], bytes[address + 1]]) as usize;
+        let end = Self::_validate_bytes(bytes, address + 2, depth + 1)?;
+        if end != address + 2 + schema_size {
+            return Err(NP_Error::new("Schema bytes contain a collection with a mismatched child length!"))
+        }
+        Ok(end)
+    }
+
+    /// Validate a number schema, the default value follows the flag byte only if the first bit is set
+    fn _validate_number_bytes(bytes: &[u8], address: usize, size: usize) -> Result<usize, NP_Error> {
+        if address + 2 > bytes.len() {
+            return Err(NP_Error::new("Schema bytes are truncated!"))
+        }
+        if bytes[address + 1] & 1 == 0 {
+            return Ok(address + 2)
+        }
+        if address + 2 + size > bytes.len() {
+            return Err(NP_Error::new("Schema bytes are truncated!"))
+        }
+        Ok(address + 2 + size)
+    }
+
+    /// Expand `{"$ref": "name"}` objects in a JSON schema against a map of named definitions
+    ///
+    /// Any other keys next to `$ref` are laid over the top level of the definition, so `{"$ref": "address", "default": ...}` works as expected.  Definitions can reference other definitions, but a definition that (directly or indirectly) references itself is rejected, recursive types should use a `portal` instead.
+    ///
+    pub fn resolve_refs(json_schema: &NP_JSON, definitions: &JSMAP, stack: &mut Vec<String>) -> Result<NP_JSON, NP_Error> {
+        match json_schema {
+            NP_JSON::Dictionary(map) => {
+                if let Some(reference) = map.get("$ref") {
+                    let name = match reference {
+                        NP_JSON::String(name) => name,
+                        _ => return Err(NP_Error::new("Schema $ref must be a string!"))
+                    };
+
+                    if stack.iter().any(|x| x == name) {
+                        let mut err = "Cyclic schema $ref: ".to_owned();
+                        for x in stack.iter() {
+                            err.push_str(x.as_str());
+                            err.push_str(" -> ");
+                        }
+                        err.push_str(name.as_str());
+                        err.push_str(", use a portal for recursive types.");
+                        return Err(NP_Error::new(err.as_str()))
+                    }
+
+                    let definition = match definitions.get(name.as_str()) {
+                        Some(x) => x,
+                        None => {
+                            let mut err = "Unknown schema $ref: ".to_owned();
+                            err.push_str(name.as_str());
+                            return Err(NP_Error::new(err.as_str()))
+                        }
+                    };
+
+                    stack.push(name.clone());
+                    let mut resolved = Self::resolve_refs(definition, definitions, stack)?;
+                    stack.pop();
+
+                    for (key, value) in map.values.iter() {
+                        if key == "$ref" { continue; }
+                        let value = Self::resolve_refs(value, definitions, stack)?;
+                        match &mut resolved {
+                            NP_JSON::Dictionary(resolved_map) => { resolved_map.insert(key.clone(), value); },
+                            _ => return Err(NP_Error::new("Schema $ref with extra keys must point to an object!"))
+                        }
+                    }
+
+                    Ok(resolved)
+                } else {
+                    let mut resolved = JSMAP::new();
+                    for (key, value) in map.values.iter() {
+                        resolved.insert(key.clone(), Self::resolve_refs(value, definitions, stack)?);
+                    }
+                    Ok(NP_JSON::Dictionary(resolved))
+                }
+            },
+            NP_JSON::Array(items) => {
+                let mut resolved = Vec::with_capacity(items.len());
+                for item in items.iter() {
+                    resolved.push(Self::resolve_refs(item, definitions, stack)?);
+                }
+                Ok(NP_JSON::Array(resolved))
+            },
+            _ => Ok(json_schema.clone())
+        }
+    }
+
+    /// Put a JSON schema in canonical form: object keys are sorted and floats with a whole number value (`2.0`) become integers.
+    /// 
+    /// The factories run this once over the whole schema before it's compiled, so equivalent schemas always compile to the same bytes no matter what order the object keys are in or how numbers are written.
+    /// 
+    #[doc(hidden)]
+    pub fn _canonical_json(json_schema: &NP_JSON) -> NP_JSON {
+        match json_schema {
+            NP_JSON::Dictionary(map) => {
+                let mut values: Vec<(String, NP_JSON)> = map.values.iter().map(|(key, value)| (key.clone(), Self::_canonical_json(value))).collect();
+                values.sort_by(|a, b| a.0.cmp(&b.0));
+                NP_JSON::Dictionary(JSMAP { values })
+            },
+            NP_JSON::Array(items) => {
+                NP_JSON::Array(items.iter().map(|item| Self::_canonical_json(item)).collect())
+            },
+            // -0.0 is left alone so float defaults keep their sign
+            NP_JSON::Float(x) if *x >= i64::MIN as f64 && *x < i64::MAX as f64 && (*x as i64) as f64 == *x && !(*x == 0.0 && x.is_sign_negative()) => {
+                NP_JSON::Integer(*x as i64)
+            },
+            _ => json_schema.clone()
+        }
+    }
+
     /// Parse schema from JSON object
     /// 
     /// Given a valid JSON schema, parse and validate, then provide a compiled byte schema.
@@ -1523,7 +2718,7 @@ impl NP_Schema {
     /// 
     pub fn from_json(schema: Vec<NP_Parsed_Schema>, json_schema: &Box<NP_JSON>) -> Result<(bool, Vec<u8>, Vec<NP_Parsed_Schema>), NP_Error> {
 
-        match &json_schema["type"] {
+        let result = match &json_schema["type"] {
             NP_JSON::String(x) => {
                 match x.as_str() {
                     "any"      => {    NP_Any::from_json_to_schema(schema, &json_schema) },
@@ -1575,14 +2770,17 @@ impl NP_Schema {
                     // "union"    => {  NP_Union::from_json_to_schema(schema, &json_schema) },
                     _ => {
                         let mut err_msg = String::from("Can't find a type that matches this schema! ");
-                        err_msg.push_str(json_schema.stringify().as_str());
-                        return Err(NP_Error::new(err_msg.as_str()))
+                        err_msg.push_str(x.as_str());
+                        return Err(NP_Error::new(err_msg.as_str()).in_schema(&["type"]))
                     }
                 }
             },
             _ => {
                 Err(NP_Error::new("Schemas must have a 'type' property!"))
             }
-        }
+        };
+
+        // nested schemas add their own path as the error comes back up
+        result.map_err(|e| e.in_schema(&[]))
     }
 }
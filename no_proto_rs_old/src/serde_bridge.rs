@@ -0,0 +1,355 @@
+//! Bridge between serde and NP_JSON, enabled with the `serde` feature.
+//!
+//! Values are serialized into `NP_JSON` first, then set into buffers with the same rules as `set_json`.  Buffers serialize to the same structure as `json_encode`.
+//!
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::borrow::ToOwned;
+use alloc::string::ToString;
+use core::fmt::{Debug, Display, Formatter};
+
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq};
+
+use crate::buffer::NP_Buffer;
+use crate::error::NP_Error;
+use crate::json_flex::{JSMAP, NP_JSON};
+
+/// Convert any serde value into `NP_JSON`
+pub fn to_json<T: ?Sized + Serialize>(value: &T) -> Result<NP_JSON, NP_Error> {
+    Ok(value.serialize(NP_JSON_Serializer)?)
+}
+
+/// Error produced while serializing into `NP_JSON`
+pub struct NP_Serde_Error {
+    message: String
+}
+
+impl Debug for NP_Serde_Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Display for NP_Serde_Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl ser::StdError for NP_Serde_Error {}
+
+impl ser::Error for NP_Serde_Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        NP_Serde_Error { message: msg.to_string() }
+    }
+}
+
+impl From<NP_Serde_Error> for NP_Error {
+    fn from(err: NP_Serde_Error) -> NP_Error {
+        NP_Error::new(err.message)
+    }
+}
+
+impl Serialize for NP_JSON {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            NP_JSON::String(x) => serializer.serialize_str(x),
+            NP_JSON::Integer(x) => serializer.serialize_i64(*x),
+            NP_JSON::Float(x) => serializer.serialize_f64(*x),
+            NP_JSON::Dictionary(map) => {
+                let mut result = serializer.serialize_map(Some(map.values.len()))?;
+                for (key, value) in &map.values {
+                    result.serialize_entry(key, value)?;
+                }
+                result.end()
+            },
+            NP_JSON::Array(values) => {
+                let mut result = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    result.serialize_element(value)?;
+                }
+                result.end()
+            },
+            NP_JSON::Null => serializer.serialize_unit(),
+            NP_JSON::False => serializer.serialize_bool(false),
+            NP_JSON::True => serializer.serialize_bool(true)
+        }
+    }
+}
+
+impl Serialize for NP_Buffer {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.json_encode(&[]) {
+            Ok(json) => json["value"].serialize(serializer),
+            Err(e) => Err(ser::Error::custom(format!("{:?}", e)))
+        }
+    }
+}
+
+// `{"variant": value}`, the same shape serde_json uses for enum variants
+fn wrap_variant(variant: Option<&'static str>, value: NP_JSON) -> NP_JSON {
+    match variant {
+        Some(name) => {
+            let mut map = JSMAP::new();
+            map.insert(name.to_owned(), value);
+            NP_JSON::Dictionary(map)
+        },
+        None => value
+    }
+}
+
+struct NP_JSON_Serializer;
+
+struct NP_JSON_Seq {
+    values: Vec<NP_JSON>,
+    variant: Option<&'static str>
+}
+
+struct NP_JSON_Map {
+    map: JSMAP,
+    key: Option<String>,
+    variant: Option<&'static str>
+}
+
+impl ser::Serializer for NP_JSON_Serializer {
+    type Ok = NP_JSON;
+    type Error = NP_Serde_Error;
+
+    type SerializeSeq = NP_JSON_Seq;
+    type SerializeTuple = NP_JSON_Seq;
+    type SerializeTupleStruct = NP_JSON_Seq;
+    type SerializeTupleVariant = NP_JSON_Seq;
+    type SerializeMap = NP_JSON_Map;
+    type SerializeStruct = NP_JSON_Map;
+    type SerializeStructVariant = NP_JSON_Map;
+
+    fn serialize_bool(self, v: bool) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(if v { NP_JSON::True } else { NP_JSON::False })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<NP_JSON, NP_Serde_Error> { Ok(NP_JSON::Integer(v as i64)) }
+    fn serialize_i16(self, v: i16) -> Result<NP_JSON, NP_Serde_Error> { Ok(NP_JSON::Integer(v as i64)) }
+    fn serialize_i32(self, v: i32) -> Result<NP_JSON, NP_Serde_Error> { Ok(NP_JSON::Integer(v as i64)) }
+    fn serialize_i64(self, v: i64) -> Result<NP_JSON, NP_Serde_Error> { Ok(NP_JSON::Integer(v)) }
+    fn serialize_u8(self, v: u8) -> Result<NP_JSON, NP_Serde_Error> { Ok(NP_JSON::Integer(v as i64)) }
+    fn serialize_u16(self, v: u16) -> Result<NP_JSON, NP_Serde_Error> { Ok(NP_JSON::Integer(v as i64)) }
+    fn serialize_u32(self, v: u32) -> Result<NP_JSON, NP_Serde_Error> { Ok(NP_JSON::Integer(v as i64)) }
+
+    fn serialize_u64(self, v: u64) -> Result<NP_JSON, NP_Serde_Error> {
+        if v > i64::MAX as u64 {
+            Err(ser::Error::custom("u64 value is too large for JSON integers!"))
+        } else {
+            Ok(NP_JSON::Integer(v as i64))
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<NP_JSON, NP_Serde_Error> { Ok(NP_JSON::Float(v as f64)) }
+    fn serialize_f64(self, v: f64) -> Result<NP_JSON, NP_Serde_Error> { Ok(NP_JSON::Float(v)) }
+
+    fn serialize_char(self, v: char) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(NP_JSON::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(NP_JSON::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(NP_JSON::Array(v.iter().map(|byte| NP_JSON::Integer(*byte as i64)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(NP_JSON::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<NP_JSON, NP_Serde_Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(NP_JSON::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(NP_JSON::Null)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(NP_JSON::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<NP_JSON, NP_Serde_Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(wrap_variant(Some(variant), value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<NP_JSON_Seq, NP_Serde_Error> {
+        Ok(NP_JSON_Seq { values: Vec::with_capacity(len.unwrap_or(0)), variant: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<NP_JSON_Seq, NP_Serde_Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<NP_JSON_Seq, NP_Serde_Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<NP_JSON_Seq, NP_Serde_Error> {
+        Ok(NP_JSON_Seq { values: Vec::with_capacity(len), variant: Some(variant) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<NP_JSON_Map, NP_Serde_Error> {
+        Ok(NP_JSON_Map { map: JSMAP::new(), key: None, variant: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<NP_JSON_Map, NP_Serde_Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<NP_JSON_Map, NP_Serde_Error> {
+        Ok(NP_JSON_Map { map: JSMAP::new(), key: None, variant: Some(variant) })
+    }
+}
+
+impl ser::SerializeSeq for NP_JSON_Seq {
+    type Ok = NP_JSON;
+    type Error = NP_Serde_Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), NP_Serde_Error> {
+        self.values.push(value.serialize(NP_JSON_Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(wrap_variant(self.variant, NP_JSON::Array(self.values)))
+    }
+}
+
+impl ser::SerializeTuple for NP_JSON_Seq {
+    type Ok = NP_JSON;
+    type Error = NP_Serde_Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), NP_Serde_Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<NP_JSON, NP_Serde_Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for NP_JSON_Seq {
+    type Ok = NP_JSON;
+    type Error = NP_Serde_Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), NP_Serde_Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<NP_JSON, NP_Serde_Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for NP_JSON_Seq {
+    type Ok = NP_JSON;
+    type Error = NP_Serde_Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), NP_Serde_Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<NP_JSON, NP_Serde_Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeMap for NP_JSON_Map {
+    type Ok = NP_JSON;
+    type Error = NP_Serde_Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), NP_Serde_Error> {
+        // JSON keys are always strings
+        self.key = Some(match key.serialize(NP_JSON_Serializer)? {
+            NP_JSON::String(x) => x,
+            NP_JSON::Integer(x) => x.to_string(),
+            NP_JSON::Float(x) => x.to_string(),
+            NP_JSON::True => String::from("true"),
+            NP_JSON::False => String::from("false"),
+            _ => return Err(ser::Error::custom("Map keys must be strings, numbers or booleans!"))
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), NP_Serde_Error> {
+        match self.key.take() {
+            Some(key) => {
+                self.map.insert(key, value.serialize(NP_JSON_Serializer)?);
+                Ok(())
+            },
+            None => Err(ser::Error::custom("Map value serialized before its key!"))
+        }
+    }
+
+    fn end(self) -> Result<NP_JSON, NP_Serde_Error> {
+        Ok(wrap_variant(self.variant, NP_JSON::Dictionary(self.map)))
+    }
+}
+
+impl ser::SerializeStruct for NP_JSON_Map {
+    type Ok = NP_JSON;
+    type Error = NP_Serde_Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), NP_Serde_Error> {
+        self.map.insert(key.to_owned(), value.serialize(NP_JSON_Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<NP_JSON, NP_Serde_Error> {
+        SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for NP_JSON_Map {
+    type Ok = NP_JSON;
+    type Error = NP_Serde_Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), NP_Serde_Error> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<NP_JSON, NP_Serde_Error> {
+        SerializeMap::end(self)
+    }
+}
+
+#[test]
+fn serde_bridge_works() -> Result<(), NP_Error> {
+    use alloc::collections::BTreeMap;
+
+    let factory = crate::NP_Factory::new("struct({fields: {name: string(), age: u8(), tags: list({of: string()}), point: tuple({values: [i32(), i32()]})}})")?;
+
+    let mut value: BTreeMap<&str, NP_JSON> = BTreeMap::new();
+    value.insert("name", to_json("Jeb Kermin")?);
+    value.insert("age", to_json(&30u8)?);
+    value.insert("tags", to_json(&vec!["pilot", "kerbal"])?);
+    value.insert("point", to_json(&(-5i32, 12i32))?);
+
+    let buffer = factory.from_serde(&value)?;
+    assert_eq!(buffer.get::<&str>(&["name"])?, Some("Jeb Kermin"));
+    assert_eq!(buffer.get::<u8>(&["age"])?, Some(30));
+    assert_eq!(buffer.get::<&str>(&["tags", "1"])?, Some("kerbal"));
+    assert_eq!(buffer.get::<i32>(&["point", "0"])?, Some(-5));
+
+    // buffers serialize like json_encode
+    assert_eq!(to_json(&buffer)?, buffer.json_encode(&[])?["value"]);
+
+    assert!(to_json(&u64::MAX).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,917 @@
+//! Walk the values in a buffer without building an intermediate copy
+//!
+//! Implement [`NP_Visitor`](trait.NP_Visitor.html) and pass it to [`NP_Buffer::visit`](../buffer/struct.NP_Buffer.html#method.visit) to receive a callback for every value in the buffer.  Every method has an empty default, so a visitor only needs to implement the callbacks it cares about.
+//!
+//! Values that aren't in the buffer are passed to the visitor as their schema default, if there is one, otherwise `visit_none` is called.
+//!
+//! [`NP_JSON_Visitor`](struct.NP_JSON_Visitor.html) is a reference implementation that produces the same JSON as `json_encode`, [`NP_Msgpack_Visitor`](struct.NP_Msgpack_Visitor.html) writes the same values as MessagePack.
+//!
+
+use crate::collection::{list::NP_List, map::NP_Map, struc::NP_Struct, tuple::NP_Tuple};
+use crate::error::NP_Error;
+use crate::json_flex::{JSMAP, NP_JSON};
+use crate::memory::NP_Memory;
+use crate::pointer::{NP_Cursor, NP_Value};
+use crate::pointer::{bytes::NP_Borrow_Bytes, date::NP_Date, dec::NP_Dec, geo::NP_Geo, option::NP_Enum, string::NP_String, ulid::NP_ULID, uuid::NP_UUID};
+use crate::schema::{NP_Portal_Data, NP_TypeKeys};
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Callbacks for traversing a buffer with [`NP_Buffer::visit`](../buffer/struct.NP_Buffer.html#method.visit).
+///
+/// Collections call `enter_*` before their children and `exit_*` after them.  Each child of a struct is preceded by `visit_field`, each child of a map by `visit_key`, and each child of a list or tuple by `visit_index`.
+///
+/// Returning an error from any callback stops the traversal and the error is passed back to the caller of `visit`.
+///
+#[allow(unused_variables)]
+pub trait NP_Visitor {
+    /// A value with no data and no default
+    fn visit_none(&mut self) -> Result<(), NP_Error> { Ok(()) }
+    /// An `i8` value
+    fn visit_i8(&mut self, value: i8) -> Result<(), NP_Error> { Ok(()) }
+    /// An `i16` value
+    fn visit_i16(&mut self, value: i16) -> Result<(), NP_Error> { Ok(()) }
+    /// An `i32` value
+    fn visit_i32(&mut self, value: i32) -> Result<(), NP_Error> { Ok(()) }
+    /// An `i64` value
+    fn visit_i64(&mut self, value: i64) -> Result<(), NP_Error> { Ok(()) }
+    /// A `u8` value
+    fn visit_u8(&mut self, value: u8) -> Result<(), NP_Error> { Ok(()) }
+    /// A `u16` value
+    fn visit_u16(&mut self, value: u16) -> Result<(), NP_Error> { Ok(()) }
+    /// A `u32` value
+    fn visit_u32(&mut self, value: u32) -> Result<(), NP_Error> { Ok(()) }
+    /// A `u64` value
+    fn visit_u64(&mut self, value: u64) -> Result<(), NP_Error> { Ok(()) }
+    /// An `f32` value
+    fn visit_f32(&mut self, value: f32) -> Result<(), NP_Error> { Ok(()) }
+    /// An `f64` value
+    fn visit_f64(&mut self, value: f64) -> Result<(), NP_Error> { Ok(()) }
+    /// A decimal value
+    fn visit_dec(&mut self, value: NP_Dec) -> Result<(), NP_Error> { Ok(()) }
+    /// A boolean value
+    fn visit_bool(&mut self, value: bool) -> Result<(), NP_Error> { Ok(()) }
+    /// A string value
+    fn visit_string(&mut self, value: &str) -> Result<(), NP_Error> { Ok(()) }
+    /// A bytes value
+    fn visit_bytes(&mut self, value: &[u8]) -> Result<(), NP_Error> { Ok(()) }
+    /// A geo value
+    fn visit_geo(&mut self, value: NP_Geo) -> Result<(), NP_Error> { Ok(()) }
+    /// A uuid value
+    fn visit_uuid(&mut self, value: &NP_UUID) -> Result<(), NP_Error> { Ok(()) }
+    /// A ulid value
+    fn visit_ulid(&mut self, value: &NP_ULID) -> Result<(), NP_Error> { Ok(()) }
+    /// A date value
+    fn visit_date(&mut self, value: NP_Date) -> Result<(), NP_Error> { Ok(()) }
+    /// An enum value
+    fn visit_enum(&mut self, value: &str) -> Result<(), NP_Error> { Ok(()) }
+
+    /// Start of a struct
+    fn enter_struct(&mut self) -> Result<(), NP_Error> { Ok(()) }
+    /// End of a struct
+    fn exit_struct(&mut self) -> Result<(), NP_Error> { Ok(()) }
+    /// The next value belongs to this struct field
+    fn visit_field(&mut self, field: &str) -> Result<(), NP_Error> { Ok(()) }
+    /// Start of a list
+    fn enter_list(&mut self) -> Result<(), NP_Error> { Ok(()) }
+    /// End of a list
+    fn exit_list(&mut self) -> Result<(), NP_Error> { Ok(()) }
+    /// Start of a map
+    fn enter_map(&mut self) -> Result<(), NP_Error> { Ok(()) }
+    /// End of a map
+    fn exit_map(&mut self) -> Result<(), NP_Error> { Ok(()) }
+    /// The next value belongs to this map key
+    fn visit_key(&mut self, key: &str) -> Result<(), NP_Error> { Ok(()) }
+    /// Start of a tuple
+    fn enter_tuple(&mut self) -> Result<(), NP_Error> { Ok(()) }
+    /// End of a tuple
+    fn exit_tuple(&mut self) -> Result<(), NP_Error> { Ok(()) }
+    /// The next value is at this list or tuple index
+    fn visit_index(&mut self, index: usize) -> Result<(), NP_Error> { Ok(()) }
+}
+
+#[inline(always)]
+fn read<'value, X: NP_Value<'value>>(cursor: &NP_Cursor, memory: &'value NP_Memory) -> Result<Option<X>, NP_Error> {
+    match X::into_value(cursor, memory)? {
+        Some(x) => Ok(Some(x)),
+        None => Ok(X::default_value(0, cursor.schema_addr, memory.get_schemas()))
+    }
+}
+
+macro_rules! visit_scalar {
+    ($t: ty, $cursor: expr, $memory: expr, $visitor: expr, $call: ident) => {
+        match read::<$t>($cursor, $memory)? {
+            Some(x) => $visitor.$call(x),
+            None => $visitor.visit_none()
+        }
+    }
+}
+
+/// Walk the value at this cursor and all of it's children, calling the visitor for each one.
+///
+pub fn visit_cursor<V: NP_Visitor>(depth: usize, cursor: &NP_Cursor, memory: &NP_Memory, visitor: &mut V) -> Result<(), NP_Error> {
+
+    if depth > memory.max_depth { return Err(NP_Error::RecursionLimit) }
+
+    let schema = memory.get_schema(cursor.schema_addr);
+
+    match schema.i {
+        NP_TypeKeys::None | NP_TypeKeys::Any => visitor.visit_none(),
+        NP_TypeKeys::Int8       => visit_scalar!(i8, cursor, memory, visitor, visit_i8),
+        NP_TypeKeys::Int16      => visit_scalar!(i16, cursor, memory, visitor, visit_i16),
+        NP_TypeKeys::Int32      => visit_scalar!(i32, cursor, memory, visitor, visit_i32),
+        NP_TypeKeys::Int64      => visit_scalar!(i64, cursor, memory, visitor, visit_i64),
+        NP_TypeKeys::Uint8      => visit_scalar!(u8, cursor, memory, visitor, visit_u8),
+        NP_TypeKeys::Uint16     => visit_scalar!(u16, cursor, memory, visitor, visit_u16),
+        NP_TypeKeys::Uint32     => visit_scalar!(u32, cursor, memory, visitor, visit_u32),
+        NP_TypeKeys::Uint64     => visit_scalar!(u64, cursor, memory, visitor, visit_u64),
+        NP_TypeKeys::Float      => visit_scalar!(f32, cursor, memory, visitor, visit_f32),
+        NP_TypeKeys::Double     => visit_scalar!(f64, cursor, memory, visitor, visit_f64),
+        NP_TypeKeys::Decimal    => visit_scalar!(NP_Dec, cursor, memory, visitor, visit_dec),
+        NP_TypeKeys::Boolean    => visit_scalar!(bool, cursor, memory, visitor, visit_bool),
+        NP_TypeKeys::UTF8String => visit_scalar!(NP_String, cursor, memory, visitor, visit_string),
+        NP_TypeKeys::Bytes      => visit_scalar!(NP_Borrow_Bytes, cursor, memory, visitor, visit_bytes),
+        NP_TypeKeys::Geo        => visit_scalar!(NP_Geo, cursor, memory, visitor, visit_geo),
+        NP_TypeKeys::Uuid       => visit_scalar!(&NP_UUID, cursor, memory, visitor, visit_uuid),
+        NP_TypeKeys::Ulid       => visit_scalar!(&NP_ULID, cursor, memory, visitor, visit_ulid),
+        NP_TypeKeys::Date       => visit_scalar!(NP_Date, cursor, memory, visitor, visit_date),
+        NP_TypeKeys::Enum       => {
+            match read::<NP_Enum>(cursor, memory)? {
+                Some(NP_Enum::Some(x)) => visitor.visit_enum(&x),
+                _ => match NP_Enum::default_value(0, cursor.schema_addr, memory.get_schemas()) {
+                    Some(NP_Enum::Some(x)) => visitor.visit_enum(&x),
+                    _ => visitor.visit_none()
+                }
+            }
+        },
+        NP_TypeKeys::Struct     => {
+            if cursor.get_value(memory).get_addr_value() == 0 { return visitor.visit_none() }
+
+            visitor.enter_struct()?;
+
+            let mut struc = NP_Struct::new_iter(&cursor, memory);
+
+            while let Some((_index, key, item)) = struc.step_iter(memory) {
+                visitor.visit_field(key)?;
+                match item {
+                    Some(real) => visit_cursor(depth + 1, &real, memory, visitor)?,
+                    None => visitor.visit_none()?
+                }
+            }
+
+            visitor.exit_struct()
+        },
+        NP_TypeKeys::List       => {
+            if cursor.get_value(memory).get_addr_value() == 0 { return visitor.visit_none() }
+
+            visitor.enter_list()?;
+
+            let mut list_iter = NP_List::new_iter(&cursor, memory, false, 0);
+
+            while let Some((index, item)) = NP_List::step_iter(&mut list_iter, memory) {
+                visitor.visit_index(index)?;
+                match item {
+                    Some(real) => visit_cursor(depth + 1, &real, memory, visitor)?,
+                    None => visitor.visit_none()?
+                }
+            }
+
+            visitor.exit_list()
+        },
+        NP_TypeKeys::Map        => {
+            if cursor.get_value(memory).get_addr_value() == 0 { return visitor.visit_none() }
+
+            visitor.enter_map()?;
+
+            let mut map_iter = NP_Map::new_iter(&cursor, memory);
+
+            while let Some((key, item)) = map_iter.step_iter(memory) {
+                visitor.visit_key(key)?;
+                visit_cursor(depth + 1, &item, memory, visitor)?;
+            }
+
+            visitor.exit_map()
+        },
+        NP_TypeKeys::Tuple      => {
+            if cursor.get_value(memory).get_addr_value() == 0 { return visitor.visit_none() }
+
+            visitor.enter_tuple()?;
+
+            let mut tuple = NP_Tuple::new_iter(&cursor, memory);
+
+            while let Some((index, item)) = tuple.step_iter(memory, false) {
+                visitor.visit_index(index)?;
+                match item {
+                    Some(real) => visit_cursor(depth + 1, &real, memory, visitor)?,
+                    None => visitor.visit_none()?
+                }
+            }
+
+            visitor.exit_tuple()
+        },
+        NP_TypeKeys::Portal     => {
+            let data = unsafe { &*(*schema.data as *const NP_Portal_Data) };
+
+            let mut next = cursor.clone();
+            next.schema_addr = data.schema;
+            next.parent_schema_addr = data.parent_schema;
+            visit_cursor(depth + 1, &next, memory, visitor)
+        }
+    }
+}
+
+enum NP_JSON_Frame {
+    Dictionary(JSMAP, Option<String>),
+    Array(Vec<NP_JSON>)
+}
+
+/// Visitor that builds an `NP_JSON` value, matching the output of `json_encode`.
+///
+/// Decimals are always exported as `{"value": float, "parts": {"num": int, "exp": int}}`, the visitor doesn't see the schema `json` option.
+///
+#[derive(Default)]
+pub struct NP_JSON_Visitor {
+    stack: Vec<NP_JSON_Frame>,
+    result: Option<NP_JSON>
+}
+
+impl NP_JSON_Visitor {
+    /// Create a new JSON visitor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the JSON built by the traversal, `NP_JSON::Null` if nothing was visited.
+    pub fn into_json(self) -> NP_JSON {
+        self.result.unwrap_or(NP_JSON::Null)
+    }
+
+    fn push(&mut self, value: NP_JSON) -> Result<(), NP_Error> {
+        match self.stack.last_mut() {
+            Some(NP_JSON_Frame::Dictionary(map, key)) => {
+                match key.take() {
+                    Some(k) => { map.insert(k, value); },
+                    None => return Err(NP_Error::new("Visited a value without a key!"))
+                }
+            },
+            Some(NP_JSON_Frame::Array(list)) => list.push(value),
+            None => self.result = Some(value)
+        }
+        Ok(())
+    }
+
+    fn set_key(&mut self, name: &str) -> Result<(), NP_Error> {
+        match self.stack.last_mut() {
+            Some(NP_JSON_Frame::Dictionary(_map, key)) => {
+                *key = Some(name.to_owned());
+                Ok(())
+            },
+            _ => Err(NP_Error::new("Visited a key outside of a struct or map!"))
+        }
+    }
+
+    fn pop(&mut self) -> Result<(), NP_Error> {
+        match self.stack.pop() {
+            Some(NP_JSON_Frame::Dictionary(map, _key)) => self.push(NP_JSON::Dictionary(map)),
+            Some(NP_JSON_Frame::Array(list)) => self.push(NP_JSON::Array(list)),
+            None => Err(NP_Error::Unreachable)
+        }
+    }
+}
+
+impl NP_Visitor for NP_JSON_Visitor {
+    fn visit_none(&mut self) -> Result<(), NP_Error> { self.push(NP_JSON::Null) }
+    fn visit_i8(&mut self, value: i8) -> Result<(), NP_Error> { self.push(NP_JSON::Integer(value as i64)) }
+    fn visit_i16(&mut self, value: i16) -> Result<(), NP_Error> { self.push(NP_JSON::Integer(value as i64)) }
+    fn visit_i32(&mut self, value: i32) -> Result<(), NP_Error> { self.push(NP_JSON::Integer(value as i64)) }
+    fn visit_i64(&mut self, value: i64) -> Result<(), NP_Error> { self.push(NP_JSON::Integer(value)) }
+    fn visit_u8(&mut self, value: u8) -> Result<(), NP_Error> { self.push(NP_JSON::Integer(value as i64)) }
+    fn visit_u16(&mut self, value: u16) -> Result<(), NP_Error> { self.push(NP_JSON::Integer(value as i64)) }
+    fn visit_u32(&mut self, value: u32) -> Result<(), NP_Error> { self.push(NP_JSON::Integer(value as i64)) }
+    fn visit_u64(&mut self, value: u64) -> Result<(), NP_Error> { self.push(NP_JSON::Integer(value as i64)) }
+    fn visit_f32(&mut self, value: f32) -> Result<(), NP_Error> { self.push(NP_JSON::Float(value as f64)) }
+    fn visit_f64(&mut self, value: f64) -> Result<(), NP_Error> { self.push(NP_JSON::Float(value)) }
+    fn visit_dec(&mut self, value: NP_Dec) -> Result<(), NP_Error> {
+        let mut object = JSMAP::new();
+        let mut parts = JSMAP::new();
+
+        parts.insert("num".to_owned(), NP_JSON::Integer(value.num));
+        parts.insert("exp".to_owned(), NP_JSON::Integer(value.exp as i64));
+        object.insert("value".to_owned(), NP_JSON::Float(value.to_float()));
+        object.insert("parts".to_owned(), NP_JSON::Dictionary(parts));
+
+        self.push(NP_JSON::Dictionary(object))
+    }
+    fn visit_bool(&mut self, value: bool) -> Result<(), NP_Error> { self.push(if value { NP_JSON::True } else { NP_JSON::False }) }
+    fn visit_string(&mut self, value: &str) -> Result<(), NP_Error> { self.push(NP_JSON::String(value.to_owned())) }
+    fn visit_bytes(&mut self, value: &[u8]) -> Result<(), NP_Error> { self.push(NP_JSON::Array(value.iter().map(|x| NP_JSON::Integer(*x as i64)).collect())) }
+    fn visit_geo(&mut self, value: NP_Geo) -> Result<(), NP_Error> { self.push(value.into_json()) }
+    fn visit_uuid(&mut self, value: &NP_UUID) -> Result<(), NP_Error> { self.push(NP_JSON::String(value.to_string())) }
+    fn visit_ulid(&mut self, value: &NP_ULID) -> Result<(), NP_Error> { self.push(NP_JSON::String(value.to_string())) }
+    fn visit_date(&mut self, value: NP_Date) -> Result<(), NP_Error> { self.push(NP_JSON::Integer(value.value as i64)) }
+    fn visit_enum(&mut self, value: &str) -> Result<(), NP_Error> { self.push(NP_JSON::String(value.to_owned())) }
+
+    fn enter_struct(&mut self) -> Result<(), NP_Error> { self.stack.push(NP_JSON_Frame::Dictionary(JSMAP::new(), None)); Ok(()) }
+    fn exit_struct(&mut self) -> Result<(), NP_Error> { self.pop() }
+    fn visit_field(&mut self, field: &str) -> Result<(), NP_Error> { self.set_key(field) }
+    fn enter_list(&mut self) -> Result<(), NP_Error> { self.stack.push(NP_JSON_Frame::Array(Vec::new())); Ok(()) }
+    fn exit_list(&mut self) -> Result<(), NP_Error> { self.pop() }
+    fn enter_map(&mut self) -> Result<(), NP_Error> { self.stack.push(NP_JSON_Frame::Dictionary(JSMAP::new(), None)); Ok(()) }
+    fn exit_map(&mut self) -> Result<(), NP_Error> { self.pop() }
+    fn visit_key(&mut self, key: &str) -> Result<(), NP_Error> { self.set_key(key) }
+    fn enter_tuple(&mut self) -> Result<(), NP_Error> { self.stack.push(NP_JSON_Frame::Array(Vec::new())); Ok(()) }
+    fn exit_tuple(&mut self) -> Result<(), NP_Error> { self.pop() }
+}
+
+struct NP_Hash_Frame {
+    bytes: Vec<u8>,
+    // map entries are collected one at a time so they can be sorted by key
+    entries: Option<Vec<Vec<u8>>>,
+    // empty values are only written once a value comes after them, so trailing empty values are dropped
+    pending_none: usize,
+    has_values: bool
+}
+
+/// Visitor that writes every value into a canonical byte stream, used by `NP_Buffer::logical_hash`.
+///
+/// Map entries are sorted by key so the order they were inserted in doesn't change the result.  Empty values at the end of a collection and map keys without a value are left out, a collection without any values is written the same as an empty value.  This way a collection that had all of its values deleted hashes the same as one that was never set.
+///
+#[doc(hidden)]
+#[derive(Default)]
+pub struct NP_Hash_Visitor {
+    stack: Vec<NP_Hash_Frame>,
+    bytes: Vec<u8>
+}
+
+impl NP_Hash_Visitor {
+    /// Take the canonical bytes built by the traversal
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn out(&mut self) -> &mut Vec<u8> {
+        match self.stack.last_mut() {
+            Some(frame) => {
+                if let Some(entries) = &mut frame.entries {
+                    if let Some(entry) = entries.last_mut() {
+                        return entry;
+                    }
+                }
+                &mut frame.bytes
+            },
+            None => &mut self.bytes
+        }
+    }
+
+    /// Add bytes for a value to the current collection, any empty values waiting in front of it are written first
+    fn emit(&mut self, bytes: &[u8]) {
+        if let Some(frame) = self.stack.last_mut() {
+            for _x in 0..frame.pending_none {
+                frame.bytes.extend_from_slice(&[0, 0, 0, 0, 0]);
+            }
+            frame.pending_none = 0;
+            frame.has_values = true;
+        }
+        self.out().extend_from_slice(bytes);
+    }
+
+    fn write(&mut self, tag: u8, value: &[u8]) -> Result<(), NP_Error> {
+        let mut bytes = vec![tag];
+        bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(value);
+        self.emit(&bytes);
+        Ok(())
+    }
+
+    fn enter(&mut self, tag: u8, map: bool) -> Result<(), NP_Error> {
+        self.stack.push(NP_Hash_Frame { bytes: vec![tag], entries: if map { Some(Vec::new()) } else { None }, pending_none: 0, has_values: false });
+        Ok(())
+    }
+
+    fn exit(&mut self) -> Result<(), NP_Error> {
+        match self.stack.pop() {
+            Some(mut frame) => {
+                if frame.has_values == false {
+                    return self.visit_none();
+                }
+                if let Some(mut entries) = frame.entries.take() {
+                    entries.sort();
+                    for entry in entries.iter() {
+                        frame.bytes.extend_from_slice(&(entry.len() as u32).to_be_bytes());
+                        frame.bytes.extend_from_slice(entry);
+                    }
+                }
+                // end of collection
+                frame.bytes.push(255);
+                self.emit(&frame.bytes);
+                Ok(())
+            },
+            None => Err(NP_Error::Unreachable)
+        }
+    }
+}
+
+impl NP_Visitor for NP_Hash_Visitor {
+    fn visit_none(&mut self) -> Result<(), NP_Error> {
+        match self.stack.last_mut() {
+            // a map key without a value is the same as no key
+            Some(NP_Hash_Frame { entries: Some(entries), .. }) => {
+                entries.pop();
+                Ok(())
+            },
+            Some(frame) => {
+                frame.pending_none += 1;
+                Ok(())
+            },
+            None => self.write(0, &[])
+        }
+    }
+    fn visit_i8(&mut self, value: i8) -> Result<(), NP_Error> { self.write(1, &value.to_be_bytes()) }
+    fn visit_i16(&mut self, value: i16) -> Result<(), NP_Error> { self.write(2, &value.to_be_bytes()) }
+    fn visit_i32(&mut self, value: i32) -> Result<(), NP_Error> { self.write(3, &value.to_be_bytes()) }
+    fn visit_i64(&mut self, value: i64) -> Result<(), NP_Error> { self.write(4, &value.to_be_bytes()) }
+    fn visit_u8(&mut self, value: u8) -> Result<(), NP_Error> { self.write(5, &value.to_be_bytes()) }
+    fn visit_u16(&mut self, value: u16) -> Result<(), NP_Error> { self.write(6, &value.to_be_bytes()) }
+    fn visit_u32(&mut self, value: u32) -> Result<(), NP_Error> { self.write(7, &value.to_be_bytes()) }
+    fn visit_u64(&mut self, value: u64) -> Result<(), NP_Error> { self.write(8, &value.to_be_bytes()) }
+    fn visit_f32(&mut self, value: f32) -> Result<(), NP_Error> { self.write(9, &value.to_bits().to_be_bytes()) }
+    fn visit_f64(&mut self, value: f64) -> Result<(), NP_Error> { self.write(10, &value.to_bits().to_be_bytes()) }
+    fn visit_dec(&mut self, value: NP_Dec) -> Result<(), NP_Error> {
+        let mut bytes = value.num.to_be_bytes().to_vec();
+        bytes.push(value.exp);
+        self.write(11, &bytes)
+    }
+    fn visit_bool(&mut self, value: bool) -> Result<(), NP_Error> { self.write(12, &[value as u8]) }
+    fn visit_string(&mut self, value: &str) -> Result<(), NP_Error> { self.write(13, value.as_bytes()) }
+    fn visit_bytes(&mut self, value: &[u8]) -> Result<(), NP_Error> { self.write(14, value) }
+    fn visit_geo(&mut self, value: NP_Geo) -> Result<(), NP_Error> {
+        let mut bytes = value.lat.to_bits().to_be_bytes().to_vec();
+        bytes.extend_from_slice(&value.lng.to_bits().to_be_bytes());
+        self.write(15, &bytes)
+    }
+    fn visit_uuid(&mut self, value: &NP_UUID) -> Result<(), NP_Error> { self.write(16, value.to_string().as_bytes()) }
+    fn visit_ulid(&mut self, value: &NP_ULID) -> Result<(), NP_Error> { self.write(17, value.to_string().as_bytes()) }
+    fn visit_date(&mut self, value: NP_Date) -> Result<(), NP_Error> { self.write(18, &value.value.to_be_bytes()) }
+    fn visit_enum(&mut self, value: &str) -> Result<(), NP_Error> { self.write(19, value.as_bytes()) }
+
+    fn enter_struct(&mut self) -> Result<(), NP_Error> { self.enter(20, false) }
+    fn exit_struct(&mut self) -> Result<(), NP_Error> { self.exit() }
+    fn enter_list(&mut self) -> Result<(), NP_Error> { self.enter(21, false) }
+    fn exit_list(&mut self) -> Result<(), NP_Error> { self.exit() }
+    fn enter_map(&mut self) -> Result<(), NP_Error> { self.enter(22, true) }
+    fn exit_map(&mut self) -> Result<(), NP_Error> { self.exit() }
+    fn visit_key(&mut self, key: &str) -> Result<(), NP_Error> {
+        match self.stack.last_mut() {
+            Some(NP_Hash_Frame { entries: Some(entries), .. }) => {
+                let mut entry = (key.len() as u32).to_be_bytes().to_vec();
+                entry.extend_from_slice(key.as_bytes());
+                entries.push(entry);
+                Ok(())
+            },
+            _ => Err(NP_Error::new("Visited a key outside of a map!"))
+        }
+    }
+    fn enter_tuple(&mut self) -> Result<(), NP_Error> { self.enter(23, false) }
+    fn exit_tuple(&mut self) -> Result<(), NP_Error> { self.exit() }
+}
+
+struct NP_Msgpack_Frame {
+    bytes: Vec<u8>,
+    // number of values for arrays, number of key/value pairs for maps
+    count: usize,
+    map: bool
+}
+
+/// Visitor that writes MessagePack bytes, used by `NP_Buffer::to_msgpack`.
+///
+/// Structs and maps become msgpack maps with string keys, lists and tuples become arrays.  Integers are written in the smallest msgpack format that holds them.  Other types follow the JSON export: decimals are a map with `value` and `parts`, geo values are a map with `lat` and `lng`, uuids and ulids are strings and dates are the integer timestamp.
+///
+#[derive(Default)]
+pub struct NP_Msgpack_Visitor {
+    stack: Vec<NP_Msgpack_Frame>,
+    bytes: Vec<u8>
+}
+
+impl NP_Msgpack_Visitor {
+    /// Create a new msgpack visitor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the msgpack bytes built by the traversal
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn out(&mut self) -> &mut Vec<u8> {
+        match self.stack.last_mut() {
+            Some(frame) => &mut frame.bytes,
+            None => &mut self.bytes
+        }
+    }
+
+    // called once for every value, keys are written without counting
+    fn count(&mut self) {
+        if let Some(frame) = self.stack.last_mut() {
+            if !frame.map {
+                frame.count += 1;
+            }
+        }
+    }
+
+    fn write_uint(&mut self, value: u64) -> Result<(), NP_Error> {
+        self.count();
+        let out = self.out();
+        if value < 128 {
+            out.push(value as u8);
+        } else if value <= u8::MAX as u64 {
+            out.push(0xcc);
+            out.push(value as u8);
+        } else if value <= u16::MAX as u64 {
+            out.push(0xcd);
+            out.extend_from_slice(&(value as u16).to_be_bytes());
+        } else if value <= u32::MAX as u64 {
+            out.push(0xce);
+            out.extend_from_slice(&(value as u32).to_be_bytes());
+        } else {
+            out.push(0xcf);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+        Ok(())
+    }
+
+    fn write_int(&mut self, value: i64) -> Result<(), NP_Error> {
+        if value >= 0 {
+            return self.write_uint(value as u64);
+        }
+        self.count();
+        let out = self.out();
+        if value >= -32 {
+            out.push(value as u8);
+        } else if value >= i8::MIN as i64 {
+            out.push(0xd0);
+            out.push(value as i8 as u8);
+        } else if value >= i16::MIN as i64 {
+            out.push(0xd1);
+            out.extend_from_slice(&(value as i16).to_be_bytes());
+        } else if value >= i32::MIN as i64 {
+            out.push(0xd2);
+            out.extend_from_slice(&(value as i32).to_be_bytes());
+        } else {
+            out.push(0xd3);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+        Ok(())
+    }
+
+    fn write_f64(&mut self, value: f64) -> Result<(), NP_Error> {
+        self.count();
+        let out = self.out();
+        out.push(0xcb);
+        out.extend_from_slice(&value.to_bits().to_be_bytes());
+        Ok(())
+    }
+
+    fn write_str(&mut self, value: &str) -> Result<(), NP_Error> {
+        let len = value.len();
+        let out = self.out();
+        if len < 32 {
+            out.push(0xa0 | len as u8);
+        } else if len <= u8::MAX as usize {
+            out.push(0xd9);
+            out.push(len as u8);
+        } else if len <= u16::MAX as usize {
+            out.push(0xda);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            out.push(0xdb);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+        out.extend_from_slice(value.as_bytes());
+        Ok(())
+    }
+
+    fn write_key(&mut self, key: &str) -> Result<(), NP_Error> {
+        match self.stack.last_mut() {
+            Some(frame) if frame.map => frame.count += 1,
+            _ => return Err(NP_Error::new("Visited a key outside of a struct or map!"))
+        }
+        self.write_str(key)
+    }
+
+    fn enter(&mut self, map: bool) -> Result<(), NP_Error> {
+        self.count();
+        self.stack.push(NP_Msgpack_Frame { bytes: Vec::new(), count: 0, map });
+        Ok(())
+    }
+
+    fn exit(&mut self) -> Result<(), NP_Error> {
+        let frame = match self.stack.pop() {
+            Some(frame) => frame,
+            None => return Err(NP_Error::Unreachable)
+        };
+
+        if frame.count > u32::MAX as usize {
+            return Err(NP_Error::new("Collection is too large for msgpack!"))
+        }
+
+        let out = self.out();
+        match (frame.map, frame.count) {
+            (true, count) if count < 16 => out.push(0x80 | count as u8),
+            (true, count) if count <= u16::MAX as usize => {
+                out.push(0xde);
+                out.extend_from_slice(&(count as u16).to_be_bytes());
+            },
+            (true, count) => {
+                out.push(0xdf);
+                out.extend_from_slice(&(count as u32).to_be_bytes());
+            },
+            (false, count) if count < 16 => out.push(0x90 | count as u8),
+            (false, count) if count <= u16::MAX as usize => {
+                out.push(0xdc);
+                out.extend_from_slice(&(count as u16).to_be_bytes());
+            },
+            (false, count) => {
+                out.push(0xdd);
+                out.extend_from_slice(&(count as u32).to_be_bytes());
+            }
+        }
+        out.extend_from_slice(&frame.bytes);
+        Ok(())
+    }
+}
+
+impl NP_Visitor for NP_Msgpack_Visitor {
+    fn visit_none(&mut self) -> Result<(), NP_Error> { self.count(); self.out().push(0xc0); Ok(()) }
+    fn visit_i8(&mut self, value: i8) -> Result<(), NP_Error> { self.write_int(value as i64) }
+    fn visit_i16(&mut self, value: i16) -> Result<(), NP_Error> { self.write_int(value as i64) }
+    fn visit_i32(&mut self, value: i32) -> Result<(), NP_Error> { self.write_int(value as i64) }
+    fn visit_i64(&mut self, value: i64) -> Result<(), NP_Error> { self.write_int(value) }
+    fn visit_u8(&mut self, value: u8) -> Result<(), NP_Error> { self.write_uint(value as u64) }
+    fn visit_u16(&mut self, value: u16) -> Result<(), NP_Error> { self.write_uint(value as u64) }
+    fn visit_u32(&mut self, value: u32) -> Result<(), NP_Error> { self.write_uint(value as u64) }
+    fn visit_u64(&mut self, value: u64) -> Result<(), NP_Error> { self.write_uint(value) }
+    fn visit_f32(&mut self, value: f32) -> Result<(), NP_Error> {
+        self.count();
+        let out = self.out();
+        out.push(0xca);
+        out.extend_from_slice(&value.to_bits().to_be_bytes());
+        Ok(())
+    }
+    fn visit_f64(&mut self, value: f64) -> Result<(), NP_Error> { self.write_f64(value) }
+    fn visit_dec(&mut self, value: NP_Dec) -> Result<(), NP_Error> {
+        self.enter(true)?;
+        self.write_key("value")?;
+        self.write_f64(value.to_float())?;
+        self.write_key("parts")?;
+        self.enter(true)?;
+        self.write_key("num")?;
+        self.write_int(value.num)?;
+        self.write_key("exp")?;
+        self.write_uint(value.exp as u64)?;
+        self.exit()?;
+        self.exit()
+    }
+    fn visit_bool(&mut self, value: bool) -> Result<(), NP_Error> { self.count(); self.out().push(if value { 0xc3 } else { 0xc2 }); Ok(()) }
+    fn visit_string(&mut self, value: &str) -> Result<(), NP_Error> { self.count(); self.write_str(value) }
+    fn visit_bytes(&mut self, value: &[u8]) -> Result<(), NP_Error> {
+        self.count();
+        let len = value.len();
+        let out = self.out();
+        if len <= u8::MAX as usize {
+            out.push(0xc4);
+            out.push(len as u8);
+        } else if len <= u16::MAX as usize {
+            out.push(0xc5);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            out.push(0xc6);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+        out.extend_from_slice(value);
+        Ok(())
+    }
+    fn visit_geo(&mut self, value: NP_Geo) -> Result<(), NP_Error> {
+        self.enter(true)?;
+        self.write_key("lat")?;
+        self.write_f64(value.lat)?;
+        self.write_key("lng")?;
+        self.write_f64(value.lng)?;
+        self.exit()
+    }
+    fn visit_uuid(&mut self, value: &NP_UUID) -> Result<(), NP_Error> { self.count(); self.write_str(&value.to_string()) }
+    fn visit_ulid(&mut self, value: &NP_ULID) -> Result<(), NP_Error> { self.count(); self.write_str(&value.to_string()) }
+    fn visit_date(&mut self, value: NP_Date) -> Result<(), NP_Error> { self.write_uint(value.value) }
+    fn visit_enum(&mut self, value: &str) -> Result<(), NP_Error> { self.count(); self.write_str(value) }
+
+    fn enter_struct(&mut self) -> Result<(), NP_Error> { self.enter(true) }
+    fn exit_struct(&mut self) -> Result<(), NP_Error> { self.exit() }
+    fn visit_field(&mut self, field: &str) -> Result<(), NP_Error> { self.write_key(field) }
+    fn enter_list(&mut self) -> Result<(), NP_Error> { self.enter(false) }
+    fn exit_list(&mut self) -> Result<(), NP_Error> { self.exit() }
+    fn enter_map(&mut self) -> Result<(), NP_Error> { self.enter(true) }
+    fn exit_map(&mut self) -> Result<(), NP_Error> { self.exit() }
+    fn visit_key(&mut self, key: &str) -> Result<(), NP_Error> { self.write_key(key) }
+    fn enter_tuple(&mut self) -> Result<(), NP_Error> { self.enter(false) }
+    fn exit_tuple(&mut self) -> Result<(), NP_Error> { self.exit() }
+}
+
+#[test]
+fn json_visitor_matches_json_encode() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new_json(r#"{
+        "type": "struct",
+        "fields": [
+            ["name",  {"type": "string"}],
+            ["age",   {"type": "u8", "default": 10}],
+            ["tags",  {"type": "list", "of": {"type": "string"}}],
+            ["meta",  {"type": "map", "value": {"type": "i32"}}],
+            ["pair",  {"type": "tuple", "values": [{"type": "bool"}, {"type": "u16"}]}],
+            ["color", {"type": "enum", "choices": ["red", "green"]}],
+            ["raw",   {"type": "bytes"}]
+        ]
+    }"#)?;
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["name"], "hello")?;
+    buffer.set(&["tags", "0"], "a")?;
+    buffer.set(&["tags", "2"], "c")?;
+    buffer.set(&["meta", "x"], -5i32)?;
+    buffer.set(&["pair", "1"], 300u16)?;
+    buffer.set(&["color"], NP_Enum::new("green"))?;
+    buffer.set(&["raw"], &[1u8, 2, 3] as &[u8])?;
+
+    let mut visitor = NP_JSON_Visitor::new();
+    buffer.visit(&mut visitor)?;
+
+    assert_eq!(visitor.into_json().stringify(), buffer.json_encode(&[])?["value"].stringify());
+
+    Ok(())
+}
+
+#[test]
+fn logical_hash_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new(r#"struct({fields: {
+        a: list({of: map({value: string()})}),
+        b: tuple({values: [string(), string()]}),
+        c: u16({default: 5})
+    }})"#)?;
+
+    let mut one = factory.new_buffer(None);
+    one.set(&["a", "1", "x"], "1")?;
+    one.set(&["a", "1", "y"], "2")?;
+    one.set(&["b", "1"], "b")?;
+    one.compact(None)?;
+
+    let mut two = factory.new_buffer(None);
+    two.set(&["b", "1"], "b")?;
+    two.set(&["a", "1", "y"], "2")?;
+    two.set(&["a", "1", "x"], "1")?;
+    two.set(&["c"], 5u16)?;
+    assert_eq!(one.logical_hash()?, two.logical_hash()?);
+
+    // the value moved to the other tuple column
+    let mut three = factory.new_buffer(None);
+    three.set(&["a", "1", "x"], "1")?;
+    three.set(&["a", "1", "y"], "2")?;
+    three.set(&["b", "0"], "b")?;
+    assert_ne!(one.logical_hash()?, three.logical_hash()?);
+
+    // keys and values can't be shifted into each other
+    let mut four = factory.new_buffer(None);
+    four.set(&["a", "1", "x1"], "")?;
+    four.set(&["a", "1", "y"], "2")?;
+    four.set(&["b", "1"], "b")?;
+    assert_ne!(one.logical_hash()?, four.logical_hash()?);
+
+    Ok(())
+}
+
+#[test]
+fn logical_hash_empty_collections_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new(r#"struct({fields: {
+        s: struct({fields: {a: string(), b: string()}}),
+        l: list({of: string()}),
+        m: map({value: string()})
+    }})"#)?;
+
+    let fresh = factory.new_buffer(None);
+
+    // every value set and then deleted
+    let mut emptied = factory.new_buffer(None);
+    emptied.set(&["s", "a"], "a")?;
+    emptied.del(&["s", "a"])?;
+    emptied.set(&["l", "2"], "c")?;
+    emptied.del(&["l", "2"])?;
+    emptied.set(&["m", "x"], "x")?;
+    emptied.del(&["m", "x"])?;
+    assert_eq!(fresh.logical_hash()?, emptied.logical_hash()?);
+
+    // empty slots at the end of a list don't count
+    let mut one = factory.new_buffer(None);
+    one.set(&["l", "1"], "b")?;
+
+    let mut two = factory.new_buffer(None);
+    two.set(&["l", "1"], "b")?;
+    two.set(&["l", "4"], "e")?;
+    two.del(&["l", "4"])?;
+    assert_eq!(one.logical_hash()?, two.logical_hash()?);
+
+    // empty slots in front of a value still do
+    let mut three = factory.new_buffer(None);
+    three.set(&["l", "0"], "b")?;
+    assert_ne!(one.logical_hash()?, three.logical_hash()?);
+
+    // and so do empty values in front of a value in a struct
+    let mut four = factory.new_buffer(None);
+    four.set(&["s", "b"], "a")?;
+    let mut five = factory.new_buffer(None);
+    five.set(&["s", "a"], "a")?;
+    assert_ne!(four.logical_hash()?, five.logical_hash()?);
+
+    Ok(())
+}
+
+#[test]
+fn msgpack_visitor_works() -> Result<(), NP_Error> {
+    let factory = crate::NP_Factory::new(r#"struct({fields: {
+        a: i8(), b: i16(), c: i32(), d: i64(),
+        e: u8(), f: u16(), g: u32(), h: u64(),
+        i: f32(), j: f64(), k: dec({exp: 2}), l: bool(),
+        m: string(), n: bytes(), o: geo16(), p: uuid(),
+        q: ulid(), r: date(), s: enum({choices: ["red", "green"]}),
+        t: map({value: bool()}), u: tuple({values: [u8(), string()]}),
+        v: list({of: u8()})
+    }})"#)?;
+
+    let uuid = NP_UUID::generate(2);
+    let ulid = NP_ULID::generate(1604965249484, 2);
+
+    let mut buffer = factory.new_buffer(None);
+    buffer.set(&["a"], -5i8)?;
+    buffer.set(&["b"], -200i16)?;
+    buffer.set(&["c"], -70000i32)?;
+    buffer.set(&["d"], i64::MIN)?;
+    buffer.set(&["e"], 200u8)?;
+    buffer.set(&["f"], 127u16)?;
+    buffer.set(&["g"], 70000u32)?;
+    buffer.set(&["h"], u64::MAX)?;
+    buffer.set(&["i"], 1.5f32)?;
+    buffer.set(&["j"], -2.25f64)?;
+    buffer.set(&["k"], NP_Dec::new(-150, 2))?;
+    buffer.set(&["l"], true)?;
+    buffer.set(&["m"], "hi")?;
+    buffer.set(&["n"], &[1u8, 2] as &[u8])?;
+    buffer.set(&["o"], NP_Geo::new(16, 1.5, -2.5))?;
+    buffer.set(&["p"], &uuid)?;
+    buffer.set(&["q"], &ulid)?;
+    buffer.set(&["r"], NP_Date::new(1604965249484))?;
+    buffer.set(&["s"], NP_Enum::new("green"))?;
+    buffer.set(&["t", "x"], false)?;
+    buffer.set(&["u", "0"], 9u8)?;
+    buffer.set(&["v", "1"], 4u8)?;
+
+    fn key(out: &mut Vec<u8>, key: &str) {
+        out.push(0xa0 | key.len() as u8);
+        out.extend_from_slice(key.as_bytes());
+    }
+
+    let geo: NP_Geo = buffer.get(&["o"])?.unwrap();
+
+    let mut expected: Vec<u8> = vec![0xde, 0x00, 22];
+    key(&mut expected, "a"); expected.push(0xfb);
+    key(&mut expected, "b"); expected.extend_from_slice(&[0xd1, 0xff, 0x38]);
+    key(&mut expected, "c"); expected.push(0xd2); expected.extend_from_slice(&(-70000i32).to_be_bytes());
+    key(&mut expected, "d"); expected.push(0xd3); expected.extend_from_slice(&i64::MIN.to_be_bytes());
+    key(&mut expected, "e"); expected.extend_from_slice(&[0xcc, 200]);
+    key(&mut expected, "f"); expected.push(0x7f);
+    key(&mut expected, "g"); expected.push(0xce); expected.extend_from_slice(&70000u32.to_be_bytes());
+    key(&mut expected, "h"); expected.push(0xcf); expected.extend_from_slice(&u64::MAX.to_be_bytes());
+    key(&mut expected, "i"); expected.push(0xca); expected.extend_from_slice(&1.5f32.to_bits().to_be_bytes());
+    key(&mut expected, "j"); expected.push(0xcb); expected.extend_from_slice(&(-2.25f64).to_bits().to_be_bytes());
+    key(&mut expected, "k"); expected.push(0x82);
+        key(&mut expected, "value"); expected.push(0xcb); expected.extend_from_slice(&(-1.5f64).to_bits().to_be_bytes());
+        key(&mut expected, "parts"); expected.push(0x82);
+            key(&mut expected, "num"); expected.extend_from_slice(&[0xd1, 0xff, 0x6a]);
+            key(&mut expected, "exp"); expected.push(0x02);
+    key(&mut expected, "l"); expected.push(0xc3);
+    key(&mut expected, "m"); key(&mut expected, "hi");
+    key(&mut expected, "n"); expected.extend_from_slice(&[0xc4, 2, 1, 2]);
+    key(&mut expected, "o"); expected.push(0x82);
+        key(&mut expected, "lat"); expected.push(0xcb); expected.extend_from_slice(&geo.lat.to_bits().to_be_bytes());
+        key(&mut expected, "lng"); expected.push(0xcb); expected.extend_from_slice(&geo.lng.to_bits().to_be_bytes());
+    key(&mut expected, "p"); expected.extend_from_slice(&[0xd9, 36]); expected.extend_from_slice(uuid.to_string().as_bytes());
+    key(&mut expected, "q"); key(&mut expected, &ulid.to_string());
+    key(&mut expected, "r"); expected.push(0xcf); expected.extend_from_slice(&1604965249484u64.to_be_bytes());
+    key(&mut expected, "s"); key(&mut expected, "green");
+    key(&mut expected, "t"); expected.push(0x81); key(&mut expected, "x"); expected.push(0xc2);
+    key(&mut expected, "u"); expected.extend_from_slice(&[0x92, 0x09, 0xc0]);
+    key(&mut expected, "v"); expected.extend_from_slice(&[0x92, 0xc0, 0x04]);
+
+    assert_eq!(buffer.to_msgpack()?, expected);
+
+    // nothing in the buffer
+    assert_eq!(factory.new_buffer(None).to_msgpack()?, vec![0xc0]);
+
+    Ok(())
+}